@@ -0,0 +1,98 @@
+//! Baseline numbers of the benchmarks, committed in `benches/baseline.json`. They are only
+//! meant to catch order-of-magnitude regressions, so the tolerance is generous.
+
+use std::{collections::BTreeMap, env, fmt, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Measurements of a fixture by name: the mean time in milliseconds of each criterion
+/// benchmark, and `evm_gas`.
+pub type Measurements = BTreeMap<String, f64>;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Largest allowed relative deviation from the baseline, either way.
+    pub tolerance: f64,
+    pub fixtures: BTreeMap<String, Measurements>,
+}
+
+/// A measurement off its baseline by more than the tolerance.
+#[derive(Clone, Debug)]
+pub struct Deviation {
+    pub fixture: String,
+    pub metric: String,
+    pub baseline: f64,
+    pub measured: f64,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: measured {:.2}, baseline {:.2} ({:+.0}%)",
+            self.fixture,
+            self.metric,
+            self.measured,
+            self.baseline,
+            (self.measured / self.baseline - 1.0) * 100.0
+        )
+    }
+}
+
+impl Baseline {
+    fn path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/baseline.json")
+    }
+
+    pub fn load() -> io::Result<Self> {
+        let json = fs::read_to_string(Self::path())?;
+        serde_json::from_str(&json).map_err(io::Error::from)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(Self::path(), serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    /// Measurements of `fixture` off their baseline, or the metrics that have none recorded.
+    pub fn deviations(
+        &self,
+        fixture: &str,
+        measured: &Measurements,
+    ) -> Result<Vec<Deviation>, Vec<String>> {
+        let baseline = self.fixtures.get(fixture);
+        let missing = measured
+            .keys()
+            .filter(|metric| baseline.map_or(true, |baseline| !baseline.contains_key(*metric)))
+            .map(|metric| format!("{fixture} {metric}"))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        let baseline = baseline.unwrap();
+        Ok(measured
+            .iter()
+            .filter_map(|(metric, measured)| {
+                let baseline = baseline[metric];
+                ((measured - baseline).abs() > self.tolerance * baseline).then(|| Deviation {
+                    fixture: fixture.to_string(),
+                    metric: metric.clone(),
+                    baseline,
+                    measured: *measured,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Mean time in milliseconds of the last run of `group/bench`, read from criterion's output.
+pub fn criterion_mean_ms(group: &str, bench: &str) -> Option<f64> {
+    let path = env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| PathBuf::from("target"), PathBuf::from)
+        .join("criterion")
+        .join(group)
+        .join(bench)
+        .join("new/estimates.json");
+    let estimates: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    Some(estimates["mean"]["point_estimate"].as_f64()? / 1e6)
+}
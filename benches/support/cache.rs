@@ -0,0 +1,81 @@
+//! Keeps the KZG params and proving keys of the benchmarks on disk between runs, so only the
+//! first run pays for the setup.
+
+use std::{
+    env, fs,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, G1Affine},
+    plonk::{keygen_pk, keygen_vk, ProvingKey},
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    SerdeFormat,
+};
+use semaphore_aggregation::plonky2_verifier::{verifier_api::gen_srs, verifier_circuit::Verifier};
+use sha3::{Digest, Keccak256};
+
+/// Seed of the cached params. The toxic waste is known, which only matters outside of
+/// benchmarks.
+const SRS_SEED: u64 = 0;
+
+/// The cache files are only read back by the build that wrote them, so the unchecked format
+/// is enough.
+const FORMAT: SerdeFormat = SerdeFormat::RawBytesUnchecked;
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Cache in `$BENCH_CACHE_DIR`, or in `bench-cache` of the target directory.
+    pub fn new() -> io::Result<Self> {
+        let dir = match env::var_os("BENCH_CACHE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => env::var_os("CARGO_TARGET_DIR")
+                .map_or_else(|| PathBuf::from("target"), PathBuf::from)
+                .join("bench-cache"),
+        };
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Params of degree `k` derived from `SRS_SEED`.
+    pub fn srs(&self, k: u32) -> io::Result<ParamsKZG<Bn256>> {
+        let path = self.dir.join(format!("srs-k{k}-seed{SRS_SEED}.bin"));
+        if let Ok(file) = File::open(&path) {
+            return ParamsKZG::<Bn256>::read_custom(&mut BufReader::new(file), FORMAT);
+        }
+        let param = gen_srs(k, Some(SRS_SEED));
+        param.write_custom(&mut BufWriter::new(File::create(&path)?), FORMAT)?;
+        Ok(param)
+    }
+
+    /// Proving key of `circuit` under `param`. Keys are looked up by the hash of the verifying
+    /// key, which is cheaper to generate than the proving key and changes with the circuit, the
+    /// fixture and the params alike.
+    pub fn pk(
+        &self,
+        name: &str,
+        param: &ParamsKZG<Bn256>,
+        circuit: &Verifier,
+    ) -> io::Result<ProvingKey<G1Affine>> {
+        let vk = keygen_vk(param, circuit).map_err(to_io)?;
+        let vk_hash = hex::encode(Keccak256::digest(vk.to_bytes(SerdeFormat::RawBytes)));
+        let path = self
+            .dir
+            .join(format!("pk-{name}-k{}-{}.bin", param.k(), &vk_hash[..16]));
+        if let Ok(file) = File::open(&path) {
+            return ProvingKey::read::<_, Verifier>(&mut BufReader::new(file), FORMAT);
+        }
+        let pk = keygen_pk(param, vk, circuit).map_err(to_io)?;
+        pk.write(&mut BufWriter::new(File::create(&path)?), FORMAT)?;
+        Ok(pk)
+    }
+}
+
+fn to_io(err: halo2_proofs::plonk::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{err:?}"))
+}
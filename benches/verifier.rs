@@ -0,0 +1,204 @@
+//! Benchmarks of the verifier circuit over the proofs of `plonky2_verifier::fixtures`: circuit
+//! synthesis with the mock prover, proving key generation, proving and verifying, plus the gas
+//! the EVM verifier takes as a point measurement.
+//!
+//! ```text
+//! cargo bench --features fixtures
+//! cargo bench --features fixtures -- --quick
+//! ```
+//!
+//! `--quick`, as well as `cargo test --benches`, only runs the tiny fixture. The params and
+//! proving keys are cached, see `support::cache`. After a run with `--bench`, the measurements
+//! are checked against `benches/baseline.json`, set `BENCH_RECORD_BASELINE=1` to overwrite it
+//! with them instead.
+
+mod support;
+
+use std::{env, process::Command};
+
+use criterion::Criterion;
+use halo2_proofs::{
+    dev::MockProver,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{keygen_pk, keygen_vk, ProvingKey},
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2_solidity_verifier::{encode_calldata, Evm};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use semaphore_aggregation::plonky2_verifier::{
+    bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+    fixtures::{
+        generate_proof_tuple, generate_semaphore_proof_tuple, generate_tiny_proof_tuple,
+        mock_verifier,
+    },
+    verifier_api::{create_proof_bytes, gen_evm_verifier, min_k, verify_proof_bytes},
+    verifier_circuit::{ProofTuple, Verifier},
+};
+
+use support::{
+    baseline::{criterion_mean_ms, Baseline, Measurements},
+    cache::Cache,
+};
+
+type Fixture = fn() -> ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>;
+
+/// Fixtures by name, the first one is the only one of the quick runs.
+const FIXTURES: [(&str, Fixture); 3] = [
+    ("tiny", generate_tiny_proof_tuple),
+    ("semaphore", generate_semaphore_proof_tuple),
+    ("recursive", generate_proof_tuple),
+];
+
+/// Criterion benchmarks of each fixture.
+const BENCHES: [&str; 4] = ["synthesis", "keygen_pk", "create_proof", "verify"];
+
+/// Verifier circuit of a fixture at the smallest `k` it fits in, with its cached params and
+/// proving key and a proof to verify.
+struct Setup {
+    name: &'static str,
+    k: u32,
+    circuit: Verifier,
+    instances: Vec<Fr>,
+    param: ParamsKZG<Bn256>,
+    pk: ProvingKey<G1Affine>,
+    proof: Vec<u8>,
+}
+
+impl Setup {
+    fn new(name: &'static str, fixture: Fixture, cache: &Cache) -> Self {
+        let (circuit, instances) = mock_verifier(fixture());
+        let k = min_k(&circuit, &instances).unwrap();
+        let circuit = circuit.with_k(k);
+        let param = cache.srs(k).unwrap();
+        let pk = cache.pk(name, &param, &circuit).unwrap();
+        let proof = prove(&param, &pk, &circuit, &instances);
+        Self {
+            name,
+            k,
+            circuit,
+            instances,
+            param,
+            pk,
+            proof,
+        }
+    }
+
+    fn bench(&self, criterion: &mut Criterion) {
+        let mut group = criterion.benchmark_group(self.name);
+        // a sample of the recursive fixture takes minutes
+        group.sample_size(10);
+        let instances = vec![self.instances.clone()];
+        group.bench_function(BENCHES[0], |b| {
+            b.iter(|| MockProver::run(self.k, &self.circuit, instances.clone()).unwrap())
+        });
+        let vk = keygen_vk(&self.param, &self.circuit).unwrap();
+        group.bench_function(BENCHES[1], |b| {
+            b.iter(|| keygen_pk(&self.param, vk.clone(), &self.circuit).unwrap())
+        });
+        group.bench_function(BENCHES[2], |b| {
+            b.iter(|| prove(&self.param, &self.pk, &self.circuit, &self.instances))
+        });
+        group.bench_function(BENCHES[3], |b| {
+            b.iter(|| {
+                verify_proof_bytes(&self.param, self.pk.get_vk(), &self.proof, &self.instances)
+                    .unwrap()
+            })
+        });
+        group.finish();
+    }
+
+    /// Gas of verifying the proof with the EVM verifier, if `solc` is there to compile it.
+    fn evm_gas(&self) -> Option<u64> {
+        if Command::new("solc").arg("--version").output().is_err() {
+            println!(
+                "solc not found, skipping the gas measurement of {}",
+                self.name
+            );
+            return None;
+        }
+        let (verifier_code, vk_code) =
+            gen_evm_verifier(&self.param, self.pk.get_vk(), self.instances.len());
+        let mut evm = Evm::default();
+        let verifier_address = evm.create(verifier_code);
+        let vk_address = evm.create(vk_code);
+        let calldata = encode_calldata(Some(vk_address.into()), &self.proof, &self.instances);
+        let (gas, _) = evm.call(verifier_address, calldata);
+        Some(gas)
+    }
+}
+
+/// Proves with fixed blinding, so every proof of a fixture is the same.
+fn prove(
+    param: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: &Verifier,
+    instances: &[Fr],
+) -> Vec<u8> {
+    let rng = ChaCha20Rng::seed_from_u64(0);
+    create_proof_bytes(param, pk, circuit.clone(), instances, rng).unwrap()
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let bench_mode = args.iter().any(|arg| arg == "--bench");
+    let quick = !bench_mode || args.iter().any(|arg| arg == "--quick");
+    let fixtures = if quick { &FIXTURES[..1] } else { &FIXTURES[..] };
+
+    let cache = Cache::new().unwrap();
+    let mut criterion = Criterion::default().configure_from_args();
+    let mut gas = vec![];
+    for (name, fixture) in fixtures {
+        let setup = Setup::new(name, *fixture, &cache);
+        setup.bench(&mut criterion);
+        gas.push(setup.evm_gas());
+    }
+    criterion.final_summary();
+
+    // the test mode of criterion runs every benchmark once and doesn't record estimates
+    if !bench_mode {
+        return;
+    }
+    let mut baseline = Baseline::load().unwrap();
+    let record = env::var_os("BENCH_RECORD_BASELINE").is_some();
+    let mut deviations = vec![];
+    let mut missing = vec![];
+    for ((name, _), gas) in fixtures.iter().zip(gas) {
+        let mut measured = BENCHES
+            .iter()
+            .filter_map(|bench| Some((bench.to_string(), criterion_mean_ms(name, bench)?)))
+            .collect::<Measurements>();
+        if let Some(gas) = gas {
+            measured.insert("evm_gas".to_string(), gas as f64);
+        }
+        println!("{name}: {measured:?}");
+        if record {
+            baseline.fixtures.insert(name.to_string(), measured);
+        } else {
+            match baseline.deviations(name, &measured) {
+                Ok(off) => deviations.extend(off),
+                Err(metrics) => missing.extend(metrics),
+            }
+        }
+    }
+    if record {
+        baseline.save().unwrap();
+        return;
+    }
+    // an empty baseline would let every regression through, so it is an error too
+    assert!(
+        missing.is_empty(),
+        "no baseline for {missing:?} in benches/baseline.json, record it with \
+         BENCH_RECORD_BASELINE=1"
+    );
+    for deviation in deviations.iter() {
+        println!("{deviation}");
+    }
+    assert!(
+        deviations.is_empty(),
+        "{} measurements are off benches/baseline.json by more than {}%",
+        deviations.len(),
+        baseline.tolerance * 100.0
+    );
+}
@@ -0,0 +1,55 @@
+//! Rows of the verifier circuit for the tiny statement of `plonky2_verifier::fixtures` proven
+//! with `Bn254PoseidonGoldilocksConfig` and with plonky2's `PoseidonGoldilocksConfig`.
+//!
+//! There is no in-circuit Goldilocks Poseidon, so a `PoseidonGoldilocksConfig` proof is verified
+//! by wrapping it into a `Bn254PoseidonGoldilocksConfig` proof first, see `compat::v0`. Its cost
+//! is the cost of the wrapped proof, which checks a whole plonky2 verifier instead of the
+//! statement.
+//!
+//! ```text
+//! cargo bench --features fixtures --bench verifier_cost
+//! ```
+
+use plonky2::{field::goldilocks_field::GoldilocksField, plonk::config::PoseidonGoldilocksConfig};
+use semaphore_aggregation::{
+    compat::v0::wrap_proof,
+    plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+        fixtures::{generate_tiny_proof_tuple_with_config, mock_verifier},
+        verifier_api::{k_for_rows, used_rows},
+        verifier_circuit::{ProofTuple, Verifier},
+    },
+};
+
+/// Degree and rows of a verifier circuit.
+#[derive(Clone, Copy, Debug)]
+struct Cost {
+    k: u32,
+    rows: usize,
+}
+
+fn cost(proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>) -> Cost {
+    let (circuit, instances) = mock_verifier(proof);
+    let rows = used_rows(&circuit, &instances).unwrap();
+    let k = k_for_rows::<Verifier>(rows).unwrap();
+    Cost { k, rows }
+}
+
+fn main() {
+    let bn254 = cost(generate_tiny_proof_tuple_with_config::<
+        Bn254PoseidonGoldilocksConfig,
+    >());
+    let wrapped_proof = wrap_proof(generate_tiny_proof_tuple_with_config::<
+        PoseidonGoldilocksConfig,
+    >())
+    .unwrap();
+    let poseidon = cost(wrapped_proof);
+
+    println!("{:<24}{:>4}{:>12}", "config", "k", "rows");
+    for (name, cost) in [
+        ("Bn254PoseidonGoldilocks", bn254),
+        ("PoseidonGoldilocks", poseidon),
+    ] {
+        println!("{name:<24}{:>4}{:>12}", cost.k, cost.rows);
+    }
+}
@@ -0,0 +1,38 @@
+use std::{env, fs, path::Path};
+
+/// Exposes the locked plonky2 version as `PLONKY2_VERSION`. Gate ids, the challenger and the
+/// `CommonCircuitData` layout all change between plonky2 releases, so errors about proofs that
+/// don't match the verifier report the version it was built against.
+///
+/// Only the lock file of this crate's own workspace is read. Built as a dependency, the
+/// version is resolved by the dependent's lock file, which a build script can't locate, and
+/// `PLONKY2_VERSION` is left unset rather than guessed.
+fn main() {
+    let lock_file = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_file.display());
+
+    if let Some(version) = fs::read_to_string(&lock_file)
+        .ok()
+        .and_then(|lock| plonky2_version(&lock))
+    {
+        println!("cargo:rustc-env=PLONKY2_VERSION={version}");
+    }
+}
+
+/// Reads `<version> (<source>)` of the `plonky2` package from the lock file.
+fn plonky2_version(lock: &str) -> Option<String> {
+    let package = lock
+        .split("[[package]]")
+        .find(|package| package.lines().any(|line| line == "name = \"plonky2\""))?;
+    let field = |key: &str| {
+        package.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix(" = ")?;
+            Some(value.trim_matches('"').to_string())
+        })
+    };
+    let version = field("version")?;
+    Some(match field("source") {
+        Some(source) => format!("{version} ({source})"),
+        None => version,
+    })
+}
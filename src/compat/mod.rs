@@ -0,0 +1,3 @@
+//! Adapters keeping older verifier APIs working on top of `plonky2_verifier`.
+
+pub mod v0;
@@ -0,0 +1,108 @@
+//! The `merkle_stark_inside_snark::snark::verifier_api` entry points, implemented with
+//! `plonky2_verifier`.
+//!
+//! The old API took proofs made with plonky2's `PoseidonGoldilocksConfig`, while the halo2
+//! verifier only checks proofs whose transcript uses `Bn254PoseidonHash`. Every entry point
+//! here first wraps the proof into `Bn254PoseidonGoldilocksConfig` with one plonky2 recursion
+//! layer, then hands the wrapped proof to `plonky2_verifier::verifier_api`.
+//!
+//! # Migrating
+//!
+//! Wrap the proof once with `WrapperCircuit::new(standard_stark_verifier_config(), ..)` and pass
+//! the wrapped proof tuple to `verifier_api::verify_inside_snark` (or `verify_inside_snark_mock`).
+//! The wrapped proof keeps the public inputs of the original one. Holding on to the
+//! `WrapperCircuit` also avoids rebuilding it for every proof, which this module has to do.
+
+use plonky2::{
+    field::goldilocks_field::GoldilocksField,
+    plonk::{circuit_data::VerifierCircuitData, config::PoseidonGoldilocksConfig},
+};
+
+use crate::{
+    plonky2_semaphore::wrapper::WrapperCircuit,
+    plonky2_verifier::{
+        bn245_poseidon::plonky2_config::{
+            standard_stark_verifier_config, Bn254PoseidonGoldilocksConfig,
+        },
+        verifier_api,
+        verifier_circuit::{ProofTuple, SUPPORTED_EXTENSION_DEGREE},
+    },
+};
+
+/// Proof tuple accepted by the old API.
+pub type PoseidonProofTuple =
+    ProofTuple<GoldilocksField, PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>;
+
+/// Runs the mock prover on the halo2 verifier circuit for `proof`.
+#[deprecated(note = "wrap the proof into `Bn254PoseidonGoldilocksConfig` and call \
+            `plonky2_verifier::verifier_api::verify_inside_snark_mock`, see `compat::v0`")]
+pub fn verify_inside_snark_mock(degree: u32, proof: PoseidonProofTuple) {
+    verifier_api::verify_inside_snark_mock(degree, wrap_proof(proof).unwrap());
+}
+
+/// Proves the halo2 verifier circuit for `proof` and runs the generated EVM verifier on it.
+#[deprecated(note = "wrap the proof into `Bn254PoseidonGoldilocksConfig` and call \
+            `plonky2_verifier::verifier_api::verify_inside_snark`, see `compat::v0`")]
+pub fn verify_inside_snark(degree: u32, proof: PoseidonProofTuple) {
+    verifier_api::verify_inside_snark(degree, wrap_proof(proof).unwrap()).unwrap();
+}
+
+/// Re-proves `proof` with a plonky2 circuit verifying it, committed with
+/// `Bn254PoseidonGoldilocksConfig`. Fails if `proof` doesn't verify.
+pub fn wrap_proof(
+    proof: PoseidonProofTuple,
+) -> anyhow::Result<
+    ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+> {
+    let (proof_with_public_inputs, verifier_only, common) = proof;
+    let verifier_data = VerifierCircuitData {
+        verifier_only,
+        common,
+    };
+    let wrapper_circuit = WrapperCircuit::new(standard_stark_verifier_config(), &verifier_data);
+    let wrapped_proof = wrapper_circuit.prove(&proof_with_public_inputs)?;
+    Ok((
+        wrapped_proof,
+        wrapper_circuit.data.verifier_only,
+        wrapper_circuit.data.common,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{field::types::Sample, plonk::proof::ProofWithPublicInputs};
+    use rand::rngs::OsRng;
+
+    use crate::plonky2_semaphore::{access_set::AccessSet, identity::Identity, signal::F};
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    #[allow(deprecated)]
+    fn test_v0_verify_inside_snark_mock_with_semaphore_proof() {
+        let identities: Vec<Identity> = (0..16).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
+
+        let i = 5;
+        let topic = F::rand_array();
+        let (signal, verifier_data) = access_set.make_signal(&identities[i], topic).unwrap();
+        let public_inputs = access_set
+            .0
+            .cap
+            .0
+            .iter()
+            .flat_map(|h| h.elements)
+            .chain(signal.nullifier.into_iter().flatten())
+            .chain(signal.topics.into_iter().flatten())
+            .collect();
+        let proof = ProofWithPublicInputs {
+            proof: signal.proof,
+            public_inputs,
+        };
+
+        // the same call an integration against the old API makes
+        super::verify_inside_snark_mock(
+            20,
+            (proof, verifier_data.verifier_only, verifier_data.common),
+        );
+    }
+}
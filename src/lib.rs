@@ -1,2 +1,3 @@
+pub mod compat;
 pub mod plonky2_semaphore;
 pub mod plonky2_verifier;
@@ -12,18 +12,35 @@ use plonky2::iop::target::Target;
 use plonky2::iop::witness::{PartialWitness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use plonky2::plonk::circuit_data::{CircuitConfig, VerifierCircuitData};
-use plonky2::plonk::config::Hasher;
 use plonky2::plonk::proof::ProofWithPublicInputs;
 
 use crate::plonky2_verifier::bn245_poseidon::plonky2_config::standard_stark_verifier_config;
+use crate::plonky2_verifier::types::{HashValues, MerkleCapValues};
 use crate::plonky2_verifier::verifier_api::verify_inside_snark;
+use halo2_proofs::halo2curves::ff::PrimeField;
 
+use super::identity::Identity;
 use super::report_elapsed;
 use super::signal::{Digest, Signal, C, F};
 use super::wrapper::WrapperCircuit;
 
 pub struct AccessSet(pub MerkleTree<F, PoseidonHash>);
 
+/// Cap of the access set tree, for checking membership inside the halo2 verifier.
+impl<N: PrimeField> From<&AccessSet> for MerkleCapValues<N> {
+    fn from(access_set: &AccessSet) -> Self {
+        MerkleCapValues(
+            access_set
+                .0
+                .cap
+                .0
+                .iter()
+                .map(|h| HashValues::from(*h))
+                .collect(),
+        )
+    }
+}
+
 impl AccessSet {
     pub fn verify_signal(
         &self,
@@ -54,17 +71,31 @@ impl AccessSet {
                 wrapper_circuit.data.verifier_only.clone(),
                 wrapper_circuit.data.common.clone(),
             ),
-        );
+        )?;
         Ok(())
     }
 
+    /// Proves that `identity` is a member of the access set and signals on `topic`. Identities
+    /// that are not members fail with [`NotAMember`](super::identity::NotAMember) before the
+    /// circuit is built.
     pub fn make_signal(
         &self,
-        private_key: Digest,
+        identity: &Identity,
+        topic: Digest,
+    ) -> Result<(Signal, VerifierCircuitData<F, C, 2>)> {
+        let public_key_index = self.index_of(identity)?;
+        self.make_signal_at(identity, topic, public_key_index)
+    }
+
+    /// `make_signal` without the membership check, so the circuit is what rejects a wrong
+    /// `public_key_index`.
+    pub(crate) fn make_signal_at(
+        &self,
+        identity: &Identity,
         topic: Digest,
         public_key_index: usize,
     ) -> Result<(Signal, VerifierCircuitData<F, C, 2>)> {
-        let nullifier = PoseidonHash::hash_no_pad(&[private_key, topic].concat()).elements;
+        let nullifier = identity.nullifier(topic);
         let config = CircuitConfig {
             zero_knowledge: true,
             num_wires: 135,
@@ -86,7 +117,7 @@ impl AccessSet {
         let mut pw = PartialWitness::new();
 
         let targets = self.semaphore_circuit(&mut builder);
-        self.fill_semaphore_targets(&mut pw, private_key, topic, public_key_index, targets);
+        self.fill_semaphore_targets(&mut pw, identity, topic, public_key_index, targets);
 
         let data = builder.build();
         println!("{}", format!("Generating 1 Semaphore proof").white().bold());
@@ -103,11 +134,8 @@ impl AccessSet {
         ))
     }
 
-    pub fn test_membership_proof(
-        &self,
-        private_key: Digest,
-        public_key_index: usize,
-    ) -> Result<()> {
+    pub fn test_membership_proof(&self, identity: &Identity) -> Result<()> {
+        let public_key_index = self.index_of(identity)?;
         let config = CircuitConfig {
             zero_knowledge: true,
             num_wires: 135,
@@ -150,7 +178,7 @@ impl AccessSet {
         );
 
         pw.set_hash_target(merkle_root, self.0.cap.0[0]);
-        pw.set_target_arr(private_key_target, private_key);
+        pw.set_target_arr(private_key_target, identity.secret());
         pw.set_target(
             public_key_index_target,
             F::from_canonical_usize(public_key_index),
@@ -181,38 +209,84 @@ impl AccessSet {
 mod tests {
     use anyhow::Result;
     use colored::Colorize;
-    use plonky2::field::types::{Field, Sample};
+    use plonky2::field::types::Sample;
     use plonky2::hash::merkle_tree::MerkleTree;
-    use plonky2::hash::poseidon::PoseidonHash;
-    use plonky2::plonk::config::Hasher;
+    use rand::rngs::OsRng;
 
     use crate::plonky2_semaphore::access_set::AccessSet;
-    use crate::plonky2_semaphore::signal::{Digest, F};
+    use crate::plonky2_semaphore::identity::{Identity, NotAMember};
+    use crate::plonky2_semaphore::signal::F;
+    use crate::plonky2_verifier::types::MerkleCapValues;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    #[test]
+    fn test_access_set_to_merkle_cap_values() {
+        let public_keys: Vec<Vec<F>> = (0..8).map(|_| F::rand_vec(4)).collect();
+        for cap_height in 0..2 {
+            let access_set = AccessSet(MerkleTree::new(public_keys.clone(), cap_height));
+            let cap_values = MerkleCapValues::<Fr>::from(&access_set);
+            assert_eq!(cap_values.0.len(), 1 << cap_height);
+            for (value, hash) in cap_values.0.iter().zip(access_set.0.cap.0.iter()) {
+                assert_eq!(value.elements, hash.elements);
+            }
+        }
+    }
 
     #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
     fn test_semaphore() -> Result<()> {
         for pow in 20..26 {
             let n = 1 << pow;
-            let private_keys: Vec<Digest> = (0..n).map(|_| F::rand_array()).collect();
-            let public_keys: Vec<Vec<F>> = private_keys
-                .iter()
-                .map(|&sk| {
-                    PoseidonHash::hash_no_pad(&[sk, [F::ZERO; 4]].concat())
-                        .elements
-                        .to_vec()
-                })
-                .collect();
-            let access_set = AccessSet(MerkleTree::new(public_keys, 0));
-
-            let i = 12;
+            let identities: Vec<Identity> =
+                (0..n).map(|_| Identity::generate(&mut OsRng)).collect();
+            let access_set = AccessSet::from_identities(&identities, 0);
+
             println!(
                 "{}",
                 format!("Testing membership proof in a group size 2^{pow}")
                     .white()
                     .bold()
             );
-            access_set.test_membership_proof(private_keys[i], i)?;
+            access_set.test_membership_proof(&identities[12])?;
         }
         Ok(())
     }
+
+    #[test]
+    fn test_signal_from_generated_identity_verifies() -> Result<()> {
+        let identities: Vec<Identity> = (0..16).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
+        let topic = F::rand_array();
+        let (signal, vd) = access_set.make_signal(&identities[5], topic)?;
+        assert_eq!(signal.nullifier, vec![identities[5].nullifier(topic)]);
+        access_set.verify_signal(signal, &vd)
+    }
+
+    #[test]
+    fn test_signal_from_non_member_fails() {
+        let identities: Vec<Identity> = (0..16).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
+        let outsider = Identity::generate(&mut OsRng);
+        let topic = F::rand_array();
+
+        // fast path: rejected before the circuit is built
+        let err = access_set.make_signal(&outsider, topic).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<NotAMember>(),
+            Some(&NotAMember {
+                public_key: outsider.public_key()
+            })
+        );
+    }
+
+    /// The Merkle root computed from the outsider's key conflicts with the root the witness
+    /// sets, at any leaf. plonky2's witness generation panics on the conflict.
+    #[test]
+    #[should_panic(expected = "was set twice with different values")]
+    fn test_circuit_rejects_non_member() {
+        let identities: Vec<Identity> = (0..16).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
+        let outsider = Identity::generate(&mut OsRng);
+        let _ = access_set.make_signal_at(&outsider, F::rand_array(), 5);
+    }
 }
@@ -7,6 +7,7 @@ use plonky2::iop::witness::{PartialWitness, WitnessWrite};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 
 use super::access_set::AccessSet;
+use super::identity::Identity;
 use super::signal::{Digest, F};
 
 pub struct SemaphoreTargets {
@@ -67,7 +68,7 @@ impl AccessSet {
     pub fn fill_semaphore_targets(
         &self,
         pw: &mut PartialWitness<F>,
-        private_key: Digest,
+        identity: &Identity,
         topic: Digest,
         public_key_index: usize,
         targets: SemaphoreTargets,
@@ -81,7 +82,7 @@ impl AccessSet {
         } = targets;
 
         pw.set_hash_target(merkle_root, self.0.cap.0[0]);
-        pw.set_target_arr(private_key_target, private_key);
+        pw.set_target_arr(private_key_target, identity.secret());
         pw.set_target_arr(topic_target, topic);
         pw.set_target(
             public_key_index_target,
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use plonky2::field::types::{Field, Sample};
+use plonky2::hash::merkle_tree::MerkleTree;
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::plonk::config::Hasher;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::access_set::AccessSet;
+use super::signal::{Digest, F};
+
+/// A Semaphore member. The secret never leaves this type; the access set only stores the
+/// public key derived from it.
+#[derive(Clone)]
+pub struct Identity {
+    secret: Digest,
+}
+
+/// The part of an [`Identity`] that is published in the access set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicIdentity {
+    pub public_key: Digest,
+}
+
+impl Identity {
+    pub fn generate<R: RngCore>(rng: &mut R) -> Self {
+        Self::from_secret([
+            F::sample(rng),
+            F::sample(rng),
+            F::sample(rng),
+            F::sample(rng),
+        ])
+    }
+
+    pub fn from_secret(secret: Digest) -> Self {
+        Self { secret }
+    }
+
+    pub(crate) fn secret(&self) -> Digest {
+        self.secret
+    }
+
+    /// The access set leaf. `semaphore_circuit` hashes `[secret, 0, 0, 0, 0]` as the leaf data of
+    /// the Merkle proof, so the public key is that hash.
+    pub fn public_key(&self) -> Digest {
+        PoseidonHash::hash_no_pad(&[self.secret, [F::ZERO; 4]].concat()).elements
+    }
+
+    pub fn public(&self) -> PublicIdentity {
+        PublicIdentity {
+            public_key: self.public_key(),
+        }
+    }
+
+    /// The nullifier `semaphore_circuit` constrains for `topic`.
+    pub fn nullifier(&self, topic: Digest) -> Digest {
+        PoseidonHash::hash_no_pad(&[self.secret, topic].concat()).elements
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Identity")
+            .field("public_key", &self.public_key())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returned by `AccessSet::make_signal` when the identity's public key is not a leaf of the
+/// access set, before any proving work is done.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotAMember {
+    pub public_key: Digest,
+}
+
+impl fmt::Display for NotAMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "public key {:?} is not a member of the access set",
+            self.public_key
+        )
+    }
+}
+
+impl std::error::Error for NotAMember {}
+
+impl AccessSet {
+    pub fn from_public_identities(identities: &[PublicIdentity], cap_height: usize) -> Self {
+        let leaves = identities
+            .iter()
+            .map(|identity| identity.public_key.to_vec())
+            .collect();
+        AccessSet(MerkleTree::new(leaves, cap_height))
+    }
+
+    pub fn from_identities(identities: &[Identity], cap_height: usize) -> Self {
+        let public_identities: Vec<_> = identities.iter().map(Identity::public).collect();
+        Self::from_public_identities(&public_identities, cap_height)
+    }
+
+    /// Index of the leaf holding `identity`'s public key.
+    pub fn index_of(&self, identity: &Identity) -> Result<usize, NotAMember> {
+        let public_key = identity.public_key();
+        self.0
+            .leaves
+            .iter()
+            .position(|leaf| leaf.as_slice() == public_key)
+            .ok_or(NotAMember { public_key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::{Identity, NotAMember, PublicIdentity};
+    use crate::plonky2_semaphore::access_set::AccessSet;
+
+    #[test]
+    fn test_public_identity_round_trip() {
+        let identity = Identity::generate(&mut OsRng);
+        let public = identity.public();
+        let json = serde_json::to_string(&public).unwrap();
+        let decoded: PublicIdentity = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, public);
+        assert_eq!(decoded.public_key, identity.public_key());
+    }
+
+    #[test]
+    fn test_index_of_finds_members_only() {
+        let identities: Vec<_> = (0..8).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
+        for (i, identity) in identities.iter().enumerate() {
+            assert_eq!(access_set.index_of(identity), Ok(i));
+        }
+        let outsider = Identity::generate(&mut OsRng);
+        assert_eq!(
+            access_set.index_of(&outsider),
+            Err(NotAMember {
+                public_key: outsider.public_key()
+            })
+        );
+    }
+}
@@ -4,6 +4,7 @@ use colored::Colorize;
 
 pub mod access_set;
 pub mod circuit;
+pub mod identity;
 pub mod recursion;
 pub mod signal;
 pub mod wrapper;
@@ -262,18 +262,13 @@ mod tests {
     use anyhow::Result;
     use colored::Colorize;
     use num_traits::pow;
-    use plonky2::{
-        field::types::{Field, Sample},
-        hash::{merkle_tree::MerkleTree, poseidon::PoseidonHash},
-        plonk::{config::Hasher, proof::ProofWithPublicInputs},
-    };
+    use plonky2::{field::types::Sample, plonk::proof::ProofWithPublicInputs};
+    use rand::rngs::OsRng;
     use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 
     use crate::{
         plonky2_semaphore::{
-            access_set::AccessSet,
-            recursion::report_elapsed,
-            signal::{Digest, F},
+            access_set::AccessSet, identity::Identity, recursion::report_elapsed, signal::F,
             wrapper::WrapperCircuit,
         },
         plonky2_verifier::{
@@ -285,7 +280,7 @@ mod tests {
     fn semaphore_aggregation(
         num_proofs: usize,
         access_set: &AccessSet,
-        private_keys: &Vec<Digest>,
+        identities: &[Identity],
     ) -> Result<()> {
         // Generate 64 Semaphore proofs
         let aggregation_targets = Arc::new(Mutex::new(vec![]));
@@ -299,7 +294,7 @@ mod tests {
         );
         (0..num_proofs).into_par_iter().for_each(|i| {
             let topic = F::rand_array();
-            let (signal, vd) = access_set.make_signal(private_keys[i], topic, i).unwrap();
+            let (signal, vd) = access_set.make_signal(&identities[i], topic).unwrap();
             aggregation_targets.lock().unwrap().push(signal);
             let mut verifier_circuit_data = verifier_circuit_data.lock().unwrap();
             if verifier_circuit_data.is_none() {
@@ -340,26 +335,19 @@ mod tests {
                 wrapper_circuit.data.verifier_only.clone(),
                 wrapper_circuit.data.common.clone(),
             ),
-        );
+        )?;
 
         Ok(())
     }
 
     #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
     fn test_semaphore_aggregation() -> Result<()> {
         let n = 1 << 20;
-        let private_keys: Vec<Digest> = (0..n).map(|_| F::rand_array()).collect();
-        let public_keys: Vec<Vec<F>> = private_keys
-            .iter()
-            .map(|&sk| {
-                PoseidonHash::hash_no_pad(&[sk, [F::ZERO; 4]].concat())
-                    .elements
-                    .to_vec()
-            })
-            .collect();
-        let access_set = AccessSet(MerkleTree::new(public_keys, 0));
+        let identities: Vec<Identity> = (0..n).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
         for i in 1..8 {
-            semaphore_aggregation(pow(2, i), &access_set, &private_keys)?;
+            semaphore_aggregation(pow(2, i), &access_set, &identities)?;
         }
         Ok(())
     }
@@ -17,32 +17,24 @@ pub struct Signal {
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use plonky2::field::types::{Field, Sample};
-    use plonky2::hash::merkle_tree::MerkleTree;
-    use plonky2::hash::poseidon::PoseidonHash;
-    use plonky2::plonk::config::Hasher;
+    use plonky2::field::types::Sample;
+    use rand::rngs::OsRng;
 
     use crate::plonky2_semaphore::access_set::AccessSet;
-    use crate::plonky2_semaphore::signal::{Digest, F};
+    use crate::plonky2_semaphore::identity::Identity;
+    use crate::plonky2_semaphore::signal::F;
 
     #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
     fn test_semaphore() -> Result<()> {
         let n = 1 << 20;
-        let private_keys: Vec<Digest> = (0..n).map(|_| F::rand_array()).collect();
-        let public_keys: Vec<Vec<F>> = private_keys
-            .iter()
-            .map(|&sk| {
-                PoseidonHash::hash_no_pad(&[sk, [F::ZERO; 4]].concat())
-                    .elements
-                    .to_vec()
-            })
-            .collect();
-        let access_set = AccessSet(MerkleTree::new(public_keys, 0));
+        let identities: Vec<Identity> = (0..n).map(|_| Identity::generate(&mut OsRng)).collect();
+        let access_set = AccessSet::from_identities(&identities, 0);
 
         let i = 12;
         let topic = F::rand_array();
 
-        let (signal, vd) = access_set.make_signal(private_keys[i], topic, i)?;
+        let (signal, vd) = access_set.make_signal(&identities[i], topic)?;
         access_set.verify_signal(signal, &vd)
     }
 }
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use halo2_proofs::circuit::AssignedCell;
 use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
 use halo2wrong_maingate::AssignedValue;
@@ -13,16 +15,52 @@ use super::{
 };
 use crate::plonky2_verifier::context::RegionCtx;
 use crate::plonky2_verifier::types::common_data::FriConfig;
+use crate::plonky2_verifier::types::proof::FriQueryRoundValues;
 use crate::plonky2_verifier::types::{
     assigned::{
         AssignedExtensionFieldValue, AssignedFriChallenges, AssignedFriInitialTreeProofValues,
         AssignedFriOpenings, AssignedFriProofValues, AssignedFriQueryRoundValues,
-        AssignedMerkleCapValues,
+        AssignedIndexBits, AssignedMerkleCapValues,
     },
     common_data::FriParams,
-    fri::{FriBatchInfo, FriInstanceInfo},
+    fri::FriInstanceInfo,
 };
 
+/// Values of the initial polynomials check which don't depend on the query round, assigned
+/// once per proof.
+pub struct InitialPolynomialsBatches<F: PrimeField> {
+    /// Opening point of each batch.
+    points: Vec<AssignedExtensionFieldValue<F, 2>>,
+    /// Openings of each batch reduced with `fri_alpha`, see `WitnessTrace::reduced_openings`.
+    reduced_openings: Vec<AssignedExtensionFieldValue<F, 2>>,
+    /// `fri_alpha^n` for the number `n` of polynomials of each batch.
+    alpha_shifts: Vec<AssignedExtensionFieldValue<F, 2>>,
+    /// `(oracle index, polynomial index, salted)` of the evaluations of each batch.
+    eval_indices: Vec<Vec<(usize, usize, bool)>>,
+}
+
+/// Query round proofs of a FRI proof, either assigned up front with the rest of the proof, or
+/// assigned by `verify_fri_proof_with_rounds` right before the round that checks them, so that
+/// only one round is held at a time.
+#[derive(Clone, Copy)]
+pub enum QueryRoundProofs<'a, F: PrimeField, const D: usize> {
+    Assigned(&'a [AssignedFriQueryRoundValues<F, D>]),
+    Unassigned(&'a [FriQueryRoundValues<F, D>]),
+}
+
+impl<'a, F: PrimeField, const D: usize> QueryRoundProofs<'a, F, D> {
+    pub fn len(&self) -> usize {
+        match self {
+            QueryRoundProofs::Assigned(rounds) => rounds.len(),
+            QueryRoundProofs::Unassigned(rounds) => rounds.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 pub struct FriVerifierChip<F: PrimeField> {
     goldilocks_chip_config: GoldilocksChipConfig<F>,
     /// Representative `g` of the coset used in FRI, so that LDEs in FRI are done over `gH`.
@@ -69,71 +107,99 @@ impl<F: PrimeField> FriVerifierChip<F> {
             .collect()
     }
 
-    fn calculate_cap_index(
-        &self,
-        ctx: &mut RegionCtx<'_, F>,
-        x_index_bits: &[AssignedValue<F>],
-    ) -> Result<AssignedValue<F>, Error> {
-        let goldilocks_chip = self.goldilocks_chip();
-        goldilocks_chip.from_bits(
-            ctx,
-            &x_index_bits[x_index_bits.len() - self.fri_params.config.cap_height..].to_vec(),
-        )
-    }
-
     // evaluation proof for initial polynomials at `x`
     fn verify_initial_merkle_proof(
         &self,
         ctx: &mut RegionCtx<'_, F>,
-        x_index_bits: &[AssignedValue<F>],
-        cap_index: &AssignedValue<F>,
+        x_index_bits: &AssignedIndexBits<F>,
         initial_merkle_caps: &[AssignedMerkleCapValues<F>],
         initial_trees_proof: &AssignedFriInitialTreeProofValues<F>,
     ) -> Result<(), Error> {
         let merkle_proof_chip = MerkleProofChip::new(&self.goldilocks_chip_config);
-        for (_, ((evals, merkle_proof), cap)) in initial_trees_proof
+        for ((evals, merkle_proof), cap) in initial_trees_proof
             .evals_proofs
             .iter()
             .zip(initial_merkle_caps)
-            .enumerate()
         {
-            merkle_proof_chip.verify_merkle_proof_to_cap_with_cap_index(
+            merkle_proof_chip.verify_merkle_proof_to_cap(
                 ctx,
                 evals,
                 x_index_bits,
-                &cap_index,
-                &cap,
+                cap,
                 merkle_proof,
             )?;
         }
         Ok(())
     }
 
-    fn batch_initial_polynomials(
+    fn precompute_initial_polynomials_batches(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         fri_instance_info: &FriInstanceInfo<F, 2>,
         fri_alpha: &AssignedExtensionFieldValue<F, 2>,
+        fri_openings: &AssignedFriOpenings<F, 2>,
+    ) -> Result<InitialPolynomialsBatches<F>, Error> {
+        let goldilocks_extension_chip = self.goldilocks_extension_chip();
+        let reduced_openings = self.compute_reduced_openings(ctx, fri_alpha, fri_openings)?;
+        debug_assert_eq!(reduced_openings.len(), fri_instance_info.batches.len());
+        let alpha_shifts = fri_instance_info
+            .batches
+            .iter()
+            .map(|batch| goldilocks_extension_chip.exp(ctx, fri_alpha, batch.polynomials.len()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let eval_indices = fri_instance_info
+            .batches
+            .iter()
+            .map(|batch| {
+                batch
+                    .polynomials
+                    .iter()
+                    .map(|p| {
+                        let oracle = &fri_instance_info.oracles[p.oracle_index];
+                        let salted = oracle.salted(self.fri_params.hiding);
+                        (p.oracle_index, p.polynomial_index, salted)
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(InitialPolynomialsBatches {
+            points: fri_instance_info
+                .batches
+                .iter()
+                .map(|batch| batch.point.clone())
+                .collect(),
+            reduced_openings,
+            alpha_shifts,
+            eval_indices,
+        })
+    }
+
+    /// Per query round, this costs one reduction of the initial evaluations by `fri_alpha` and a
+    /// constant number of operations per batch, the round-invariant values are taken from
+    /// `batches`.
+    fn batch_initial_polynomials(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        batches: &InitialPolynomialsBatches<F>,
+        fri_alpha: &AssignedExtensionFieldValue<F, 2>,
         // `x` is the initially selected point in FRI
         x: &AssignedValue<F>,
         initial_trees_proof: &AssignedFriInitialTreeProofValues<F>,
-        reduced_openings: &[AssignedExtensionFieldValue<F, 2>],
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         let goldilocks_extension_chip = self.goldilocks_extension_chip();
         let x = goldilocks_extension_chip.convert_to_extension(ctx, &x)?;
-        let mut sum = goldilocks_extension_chip.zero_extension(ctx)?;
-        for (batch, reduced_openings) in fri_instance_info
-            .batches
+        let mut sum = None;
+        for (((point, reduced_openings), alpha_shift), eval_indices) in batches
+            .points
             .iter()
-            .zip(reduced_openings.iter())
+            .zip(batches.reduced_openings.iter())
+            .zip(batches.alpha_shifts.iter())
+            .zip(batches.eval_indices.iter())
         {
-            let FriBatchInfo { point, polynomials } = batch;
-            let evals = polynomials
+            let evals = eval_indices
                 .iter()
-                .map(|p| {
-                    let poly_blinding = fri_instance_info.oracles[p.oracle_index].blinding;
-                    let salted = self.fri_params.hiding && poly_blinding;
-                    initial_trees_proof.unsalted_eval(p.oracle_index, p.polynomial_index, salted)
+                .map(|&(oracle_index, polynomial_index, salted)| {
+                    initial_trees_proof.unsalted_eval(oracle_index, polynomial_index, salted)
                 })
                 .collect_vec();
             let reduced_evals = goldilocks_extension_chip
@@ -141,11 +207,19 @@ impl<F: PrimeField> FriVerifierChip<F> {
             let numerator =
                 goldilocks_extension_chip.sub_extension(ctx, &reduced_evals, reduced_openings)?;
             let denominator = goldilocks_extension_chip.sub_extension(ctx, &x, point)?;
-            sum = goldilocks_extension_chip.shift(ctx, fri_alpha, evals.len(), &sum)?;
-            sum =
-                goldilocks_extension_chip.div_add_extension(ctx, &numerator, &denominator, &sum)?;
+            // the first batch has nothing to shift
+            let shifted = match sum {
+                Some(sum) => goldilocks_extension_chip.mul_extension(ctx, alpha_shift, &sum)?,
+                None => goldilocks_extension_chip.zero_extension(ctx)?,
+            };
+            sum = Some(goldilocks_extension_chip.div_add_extension(
+                ctx,
+                &numerator,
+                &denominator,
+                &shifted,
+            )?);
         }
-        Ok(sum)
+        sum.ok_or(Error::Synthesis)
     }
 
     /// obtain subgroup element at index `x_index_bits` from the domain
@@ -174,6 +248,11 @@ impl<F: PrimeField> FriVerifierChip<F> {
         arity_bits: usize,
         beta: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        // a step of arity 1 doesn't fold: the coset is `x` alone and the interpolant of its
+        // evaluation, already checked against the previous one, is that evaluation
+        if arity_bits == 0 {
+            return Ok(evals[0].clone());
+        }
         let goldilocks_chip = self.goldilocks_chip();
         let goldilocks_extension_chip = self.goldilocks_extension_chip();
         // computes `P'(x^arity)` where `arity = 1 << arity_bits` from `P(x*g^i), (i = 0, ..., arity)` where
@@ -229,55 +308,48 @@ impl<F: PrimeField> FriVerifierChip<F> {
         &self,
         ctx: &mut RegionCtx<'_, F>,
         initial_merkle_caps: &[AssignedMerkleCapValues<F>],
-        fri_instance_info: &FriInstanceInfo<F, 2>,
+        batches: &InitialPolynomialsBatches<F>,
         fri_alpha: &AssignedExtensionFieldValue<F, 2>,
         fri_betas: &[AssignedExtensionFieldValue<F, 2>],
         fri_proof: &AssignedFriProofValues<F, 2>,
         x_index: &AssignedValue<F>,
         round_proof: &AssignedFriQueryRoundValues<F, 2>,
-        reduced_openings: &[AssignedExtensionFieldValue<F, 2>],
     ) -> Result<(), Error> {
         let goldilocks_chip = self.goldilocks_chip();
         let goldilocks_extension_chip = self.goldilocks_extension_chip();
         let lde_bits = self.fri_params.lde_bits();
 
         // `x_index` is the index of point selected from initial domain
-        let mut x_index_bits = goldilocks_chip
-            .to_bits(ctx, x_index, 64)?
-            .iter()
-            .take(lde_bits)
-            .cloned()
-            .collect_vec();
+        let mut x_index_bits =
+            AssignedIndexBits::decompose(&goldilocks_chip, ctx, x_index, lde_bits)?;
 
-        let cap_index = self.calculate_cap_index(ctx, &x_index_bits[..])?;
         // verify evaluation proofs for initial polynomials at `x_index` point
         self.verify_initial_merkle_proof(
             ctx,
             &x_index_bits,
-            &cap_index,
             initial_merkle_caps,
             &round_proof.initial_trees_proof,
         )?;
 
-        let x_from_subgroup =
-            self.x_from_subgroup(ctx, &x_index_bits.iter().rev().cloned().collect_vec())?;
+        let x_from_subgroup = self.x_from_subgroup(
+            ctx,
+            &x_index_bits.bits().iter().rev().cloned().collect_vec(),
+        )?;
         let mut x_from_subgroup = goldilocks_chip.mul(ctx, &self.offset, &x_from_subgroup)?;
 
         let mut prev_eval = self.batch_initial_polynomials(
             ctx,
-            fri_instance_info,
+            batches,
             fri_alpha,
             &x_from_subgroup,
             &round_proof.initial_trees_proof,
-            reduced_openings,
         )?;
 
         for (i, &arity_bits) in self.fri_params.reduction_arity_bits.iter().enumerate() {
             let evals = &round_proof.steps[i].evals;
 
             // Split x_index into the index of the coset x is in, and the index of x within that coset.
-            let coset_index_bits = x_index_bits[arity_bits..].to_vec();
-            let x_index_within_coset_bits = &x_index_bits[..arity_bits];
+            let (x_index_within_coset_bits, coset_index_bits) = x_index_bits.split_low(arity_bits);
             let x_index_within_coset =
                 goldilocks_chip.from_bits(ctx, &x_index_within_coset_bits.to_vec())?;
 
@@ -285,10 +357,13 @@ impl<F: PrimeField> FriVerifierChip<F> {
             for i in 0..2 {
                 let vector_chip = VectorChip::new(
                     &self.goldilocks_chip_config,
-                    evals.iter().map(|eval| eval.0[i].clone()).collect_vec(),
+                    evals
+                        .iter()
+                        .map(|eval| eval.limbs()[i].clone())
+                        .collect_vec(),
                 );
                 let next_eval_i = vector_chip.access(ctx, &x_index_within_coset)?;
-                goldilocks_chip.assert_equal(ctx, &prev_eval.0[i], &next_eval_i)?;
+                goldilocks_chip.check_equal(ctx, &prev_eval.limbs()[i], &next_eval_i)?;
             }
 
             prev_eval = self.next_eval(
@@ -301,11 +376,13 @@ impl<F: PrimeField> FriVerifierChip<F> {
             )?;
 
             let merkle_proof_chip = MerkleProofChip::new(&self.goldilocks_chip_config);
-            merkle_proof_chip.verify_merkle_proof_to_cap_with_cap_index(
+            merkle_proof_chip.verify_merkle_proof_to_cap(
                 ctx,
-                &evals.iter().flat_map(|eval| eval.0.clone()).collect_vec(),
+                &evals
+                    .iter()
+                    .flat_map(|eval| eval.limbs().clone())
+                    .collect_vec(),
                 &coset_index_bits,
-                &cap_index,
                 &fri_proof.commit_phase_merkle_cap_values[i],
                 &round_proof.steps[i].merkle_proof,
             )?;
@@ -317,15 +394,17 @@ impl<F: PrimeField> FriVerifierChip<F> {
         // Final check of FRI. After all the reductions, we check that the final polynomial is equal
         // to the one sent by the prover.
         let final_poly_coeffs = &fri_proof.final_poly.0;
-        let final_poly_eval = goldilocks_extension_chip.reduce_extension_field_terms_base(
+        let final_poly_eval = goldilocks_extension_chip.reduce_with_powers_base(
             ctx,
             &x_from_subgroup,
             final_poly_coeffs,
         )?;
-        goldilocks_extension_chip.assert_equal_extension(ctx, &prev_eval, &final_poly_eval)?;
+        goldilocks_extension_chip.check_equal_extension(ctx, &prev_eval, &final_poly_eval)?;
         Ok(())
     }
 
+    /// Verifies `fri_proof` with the query rounds assigned along with it by
+    /// `FriProofValues::assign`.
     pub fn verify_fri_proof(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -335,6 +414,53 @@ impl<F: PrimeField> FriVerifierChip<F> {
         fri_proof: &AssignedFriProofValues<F, 2>,
         fri_instance_info: &FriInstanceInfo<F, 2>,
     ) -> Result<(), Error> {
+        self.verify_fri_proof_with_rounds(
+            ctx,
+            initial_merkle_caps,
+            fri_challenges,
+            fri_openings,
+            fri_proof,
+            QueryRoundProofs::Assigned(&fri_proof.query_round_proofs),
+            fri_instance_info,
+        )
+    }
+
+    /// Like `verify_fri_proof`, with the query rounds taken from `query_round_proofs` instead of
+    /// `fri_proof.query_round_proofs`, e.g. for a `fri_proof` assigned by
+    /// `FriProofValues::assign_without_query_rounds`.
+    pub fn verify_fri_proof_with_rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        initial_merkle_caps: &[AssignedMerkleCapValues<F>],
+        fri_challenges: &AssignedFriChallenges<F, 2>,
+        fri_openings: &AssignedFriOpenings<F, 2>,
+        fri_proof: &AssignedFriProofValues<F, 2>,
+        query_round_proofs: QueryRoundProofs<'_, F, 2>,
+        fri_instance_info: &FriInstanceInfo<F, 2>,
+    ) -> Result<(), Error> {
+        let batches =
+            self.prepare_query_rounds(ctx, fri_challenges, fri_openings, fri_instance_info)?;
+        self.verify_query_rounds(
+            ctx,
+            initial_merkle_caps,
+            fri_challenges,
+            fri_proof,
+            query_round_proofs,
+            0..query_round_proofs.len(),
+            &batches,
+            fri_instance_info,
+        )
+    }
+
+    /// Part of `verify_fri_proof_with_rounds` shared by all query rounds: checks the proof of work and
+    /// assigns the values every round reads.
+    pub fn prepare_query_rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        fri_challenges: &AssignedFriChallenges<F, 2>,
+        fri_openings: &AssignedFriOpenings<F, 2>,
+        fri_instance_info: &FriInstanceInfo<F, 2>,
+    ) -> Result<InitialPolynomialsBatches<F>, Error> {
         // verify proof of work
         self.fri_verify_proof_of_work(
             ctx,
@@ -342,21 +468,60 @@ impl<F: PrimeField> FriVerifierChip<F> {
             &self.fri_params.config,
         )?;
 
-        // this value is the same across all queries
-        let reduced_openings =
-            self.compute_reduced_openings(ctx, &fri_challenges.fri_alpha, fri_openings)?;
-        for (i, round_proof) in fri_proof.query_round_proofs.iter().enumerate() {
+        // the reduced openings, alpha powers and evaluation indices are the same across all
+        // queries, since they only depend on `fri_alpha`, the openings and the common data
+        self.precompute_initial_polynomials_batches(
+            ctx,
+            fri_instance_info,
+            &fri_challenges.fri_alpha,
+            fri_openings,
+        )
+    }
+
+    /// Checks the query rounds `rounds` against the `batches` of `prepare_query_rounds`. The
+    /// batches may be assigned in another region, so the rounds can be split across regions.
+    /// Rounds share nothing keyed by their index: two rounds drawing the same index, which
+    /// plonky2 keeps as separate rounds in uncompressed proofs, are each checked in full.
+    pub fn verify_query_rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        initial_merkle_caps: &[AssignedMerkleCapValues<F>],
+        fri_challenges: &AssignedFriChallenges<F, 2>,
+        fri_proof: &AssignedFriProofValues<F, 2>,
+        query_round_proofs: QueryRoundProofs<'_, F, 2>,
+        rounds: Range<usize>,
+        batches: &InitialPolynomialsBatches<F>,
+        fri_instance_info: &FriInstanceInfo<F, 2>,
+    ) -> Result<(), Error> {
+        let mut rows_per_round = 0;
+        for i in rounds {
+            // rounds take as many rows as each other, a round that doesn't fit fails before it
+            // is assigned
+            ctx.ensure_capacity(rows_per_round)?;
+            let round_start = ctx.offset();
+            let assigned;
+            let round_proof = match query_round_proofs {
+                QueryRoundProofs::Assigned(proofs) => &proofs[i],
+                QueryRoundProofs::Unassigned(proofs) => {
+                    assigned =
+                        FriQueryRoundValues::assign(&self.goldilocks_chip_config, ctx, &proofs[i])?;
+                    &assigned
+                }
+            };
+            round_proof
+                .initial_trees_proof
+                .check_leaf_lens(&fri_instance_info.oracles, self.fri_params.hiding)?;
             self.check_consistency(
                 ctx,
                 initial_merkle_caps,
-                fri_instance_info,
+                batches,
                 &fri_challenges.fri_alpha,
                 &fri_challenges.fri_betas,
                 fri_proof,
                 &fri_challenges.fri_query_indices[i],
                 round_proof,
-                &reduced_openings,
             )?;
+            rows_per_round = ctx.offset() - round_start;
         }
         Ok(())
     }
@@ -370,8 +535,325 @@ impl<F: PrimeField> FriVerifierChip<F> {
         let goldilocks_chip = self.goldilocks_chip();
         let bits = goldilocks_chip.to_bits(ctx, fri_pow_response, 64)?;
         for b in bits.iter().rev().take(config.proof_of_work_bits as usize) {
-            goldilocks_chip.assert_zero(ctx, &b)?;
+            goldilocks_chip.check_zero(ctx, &b)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+        fixtures::{generate_tiny_proof_tuple, mock_verifier},
+        types::{
+            assigned::AssignedExtensionFieldValue,
+            common_data::CommonData,
+            fri::FriInstanceInfo,
+            proof::{FriQueryRoundValues, OpeningSetValues, ProofValues},
+        },
+        verifier_circuit::plonky2_query_indices,
+        witness_trace::WitnessTrace,
+    };
+
+    use super::FriVerifierChip;
+
+    #[derive(Clone, Default)]
+    struct TestCircuit {
+        openings: OpeningSetValues<Fr, 2>,
+        common_data: CommonData<Fr>,
+        num_query_rounds: usize,
+        trace: WitnessTrace,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "reduced openings",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let openings = OpeningSetValues::assign(&config, ctx, &self.openings)?;
+                    let fri_alpha = self
+                        .trace
+                        .challenges
+                        .fri_alpha
+                        .iter()
+                        .map(|e| {
+                            goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*e)))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let fri_alpha = AssignedExtensionFieldValue::from_vec(fri_alpha)?;
+                    let offset = goldilocks_chip
+                        .assign_constant(ctx, GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR)?;
+                    let fri_chip = FriVerifierChip::construct(
+                        &config,
+                        &offset,
+                        self.common_data.fri_params.clone(),
+                    );
+                    let fri_openings = openings.to_fri_openings(&self.common_data)?;
+
+                    // recomputing per round must give the native, query-independent values
+                    for _ in 0..self.num_query_rounds {
+                        let reduced_openings =
+                            fri_chip.compute_reduced_openings(ctx, &fri_alpha, &fri_openings)?;
+                        assert_eq!(reduced_openings.len(), self.trace.reduced_openings.len());
+                        for (assigned, expected) in reduced_openings
+                            .iter()
+                            .zip(self.trace.reduced_openings.iter())
+                        {
+                            for (assigned, expected) in assigned.limbs().iter().zip(expected.iter())
+                            {
+                                let expected = goldilocks_chip.assign_constant(ctx, *expected)?;
+                                goldilocks_chip.assert_equal(ctx, assigned, &expected)?;
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reduced_openings_match_native() {
+        let (verifier, _) = mock_verifier(generate_tiny_proof_tuple());
+        let trace = verifier.witness_trace().unwrap();
+
+        let circuit = TestCircuit {
+            openings: verifier.proof().openings.clone(),
+            common_data: verifier.common_data().clone(),
+            // a few rounds are enough to catch per-round divergence
+            num_query_rounds: 2,
+            trace,
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    fn assign_extension(
+        goldilocks_chip: &GoldilocksChip<Fr>,
+        ctx: &mut RegionCtx<'_, Fr>,
+        value: [GoldilocksField; 2],
+    ) -> Result<AssignedExtensionFieldValue<Fr, 2>, Error> {
+        let limbs = value
+            .iter()
+            .map(|e| goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*e))))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(AssignedExtensionFieldValue::from_vec(limbs)?)
+    }
+
+    /// Batches the initial polynomials of a few query rounds, checking that every round takes
+    /// the same number of rows once the round-invariant values are assigned.
+    #[derive(Clone, Default)]
+    struct BatchRowsCircuit {
+        proof: ProofValues<Fr, 2>,
+        common_data: CommonData<Fr>,
+        trace: WitnessTrace,
+        num_query_rounds: usize,
+    }
+
+    impl Circuit<Fr> for BatchRowsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "batch initial polynomials",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let challenges = &self.trace.challenges;
+                    let fri_alpha = assign_extension(&goldilocks_chip, ctx, challenges.fri_alpha)?;
+                    let zeta = assign_extension(&goldilocks_chip, ctx, challenges.plonk_zeta)?;
+                    // any point other than zeta gives the same layout
+                    let zeta_next = assign_extension(
+                        &goldilocks_chip,
+                        ctx,
+                        [challenges.plonk_zeta[0].double(), challenges.plonk_zeta[1]],
+                    )?;
+                    let openings = OpeningSetValues::assign(&config, ctx, &self.proof.openings)?;
+                    let offset = goldilocks_chip
+                        .assign_constant(ctx, GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR)?;
+                    let fri_chip = FriVerifierChip::construct(
+                        &config,
+                        &offset,
+                        self.common_data.fri_params.clone(),
+                    );
+
+                    let fri_instance_info =
+                        FriInstanceInfo::new(&zeta, &zeta_next, &self.common_data);
+                    let batches = fri_chip.precompute_initial_polynomials_batches(
+                        ctx,
+                        &fri_instance_info,
+                        &fri_alpha,
+                        &openings.to_fri_openings(&self.common_data)?,
+                    )?;
+                    let mut rows_per_round = vec![];
+                    for (round_proof, index) in self
+                        .proof
+                        .opening_proof
+                        .query_round_proofs
+                        .iter()
+                        .zip(challenges.fri_query_indices.iter())
+                        .take(self.num_query_rounds)
+                    {
+                        let round_proof = FriQueryRoundValues::assign(&config, ctx, round_proof)?;
+                        let x = goldilocks_chip
+                            .assign_value(ctx, Value::known(goldilocks_to_fe(*index)))?;
+                        let start = ctx.offset();
+                        fri_chip.batch_initial_polynomials(
+                            ctx,
+                            &batches,
+                            &fri_alpha,
+                            &x,
+                            &round_proof.initial_trees_proof,
+                        )?;
+                        rows_per_round.push(ctx.offset() - start);
+                    }
+                    assert_eq!(rows_per_round.len(), self.num_query_rounds);
+                    assert!(rows_per_round.iter().all(|rows| *rows == rows_per_round[0]));
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_batch_initial_polynomials_rows_per_round() {
+        let (verifier, _) = mock_verifier(generate_tiny_proof_tuple());
+        let trace = verifier.witness_trace().unwrap();
+
+        let circuit = BatchRowsCircuit {
+            proof: verifier.proof().clone(),
+            common_data: verifier.common_data().clone(),
+            trace,
+            num_query_rounds: 3,
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+    }
+
+    /// Checks the `lde_bits` low bits the FRI chip takes from each squeezed query challenge
+    /// against the query index plonky2's verifier derives, padded with high zeros to `lde_bits`.
+    #[derive(Clone, Default)]
+    struct QueryIndexBitsCircuit {
+        lde_bits: usize,
+        query_challenges: Vec<GoldilocksField>,
+        plonky2_indices: Vec<usize>,
+    }
+
+    impl Circuit<Fr> for QueryIndexBitsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "query index bits",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    assert_eq!(self.query_challenges.len(), self.plonky2_indices.len());
+                    for (challenge, x_index) in self
+                        .query_challenges
+                        .iter()
+                        .zip(self.plonky2_indices.iter())
+                    {
+                        let expected_bits = (0..self.lde_bits)
+                            .map(|i| GoldilocksField::from_canonical_usize((x_index >> i) & 1))
+                            .collect::<Vec<_>>();
+
+                        let assigned = goldilocks_chip
+                            .assign_value(ctx, Value::known(goldilocks_to_fe(*challenge)))?;
+                        let actual_bits = goldilocks_chip.to_bits(ctx, &assigned, 64)?;
+                        let actual_bits = &actual_bits[..self.lde_bits];
+                        assert_eq!(actual_bits.len(), expected_bits.len());
+                        for (actual, expected) in actual_bits.iter().zip(expected_bits.iter()) {
+                            let expected = goldilocks_chip.assign_constant(ctx, *expected)?;
+                            goldilocks_chip.assert_equal(ctx, actual, &expected)?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_query_index_bits_match_plonky2() {
+        let proof_tuple = generate_tiny_proof_tuple();
+        let plonky2_indices = plonky2_query_indices(&proof_tuple).unwrap();
+        let (verifier, _) = mock_verifier(proof_tuple);
+        let trace = verifier.witness_trace().unwrap();
+
+        let circuit = QueryIndexBitsCircuit {
+            lde_bits: verifier.common_data().fri_params.lde_bits(),
+            query_challenges: trace.challenges.fri_query_indices,
+            plonky2_indices,
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+}
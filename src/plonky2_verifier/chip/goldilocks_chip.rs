@@ -1,7 +1,7 @@
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Value},
     halo2curves::ff::PrimeField,
-    plonk::Error,
+    plonk::{ConstraintSystem, Error},
 };
 use halo2wrong_maingate::{fe_to_big, AssignedCondition, AssignedValue};
 
@@ -15,15 +15,47 @@ use crate::plonky2_verifier::context::RegionCtx;
 
 use super::native_chip::{
     all_chip::{AllChip, AllChipConfig},
-    arithmetic_chip::{ArithmeticChip, Term},
+    arithmetic_chip::{ArithmeticChip, RangeChecked, TableMode, TableModeConfig, Term},
     utils::goldilocks_to_fe,
 };
 
+/// Operand of `GoldilocksChip::assert_equal`. `CANONICAL` operands are known to hold canonical
+/// elements, so two of them are equal exactly when their cells are.
+pub trait EqualityOperand<F: PrimeField> {
+    const CANONICAL: bool;
+
+    fn cell(&self) -> &AssignedValue<F>;
+}
+
+/// A raw cell says nothing of where it was assigned, so it can hold a non-canonical
+/// representative such as `x + p`, e.g. an instance cell or a cell of another chip.
+impl<F: PrimeField> EqualityOperand<F> for AssignedValue<F> {
+    const CANONICAL: bool = false;
+
+    fn cell(&self) -> &AssignedValue<F> {
+        self
+    }
+}
+
+impl<F: PrimeField> EqualityOperand<F> for RangeChecked<F> {
+    const CANONICAL: bool = true;
+
+    fn cell(&self) -> &AssignedValue<F> {
+        RangeChecked::cell(self)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct GoldilocksChipConfig<F: PrimeField> {
     all_chip_config: AllChipConfig<F>,
 }
 
+impl<F: PrimeField> TableModeConfig<F> for GoldilocksChipConfig<F> {
+    fn configure_with_table_mode(meta: &mut ConstraintSystem<F>, table_mode: TableMode) -> Self {
+        GoldilocksChip::configure(&AllChipConfig::configure_with_table_mode(meta, table_mode))
+    }
+}
+
 pub struct GoldilocksChip<F: PrimeField> {
     goldilocks_chip_config: GoldilocksChipConfig<F>,
 }
@@ -73,6 +105,17 @@ impl<F: PrimeField> GoldilocksChip<F> {
         self.arithmetic_chip().assign_value(ctx, unassigned)
     }
 
+    /// Canonical Goldilocks representative of `x`, constrained. `x` can be any cell below
+    /// `2^80 * GOLDILOCKS_MODULUS`, e.g. an intermediate result accumulated without reduction.
+    pub fn reduce(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedValue<F>,
+    ) -> Result<RangeChecked<F>, Error> {
+        let reduced = self.arithmetic_chip().reduce(ctx, x)?;
+        Ok(RangeChecked::assume_canonical(reduced))
+    }
+
     pub fn assign_constant(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -82,6 +125,16 @@ impl<F: PrimeField> GoldilocksChip<F> {
             .assign_constant(ctx, goldilocks_to_fe(constant))
     }
 
+    /// `assign_constant`, as the canonical element a `GoldilocksField` constant is.
+    pub fn assign_canonical_constant(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        constant: GoldilocksField,
+    ) -> Result<RangeChecked<F>, Error> {
+        let assigned = self.assign_constant(ctx, constant)?;
+        Ok(RangeChecked::assume_canonical(assigned))
+    }
+
     pub fn compose(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -118,6 +171,15 @@ impl<F: PrimeField> GoldilocksChip<F> {
         Ok(assigned.r)
     }
 
+    /// Sum of `terms`, reduced once instead of after every addition.
+    pub fn sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        self.arithmetic_chip().sum(ctx, terms)
+    }
+
     pub fn sub(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -134,6 +196,16 @@ impl<F: PrimeField> GoldilocksChip<F> {
         Ok(assigned.r)
     }
 
+    /// `-a`
+    pub fn neg(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let zero = self.assign_constant(ctx, GoldilocksField::ZERO)?;
+        self.mul_const_add(ctx, a, -GoldilocksField::ONE, &zero)
+    }
+
     pub fn mul(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -142,6 +214,35 @@ impl<F: PrimeField> GoldilocksChip<F> {
     ) -> Result<AssignedValue<F>, Error> {
         self.mul_add_constant(ctx, lhs, rhs, GoldilocksField::ZERO)
     }
+
+    /// `mul`, as the canonical element the range checked remainder of the product is.
+    pub fn mul_canonical(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        lhs: &AssignedValue<F>,
+        rhs: &AssignedValue<F>,
+    ) -> Result<RangeChecked<F>, Error> {
+        let product = self.mul(ctx, lhs, rhs)?;
+        Ok(RangeChecked::assume_canonical(product))
+    }
+
+    /// `a * constant`. The constant is fixed on the rows of the multiplication, so unlike
+    /// `mul` with an assigned constant it takes no row of its own.
+    pub fn mul_const(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        constant: GoldilocksField,
+    ) -> Result<AssignedValue<F>, Error> {
+        let assigned = self.arithmetic_chip().apply(
+            ctx,
+            Term::Assigned(a),
+            Term::Constant(goldilocks_to_fe(constant)),
+            Term::Constant(F::ZERO),
+        )?;
+        Ok(assigned.r)
+    }
+
     /// `lhs * rhs * constant`
     pub fn mul_with_constant(
         &self,
@@ -215,13 +316,90 @@ impl<F: PrimeField> GoldilocksChip<F> {
         self.mul_add_constant(ctx, a, &one, constant)
     }
 
-    pub fn assert_equal(
+    /// Constrains `lhs` and `rhs` to the same Goldilocks element. `RangeChecked` operands take a
+    /// copy constraint, a raw cell takes a subtraction.
+    pub fn assert_equal<L: EqualityOperand<F>, R: EqualityOperand<F>>(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        lhs: &L,
+        rhs: &R,
+    ) -> Result<(), Error> {
+        if L::CANONICAL && R::CANONICAL {
+            return self
+                .arithmetic_chip()
+                .assert_equal(ctx, lhs.cell(), rhs.cell());
+        }
+        // A copy constraint would compare representatives, so compare the
+        // difference reduced mod p instead: this accepts `x` and `x + p` as the same element
+        // and nothing that differs mod p.
+        let diff = self.sub(ctx, lhs.cell(), rhs.cell())?;
+        let zero = self.assign_constant(ctx, GoldilocksField::ZERO)?;
+        self.arithmetic_chip().assert_equal(ctx, &diff, &zero)
+    }
+
+    /// Constrains `x < GOLDILOCKS_MODULUS`.
+    pub fn range_check(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedValue<F>,
+    ) -> Result<RangeChecked<F>, Error> {
+        self.arithmetic_chip().range_check(ctx, x)
+    }
+
+    /// Constrains `x < 2^bits` for `bits <= 63`, on the limb lookups of the arithmetic chip
+    /// rather than a bit decomposition. Any cell can be passed, it is range checked first.
+    pub fn assert_in_range(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedValue<F>,
+        bits: usize,
+    ) -> Result<RangeChecked<F>, Error> {
+        self.arithmetic_chip().assert_in_range(ctx, x, bits)
+    }
+
+    /// Verification check that `lhs == rhs`. It is constrained like `assert_equal`, unless
+    /// `ctx` is recording checks, in which case only the condition is recorded so that an
+    /// invalid proof yields a false bit instead of an unsatisfied circuit.
+    pub fn check_equal(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         lhs: &AssignedValue<F>,
         rhs: &AssignedValue<F>,
     ) -> Result<(), Error> {
-        self.arithmetic_chip().assert_equal(ctx, lhs, rhs)
+        if ctx.is_recording_checks() {
+            let is_equal = self.is_equal(ctx, lhs, rhs)?;
+            ctx.push_check(is_equal);
+            Ok(())
+        } else {
+            self.assert_equal(ctx, lhs, rhs)
+        }
+    }
+
+    /// Verification check that `a == 0`, see `check_equal`.
+    pub fn check_zero(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        if ctx.is_recording_checks() {
+            let is_zero = self.is_zero(ctx, a)?;
+            ctx.push_check(is_zero);
+            Ok(())
+        } else {
+            self.assert_zero(ctx, a)
+        }
+    }
+
+    /// `1` if every condition is `1`, the empty conjunction is `1`.
+    pub fn and_many(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        conditions: &[AssignedCondition<F>],
+    ) -> Result<AssignedCondition<F>, Error> {
+        let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
+        conditions
+            .iter()
+            .try_fold(one, |acc, condition| self.mul(ctx, &acc, condition))
     }
 
     pub fn assert_one(
@@ -248,7 +426,7 @@ impl<F: PrimeField> GoldilocksChip<F> {
         bit: &Value<F>,
     ) -> Result<AssignedValue<F>, Error> {
         let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
-        let zero = self.assign_constant(ctx, GoldilocksField::ZERO)?;
+        let zero = self.assign_canonical_constant(ctx, GoldilocksField::ZERO)?;
         let neg_one = self.assign_constant(ctx, -GoldilocksField::ONE)?;
         let assigned = self.arithmetic_chip().apply(
             ctx,
@@ -258,30 +436,80 @@ impl<F: PrimeField> GoldilocksChip<F> {
         )?;
         let b = assigned.a;
         let b_minus_one = assigned.r;
-        let should_zero = self.mul(ctx, &b, &b_minus_one)?;
+        let should_zero = self.mul_canonical(ctx, &b, &b_minus_one)?;
         self.assert_equal(ctx, &should_zero, &zero)?;
         Ok(b)
     }
 
+    /// Constrains `a` to be `0` or `1`.
+    pub fn assert_bool(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
+        let neg_one = self.assign_constant(ctx, -GoldilocksField::ONE)?;
+        let a_minus_one = self
+            .arithmetic_chip()
+            .apply(
+                ctx,
+                Term::Assigned(a),
+                Term::Assigned(&one),
+                Term::Assigned(&neg_one),
+            )?
+            .r;
+        let should_zero = self.mul(ctx, a, &a_minus_one)?;
+        self.assert_zero(ctx, &should_zero)
+    }
+
+    /// `a` if `cond` is `1` and `b` if it's `0`. `cond` is trusted to be boolean, any other
+    /// value yields `(a - b) * cond + b`. Conditions that don't come from a bit decomposition or
+    /// a comparison should go through `select_checked`. With the `debug-select` feature, a
+    /// known non-boolean `cond` panics at synthesis.
     pub fn select(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         a: &AssignedValue<F>,
         b: &AssignedValue<F>,
         cond: &AssignedCondition<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        #[cfg(feature = "debug-select")]
+        cond.value()
+            .assert_if_known(|cond| **cond == F::ZERO || **cond == F::ONE);
+        self.select_unchecked(ctx, a, b, cond)
+    }
+
+    /// `select` constraining `cond` to be boolean first.
+    pub fn select_checked(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        cond: &AssignedCondition<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.assert_bool(ctx, cond)?;
+        self.select_unchecked(ctx, a, b, cond)
+    }
+
+    fn select_unchecked(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        cond: &AssignedCondition<F>,
     ) -> Result<AssignedValue<F>, Error> {
         // a * cond + b * (1- cond) = (a -b) * cond + b
         let a_minus_b = self.sub(ctx, a, b)?;
         self.mul_add(ctx, &a_minus_b, cond, b)
     }
 
-    // 4 rows
+    // 3 arithmetic operations, plus the zero and one constants
     pub fn is_zero(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         a: &AssignedValue<F>,
     ) -> Result<AssignedCondition<F>, Error> {
-        let zero = self.assign_constant(ctx, GoldilocksField::ZERO)?;
+        let zero = self.assign_canonical_constant(ctx, GoldilocksField::ZERO)?;
         let a_inv = a.value().map(|a| {
             let a = self.native_fe_to_goldilocks(*a);
             if a == GoldilocksField::ZERO {
@@ -294,13 +522,12 @@ impl<F: PrimeField> GoldilocksChip<F> {
             ctx,
             Term::Assigned(a),
             Term::Unassigned(a_inv),
-            Term::Assigned(&zero),
+            Term::Assigned(zero.cell()),
         )?;
         let a_a_inv = assigned.r;
-        let zero = assigned.c;
         let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
         let out = self.sub(ctx, &one, &a_a_inv)?;
-        let out_a = self.mul(ctx, &out, &a)?;
+        let out_a = self.mul_canonical(ctx, &out, a)?;
         self.assert_equal(ctx, &out_a, &zero)?;
         Ok(out)
     }
@@ -312,12 +539,27 @@ impl<F: PrimeField> GoldilocksChip<F> {
         ctx: &mut RegionCtx<'_, F>,
         composed: &AssignedValue<F>,
         number_of_bits: usize,
+    ) -> Result<Vec<AssignedCondition<F>>, Error> {
+        let decomposed = self.canonical_u64(composed);
+        let bits = self.to_bits_with_witness(ctx, composed, decomposed)?;
+        Ok(bits[0..number_of_bits].to_vec())
+    }
+
+    fn canonical_u64(&self, x: &AssignedValue<F>) -> Value<u64> {
+        x.value()
+            .map(|x| self.native_fe_to_goldilocks(*x).to_canonical_u64())
+    }
+
+    // the 64 bits of `decomposed`, constrained to sum to `composed` modulo p only
+    fn to_bits_with_witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        composed: &AssignedValue<F>,
+        decomposed: Value<u64>,
     ) -> Result<Vec<AssignedCondition<F>>, Error> {
         let zero = self.assign_constant(ctx, GoldilocksField::ZERO)?;
-        let bit_value = composed
-            .value()
+        let bit_value = decomposed
             .map(|x| {
-                let x = self.native_fe_to_goldilocks(*x).to_canonical_u64();
                 let mut bits = Vec::new();
                 for i in 0..64 {
                     let bit = F::from((x >> i) & 1);
@@ -346,7 +588,24 @@ impl<F: PrimeField> GoldilocksChip<F> {
             },
         )?;
         self.assert_equal(ctx, &acc, composed)?;
-        Ok(bit_assigned[0..number_of_bits].to_vec())
+        Ok(bit_assigned)
+    }
+
+    /// Constrains 64 little-endian `bits` to a value below p. With `p = 2^64 - 2^32 + 1`, they
+    /// are iff their high half isn't all ones or their low half is zero.
+    fn assert_canonical_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bits: &[AssignedCondition<F>],
+    ) -> Result<(), Error> {
+        assert_eq!(bits.len(), 64);
+        let lo = self.from_bits(ctx, &bits[..32].to_vec())?;
+        let hi = self.from_bits(ctx, &bits[32..].to_vec())?;
+        let hi_max =
+            self.assign_constant(ctx, GoldilocksField::from_canonical_u64(u32::MAX as u64))?;
+        let hi_is_max = self.is_equal(ctx, &hi, &hi_max)?;
+        let lo_below_max_hi = self.mul(ctx, &lo, &hi_is_max)?;
+        self.assert_zero(ctx, &lo_below_max_hi)
     }
 
     pub fn from_bits(
@@ -385,6 +644,37 @@ impl<F: PrimeField> GoldilocksChip<F> {
         Ok(result)
     }
 
+    /// Asserts that `g` generates the subgroup of order `2^log_order`: `g^(2^(log_order - 1))`
+    /// is `-1`, the square root of one other than one, and squares to one. Takes `log_order`
+    /// squarings.
+    pub fn assert_subgroup_generator(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        g: &AssignedValue<F>,
+        log_order: usize,
+    ) -> Result<(), Error> {
+        if log_order == 0 {
+            return self.assert_one(ctx, g);
+        }
+        let half_order_power = self.exp_power_of_2(ctx, g, log_order - 1)?;
+        let neg_one = self.assign_constant(ctx, GoldilocksField::NEG_ONE)?;
+        self.assert_equal(ctx, &half_order_power, &neg_one)?;
+        let order_power = self.mul(ctx, &half_order_power, &half_order_power)?;
+        self.assert_one(ctx, &order_power)
+    }
+
+    /// Assigns plonky2's generator of the subgroup of order `2^log_order`, checked by
+    /// `assert_subgroup_generator`.
+    pub fn assign_subgroup_generator(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        log_order: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        let g = self.assign_constant(ctx, GoldilocksField::primitive_root_of_unity(log_order))?;
+        self.assert_subgroup_generator(ctx, &g, log_order)?;
+        Ok(g)
+    }
+
     pub fn exp_from_bits(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -393,12 +683,14 @@ impl<F: PrimeField> GoldilocksChip<F> {
     ) -> Result<AssignedValue<F>, Error> {
         let mut x = self.assign_constant(ctx, GoldilocksField::ONE)?;
         let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
-        for (i, bit) in power_bits.iter().enumerate() {
+        // `base^(2^i)`, squared natively so any number of bits works
+        let mut base_power = base;
+        for bit in power_bits.iter() {
             let is_zero_bit = self.is_zero(ctx, bit)?;
-            let power = u64::from(1u64 << i).to_le();
-            let base = self.assign_constant(ctx, base.exp_u64(power))?;
+            let base = self.assign_constant(ctx, base_power)?;
             let multiplicand = self.select(ctx, &one, &base, &is_zero_bit)?;
             x = self.mul(ctx, &x, &multiplicand)?;
+            base_power = base_power.square();
         }
         Ok(x)
     }
@@ -413,6 +705,59 @@ impl<F: PrimeField> GoldilocksChip<F> {
         self.is_zero(ctx, &a_mimus_b)
     }
 
+    /// Returns `1` if `a < b` and `0` otherwise, for `a` and `b` below `2^num_bits` with
+    /// `num_bits <= 64`, which is constrained. Both are decomposed into 64 bits and the bits
+    /// from `num_bits` up are constrained to zero. `to_bits` only binds a decomposition to its
+    /// value modulo p, which below 64 bits makes it the canonical one, so with `num_bits = 64`
+    /// both decompositions are also constrained below p.
+    pub fn less_than(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        num_bits: usize,
+    ) -> Result<AssignedCondition<F>, Error> {
+        let (a_decomposed, b_decomposed) = (self.canonical_u64(a), self.canonical_u64(b));
+        self.less_than_with_witness(ctx, a, b, num_bits, a_decomposed, b_decomposed)
+    }
+
+    // `less_than` with the decompositions of `a` and `b` given, so that tests can try others
+    fn less_than_with_witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        num_bits: usize,
+        a_decomposed: Value<u64>,
+        b_decomposed: Value<u64>,
+    ) -> Result<AssignedCondition<F>, Error> {
+        assert!(num_bits <= 64);
+        let a_bits = self.to_bits_with_witness(ctx, a, a_decomposed)?;
+        let b_bits = self.to_bits_with_witness(ctx, b, b_decomposed)?;
+        for bit in a_bits[num_bits..].iter().chain(b_bits[num_bits..].iter()) {
+            self.assert_zero(ctx, bit)?;
+        }
+        if num_bits == 64 {
+            self.assert_canonical_bits(ctx, &a_bits)?;
+            self.assert_canonical_bits(ctx, &b_bits)?;
+        }
+
+        // borrow chain of `a - b` from the least significant bit, `a < b` iff the last
+        // subtraction borrows
+        let one = self.assign_constant(ctx, GoldilocksField::ONE)?;
+        let mut borrow = self.assign_constant(ctx, GoldilocksField::ZERO)?;
+        for (a_i, b_i) in a_bits.iter().zip(b_bits.iter()).take(num_bits) {
+            // borrow' = (1 - a_i) * b_i + (1 - (a_i xor b_i)) * borrow
+            let a_i_b_i = self.mul(ctx, a_i, b_i)?;
+            let a_i_plus_b_i = self.add(ctx, a_i, b_i)?;
+            let xor = self.mul_const_add(ctx, &a_i_b_i, -GoldilocksField::TWO, &a_i_plus_b_i)?;
+            let not_xor = self.sub(ctx, &one, &xor)?;
+            let not_a_i_and_b_i = self.sub(ctx, b_i, &a_i_b_i)?;
+            borrow = self.mul_add(ctx, &not_xor, &borrow, &not_a_i_and_b_i)?;
+        }
+        Ok(borrow)
+    }
+
     pub fn load_table(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -424,15 +769,23 @@ impl<F: PrimeField> GoldilocksChip<F> {
 #[cfg(test)]
 mod tests {
     use halo2_proofs::{
-        circuit::{floor_planner::V1, Layouter},
-        dev::MockProver,
-        halo2curves::bn256::Fr,
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::{bn256::Fr, ff::Field as _},
         plonk::{Circuit, ConstraintSystem, Error},
     };
-    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+    use plonky2::field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, Sample},
+    };
+    use rand::Rng;
 
     use crate::plonky2_verifier::{
-        chip::native_chip::{all_chip::AllChipConfig, arithmetic_chip::GOLDILOCKS_MODULUS},
+        chip::native_chip::{
+            all_chip::AllChipConfig,
+            arithmetic_chip::GOLDILOCKS_MODULUS,
+            test_utils::{mock_prover_at_min_k, SmallTable},
+            utils::goldilocks_to_fe,
+        },
         context::RegionCtx,
     };
 
@@ -473,6 +826,53 @@ mod tests {
                     let b = chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(3))?;
                     let _c = chip.add(ctx, &a, &b)?;
 
+                    let neg_b = chip.neg(ctx, &b)?;
+                    let expected_neg_b =
+                        chip.assign_constant(ctx, -GoldilocksField::from_canonical_u64(3))?;
+                    chip.assert_equal(ctx, &neg_b, &expected_neg_b)?;
+                    let zero = chip.assign_constant(ctx, GoldilocksField::ZERO)?;
+                    let neg_zero = chip.neg(ctx, &zero)?;
+                    chip.assert_equal(ctx, &neg_zero, &zero)?;
+
+                    // wraps around the modulus like `mul`
+                    let c = GoldilocksField::from_canonical_u64(GOLDILOCKS_MODULUS - 5);
+                    let a_c = chip.mul_const(ctx, &a, c)?;
+                    let expected_a_c = chip.assign_constant(
+                        ctx,
+                        GoldilocksField::from_canonical_u64(GOLDILOCKS_MODULUS - 2) * c,
+                    )?;
+                    chip.assert_equal(ctx, &a_c, &expected_a_c)?;
+                    // only the two rows of the multiplication, the constants aren't assigned
+                    let offset = ctx.offset();
+                    let b_c = chip.mul_const(ctx, &b, GoldilocksField::from_canonical_u64(7))?;
+                    assert_eq!(ctx.offset(), offset + 2);
+                    let expected_b_c =
+                        chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(21))?;
+                    chip.assert_equal(ctx, &b_c, &expected_b_c)?;
+
+                    let max = GOLDILOCKS_MODULUS - 1;
+                    for (a, b, num_bits, expected) in [
+                        (3, 3, 64, false),
+                        (3, 4, 64, true),
+                        (4, 3, 64, false),
+                        (0, max, 64, true),
+                        (max, 0, 64, false),
+                        (max - 1, max, 64, true),
+                        (max, max, 64, false),
+                        (254, 255, 8, true),
+                        (255, 254, 8, false),
+                        (0, 0, 0, false),
+                    ] {
+                        let a =
+                            chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(a))?;
+                        let b =
+                            chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(b))?;
+                        let lt = chip.less_than(ctx, &a, &b, num_bits)?;
+                        let expected =
+                            chip.assign_constant(ctx, GoldilocksField::from_bool(expected))?;
+                        chip.assert_equal(ctx, &lt, &expected)?;
+                    }
+
                     // let a_bits = chip.to_bits(ctx, &a, 64)?;
                     // let a_recovered = chip.from_bits(ctx, &a_bits)?;
 
@@ -499,13 +899,516 @@ mod tests {
         }
     }
 
-    const DEGREE: u32 = 17;
-
     #[test]
     fn test_goldilocks_chip() {
         let circuit = TestCircuit;
         let instance = Vec::<Fr>::new();
-        let mock_prover = MockProver::run(DEGREE, &circuit, vec![instance.clone()]).unwrap();
+        let mock_prover =
+            mock_prover_at_min_k(&SmallTable(circuit), vec![instance.clone()]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    /// `(base, exponent, num_bits)` cases checked against `GoldilocksField::exp_u64`.
+    #[derive(Clone, Default)]
+    struct ExpFromBitsCircuit {
+        cases: Vec<(GoldilocksField, u64, usize)>,
+    }
+
+    impl Circuit<Fr> for ExpFromBitsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "exp from bits",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    for (base, exponent, num_bits) in self.cases.iter() {
+                        let power = chip
+                            .assign_constant(ctx, GoldilocksField::from_canonical_u64(*exponent))?;
+                        let power_bits = chip.to_bits(ctx, &power, *num_bits)?;
+                        let x = chip.exp_from_bits(ctx, *base, &power_bits)?;
+                        let expected = chip.assign_constant(ctx, base.exp_u64(*exponent))?;
+                        chip.assert_equal(ctx, &x, &expected)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_exp_from_bits() {
+        let mut rng = rand::thread_rng();
+        let mut cases = (0..4)
+            .map(|_| {
+                let exponent = rng.gen_range(0..GOLDILOCKS_MODULUS);
+                (GoldilocksField::rand(), exponent, 64)
+            })
+            .collect::<Vec<_>>();
+        cases.extend([
+            // zero exponent, including `0^0`
+            (GoldilocksField::rand(), 0, 20),
+            (GoldilocksField::ZERO, 0, 20),
+            (GoldilocksField::ZERO, 5, 20),
+            // all ones
+            (GoldilocksField::rand(), (1 << 20) - 1, 20),
+            (GoldilocksField::rand(), (1 << 63) - 1, 63),
+            // the multiplicative generator as FRI uses it, and a non-canonical base
+            (GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR, 12345, 16),
+            (GoldilocksField(GOLDILOCKS_MODULUS + 3), 1 << 40, 41),
+        ]);
+        let circuit = ExpFromBitsCircuit { cases };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    /// `less_than(0, p - 1, 64)` with `0` decomposed as `a_decomposed`, asserting the result
+    /// the decomposition implies.
+    #[derive(Clone, Default)]
+    struct LessThanWitnessCircuit {
+        a_decomposed: u64,
+    }
+
+    impl Circuit<Fr> for LessThanWitnessCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "less than witness",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let max = GOLDILOCKS_MODULUS - 1;
+                    let a = chip.assign_constant(ctx, GoldilocksField::ZERO)?;
+                    let b = chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(max))?;
+                    let lt = chip.less_than_with_witness(
+                        ctx,
+                        &a,
+                        &b,
+                        64,
+                        Value::known(self.a_decomposed),
+                        Value::known(max),
+                    )?;
+                    let expected = GoldilocksField::from_bool(self.a_decomposed < max);
+                    let expected = chip.assign_constant(ctx, expected)?;
+                    chip.assert_equal(ctx, &lt, &expected)
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_less_than_rejects_non_canonical_decomposition() {
+        // `p` is `0` modulo p, and would compare as not less than `p - 1`
+        for (a_decomposed, satisfied) in [(0, true), (GOLDILOCKS_MODULUS, false)] {
+            let circuit = LessThanWitnessCircuit { a_decomposed };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            assert_eq!(
+                mock_prover.verify().is_ok(),
+                satisfied,
+                "0 decomposed as {a_decomposed}"
+            );
+        }
+    }
+
+    /// Asserts `lhs == rhs` on raw cells, which can hold non-canonical values, either as they
+    /// are or after range checking both.
+    #[derive(Clone, Default)]
+    struct AssertEqualCircuit {
+        lhs: u64,
+        rhs: u64,
+        range_checked: bool,
+    }
+
+    impl Circuit<Fr> for AssertEqualCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            let column = config.all_chip_config.arithmetic_config.a;
+            layouter.assign_region(
+                || "assert equal",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let lhs = Value::known(Fr::from(self.lhs));
+                    let lhs = ctx.assign_advice(|| "lhs", column, lhs)?;
+                    ctx.next();
+                    let rhs = Value::known(Fr::from(self.rhs));
+                    let rhs = ctx.assign_advice(|| "rhs", column, rhs)?;
+                    ctx.next();
+                    if self.range_checked {
+                        let lhs = chip.range_check(ctx, &lhs)?;
+                        let rhs = chip.range_check(ctx, &rhs)?;
+                        chip.assert_equal(ctx, &lhs, &rhs)
+                    } else {
+                        chip.assert_equal(ctx, &lhs, &rhs)
+                    }
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assert_equal_on_values_differing_by_p() {
+        let x = 5;
+        for (lhs, rhs, range_checked, satisfied) in [
+            // raw cells are compared mod p, so `x + p` is the element `x`
+            (x, x + GOLDILOCKS_MODULUS, false, true),
+            (x, x, false, true),
+            (x, x + 1, false, false),
+            // range checked cells are compared as cells, and `x + p` fails its range check
+            (x, x + GOLDILOCKS_MODULUS, true, false),
+            (x, x, true, true),
+            (x, x + 1, true, false),
+        ] {
+            let circuit = AssertEqualCircuit {
+                lhs,
+                rhs,
+                range_checked,
+            };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            assert_eq!(
+                mock_prover.verify().is_ok(),
+                satisfied,
+                "{lhs} == {rhs}, range checked: {range_checked}"
+            );
+        }
+    }
+
+    /// Rows `assert_equal` takes on `RangeChecked` cells and on raw ones.
+    #[derive(Clone, Default)]
+    struct AssertEqualRowsCircuit;
+
+    impl Circuit<Fr> for AssertEqualRowsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "assert equal rows",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let x = chip.assign_value(ctx, Value::known(Fr::from(5)))?;
+                    let x = chip.reduce(ctx, &x)?;
+                    let y = chip
+                        .assign_canonical_constant(ctx, GoldilocksField::from_canonical_u64(5))?;
+                    chip.assign_constant(ctx, GoldilocksField::ZERO)?;
+                    chip.assign_constant(ctx, -GoldilocksField::ONE)?;
+
+                    let offset = ctx.offset();
+                    chip.assert_equal(ctx, &x, &y)?;
+                    assert_eq!(
+                        ctx.offset(),
+                        offset,
+                        "range checked cells take a copy constraint"
+                    );
+
+                    // the same cells, raw
+                    chip.assert_equal(ctx, x.cell(), y.cell())?;
+                    // one arithmetic op, its constants are already assigned
+                    assert_eq!(ctx.offset(), offset + 2);
+                    Ok(())
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assert_equal_rows() {
+        let mock_prover =
+            mock_prover_at_min_k(&SmallTable(AssertEqualRowsCircuit), vec![vec![]]).unwrap();
         mock_prover.assert_satisfied();
     }
+
+    #[derive(Clone, Default)]
+    struct SelectCheckedCircuit {
+        cond: u64,
+    }
+
+    impl Circuit<Fr> for SelectCheckedCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "select checked",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let [a_value, b_value, cond_value] =
+                        [7, 11, self.cond].map(GoldilocksField::from_canonical_u64);
+                    let a = chip.assign_constant(ctx, a_value)?;
+                    let b = chip.assign_constant(ctx, b_value)?;
+                    let cond = chip.assign_value(ctx, Value::known(Fr::from(self.cond)))?;
+                    let selected = chip.select_checked(ctx, &a, &b, &cond)?;
+                    // what `select` computes, so a non-boolean condition is only caught by
+                    // `assert_bool`
+                    let expected = (a_value - b_value) * cond_value + b_value;
+                    let expected = chip.assign_constant(ctx, expected)?;
+                    chip.assert_equal(ctx, &selected, &expected)
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_select_checked_rejects_non_boolean_conditions() {
+        for (cond, satisfied) in [
+            (0, true),
+            (1, true),
+            (2, false),
+            (GOLDILOCKS_MODULUS - 1, false),
+        ] {
+            let circuit = SelectCheckedCircuit { cond };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            assert_eq!(mock_prover.verify().is_ok(), satisfied, "cond {cond}");
+        }
+    }
+
+    /// Asserts `x < 2^bits` on a raw cell, which can hold any field element.
+    #[derive(Clone, Default)]
+    struct AssertInRangeCircuit {
+        x: Fr,
+        bits: usize,
+    }
+
+    impl Circuit<Fr> for AssertInRangeCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            let column = config.all_chip_config.arithmetic_config.a;
+            layouter.assign_region(
+                || "assert in range",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let x = ctx.assign_advice(|| "x", column, Value::known(self.x))?;
+                    ctx.next();
+                    chip.assert_in_range(ctx, &x, self.bits)?;
+                    Ok(())
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assert_in_range() {
+        let p = GOLDILOCKS_MODULUS;
+        for bits in [0, 1, 15, 16, 17, 32, 48, 63] {
+            let max = (1u64 << bits) - 1;
+            let mut cases = vec![(0, true), (max, true), (max + 1, false), (p - 1, false)];
+            if bits > 0 {
+                cases.push((max / 2, true));
+            }
+            for (x, satisfied) in cases {
+                let circuit = AssertInRangeCircuit {
+                    x: Fr::from(x),
+                    bits,
+                };
+                let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+                assert_eq!(mock_prover.verify().is_ok(), satisfied, "{x} < 2^{bits}");
+            }
+        }
+        // values that are small only mod p, and 2^-64, which the shift by 2^64 for 16 bits maps
+        // to 1 in the native field
+        let inv_2_64 = Fr::from(1u64 << 32).square().invert().unwrap();
+        for x in [Fr::from(p), Fr::from(p + 1), -Fr::from(1u64), inv_2_64] {
+            let circuit = AssertInRangeCircuit { x, bits: 16 };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            assert!(mock_prover.verify().is_err(), "{x:?} < 2^16");
+        }
+    }
+
+    /// Generators assigned by `assign_subgroup_generator` for `log_orders`, and `claimed`
+    /// generators checked by `assert_subgroup_generator`.
+    #[derive(Clone, Default)]
+    struct SubgroupGeneratorCircuit {
+        log_orders: Vec<usize>,
+        claimed: Vec<(GoldilocksField, usize)>,
+    }
+
+    impl Circuit<Fr> for SubgroupGeneratorCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChipConfig { all_chip_config }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "subgroup generator",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    for log_order in self.log_orders.iter() {
+                        let g = chip.assign_subgroup_generator(ctx, *log_order)?;
+                        let expected = GoldilocksField::primitive_root_of_unity(*log_order);
+                        g.value()
+                            .map(|g| assert_eq!(chip.native_fe_to_goldilocks(*g), expected));
+                    }
+                    for (g, log_order) in self.claimed.iter() {
+                        let g = chip.assign_value(ctx, Value::known(goldilocks_to_fe(*g)))?;
+                        chip.assert_subgroup_generator(ctx, &g, *log_order)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            chip.load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_subgroup_generator() {
+        let circuit = SubgroupGeneratorCircuit {
+            log_orders: (3..=26).collect(),
+            claimed: vec![],
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+
+        // the generator `zeta_next` was derived from before, `g^((p - 1) / 2^log_order)`
+        let log_order = 10;
+        let g = GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR
+            .exp_u64((GOLDILOCKS_MODULUS - 1) >> log_order);
+        let circuit = SubgroupGeneratorCircuit {
+            log_orders: vec![],
+            claimed: vec![(g, log_order)],
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+
+        for (g, log_order) in [
+            // order too large or too small
+            (GoldilocksField::primitive_root_of_unity(11), 10),
+            (GoldilocksField::primitive_root_of_unity(9), 10),
+            (GoldilocksField::ONE, 10),
+            (GoldilocksField::NEG_ONE, 0),
+        ] {
+            let circuit = SubgroupGeneratorCircuit {
+                log_orders: vec![],
+                claimed: vec![(g, log_order)],
+            };
+            let prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            assert!(
+                prover.verify().is_err(),
+                "{g} claimed of order 2^{log_order}"
+            );
+        }
+    }
 }
@@ -3,13 +3,14 @@ use halo2_proofs::plonk::Error;
 use halo2wrong_maingate::{fe_to_big, AssignedValue};
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field;
-use plonky2::field::{extension::quadratic::QuadraticExtension, types::PrimeField64};
+use plonky2::field::types::PrimeField64;
 
 use crate::plonky2_verifier::context::RegionCtx;
 use crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue;
 
 use super::goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig};
 use super::native_chip::arithmetic_chip::{ArithmeticChip, TermExt};
+use super::native_chip::utils::{goldilocks_ext_inverse, goldilocks_to_fe};
 
 pub struct AssignedExtensionAlgebra<F: PrimeField>(pub [AssignedExtensionFieldValue<F, 2>; 2]);
 
@@ -62,11 +63,11 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         let assigned = self.arithmetic_chip().apply_ext(
             ctx,
-            TermExt::Assigned(&a.0),
-            TermExt::Assigned(&b.0),
-            TermExt::Assigned(&c.0),
+            TermExt::Assigned(a.limbs()),
+            TermExt::Assigned(b.limbs()),
+            TermExt::Assigned(c.limbs()),
         )?;
-        Ok(AssignedExtensionFieldValue(assigned.r))
+        Ok(AssignedExtensionFieldValue::new(assigned.r))
     }
 
     pub fn div_extension(
@@ -75,28 +76,27 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         x: &AssignedExtensionFieldValue<F, 2>,
         y: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
-        y.0[0]
+        y.real()
             .value()
-            .zip(y.0[1].value())
+            .zip(y.imag().value())
             .map(|(a, b)| assert!(*a != F::from(0) || *b != F::from(0)));
         let goldilocks_chip = self.goldilocks_chip();
-        let y_inv = y.0[0]
+        let y_inv = y
+            .real()
             .value()
-            .zip(y.0[1].value())
+            .zip(y.imag().value())
             .map(|(&hi, &lo)| {
-                let y_inv = QuadraticExtension([
+                let y_inv = goldilocks_ext_inverse([
                     self.native_fe_to_goldilocks(hi),
                     self.native_fe_to_goldilocks(lo),
                 ])
-                .inverse()
-                .0
                 .map(|v| self.goldilocks_to_native_fe(v));
                 (y_inv[0], y_inv[1])
             })
             .unzip();
         let y_inv0 = goldilocks_chip.assign_value(ctx, y_inv.0)?;
         let y_inv1 = goldilocks_chip.assign_value(ctx, y_inv.1)?;
-        let y_inv = AssignedExtensionFieldValue([y_inv0, y_inv1]);
+        let y_inv = AssignedExtensionFieldValue::new([y_inv0, y_inv1]);
         // y * y_inv = 1
         let yy_inv = self.mul_extension(ctx, y, &y_inv)?;
         self.assert_one_extension(ctx, &yy_inv)?;
@@ -124,12 +124,32 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         let goldilocks_chip = self.goldilocks_chip();
         let added = addend_0
-            .0
+            .limbs()
             .iter()
-            .zip(addend_1.0.iter())
+            .zip(addend_1.limbs().iter())
             .map(|(addend_0, addend_1)| goldilocks_chip.add(ctx, addend_0, addend_1))
             .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
-        Ok(AssignedExtensionFieldValue(added.try_into().unwrap()))
+        Ok(AssignedExtensionFieldValue::from_vec(added)?)
+    }
+
+    /// Sum of `terms`, added limbwise with one `GoldilocksChip::sum` per limb. Each term costs
+    /// a row per limb, where chaining `add_extension` costs two.
+    pub fn add_many_extension(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[AssignedExtensionFieldValue<F, 2>],
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        let goldilocks_chip = self.goldilocks_chip();
+        let sums = (0..2)
+            .map(|i| {
+                let limbs = terms
+                    .iter()
+                    .map(|term| term.limbs()[i].clone())
+                    .collect::<Vec<_>>();
+                goldilocks_chip.sum(ctx, &limbs)
+            })
+            .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
+        Ok(AssignedExtensionFieldValue::from_vec(sums)?)
     }
 
     pub fn scalar_mul(
@@ -139,13 +159,12 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         scalar: GoldilocksField,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         let goldilocks_chip = self.goldilocks_chip();
-        let assigned_scalar = goldilocks_chip.assign_constant(ctx, scalar)?;
         let multiplied = multiplicand
-            .0
+            .limbs()
             .iter()
-            .map(|v| goldilocks_chip.mul(ctx, v, &assigned_scalar))
+            .map(|v| goldilocks_chip.mul_const(ctx, v, scalar))
             .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
-        Ok(AssignedExtensionFieldValue(multiplied.try_into().unwrap()))
+        Ok(AssignedExtensionFieldValue::from_vec(multiplied)?)
     }
 
     /// const_0 * multiplicand_0 * multiplicand_1 + const_1 * addend
@@ -175,7 +194,7 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         let elements = (0..2)
             .map(|_| goldilocks_chip.assign_constant(ctx, GoldilocksField::ZERO))
             .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
-        Ok(AssignedExtensionFieldValue(elements.try_into().unwrap()))
+        Ok(AssignedExtensionFieldValue::from_vec(elements)?)
     }
 
     pub fn one_extension(
@@ -187,7 +206,7 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
             goldilocks_chip.assign_constant(ctx, GoldilocksField::ONE)?,
             goldilocks_chip.assign_constant(ctx, GoldilocksField::ZERO)?,
         ];
-        Ok(AssignedExtensionFieldValue(elements))
+        Ok(AssignedExtensionFieldValue::new(elements))
     }
 
     pub fn two_extension(
@@ -199,7 +218,7 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
             goldilocks_chip.assign_constant(ctx, GoldilocksField::from_canonical_u64(2))?,
             goldilocks_chip.assign_constant(ctx, GoldilocksField::ZERO)?,
         ];
-        Ok(AssignedExtensionFieldValue(elements))
+        Ok(AssignedExtensionFieldValue::new(elements))
     }
 
     pub fn mul_extension_with_const(
@@ -230,6 +249,20 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         self.mul_add_extension(ctx, multiplicand_0, multiplicand_1, &zero)
     }
 
+    /// `-a`
+    pub fn neg_extension(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedExtensionFieldValue<F, 2>,
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        let neg_one =
+            self.constant_extension(ctx, &[-GoldilocksField::ONE, GoldilocksField::ZERO])?;
+        let zero = self.zero_extension(ctx)?;
+        self.mul_add_extension(ctx, a, &neg_one, &zero)
+    }
+
+    /// `a * b - c`, two extension mul-adds. `arithmetic_extension(1, -1, ..)` computes the same
+    /// with a mul-add, two scalar muls and an addition, twice the rows.
     pub fn mul_sub_extension(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -237,8 +270,8 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         b: &AssignedExtensionFieldValue<F, 2>,
         c: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
-        let one = GoldilocksField::ONE;
-        self.arithmetic_extension(ctx, one, -one, a, b, c)
+        let neg_c = self.neg_extension(ctx, c)?;
+        self.mul_add_extension(ctx, a, b, &neg_c)
     }
 
     pub fn square_extension(
@@ -280,16 +313,38 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         Ok(product)
     }
 
+    /// `accs[i] = a * bs[i] + accs[i]`, e.g. to add filtered gate constraints into the combined
+    /// ones. `a` is copied into each operation instead of being re-assigned, so every term costs
+    /// exactly one extension mul-add.
+    pub fn mul_add_many_extension(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedExtensionFieldValue<F, 2>,
+        bs: &[AssignedExtensionFieldValue<F, 2>],
+        accs: &mut [AssignedExtensionFieldValue<F, 2>],
+    ) -> Result<(), Error> {
+        for (acc, b) in accs.iter_mut().zip(bs) {
+            let assigned = self.arithmetic_chip().apply_ext(
+                ctx,
+                TermExt::Assigned(a.limbs()),
+                TermExt::Assigned(b.limbs()),
+                TermExt::Assigned(acc.limbs()),
+            )?;
+            *acc = AssignedExtensionFieldValue::new(assigned.r);
+        }
+        Ok(())
+    }
+
     pub fn mul_many_extension(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         terms: Vec<AssignedExtensionFieldValue<F, 2>>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        // the empty product is one, as in plonky2
         let one = self.one_extension(ctx)?;
-        let result = terms.into_iter().fold(one, |acc, term| {
-            self.mul_extension(ctx, &acc, &term).unwrap()
-        });
-        Ok(result)
+        terms
+            .into_iter()
+            .try_fold(one, |acc, term| self.mul_extension(ctx, &acc, &term))
     }
 
     pub fn sub_extension(
@@ -298,9 +353,10 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         lhs: &AssignedExtensionFieldValue<F, 2>,
         rhs: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
-        let one = GoldilocksField::ONE;
-        let one_extension = self.one_extension(ctx)?;
-        self.arithmetic_extension(ctx, one, -one, lhs, &one_extension, rhs)
+        // lhs - rhs = rhs * (-1) + lhs
+        let neg_one =
+            self.constant_extension(ctx, &[-GoldilocksField::ONE, GoldilocksField::ZERO])?;
+        self.mul_add_extension(ctx, rhs, &neg_one, lhs)
     }
 
     pub fn constant_extension(
@@ -308,12 +364,17 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         ctx: &mut RegionCtx<'_, F>,
         constant: &[GoldilocksField; 2],
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        let value = constant.map(goldilocks_to_fe);
+        if let Some(cells) = ctx.get_fixed_extension(&value) {
+            return Ok(AssignedExtensionFieldValue::new(cells.clone()));
+        }
         let goldilocks_chip = self.goldilocks_chip();
-        let elements = constant
-            .into_iter()
-            .map(|c| goldilocks_chip.assign_constant(ctx, *c))
-            .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
-        Ok(AssignedExtensionFieldValue(elements.try_into().unwrap()))
+        let elements = [
+            goldilocks_chip.assign_constant(ctx, constant[0])?,
+            goldilocks_chip.assign_constant(ctx, constant[1])?,
+        ];
+        ctx.register_fixed_extension(value, elements.clone());
+        Ok(AssignedExtensionFieldValue::new(elements))
     }
 
     pub fn convert_to_extension(
@@ -322,7 +383,7 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         value: &AssignedValue<F>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         let goldilocks_chip = self.goldilocks_chip();
-        Ok(AssignedExtensionFieldValue([
+        Ok(AssignedExtensionFieldValue::new([
             value.clone(),
             goldilocks_chip.assign_constant(ctx, GoldilocksField::ZERO)?,
         ]))
@@ -334,11 +395,26 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         base: &AssignedExtensionFieldValue<F, 2>,
         terms: &Vec<AssignedExtensionFieldValue<F, 2>>,
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        // the empty sum is zero, as in plonky2's `reduce_with_powers`
         let zero_extension = self.zero_extension(ctx)?;
-        let result = terms.iter().rev().fold(zero_extension, |acc, term| {
-            self.mul_add_extension(ctx, &acc, base, term).unwrap()
-        });
-        Ok(result)
+        terms.iter().rev().try_fold(zero_extension, |acc, term| {
+            self.mul_add_extension(ctx, &acc, base, term)
+        })
+    }
+
+    /// `sum a_i * b_i` over two extension vectors of the same length.
+    pub fn dot_product(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &[AssignedExtensionFieldValue<F, 2>],
+        b: &[AssignedExtensionFieldValue<F, 2>],
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        assert_eq!(a.len(), b.len(), "a and b must have the same length");
+        let mut acc = self.zero_extension(ctx)?;
+        for (a, b) in a.iter().zip(b.iter()) {
+            acc = self.mul_add_extension(ctx, a, b, &acc)?;
+        }
+        Ok(acc)
     }
 
     pub fn reduce_base_field_terms_extension(
@@ -364,6 +440,31 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         self.reduce_extension(ctx, &base, terms)
     }
 
+    /// `sum terms[i] * base^i` like `reduce_extension_field_terms_base`, multiplying each limb
+    /// by `base` with a base field mul-add. An extension mul-add already takes one operation
+    /// per limb, so a step costs the same four rows; the saving is starting Horner's method at
+    /// the leading term instead of at zero, one step fewer.
+    pub fn reduce_with_powers_base(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        base: &AssignedValue<F>,
+        terms: &[AssignedExtensionFieldValue<F, 2>],
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        let goldilocks_chip = self.goldilocks_chip();
+        let Some((leading, rest)) = terms.split_last() else {
+            return self.zero_extension(ctx);
+        };
+        rest.iter().rev().try_fold(leading.clone(), |acc, term| {
+            let limbs = acc
+                .limbs()
+                .iter()
+                .zip(term.limbs().iter())
+                .map(|(acc, term)| goldilocks_chip.mul_add(ctx, acc, base, term))
+                .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
+            Ok(AssignedExtensionFieldValue::from_vec(limbs)?)
+        })
+    }
+
     // shifted * factor^power
     pub fn shift(
         &self,
@@ -383,8 +484,21 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         rhs: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<(), Error> {
         let goldilocks_chip = self.goldilocks_chip();
-        goldilocks_chip.assert_equal(ctx, &lhs.0[0], &rhs.0[0])?;
-        goldilocks_chip.assert_equal(ctx, &lhs.0[1], &rhs.0[1])?;
+        goldilocks_chip.assert_equal(ctx, lhs.real(), rhs.real())?;
+        goldilocks_chip.assert_equal(ctx, lhs.imag(), rhs.imag())?;
+        Ok(())
+    }
+
+    /// Verification check that `lhs == rhs`, see `GoldilocksChip::check_equal`.
+    pub fn check_equal_extension(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        lhs: &AssignedExtensionFieldValue<F, 2>,
+        rhs: &AssignedExtensionFieldValue<F, 2>,
+    ) -> Result<(), Error> {
+        let goldilocks_chip = self.goldilocks_chip();
+        goldilocks_chip.check_equal(ctx, lhs.real(), rhs.real())?;
+        goldilocks_chip.check_equal(ctx, lhs.imag(), rhs.imag())?;
         Ok(())
     }
 
@@ -394,8 +508,8 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         a: &AssignedExtensionFieldValue<F, 2>,
     ) -> Result<(), Error> {
         let goldilocks_chip = self.goldilocks_chip();
-        goldilocks_chip.assert_one(ctx, &a.0[0])?;
-        goldilocks_chip.assert_zero(ctx, &a.0[1])?;
+        goldilocks_chip.assert_one(ctx, a.real())?;
+        goldilocks_chip.assert_zero(ctx, a.imag())?;
         Ok(())
     }
 
@@ -415,3 +529,579 @@ impl<F: PrimeField> GoldilocksExtensionChip<F> {
         self.arithmetic_extension(ctx, one, one, cond, &a_minus_b, b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::field::{
+        extension::quadratic::QuadraticExtension,
+        goldilocks_field::GoldilocksField,
+        types::{Field, Sample},
+    };
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+        types::assigned::AssignedExtensionFieldValue,
+    };
+
+    use super::GoldilocksExtensionChip;
+
+    #[derive(Clone, Default)]
+    pub struct TestCircuit {
+        /// `(x, y)` pairs checked against native `x / y`
+        div_cases: Vec<([GoldilocksField; 2], [GoldilocksField; 2])>,
+        /// Vectors checked against their native dot product
+        dot_product_case: (Vec<[GoldilocksField; 2]>, Vec<[GoldilocksField; 2]>),
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "extension arithmetic",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let a = chip.constant_extension(
+                        ctx,
+                        &[
+                            GoldilocksField::from_canonical_u64(5),
+                            GoldilocksField::from_canonical_u64(7),
+                        ],
+                    )?;
+                    let b = chip.constant_extension(
+                        ctx,
+                        &[
+                            GoldilocksField::from_canonical_u64(2),
+                            GoldilocksField::from_canonical_u64(3),
+                        ],
+                    )?;
+
+                    let neg_a = chip.neg_extension(ctx, &a)?;
+                    let expected_neg_a = chip.constant_extension(
+                        ctx,
+                        &[
+                            -GoldilocksField::from_canonical_u64(5),
+                            -GoldilocksField::from_canonical_u64(7),
+                        ],
+                    )?;
+                    chip.assert_equal_extension(ctx, &neg_a, &expected_neg_a)?;
+
+                    let zero = chip.zero_extension(ctx)?;
+                    let sum = chip.add_extension(ctx, &a, &neg_a)?;
+                    chip.assert_equal_extension(ctx, &sum, &zero)?;
+
+                    // (5 + 7w) - (2 + 3w) = 3 + 4w
+                    let a_minus_b = chip.sub_extension(ctx, &a, &b)?;
+                    let expected_a_minus_b = chip.constant_extension(
+                        ctx,
+                        &[
+                            GoldilocksField::from_canonical_u64(3),
+                            GoldilocksField::from_canonical_u64(4),
+                        ],
+                    )?;
+                    chip.assert_equal_extension(ctx, &a_minus_b, &expected_a_minus_b)?;
+
+                    // (5 + 7w)(2 + 3w) - (2 + 3w) = (10 + 21 * 7 - 2) + (15 + 14 - 3)w
+                    let ab_minus_b = chip.mul_sub_extension(ctx, &a, &b, &b)?;
+                    let expected_ab_minus_b = chip.constant_extension(
+                        ctx,
+                        &[
+                            GoldilocksField::from_canonical_u64(155),
+                            GoldilocksField::from_canonical_u64(26),
+                        ],
+                    )?;
+                    chip.assert_equal_extension(ctx, &ab_minus_b, &expected_ab_minus_b)?;
+
+                    for (x, y) in self.div_cases.iter() {
+                        let expected = (QuadraticExtension(*x) / QuadraticExtension(*y)).0;
+                        let x = chip.constant_extension(ctx, x)?;
+                        let y = chip.constant_extension(ctx, y)?;
+                        let x_div_y = chip.div_extension(ctx, &x, &y)?;
+                        let expected = chip.constant_extension(ctx, &expected)?;
+                        chip.assert_equal_extension(ctx, &x_div_y, &expected)?;
+                    }
+
+                    let (xs, ys) = &self.dot_product_case;
+                    let expected = xs
+                        .iter()
+                        .zip(ys.iter())
+                        .fold(QuadraticExtension::ZERO, |acc, (x, y)| {
+                            acc + QuadraticExtension(*x) * QuadraticExtension(*y)
+                        });
+                    let xs = xs
+                        .iter()
+                        .map(|x| chip.constant_extension(ctx, x))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let ys = ys
+                        .iter()
+                        .map(|y| chip.constant_extension(ctx, y))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let dot_product = chip.dot_product(ctx, &xs, &ys)?;
+                    let expected = chip.constant_extension(ctx, &expected.0)?;
+                    chip.assert_equal_extension(ctx, &dot_product, &expected)?;
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MulAddManyCircuit {
+        a: [GoldilocksField; 2],
+        bs: Vec<[GoldilocksField; 2]>,
+        accs: Vec<[GoldilocksField; 2]>,
+    }
+
+    impl Circuit<Fr> for MulAddManyCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "mul add many",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let a = chip.constant_extension(ctx, &self.a)?;
+                    let bs = self
+                        .bs
+                        .iter()
+                        .map(|b| chip.constant_extension(ctx, b))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let accs = self
+                        .accs
+                        .iter()
+                        .map(|acc| chip.constant_extension(ctx, acc))
+                        .collect::<Result<Vec<_>, Error>>()?;
+
+                    let start = ctx.offset();
+                    let mut fused = accs.clone();
+                    chip.mul_add_many_extension(ctx, &a, &bs, &mut fused)?;
+                    let fused_rows = ctx.offset() - start;
+
+                    let start = ctx.offset();
+                    let unfused = accs
+                        .iter()
+                        .zip(bs.iter())
+                        .map(|(acc, b)| chip.mul_add_extension(ctx, &a, b, acc))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let unfused_rows = ctx.offset() - start;
+
+                    assert!(fused_rows <= unfused_rows);
+                    // one extension mul-add, i.e. two operations of two rows, per term
+                    assert_eq!(fused_rows, 4 * bs.len());
+                    for (fused, unfused) in fused.iter().zip(unfused.iter()) {
+                        chip.assert_equal_extension(ctx, fused, unfused)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MulSubRowsCircuit {
+        a: [GoldilocksField; 2],
+        b: [GoldilocksField; 2],
+        c: [GoldilocksField; 2],
+    }
+
+    impl Circuit<Fr> for MulSubRowsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "mul sub rows",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let a = chip.constant_extension(ctx, &self.a)?;
+                    let b = chip.constant_extension(ctx, &self.b)?;
+                    let c = chip.constant_extension(ctx, &self.c)?;
+                    // the constants both formulations use are cached
+                    chip.constant_extension(ctx, &[-GoldilocksField::ONE, GoldilocksField::ZERO])?;
+                    chip.zero_extension(ctx)?;
+                    chip.goldilocks_chip()
+                        .assign_constant(ctx, GoldilocksField::ONE)?;
+
+                    let start = ctx.offset();
+                    let mul_sub = chip.mul_sub_extension(ctx, &a, &b, &c)?;
+                    let mul_sub_rows = ctx.offset() - start;
+
+                    let start = ctx.offset();
+                    let one = GoldilocksField::ONE;
+                    let arithmetic = chip.arithmetic_extension(ctx, one, -one, &a, &b, &c)?;
+                    let arithmetic_rows = ctx.offset() - start;
+
+                    // a negation and a mul-add, against a mul-add, two scalar muls of a
+                    // multiplication per limb and an addition per limb
+                    assert_eq!(mul_sub_rows, 8);
+                    assert_eq!(arithmetic_rows, 16);
+
+                    let expected = QuadraticExtension(self.a) * QuadraticExtension(self.b)
+                        - QuadraticExtension(self.c);
+                    let expected = chip.constant_extension(ctx, &expected.0)?;
+                    chip.assert_equal_extension(ctx, &mul_sub, &expected)?;
+                    chip.assert_equal_extension(ctx, &arithmetic, &expected)?;
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mul_sub_extension_rows() {
+        let rand_ext = || QuadraticExtension::<GoldilocksField>::rand().0;
+        let circuit = MulSubRowsCircuit {
+            a: rand_ext(),
+            b: rand_ext(),
+            c: rand_ext(),
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    #[derive(Clone, Default)]
+    struct AddManyCircuit {
+        terms: Vec<[GoldilocksField; 2]>,
+    }
+
+    impl Circuit<Fr> for AddManyCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "add many",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let terms = self
+                        .terms
+                        .iter()
+                        .map(|term| chip.constant_extension(ctx, term))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    // the constants `add_many_extension` and `add_extension` use are cached
+                    chip.goldilocks_chip()
+                        .assign_constant(ctx, GoldilocksField::ONE)?;
+                    chip.zero_extension(ctx)?;
+
+                    let start = ctx.offset();
+                    let sum = chip.add_many_extension(ctx, &terms)?;
+                    let many_rows = ctx.offset() - start;
+
+                    let start = ctx.offset();
+                    let zero = chip.zero_extension(ctx)?;
+                    let chained = terms
+                        .iter()
+                        .try_fold(zero, |acc, term| chip.add_extension(ctx, &acc, term))?;
+                    let chained_rows = ctx.offset() - start;
+
+                    // per limb, a row per term after the first and two to reduce, against two
+                    // rows per addition
+                    if terms.len() > 1 {
+                        assert_eq!(many_rows, 2 * (terms.len() + 1));
+                    }
+                    assert_eq!(chained_rows, 4 * terms.len());
+                    assert!(many_rows <= chained_rows);
+
+                    let expected = self
+                        .terms
+                        .iter()
+                        .map(|term| QuadraticExtension(*term))
+                        .sum::<QuadraticExtension<GoldilocksField>>();
+                    let expected = chip.constant_extension(ctx, &expected.0)?;
+                    chip.assert_equal_extension(ctx, &sum, &expected)?;
+                    chip.assert_equal_extension(ctx, &sum, &chained)?;
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_add_many_extension() {
+        let rand_ext = || QuadraticExtension::<GoldilocksField>::rand().0;
+        for terms in [
+            vec![],
+            vec![rand_ext()],
+            (0..8).map(|_| rand_ext()).collect(),
+            // the largest canonical limbs give the largest quotient to reduce
+            vec![[GoldilocksField::NEG_ONE; 2]; 64],
+        ] {
+            let circuit = AddManyCircuit { terms };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            mock_prover.assert_satisfied();
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct ConstantCacheCircuit;
+
+    impl Circuit<Fr> for ConstantCacheCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "constant cache",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let five = GoldilocksField::from_canonical_u64(5);
+                    let seven = GoldilocksField::from_canonical_u64(7);
+                    // a row per new limb
+                    let start = ctx.offset();
+                    let a = chip.constant_extension(ctx, &[five, seven])?;
+                    assert_eq!(ctx.offset() - start, 2);
+
+                    // both limbs are cached, so neither call assigns a row
+                    let start = ctx.offset();
+                    let a_again = chip.constant_extension(ctx, &[five, seven])?;
+                    let swapped = chip.constant_extension(ctx, &[seven, five])?;
+                    assert_eq!(ctx.offset(), start);
+
+                    let cells =
+                        |x: &AssignedExtensionFieldValue<Fr, 2>| x.0.clone().map(|c| c.cell());
+                    assert_eq!(cells(&a_again), cells(&a));
+                    let [a_0, a_1] = cells(&a);
+                    assert_eq!(cells(&swapped), [a_1, a_0]);
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_constant_extension_cache() {
+        let mock_prover =
+            mock_prover_at_min_k(&SmallTable(ConstantCacheCircuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_mul_add_many_extension() {
+        let rand_ext = || QuadraticExtension::<GoldilocksField>::rand().0;
+        let circuit = MulAddManyCircuit {
+            a: rand_ext(),
+            bs: (0..8).map(|_| rand_ext()).collect(),
+            accs: (0..8).map(|_| rand_ext()).collect(),
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    #[derive(Clone, Default)]
+    struct ReduceBaseCircuit {
+        base: GoldilocksField,
+        terms: Vec<[GoldilocksField; 2]>,
+    }
+
+    impl Circuit<Fr> for ReduceBaseCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "reduce with powers base",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let base = chip
+                        .goldilocks_chip()
+                        .assign_value(ctx, Value::known(goldilocks_to_fe(self.base)))?;
+                    let terms = self
+                        .terms
+                        .iter()
+                        .map(|term| chip.constant_extension(ctx, term))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    // the zero both reducers start from or pad with is cached
+                    chip.zero_extension(ctx)?;
+
+                    let start = ctx.offset();
+                    let specialized = chip.reduce_with_powers_base(ctx, &base, &terms)?;
+                    let specialized_rows = ctx.offset() - start;
+
+                    let start = ctx.offset();
+                    let generic = chip.reduce_extension_field_terms_base(ctx, &base, &terms)?;
+                    let generic_rows = ctx.offset() - start;
+
+                    // four rows per term after the leading one, against four per term
+                    assert_eq!(specialized_rows, 4 * self.terms.len().saturating_sub(1));
+                    assert_eq!(generic_rows, 4 * self.terms.len());
+
+                    let base = QuadraticExtension([self.base, GoldilocksField::ZERO]);
+                    let expected = self
+                        .terms
+                        .iter()
+                        .rev()
+                        .fold(QuadraticExtension::ZERO, |acc, term| {
+                            acc * base + QuadraticExtension(*term)
+                        });
+                    let expected = chip.constant_extension(ctx, &expected.0)?;
+                    chip.assert_equal_extension(ctx, &specialized, &expected)?;
+                    chip.assert_equal_extension(ctx, &specialized, &generic)?;
+                    Ok(())
+                },
+            )?;
+            chip.goldilocks_chip().load_table(&mut layouter)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reduce_with_powers_base() {
+        let rand_ext = || QuadraticExtension::<GoldilocksField>::rand().0;
+        for (base, terms) in [
+            (GoldilocksField::rand(), vec![]),
+            (GoldilocksField::rand(), vec![rand_ext()]),
+            // the length of the final polynomial of `ConstantArityBits(_, 5)`
+            (
+                GoldilocksField::rand(),
+                (0..32).map(|_| rand_ext()).collect(),
+            ),
+            (
+                GoldilocksField::NEG_ONE,
+                vec![[GoldilocksField::NEG_ONE; 2]; 8],
+            ),
+            (GoldilocksField::ZERO, (0..4).map(|_| rand_ext()).collect()),
+        ] {
+            let circuit = ReduceBaseCircuit { base, terms };
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            mock_prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_goldilocks_extension_chip() {
+        let div_cases = (0..16)
+            .map(|_| {
+                (
+                    QuadraticExtension::<GoldilocksField>::rand().0,
+                    QuadraticExtension::<GoldilocksField>::rand().0,
+                )
+            })
+            .chain([(
+                [GoldilocksField::ONE, GoldilocksField::ZERO],
+                [GoldilocksField::ZERO, GoldilocksField::ONE],
+            )])
+            .collect();
+        let rand_exts = |n| {
+            (0..n)
+                .map(|_| QuadraticExtension::<GoldilocksField>::rand().0)
+                .collect::<Vec<_>>()
+        };
+        let circuit = TestCircuit {
+            div_cases,
+            dot_product_case: (rand_exts(12), rand_exts(12)),
+        };
+        let instance = Vec::<Fr>::new();
+        let mock_prover =
+            mock_prover_at_min_k(&SmallTable(circuit), vec![instance.clone()]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+}
@@ -11,6 +11,9 @@ use super::goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig};
 
 const RATE: usize = 8;
 
+/// Elements of the state the inputs are never written to.
+pub const CAPACITY: usize = SPONGE_WIDTH - RATE;
+
 /// `AssignedState` is composed of `T` sized assigned values
 #[derive(Debug, Clone)]
 pub struct AssignedState<F: PrimeField>(pub(super) [AssignedValue<F>; SPONGE_WIDTH]);
@@ -30,12 +33,27 @@ impl<F: PrimeField> HasherChip<F> {
     pub fn new(
         ctx: &mut RegionCtx<'_, F>,
         goldilocks_chip_config: &GoldilocksChipConfig<F>,
+    ) -> Result<Self, Error> {
+        Self::with_capacity_init(
+            ctx,
+            goldilocks_chip_config,
+            [GoldilocksField::ZERO; CAPACITY],
+        )
+    }
+
+    /// Like `new`, with the capacity of the initial state set to `capacity_init` instead of
+    /// zeros, to match a sponge domain separated by a tag in its capacity.
+    pub fn with_capacity_init(
+        ctx: &mut RegionCtx<'_, F>,
+        goldilocks_chip_config: &GoldilocksChipConfig<F>,
+        capacity_init: [GoldilocksField; CAPACITY],
     ) -> Result<Self, Error> {
         let goldilocks_chip = GoldilocksChip::new(goldilocks_chip_config);
 
-        let initial_state = [(); SPONGE_WIDTH]
+        let initial_state = [GoldilocksField::ZERO; RATE]
             .iter()
-            .map(|_| goldilocks_chip.assign_constant(ctx, GoldilocksField::ZERO))
+            .chain(capacity_init.iter())
+            .map(|element| goldilocks_chip.assign_constant(ctx, *element))
             .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
 
         Ok(Self {
@@ -181,23 +199,29 @@ mod tests {
         plonk::{Circuit, ConstraintSystem, Error},
     };
     use plonky2::{
-        field::{goldilocks_field::GoldilocksField, types::Sample},
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::{Field, Sample},
+        },
         hash::hashing::PlonkyPermutation,
+        plonk::config::Hasher,
     };
 
     use crate::plonky2_verifier::{
-        bn245_poseidon::plonky2_config::Bn254PoseidonPermutation,
+        bn245_poseidon::plonky2_config::{Bn254PoseidonHash, Bn254PoseidonPermutation},
         chip::{
             goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
             native_chip::{
                 all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
                 utils::{fe_to_goldilocks, goldilocks_to_fe},
             },
         },
-        context::RegionCtx,
+        context::{RegionCapacityExceeded, RegionCtx, RowBudget},
+        verifier_circuit::usable_rows,
     };
 
-    use super::HasherChip;
+    use super::{HasherChip, CAPACITY, RATE, SPONGE_WIDTH};
 
     #[derive(Clone, Default)]
     pub struct TestCircuit {
@@ -264,13 +288,171 @@ mod tests {
         let input = [(); 12].map(|_| GoldilocksField::rand());
         let expected_output = Bn254PoseidonPermutation::permute(input);
 
-        const DEGREE: u32 = 17;
         let circuit = TestCircuit {
             input,
             expected_output: expected_output.to_vec().try_into().unwrap(),
         };
         let instance: Vec<Fr> = vec![];
-        let mock_prover = MockProver::run(DEGREE, &circuit, vec![instance.clone()]).unwrap();
+        let mock_prover =
+            mock_prover_at_min_k(&SmallTable(circuit), vec![instance.clone()]).unwrap();
         mock_prover.assert_satisfied();
     }
+
+    #[derive(Clone, Default)]
+    struct RegionCapacityCircuit {
+        k: u32,
+    }
+
+    impl Circuit<Fr> for RegionCapacityCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            // the lookup table alone needs more rows than `k` gives, it is left out since the
+            // regions fail before anything is looked up
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            let mut budget = RowBudget::new(Some(usable_rows::<Self>(self.k)));
+            budget.assign_region(&mut layouter, "single value", |ctx| {
+                goldilocks_chip.assign_value(ctx, Value::known(Fr::from(1u64)))?;
+                Ok(())
+            })?;
+            budget.assign_region(&mut layouter, "hash", |ctx| {
+                let inputs = (0..200u64)
+                    .map(|i| goldilocks_chip.assign_value(ctx, Value::known(Fr::from(i))))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                HasherChip::new(ctx, &config)?.hash(ctx, inputs, 4)?;
+                Ok(())
+            })?;
+            budget.assign_region(&mut layouter, "after hash", |_| Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_region_capacity_names_first_region_that_does_not_fit() {
+        const DEGREE: u32 = 8;
+        let circuit = RegionCapacityCircuit { k: DEGREE };
+        let Err(Error::Transcript(err)) = MockProver::run(DEGREE, &circuit, vec![vec![]]) else {
+            panic!("the hash doesn't fit in 2^{DEGREE} rows");
+        };
+        let exceeded = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<RegionCapacityExceeded>())
+            .unwrap();
+        assert_eq!(exceeded.region, "hash");
+        // the single value took some of the usable rows
+        assert!(exceeded.usable_rows < usable_rows::<RegionCapacityCircuit>(DEGREE));
+        assert!(exceeded.shortfall() > 0);
+    }
+
+    /// Native sponge of `HasherChip::hash`, started with `capacity_init` in its capacity.
+    fn native_hash_with_capacity(
+        inputs: &[GoldilocksField],
+        capacity_init: [GoldilocksField; CAPACITY],
+        num_outputs: usize,
+    ) -> Vec<GoldilocksField> {
+        let mut state = [GoldilocksField::ZERO; SPONGE_WIDTH];
+        state[RATE..].copy_from_slice(&capacity_init);
+        for chunk in inputs.chunks(RATE) {
+            state[..chunk.len()].copy_from_slice(chunk);
+            state = Bn254PoseidonPermutation::permute(state);
+        }
+        state[..num_outputs].to_vec()
+    }
+
+    #[derive(Clone, Default)]
+    struct CapacityInitCircuit {
+        inputs: Vec<GoldilocksField>,
+        capacity_init: [GoldilocksField; CAPACITY],
+        expected_output: Vec<GoldilocksField>,
+    }
+
+    impl Circuit<Fr> for CapacityInitCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "hash with capacity init",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let inputs = self
+                        .inputs
+                        .iter()
+                        .map(|x| goldilocks_to_fe::<Fr>(*x))
+                        .map(|x| goldilocks_chip.assign_value(ctx, Value::known(x)))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let mut hasher_chip =
+                        HasherChip::with_capacity_init(ctx, &config, self.capacity_init)?;
+                    let outputs = hasher_chip.hash(ctx, inputs, self.expected_output.len())?;
+                    for (output, expected) in outputs.iter().zip(self.expected_output.iter()) {
+                        let expected = goldilocks_chip.assign_constant(ctx, *expected)?;
+                        goldilocks_chip.assert_equal(ctx, output, &expected)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_hash_with_capacity_init() {
+        let inputs = GoldilocksField::rand_vec(20);
+        let capacity_init = [1, 2, 3, 4].map(GoldilocksField::from_canonical_u64);
+        let expected_output = native_hash_with_capacity(&inputs, capacity_init, 4);
+        // a zero tag is the sponge of `new`, a nonzero one separates the domain
+        let untagged = native_hash_with_capacity(&inputs, [GoldilocksField::ZERO; CAPACITY], 4);
+        assert_eq!(
+            untagged,
+            Bn254PoseidonHash::hash_no_pad(&inputs).elements.to_vec()
+        );
+        assert_ne!(expected_output, untagged);
+
+        let circuit = CapacityInitCircuit {
+            inputs: inputs.clone(),
+            capacity_init,
+            expected_output,
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+
+        // the untagged output doesn't open a tagged sponge
+        let circuit = CapacityInitCircuit {
+            inputs,
+            capacity_init,
+            expected_output: untagged,
+        };
+        assert!(mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .verify()
+            .is_err());
+    }
 }
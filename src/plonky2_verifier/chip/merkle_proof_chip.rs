@@ -6,7 +6,7 @@ use itertools::Itertools;
 
 use crate::plonky2_verifier::{
     context::RegionCtx,
-    types::assigned::{AssignedMerkleCapValues, AssignedMerkleProofValues},
+    types::assigned::{AssignedIndexBits, AssignedMerkleCapValues, AssignedMerkleProofValues},
 };
 
 use super::{
@@ -36,18 +36,34 @@ impl<F: PrimeField> MerkleProofChip<F> {
         HasherChip::new(ctx, &self.goldilocks_chip_config)
     }
 
-    pub fn verify_merkle_proof_to_cap_with_cap_index(
+    /// Verifies `proof` of `leaf_data` at `leaf_index` against `merkle_cap`. The most significant
+    /// `cap_height` bits of the index select the cap entry, the others give the path.
+    pub fn verify_merkle_proof_to_cap(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         leaf_data: &Vec<AssignedValue<F>>,
-        leaf_index_bits: &[AssignedValue<F>],
-        cap_index: &AssignedValue<F>,
+        leaf_index: &AssignedIndexBits<F>,
         merkle_cap: &AssignedMerkleCapValues<F>,
         proof: &AssignedMerkleProofValues<F>,
     ) -> Result<(), Error> {
         let mut hasher = self.hasher(ctx)?;
         let goldilocks_chip = self.goldilocks_chip();
 
+        debug_assert!(merkle_cap.0.len().is_power_of_two());
+        let cap_height = merkle_cap.0.len().trailing_zeros() as usize;
+        // tiny circuits can have fewer index bits than `cap_height`, which
+        // `CommonData::validate` rejects
+        let path_len = leaf_index
+            .bits()
+            .len()
+            .checked_sub(cap_height)
+            .ok_or(Error::Synthesis)?;
+        if proof.siblings.len() != path_len {
+            return Err(Error::Synthesis);
+        }
+        let (leaf_index_bits, cap_index_bits) = leaf_index.bits().split_at(path_len);
+        let cap_index = goldilocks_chip.from_bits(ctx, &cap_index_bits.to_vec())?;
+
         let mut state;
         if leaf_data.len() <= 4 {
             state = leaf_data.clone();
@@ -80,9 +96,132 @@ impl<F: PrimeField> MerkleProofChip<F> {
                     .collect_vec(),
             );
             let cap_i = vector_chip.access(ctx, &cap_index)?;
-            goldilocks_chip.assert_equal(ctx, &cap_i, &state[i])?;
+            goldilocks_chip.check_equal(ctx, &cap_i, &state[i])?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{goldilocks_field::GoldilocksField, types::Sample},
+        hash::merkle_tree::MerkleTree,
+    };
+
+    use super::MerkleProofChip;
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonHash,
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+        types::{assigned::AssignedIndexBits, proof::MerkleProofValues, MerkleCapValues},
+    };
+
+    const HEIGHT: usize = 5;
+    const CAP_HEIGHT: usize = 2;
+
+    #[derive(Clone, Default)]
+    struct MerkleProofCircuit {
+        leaf: Vec<GoldilocksField>,
+        leaf_index: usize,
+        cap: MerkleCapValues<Fr>,
+        proof: MerkleProofValues<Fr>,
+    }
+
+    impl MerkleProofCircuit {
+        fn new(leaf_index: usize) -> Self {
+            let leaves = (0..1 << HEIGHT)
+                .map(|_| GoldilocksField::rand_vec(7))
+                .collect::<Vec<_>>();
+            let tree =
+                MerkleTree::<GoldilocksField, Bn254PoseidonHash>::new(leaves.clone(), CAP_HEIGHT);
+            Self {
+                leaf: leaves[leaf_index].clone(),
+                leaf_index,
+                cap: MerkleCapValues::from(tree.cap.clone()),
+                proof: MerkleProofValues::from(tree.prove(leaf_index)),
+            }
+        }
+    }
+
+    impl Circuit<Fr> for MerkleProofCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "merkle proof",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let leaf = self
+                        .leaf
+                        .iter()
+                        .map(|e| {
+                            goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*e)))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let leaf_index = goldilocks_chip
+                        .assign_value(ctx, Value::known(Fr::from(self.leaf_index as u64)))?;
+                    let leaf_index =
+                        AssignedIndexBits::decompose(&goldilocks_chip, ctx, &leaf_index, HEIGHT)?;
+                    let cap = MerkleCapValues::assign(&config, ctx, &self.cap)?;
+                    let proof = MerkleProofValues::assign(&config, ctx, &self.proof)?;
+                    MerkleProofChip::new(&config).verify_merkle_proof_to_cap(
+                        ctx,
+                        &leaf,
+                        &leaf_index,
+                        &cap,
+                        &proof,
+                    )
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_verify_merkle_proof_to_cap() {
+        // the top `CAP_HEIGHT` bits of the index select the third cap entry
+        let circuit = MerkleProofCircuit::new(0b10110);
+        let prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_cap_index_binds_to_leaf_index() {
+        // the root of the proven subtree moves to another cap entry, and nothing but the leaf
+        // index selects the entry the proof is checked against
+        let mut circuit = MerkleProofCircuit::new(0b10110);
+        circuit.cap.0.rotate_left(1);
+        let prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -8,5 +8,6 @@ pub mod native_chip;
 pub mod plonk;
 pub mod poseidon_spec;
 pub mod public_inputs_hasher_chip;
+pub mod reducing_factor_chip;
 pub mod transcript_chip;
 pub mod vector_chip;
@@ -8,7 +8,9 @@ use plonky2::hash::hashing::SPONGE_WIDTH;
 use crate::plonky2_verifier::{bn245_poseidon::constants::T_BN254_POSEIDON, context::RegionCtx};
 
 use super::{
-    arithmetic_chip::{ArithmeticChip, ArithmeticChipConfig},
+    arithmetic_chip::{
+        ArithmeticChip, ArithmeticChipConfig, RangeChecked, TableMode, TableModeConfig,
+    },
     poseidon_bn254_chip::{PoseidonBn254Chip, PoseidonBn254ChipConfig},
 };
 
@@ -20,7 +22,13 @@ pub struct AllChipConfig<F: PrimeField> {
 
 impl<F: PrimeField> AllChipConfig<F> {
     pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let arithmetic_config = ArithmeticChipConfig::configure(meta);
+        Self::configure_with_table_mode(meta, TableMode::default())
+    }
+}
+
+impl<F: PrimeField> TableModeConfig<F> for AllChipConfig<F> {
+    fn configure_with_table_mode(meta: &mut ConstraintSystem<F>, table_mode: TableMode) -> Self {
+        let arithmetic_config = ArithmeticChipConfig::configure_with_table_mode(meta, table_mode);
         let poseidon_config = PoseidonBn254ChipConfig::configure(meta);
         Self {
             arithmetic_config,
@@ -49,6 +57,9 @@ impl<F: PrimeField> AllChip<F> {
         PoseidonBn254Chip::new(&self.config.poseidon_config)
     }
 
+    /// Poseidon permutation of a state of canonical Goldilocks cells, which holds for the cells
+    /// assigned by `GoldilocksChip` and so for every state of `HasherChip`, including the
+    /// outputs of the previous permutation.
     pub fn permute(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -63,8 +74,8 @@ impl<F: PrimeField> AllChip<F> {
         let mut encoded_state = state
             .chunks(3)
             .map(|chunk| {
-                let composed = arithmetic_chip.pack(ctx, chunk.to_vec().try_into().unwrap())?;
-                Ok(composed)
+                let checked = chunk.iter().cloned().map(RangeChecked::assume_canonical);
+                arithmetic_chip.pack(ctx, checked.collect::<Vec<_>>().try_into().unwrap())
             })
             .collect::<Result<Vec<_>, Error>>()?;
         encoded_state.resize(T_BN254_POSEIDON, zero.clone());
@@ -84,6 +95,8 @@ impl<F: PrimeField> AllChip<F> {
         let offset_end_decompose = ctx.offset();
 
         let max_offset = offset_end_decompose.max(offset_end_permute);
+        ctx.set_offset(offset_start);
+        ctx.ensure_capacity(max_offset - offset_start)?;
         ctx.set_offset(max_offset);
         Ok(decoded_state.try_into().unwrap())
     }
@@ -136,6 +149,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
     fn test_all_chip_on_chain_verification() {
         const DEGREE: u32 = 17;
         let circuit = TestCircuit;
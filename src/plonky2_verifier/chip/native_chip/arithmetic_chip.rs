@@ -18,9 +18,77 @@ use super::utils::goldilocks_decompose;
 
 pub const GOLDILOCKS_MODULUS: u64 = ((1 << 32) - 1) * (1 << 32) + 1;
 
-const Q_LIMBS: usize = 5;
+/// Bits the limbs of `q` bound it by.
+const Q_BITS: usize = 80;
+/// Bits the limbs of `r` bound it by, on top of which the limb gate checks `r < p`.
+const R_BITS: usize = 64;
+
+/// Bits of the limbs of `q` and `r`, and of the values of the lookup table they are range
+/// checked with. `q` and `r` are bound by the same `2^80` and `2^64` either way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableMode {
+    /// 16-bit limbs, 5 limb columns and a table of `2^16` rows.
+    #[default]
+    Bits16,
+    /// 8-bit limbs, 10 limb columns and a table of `2^8` rows. It takes twice the limb columns
+    /// and lookups, but fits circuits of `2^9` rows, so the tests of the chips run at small `k`.
+    Bits8,
+}
+
+impl TableMode {
+    pub const fn bits(self) -> usize {
+        match self {
+            TableMode::Bits16 => 16,
+            TableMode::Bits8 => 8,
+        }
+    }
+
+    /// Number of limb columns, shared by the decompositions of `q` and `r`.
+    pub fn limbs(self) -> usize {
+        Q_BITS / self.bits()
+    }
+
+    fn r_limbs(self) -> usize {
+        R_BITS / self.bits()
+    }
+
+    /// Smallest `k` of a circuit with the table, which leaves no room for the blinding rows in
+    /// `2^bits` rows.
+    pub const fn min_k(self) -> u32 {
+        self.bits() as u32 + 1
+    }
+}
+
+/// Configs built on `ArithmeticChipConfig`, which can be configured with any `TableMode`.
+pub trait TableModeConfig<F: PrimeField>: Sized {
+    fn configure_with_table_mode(meta: &mut ConstraintSystem<F>, table_mode: TableMode) -> Self;
+}
+
+/// A cell constrained to hold a canonical Goldilocks element, i.e. a value below
+/// `GOLDILOCKS_MODULUS`. Packing cells that are not, e.g. the unreduced results of
+/// `mul_add_no_mod`, would let `(p + a, b)` and `(a, b + 1)` encode to the same word.
+#[derive(Clone, Debug)]
+pub struct RangeChecked<F: PrimeField>(AssignedCell<F, F>);
+
+impl<F: PrimeField> RangeChecked<F> {
+    /// Wraps a cell without a range check, for cells already known to be canonical, such as
+    /// every cell assigned by `GoldilocksChip` or returned by `unpack`.
+    pub(crate) fn assume_canonical(x: AssignedCell<F, F>) -> Self {
+        Self(x)
+    }
+
+    pub fn cell(&self) -> &AssignedCell<F, F> {
+        &self.0
+    }
+}
 
 // a*b + c = q*p + r, with range check of q and r
+// The limbs of q sit on the row of the operation and the limbs of r on the row below it, so
+// every operation with a range check takes two rows and only `TableMode::limbs` lookups are
+// needed.
+// The operands are expected to be below 2^64, which holds for every cell assigned by the chip,
+// so that q < 2^80 and a*b + c = q*p + r holds over the integers. Together with r < p this
+// makes r the canonical reduction of a*b + c.
 #[derive(Clone, Debug)]
 pub struct ArithmeticChipConfig<F: PrimeField> {
     pub a: Column<Advice>,
@@ -28,13 +96,13 @@ pub struct ArithmeticChipConfig<F: PrimeField> {
     pub c: Column<Advice>,
     pub q: Column<Advice>,
     pub r: Column<Advice>,
-    pub q_limbs: [Column<Advice>; Q_LIMBS],
-    pub r_limbs: [Column<Advice>; 4],
+    pub limbs: Vec<Column<Advice>>,
     pub table: TableColumn,
+    pub table_mode: TableMode,
     pub instance: Column<Instance>,
     pub constant: Column<Fixed>,
-    pub s_limb: Selector,  // limb decomposition of q and r
-    pub s_range: Selector, // contraint q = p - r
+    pub s_limb: Selector,  // limb decomposition of q and r, and r < p
+    pub s_range: Selector, // contraint q = p - 1 - r
     pub s_base: Selector,  // contraint a*b + c == q*p + r
     pub s_ext: Selector,   // contraint a*b + c == q*p + r on extension field
     _marker: PhantomData<F>,
@@ -42,13 +110,35 @@ pub struct ArithmeticChipConfig<F: PrimeField> {
 
 impl<F: PrimeField> ArithmeticChipConfig<F> {
     pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_with_table_mode(meta, TableMode::default())
+    }
+
+    /// Values of the lookup table, `0..2^bits` in order.
+    pub fn table_values(&self) -> impl Iterator<Item = F> {
+        (0..1u64 << self.table_mode.bits()).map(F::from)
+    }
+
+    /// Hex encoded blake2b-512 digest of the representations of `table_values`, so tests can
+    /// pin the table contents.
+    pub fn table_checksum(&self) -> String {
+        let mut state = blake2b_simd::State::new();
+        for value in self.table_values() {
+            state.update(value.to_repr().as_ref());
+        }
+        state.finalize().to_hex().to_string()
+    }
+}
+
+impl<F: PrimeField> TableModeConfig<F> for ArithmeticChipConfig<F> {
+    fn configure_with_table_mode(meta: &mut ConstraintSystem<F>, table_mode: TableMode) -> Self {
         let a = meta.advice_column();
         let b = meta.advice_column();
         let c = meta.advice_column();
         let q = meta.advice_column();
         let r = meta.advice_column();
-        let q_limbs = [(); Q_LIMBS].map(|_| meta.advice_column());
-        let r_limbs = [(); 4].map(|_| meta.advice_column());
+        let limbs = (0..table_mode.limbs())
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>();
 
         let constant = meta.fixed_column();
         let s_limb = meta.selector();
@@ -68,31 +158,63 @@ impl<F: PrimeField> ArithmeticChipConfig<F> {
         meta.enable_constant(constant);
 
         meta.create_gate("limb decomposition", |meta| {
+            let shift =
+                |i: usize| Expression::Constant(F::from_u128(1u128 << (i * table_mode.bits())));
             let s_limb = meta.query_selector(s_limb);
             let q = meta.query_advice(q, Rotation::cur());
-            let q_limbs = q_limbs
-                .map(|l| meta.query_advice(l, Rotation::cur()))
-                .to_vec();
-            let q_acc = (0..Q_LIMBS).fold(Expression::Constant(F::from(0)), |acc, i| {
-                acc + q_limbs[i].clone() * Expression::Constant(F::from_u128(1u128 << (i * 16)))
-            });
+            let q_limbs = limbs
+                .iter()
+                .map(|l| meta.query_advice(*l, Rotation::cur()))
+                .collect::<Vec<_>>();
             let r = meta.query_advice(r, Rotation::cur());
-            let r_limbs = r_limbs
-                .map(|l| meta.query_advice(l, Rotation::cur()))
-                .to_vec();
-            let r_acc = (0..4).fold(Expression::Constant(F::from(0)), |acc, i| {
-                acc + r_limbs[i].clone() * Expression::Constant(F::from_u128(1u128 << (i * 16)))
-            });
-            vec![s_limb.clone() * (q - q_acc), s_limb.clone() * (r - r_acc)]
+            let r_limbs = limbs
+                .iter()
+                .map(|l| meta.query_advice(*l, Rotation::next()))
+                .collect::<Vec<_>>();
+            let (r_limbs, unused_limbs) = r_limbs.split_at(table_mode.r_limbs());
+            let acc = |limbs: &[Expression<F>]| {
+                limbs
+                    .iter()
+                    .enumerate()
+                    .fold(Expression::Constant(F::from(0)), |acc, (i, limb)| {
+                        acc + limb.clone() * shift(i)
+                    })
+            };
+            let q_acc = acc(&q_limbs);
+            let r_acc = acc(r_limbs);
+            // r < p iff the low half of r is zero whenever the high half is 2^32 - 1. The q
+            // cell below q holds `r_lo / (r_hi - (2^32 - 1))`, or anything if r_hi = 2^32 - 1.
+            let half = |limbs: &[Expression<F>]| {
+                let (first, rest) = limbs.split_first().unwrap();
+                rest.iter()
+                    .enumerate()
+                    .fold(first.clone(), |acc, (i, limb)| {
+                        acc + limb.clone() * shift(i + 1)
+                    })
+            };
+            let (r_lo_limbs, r_hi_limbs) = r_limbs.split_at(r_limbs.len() / 2);
+            let r_lo = half(r_lo_limbs);
+            let r_hi = half(r_hi_limbs);
+            let r_hi_max = Expression::Constant(F::from(u32::MAX as u64));
+            let r_lo_ratio = meta.query_advice(q, Rotation::next());
+            let mut constraints = vec![s_limb.clone() * (q - q_acc), s_limb.clone() * (r - r_acc)];
+            // r fits in fewer limbs than q, so the last limb columns are zero below q
+            constraints.extend(
+                unused_limbs
+                    .iter()
+                    .map(|limb| s_limb.clone() * limb.clone()),
+            );
+            constraints.push(s_limb.clone() * ((r_hi - r_hi_max) * r_lo_ratio - r_lo));
+            constraints
         });
 
         // This custom gate ensures that r satisfies 0 <= r < GOLDILOCKS_MODULUS when s_range is enabled.
-        meta.create_gate("q = p - r", |meta| {
+        meta.create_gate("q = p - 1 - r", |meta| {
             let q = meta.query_advice(q, Rotation::cur());
             let r = meta.query_advice(r, Rotation::cur());
-            let p = Expression::Constant(F::from(GOLDILOCKS_MODULUS));
+            let p_minus_one = Expression::Constant(F::from(GOLDILOCKS_MODULUS - 1));
             let s_range = meta.query_selector(s_range);
-            vec![s_range * (q - p + r)]
+            vec![s_range * (q - p_minus_one + r)]
         });
 
         meta.create_gate("base field constraint", |meta| {
@@ -109,15 +231,16 @@ impl<F: PrimeField> ArithmeticChipConfig<F> {
         meta.create_gate("extension field contraint", |meta| {
             let s_ext = meta.query_selector(s_ext);
             let a_x = meta.query_advice(a, Rotation::cur());
-            let a_y = meta.query_advice(a, Rotation::next());
+            // the y coordinate follows the two rows of the x coordinate
+            let a_y = meta.query_advice(a, Rotation(2));
             let b_x = meta.query_advice(b, Rotation::cur());
-            let b_y = meta.query_advice(b, Rotation::next());
+            let b_y = meta.query_advice(b, Rotation(2));
             let c_x = meta.query_advice(c, Rotation::cur());
-            let c_y = meta.query_advice(c, Rotation::next());
+            let c_y = meta.query_advice(c, Rotation(2));
             let q_x = meta.query_advice(q, Rotation::cur());
-            let q_y = meta.query_advice(q, Rotation::next());
+            let q_y = meta.query_advice(q, Rotation(2));
             let r_x = meta.query_advice(r, Rotation::cur());
-            let r_y = meta.query_advice(r, Rotation::next());
+            let r_y = meta.query_advice(r, Rotation(2));
             let p = Expression::Constant(F::from(GOLDILOCKS_MODULUS));
             let left_x = a_x.clone() * b_x.clone()
                 + Expression::Constant(F::from(7)) * a_y.clone() * b_y.clone()
@@ -131,14 +254,8 @@ impl<F: PrimeField> ArithmeticChipConfig<F> {
             ]
         });
 
-        q_limbs.iter().for_each(|limb| {
-            meta.lookup("q_limbs range check", |meta| {
-                let l = meta.query_advice(*limb, Rotation::cur());
-                vec![(l, table)]
-            });
-        });
-        r_limbs.iter().for_each(|limb| {
-            meta.lookup("r_limbs range check", |meta| {
+        limbs.iter().for_each(|limb| {
+            meta.lookup("limbs range check", |meta| {
                 let l = meta.query_advice(*limb, Rotation::cur());
                 vec![(l, table)]
             });
@@ -149,9 +266,9 @@ impl<F: PrimeField> ArithmeticChipConfig<F> {
             c,
             q,
             r,
-            q_limbs,
-            r_limbs,
+            limbs,
             table,
+            table_mode,
             instance,
             constant,
             s_limb,
@@ -181,6 +298,9 @@ pub struct AssignedArithmeticExt<F: PrimeField> {
 pub enum Term<'a, F: PrimeField> {
     Assigned(&'a AssignedCell<F, F>),
     Unassigned(Value<F>),
+    /// Fixed on a row of the operation instead of assigned on a row of its own. An operation
+    /// takes two rows, so it has room for two constants.
+    Constant(F),
 }
 
 #[derive(Clone)]
@@ -257,11 +377,20 @@ impl<F: PrimeField> ArithmeticChip<F> {
         &self,
         ctx: &mut RegionCtx<'_, F>,
         unassigned: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let q = Value::known(F::from(GOLDILOCKS_MODULUS - 1)) - unassigned;
+        self.assign_value_with_witness(ctx, q, unassigned)
+    }
+
+    // `assign_value` with the given q instead of p - 1 - r
+    fn assign_value_with_witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        q: Value<F>,
+        r: Value<F>,
     ) -> Result<AssignedCell<F, F>, Error> {
         ctx.enable(self.config.s_limb)?;
         ctx.enable(self.config.s_range)?;
-        let r = unassigned.clone();
-        let q = Value::known(F::from(GOLDILOCKS_MODULUS)) - r.clone();
         let (_q_assigned, r_assigned) = assign_q_and_r(&self.config, ctx, q, r)?;
         ctx.next();
         Ok(r_assigned)
@@ -272,10 +401,55 @@ impl<F: PrimeField> ArithmeticChip<F> {
         &self,
         ctx: &mut RegionCtx<'_, F>,
         x: &AssignedCell<F, F>,
-    ) -> Result<(), Error> {
+    ) -> Result<RangeChecked<F>, Error> {
         let assigned = self.assign_value(ctx, x.value().cloned())?;
         self.assert_equal(ctx, x, &assigned)?;
-        Ok(())
+        Ok(RangeChecked(x.clone()))
+    }
+
+    // assert 0 <= x < 2^bits for bits <= 63. Once x < GOLDILOCKS_MODULUS, the row
+    // x * (2^(80 - bits) * p) + 0 = q * p + 0 has q = x * 2^(80 - bits) without wrapping, and
+    // the limbs of q bound it by 2^80
+    pub fn assert_in_range(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<RangeChecked<F>, Error> {
+        assert!(
+            bits <= 63,
+            "at most 63 bits, use range_check for the whole field"
+        );
+        let checked = self.range_check(ctx, x)?;
+        let shift = F::from(2).pow([(Q_BITS - bits) as u64]);
+        let shifted = self.apply(
+            ctx,
+            Term::Assigned(x),
+            Term::Constant(shift * F::from(GOLDILOCKS_MODULUS)),
+            Term::Constant(F::ZERO),
+        )?;
+        let zero = self.assign_constant(ctx, F::ZERO)?;
+        self.assert_equal(ctx, &shifted.r, &zero)?;
+        Ok(checked)
+    }
+
+    // canonical goldilocks representative of x, i.e. x mod GOLDILOCKS_MODULUS. x is any cell
+    // below 2^80 * GOLDILOCKS_MODULUS, e.g. an unreduced result of `sum` or `pack`, so that the
+    // quotient fits in its limbs
+    pub fn reduce(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let one = self.assign_constant(ctx, F::ONE)?;
+        let zero = self.assign_constant(ctx, F::ZERO)?;
+        let reduced = self.apply(
+            ctx,
+            Term::Assigned(x),
+            Term::Assigned(&one),
+            Term::Assigned(&zero),
+        )?;
+        Ok(reduced.r)
     }
 
     fn assign(
@@ -284,20 +458,27 @@ impl<F: PrimeField> ArithmeticChip<F> {
         a: Value<F>,
         b: Value<F>,
         c: Value<F>,
+    ) -> Result<AssignedArithmetic<F>, Error> {
+        let (q, r) = (a * b + c).map(div_rem_goldilocks).unzip();
+        self.assign_with_witness(ctx, a, b, c, q, r)
+    }
+
+    // `assign` with the given q and r instead of the quotient and remainder of a*b + c
+    fn assign_with_witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: Value<F>,
+        b: Value<F>,
+        c: Value<F>,
+        q: Value<F>,
+        r: Value<F>,
     ) -> Result<AssignedArithmetic<F>, Error> {
         ctx.enable(self.config.s_base)?;
         ctx.enable(self.config.s_limb)?;
-        let tmp = a * b + c;
-        let (q, r) = tmp
-            .map(|t| {
-                let (q, r) = fe_to_big(t).div_rem(&BigUint::from(GOLDILOCKS_MODULUS));
-                (big_to_fe::<F>(q), big_to_fe::<F>(r))
-            })
-            .unzip();
-        let (_q_assigned, r_assigned) = assign_q_and_r(&self.config, ctx, q, r)?;
         let a_assigned = ctx.assign_advice(|| "a", self.config.a, a)?;
         let b_assigned = ctx.assign_advice(|| "b", self.config.b, b)?;
         let c_assigned = ctx.assign_advice(|| "c", self.config.c, c)?;
+        let (_q_assigned, r_assigned) = assign_q_and_r(&self.config, ctx, q, r)?;
         ctx.next();
         Ok(AssignedArithmetic {
             a: a_assigned,
@@ -314,31 +495,38 @@ impl<F: PrimeField> ArithmeticChip<F> {
         b: [Value<F>; 2],
         c: [Value<F>; 2],
     ) -> Result<AssignedArithmeticExt<F>, Error> {
-        ctx.enable(self.config.s_ext)?;
-        ctx.enable(self.config.s_limb)?;
         let tmp_x = a[0] * b[0] + Value::known(F::from(7)) * a[1] * b[1] + c[0];
         let tmp_y = a[0] * b[1] + a[1] * b[0] + c[1];
-        let (q_x, r_x) = tmp_x
-            .map(|t| {
-                let (q, r) = fe_to_big(t).div_rem(&BigUint::from(GOLDILOCKS_MODULUS));
-                (big_to_fe::<F>(q), big_to_fe::<F>(r))
-            })
-            .unzip();
-        let (q_y, r_y) = tmp_y
-            .map(|t| {
-                let (q, r) = fe_to_big(t).div_rem(&BigUint::from(GOLDILOCKS_MODULUS));
-                (big_to_fe::<F>(q), big_to_fe::<F>(r))
-            })
-            .unzip();
-        let (_q_x_assigned, r_x_assigned) = assign_q_and_r(&self.config, ctx, q_x, r_x)?;
+        let (q_x, r_x) = tmp_x.map(div_rem_goldilocks).unzip();
+        let (q_y, r_y) = tmp_y.map(div_rem_goldilocks).unzip();
+        self.assign_ext_with_witness(ctx, a, b, c, [q_x, q_y], [r_x, r_y])
+    }
+
+    // `assign_ext` with the given q and r instead of the quotients and remainders of the
+    // coordinates
+    fn assign_ext_with_witness(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: [Value<F>; 2],
+        b: [Value<F>; 2],
+        c: [Value<F>; 2],
+        q: [Value<F>; 2],
+        r: [Value<F>; 2],
+    ) -> Result<AssignedArithmeticExt<F>, Error> {
+        let [q_x, q_y] = q;
+        let [r_x, r_y] = r;
+        ctx.enable(self.config.s_ext)?;
+        ctx.enable(self.config.s_limb)?;
         let a_x_assigned = ctx.assign_advice(|| "a", self.config.a, a[0])?;
         let b_x_assigned = ctx.assign_advice(|| "b", self.config.b, b[0])?;
         let c_x_assigned = ctx.assign_advice(|| "c", self.config.c, c[0])?;
+        let (_q_x_assigned, r_x_assigned) = assign_q_and_r(&self.config, ctx, q_x, r_x)?;
         ctx.next();
-        let (_q_y_assigned, r_y_assigned) = assign_q_and_r(&self.config, ctx, q_y, r_y)?;
+        ctx.enable(self.config.s_limb)?;
         let a_y_assigned = ctx.assign_advice(|| "a", self.config.a, a[1])?;
         let b_y_assigned = ctx.assign_advice(|| "b", self.config.b, b[1])?;
         let c_y_assigned = ctx.assign_advice(|| "c", self.config.c, c[1])?;
+        let (_q_y_assigned, r_y_assigned) = assign_q_and_r(&self.config, ctx, q_y, r_y)?;
         ctx.next();
         Ok(AssignedArithmeticExt {
             a: [a_x_assigned, a_y_assigned],
@@ -362,17 +550,31 @@ impl<F: PrimeField> ArithmeticChip<F> {
                 let x = match x {
                     Term::Assigned(x) => x.value().cloned(),
                     Term::Unassigned(x) => x.clone(),
+                    Term::Constant(x) => Value::known(*x),
                 };
                 x
             })
             .collect::<Vec<_>>();
+        let row = ctx.offset();
         let assigned = self.assign(ctx, unassigned[0], unassigned[1], unassigned[2])?;
         let assigned_terms = vec![&assigned.a, &assigned.b, &assigned.c];
         // constrain
+        let mut constant_row = row;
         for (input_term, assigned_term) in inputs.iter().zip(assigned_terms.iter()) {
             match input_term {
                 Term::Assigned(input_term) => self.assert_equal(ctx, input_term, assigned_term)?,
                 Term::Unassigned(_) => (),
+                Term::Constant(constant) => {
+                    if constant_row == ctx.offset() {
+                        return Err(Error::Synthesis);
+                    }
+                    let offset = ctx.offset();
+                    ctx.set_offset(constant_row);
+                    let fixed = ctx.assign_fixed(|| "constant", self.config.constant, *constant);
+                    ctx.set_offset(offset);
+                    ctx.constrain_equal(assigned_term.cell(), fixed?.cell())?;
+                    constant_row += 1;
+                }
             }
         }
         Ok(assigned)
@@ -450,15 +652,39 @@ impl<F: PrimeField> ArithmeticChip<F> {
         Ok(acc)
     }
 
-    // pack 3 goldilocks field elements to a single field element
+    // sum of goldilocks field elements, accumulated without taking modulo and reduced once
+    pub fn sum(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // every term is below 2^64, so the quotient of the reduction stays below 2^80
+        assert!(terms.len() <= 1 << 16, "too many terms to reduce at once");
+        let (first, rest) = match terms.split_first() {
+            Some(split) => split,
+            None => return self.assign_constant(ctx, F::ZERO),
+        };
+        if rest.is_empty() {
+            return Ok(first.clone());
+        }
+        let one = self.assign_constant(ctx, F::ONE)?;
+        let mut acc = first.clone();
+        for term in rest {
+            acc = self.mul_add_no_mod(ctx, term, &one, &acc)?;
+        }
+        self.reduce(ctx, &acc)
+    }
+
+    // pack 3 canonical goldilocks field elements to a single field element
     pub fn pack(
         &self,
         ctx: &mut RegionCtx<'_, F>,
-        x: [AssignedCell<F, F>; 3],
+        x: [RangeChecked<F>; 3],
     ) -> Result<AssignedCell<F, F>, Error> {
         let coeff = (0..3)
             .map(|i| self.assign_constant(ctx, F::from(GOLDILOCKS_MODULUS).pow([i as u64])))
             .collect::<Result<Vec<_>, Error>>()?;
+        let x = x.map(|x| x.0);
         self.inner_product_no_mod(ctx, &x, &coeff)
     }
 
@@ -485,6 +711,11 @@ impl<F: PrimeField> ArithmeticChip<F> {
         Ok(decomposed[0..3].to_vec().try_into().unwrap())
     }
 
+    /// Assigns `ArithmeticChipConfig::table_values` to the table column. The column is fixed,
+    /// so its values only matter to keygen, which commits to them in the proving key, and to
+    /// the mock prover. The prover's witness collection ignores fixed assignments without
+    /// calling their value closures, which leaves it the table layouter's bookkeeping of the
+    /// `2^bits` cells.
     pub fn load_table(
         &self,
         layouter: &mut impl Layouter<F>,
@@ -492,12 +723,12 @@ impl<F: PrimeField> ArithmeticChip<F> {
         layouter.assign_table(
             || "range table",
             |mut table| {
-                for offset in 0..1 << 16 {
+                for (offset, value) in self.config.table_values().enumerate() {
                     table.assign_cell(
                         || "value",
                         self.config.table,
                         offset,
-                        || Value::known(F::from(offset as u64)),
+                        || Value::known(value),
                     )?;
                 }
                 Ok(())
@@ -507,44 +738,71 @@ impl<F: PrimeField> ArithmeticChip<F> {
     }
 }
 
-// assign q and r with limb decomposition
+fn div_rem_goldilocks<F: PrimeField>(x: F) -> (F, F) {
+    let (q, r) = fe_to_big(x).div_rem(&BigUint::from(GOLDILOCKS_MODULUS));
+    (big_to_fe(q), big_to_fe(r))
+}
+
+// `r_lo / (r_hi - (2^32 - 1))` of the 32-bit halves of r, zero if the high half is 2^32 - 1
+fn r_lo_ratio<F: PrimeField>(r: F) -> F {
+    let halves = decompose(r, 2, 32);
+    let (r_lo, r_hi) = (halves[0], halves[1]);
+    Option::<F>::from((r_hi - F::from(u32::MAX as u64)).invert()).map_or(F::ZERO, |inv| r_lo * inv)
+}
+
+// assign q and r with limb decomposition, leaving the offset on the row of the r limbs
 fn assign_q_and_r<F: PrimeField>(
     config: &ArithmeticChipConfig<F>,
     ctx: &mut RegionCtx<'_, F>,
     q: Value<F>,
     r: Value<F>,
 ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
-    let q_limb = q.map(|x| decompose(x, Q_LIMBS, 16)).transpose_vec(Q_LIMBS);
-    let r_limb = r.map(|x| decompose(x, 4, 16)).transpose_vec(4);
+    let (limbs, bits) = (config.table_mode.limbs(), config.table_mode.bits());
+    let q_limb = q.map(|x| decompose(x, limbs, bits)).transpose_vec(limbs);
+    let r_limb = r.map(|x| decompose(x, limbs, bits)).transpose_vec(limbs);
+    let q_assigned = ctx.assign_advice(|| "q", config.q, q)?;
+    let r_assigned = ctx.assign_advice(|| "r", config.r, r)?;
     config
-        .q_limbs
+        .limbs
         .iter()
         .zip(q_limb.iter())
         .map(|(limb_col, limb)| ctx.assign_advice(|| "", *limb_col, *limb))
         .collect::<Result<Vec<_>, Error>>()?;
+    ctx.next();
     config
-        .r_limbs
+        .limbs
         .iter()
         .zip(r_limb.iter())
         .map(|(limb_col, limb)| ctx.assign_advice(|| "", *limb_col, *limb))
         .collect::<Result<Vec<_>, Error>>()?;
-    let q_assigned = ctx.assign_advice(|| "q", config.q, q)?;
-    let r_assigned = ctx.assign_advice(|| "r", config.r, r)?;
+    ctx.assign_advice(|| "r_lo ratio", config.q, r.map(r_lo_ratio))?;
     Ok((q_assigned, r_assigned))
 }
 
 #[cfg(test)]
 mod tests {
     use halo2_proofs::{
-        circuit::{floor_planner::V1, Layouter},
-        dev::MockProver,
-        halo2curves::bn256::Fr,
+        circuit::{floor_planner::V1, Layouter, Value},
+        dev::{MockProver, VerifyFailure},
+        halo2curves::{
+            bn256::Fr,
+            ff::{Field, PrimeField},
+        },
         plonk::{Circuit, ConstraintSystem, Error},
     };
+    use halo2wrong_maingate::{big_to_fe, fe_to_big};
+    use num_bigint::BigUint;
+    use num_integer::Integer;
+    use proptest::{array::uniform2, collection::vec, prelude::*, sample::select};
 
-    use crate::plonky2_verifier::context::RegionCtx;
+    use crate::plonky2_verifier::{
+        chip::native_chip::test_utils::{mock_prover_at_min_k, SmallTable},
+        context::RegionCtx,
+    };
 
-    use super::{ArithmeticChipConfig, TermExt};
+    use super::{
+        ArithmeticChipConfig, TableMode, TableModeConfig, Term, TermExt, GOLDILOCKS_MODULUS,
+    };
 
     #[derive(Clone, Default)]
     pub struct TestCircuit;
@@ -576,16 +834,26 @@ mod tests {
                     let a = chip.assign_constant(ctx, Fr::from(1u64))?;
                     let _b = chip.assign_value(ctx, a.value().cloned())?;
 
-                    let packed = chip.pack(ctx, [a.clone(), a.clone(), a.clone()])?;
+                    let checked = chip.range_check(ctx, &a)?;
+                    let packed = chip.pack(ctx, [checked.clone(), checked.clone(), checked])?;
                     let decomposed = chip.unpack(ctx, &packed)?;
                     for i in 0..3 {
                         chip.assert_equal(ctx, &a, &decomposed[i])?;
                     }
-                    chip.range_check(ctx, &a)?;
 
                     let c_x = chip.assign_constant(ctx, Fr::from(1u64))?;
                     let c_y = chip.assign_constant(ctx, Fr::from(1u64))?;
                     let c = [c_x.clone(), c_y.clone()];
+                    let b = chip.assign_constant(ctx, Fr::from(3u64))?;
+                    let e = chip.apply(
+                        ctx,
+                        Term::Assigned(&b),
+                        Term::Assigned(&b),
+                        Term::Assigned(&a),
+                    )?;
+                    let expected = chip.assign_constant(ctx, Fr::from(10u64))?;
+                    chip.assert_equal(ctx, &e.r, &expected)?;
+
                     let _d = chip.apply_ext(
                         ctx,
                         TermExt::Assigned(&c),
@@ -606,5 +874,451 @@ mod tests {
         let instance = vec![];
         let mock_prover = MockProver::run(17, &circuit, vec![instance.clone()]).unwrap();
         mock_prover.assert_satisfied();
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![instance]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    /// Reduces `a * b + c`, computed without taking modulo, for each of `operands`.
+    #[derive(Clone, Default)]
+    struct ReduceCircuit {
+        operands: Vec<(u64, u64, u64)>,
+    }
+
+    impl Circuit<Fr> for ReduceCircuit {
+        type Config = ArithmeticChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            ArithmeticChipConfig::<Fr>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = super::ArithmeticChip::new(&config);
+            chip.load_table(&mut layouter)?;
+
+            layouter.assign_region(
+                || "reduce",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    for &(a, b, c) in self.operands.iter() {
+                        let [a, b, c] =
+                            [a, b, c].map(|x| chip.assign_value(ctx, Value::known(Fr::from(x))));
+                        let x = chip.mul_add_no_mod(ctx, &a?, &b?, &c?)?;
+                        let reduced = chip.reduce(ctx, &x)?;
+
+                        let canonical = x
+                            .value()
+                            .map(|x| big_to_fe::<Fr>(fe_to_big(*x) % GOLDILOCKS_MODULUS));
+                        reduced
+                            .value()
+                            .zip(canonical)
+                            .assert_if_known(|(r, canonical)| {
+                                *r == canonical && fe_to_big(**r) < GOLDILOCKS_MODULUS.into()
+                            });
+                        let canonical = chip.assign_value(ctx, canonical)?;
+                        chip.assert_equal(ctx, &reduced, &canonical)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reduce_to_canonical() {
+        let p = GOLDILOCKS_MODULUS;
+        let circuit = ReduceCircuit {
+            operands: vec![
+                // already canonical
+                (0, 0, 0),
+                (1, 1, p - 2),
+                // p itself, and just above p and 2p
+                (1, 1, p - 1),
+                (1, p - 1, 6),
+                (2, p - 1, 5),
+                // the largest product of canonical elements
+                (p - 1, p - 1, p - 1),
+            ],
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_limb_columns_are_shared() {
+        for table_mode in [TableMode::Bits16, TableMode::Bits8] {
+            let mut meta = ConstraintSystem::<Fr>::default();
+            ArithmeticChipConfig::<Fr>::configure_with_table_mode(&mut meta, table_mode);
+            assert_eq!(meta.lookups().len(), table_mode.limbs());
+        }
+    }
+
+    /// One operation of the chip with an injected quotient and remainder.
+    #[derive(Clone, Debug)]
+    enum Case {
+        Range {
+            q: Fr,
+            r: Fr,
+        },
+        Base {
+            a: u64,
+            b: u64,
+            c: u64,
+            q: Fr,
+            r: Fr,
+        },
+        Ext {
+            a: [u64; 2],
+            b: [u64; 2],
+            c: [u64; 2],
+            q: [Fr; 2],
+            r: [Fr; 2],
+        },
+    }
+
+    impl Case {
+        /// The acceptance predicate of the gates: r is the canonical reduction of the result
+        /// and q its quotient, over the integers. Operands are below 2^64 by construction.
+        fn is_valid(&self) -> bool {
+            let p = BigUint::from(GOLDILOCKS_MODULUS);
+            let big = BigUint::from;
+            let reduces = |t: BigUint, q: &Fr, r: &Fr| {
+                let r = fe_to_big(*r);
+                r < p && fe_to_big(*q) * &p + r == t
+            };
+            match self {
+                Case::Range { q, r } => {
+                    fe_to_big(*r) < p && *q == Fr::from(GOLDILOCKS_MODULUS - 1) - r
+                }
+                Case::Base { a, b, c, q, r } => reduces(big(*a) * big(*b) + big(*c), q, r),
+                Case::Ext { a, b, c, q, r } => {
+                    let x = big(a[0]) * big(b[0]) + 7u64 * big(a[1]) * big(b[1]) + big(c[0]);
+                    let y = big(a[0]) * big(b[1]) + big(a[1]) * big(b[0]) + big(c[1]);
+                    reduces(x, &q[0], &r[0]) && reduces(y, &q[1], &r[1])
+                }
+            }
+        }
+    }
+
+    /// Quotient and remainder injected for a result `t`.
+    #[derive(Clone, Debug)]
+    enum Witness {
+        Honest,
+        /// `(q - 1, r + p)`, the non-canonical remainder of the same value.
+        NonCanonical,
+        ShiftR(i64),
+        /// `(q + delta, r - delta * p)`, which keeps `q * p + r` over the field.
+        ShiftQ(i64),
+        /// `q = 2^bits + delta` for a limb boundary `bits`, `r = t - q * p` over the field.
+        LimbBoundary {
+            bits: usize,
+            delta: i64,
+        },
+        Random(Fr, Fr),
+    }
+
+    impl Witness {
+        fn apply(&self, t: &BigUint) -> (Fr, Fr) {
+            let (q, r) = t.div_rem(&BigUint::from(GOLDILOCKS_MODULUS));
+            let (q, r) = (big_to_fe::<Fr>(q), big_to_fe::<Fr>(r));
+            let p = Fr::from(GOLDILOCKS_MODULUS);
+            match self {
+                Witness::Honest => (q, r),
+                Witness::NonCanonical => (q - Fr::ONE, r + p),
+                Witness::ShiftR(delta) => (q, r + signed(*delta)),
+                Witness::ShiftQ(delta) => (q + signed(*delta), r - signed(*delta) * p),
+                Witness::LimbBoundary { bits, delta } => {
+                    let q = Fr::from_u128(1 << bits) + signed(*delta);
+                    (q, big_to_fe::<Fr>(t.clone()) - q * p)
+                }
+                Witness::Random(q, r) => (*q, *r),
+            }
+        }
+    }
+
+    fn signed(x: i64) -> Fr {
+        let abs = Fr::from(x.unsigned_abs());
+        if x < 0 {
+            -abs
+        } else {
+            abs
+        }
+    }
+
+    fn range_case(value: u64, delta: i64) -> Case {
+        let r = Fr::from(value);
+        let q = Fr::from(GOLDILOCKS_MODULUS - 1) - r + signed(delta);
+        Case::Range { q, r }
+    }
+
+    fn base_case(a: u64, b: u64, c: u64, witness: &Witness) -> Case {
+        let t = BigUint::from(a) * b + c;
+        let (q, r) = witness.apply(&t);
+        Case::Base { a, b, c, q, r }
+    }
+
+    fn ext_case(a: [u64; 2], b: [u64; 2], c: [u64; 2], witness: [&Witness; 2]) -> Case {
+        let big = BigUint::from;
+        let x = big(a[0]) * big(b[0]) + 7u64 * big(a[1]) * big(b[1]) + big(c[0]);
+        let y = big(a[0]) * big(b[1]) + big(a[1]) * big(b[0]) + big(c[1]);
+        let (q_x, r_x) = witness[0].apply(&x);
+        let (q_y, r_y) = witness[1].apply(&y);
+        Case::Ext {
+            a,
+            b,
+            c,
+            q: [q_x, q_y],
+            r: [r_x, r_y],
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct AcceptanceCircuit {
+        cases: Vec<Case>,
+    }
+
+    impl Circuit<Fr> for AcceptanceCircuit {
+        type Config = ArithmeticChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            ArithmeticChipConfig::<Fr>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = super::ArithmeticChip::new(&config);
+            chip.load_table(&mut layouter)?;
+            let known = |x: u64| Value::known(Fr::from(x));
+            for (i, case) in self.cases.iter().enumerate() {
+                layouter.assign_region(
+                    || format!("case {i}"),
+                    |region| {
+                        let ctx = &mut RegionCtx::new(region, 0);
+                        match case {
+                            Case::Range { q, r } => {
+                                chip.assign_value_with_witness(
+                                    ctx,
+                                    Value::known(*q),
+                                    Value::known(*r),
+                                )?;
+                            }
+                            Case::Base { a, b, c, q, r } => {
+                                chip.assign_with_witness(
+                                    ctx,
+                                    known(*a),
+                                    known(*b),
+                                    known(*c),
+                                    Value::known(*q),
+                                    Value::known(*r),
+                                )?;
+                            }
+                            Case::Ext { a, b, c, q, r } => {
+                                chip.assign_ext_with_witness(
+                                    ctx,
+                                    a.map(known),
+                                    b.map(known),
+                                    c.map(known),
+                                    q.map(Value::known),
+                                    r.map(Value::known),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Runs the cases through the mock prover with the limbs of `table_mode` and returns which
+    /// of them satisfy every constraint. Each case has its own region, so failures are
+    /// attributed by region name.
+    fn accepted(cases: &[Case], table_mode: TableMode) -> Vec<bool> {
+        let circuit = AcceptanceCircuit {
+            cases: cases.to_vec(),
+        };
+        let prover = match table_mode {
+            TableMode::Bits16 => mock_prover_at_min_k(&circuit, vec![vec![]]),
+            TableMode::Bits8 => mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]),
+        }
+        .unwrap();
+        let mut accepted = vec![true; cases.len()];
+        for failure in prover.verify().err().unwrap_or_default() {
+            let location = match &failure {
+                VerifyFailure::ConstraintNotSatisfied { location, .. }
+                | VerifyFailure::Lookup { location, .. }
+                | VerifyFailure::Permutation { location, .. } => location.to_string(),
+                _ => panic!("unexpected failure: {failure}"),
+            };
+            let case = location
+                .split("('case ")
+                .nth(1)
+                .and_then(|rest| rest.split('\'').next())
+                .and_then(|index| index.parse::<usize>().ok())
+                .unwrap_or_else(|| panic!("failure outside of a case: {failure}"));
+            accepted[case] = false;
+        }
+        accepted
+    }
+
+    const EDGE_OPERANDS: [u64; 9] = [
+        0,
+        1,
+        2,
+        u32::MAX as u64,
+        1 << 32,
+        GOLDILOCKS_MODULUS - 1,
+        GOLDILOCKS_MODULUS,
+        GOLDILOCKS_MODULUS + 1,
+        u64::MAX,
+    ];
+
+    fn operand() -> impl Strategy<Value = u64> {
+        prop_oneof![any::<u64>(), select(EDGE_OPERANDS.to_vec())]
+    }
+
+    fn witness() -> impl Strategy<Value = Witness> {
+        prop_oneof![
+            4 => Just(Witness::Honest),
+            1 => Just(Witness::NonCanonical),
+            1 => (-2i64..=2).prop_map(Witness::ShiftR),
+            1 => (-2i64..=2).prop_map(Witness::ShiftQ),
+            1 => (1usize..=10, -1i64..=1)
+                .prop_map(|(limb, delta)| Witness::LimbBoundary { bits: 8 * limb, delta }),
+            1 => (any::<u128>(), any::<u128>())
+                .prop_map(|(q, r)| Witness::Random(Fr::from_u128(q), Fr::from_u128(r))),
+        ]
+    }
+
+    fn case() -> impl Strategy<Value = Case> {
+        prop_oneof![
+            (operand(), -1i64..=1).prop_map(|(value, delta)| range_case(value, delta)),
+            (operand(), operand(), operand(), witness())
+                .prop_map(|(a, b, c, witness)| base_case(a, b, c, &witness)),
+            (
+                uniform2(operand()),
+                uniform2(operand()),
+                uniform2(operand()),
+                witness(),
+                witness()
+            )
+                .prop_map(|(a, b, c, w_x, w_y)| ext_case(a, b, c, [&w_x, &w_y])),
+        ]
+    }
+
+    proptest! {
+        // every run proves a batch of 1000 cases, 10k in total, with the 8-bit limbs whose
+        // boundaries include those of the 16-bit ones
+        #![proptest_config(ProptestConfig::with_cases(10))]
+
+        #[test]
+        fn test_arithmetic_gates_acceptance(cases in vec(case(), 1000)) {
+            for (case, accepted) in cases.iter().zip(accepted(&cases, TableMode::Bits8)) {
+                prop_assert_eq!(accepted, case.is_valid(), "{:?}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn test_table_checksum() {
+        let config = |table_mode| {
+            let mut meta = ConstraintSystem::<Fr>::default();
+            ArithmeticChipConfig::<Fr>::configure_with_table_mode(&mut meta, table_mode)
+        };
+        let values = config(TableMode::Bits8).table_values().collect::<Vec<_>>();
+        assert_eq!(values, (0..1u64 << 8).map(Fr::from).collect::<Vec<_>>());
+
+        let config = config(TableMode::Bits16);
+        let values = config.table_values().collect::<Vec<_>>();
+        assert_eq!(values.len(), 1 << 16);
+        assert_eq!(values.last(), Some(&Fr::from((1 << 16) - 1)));
+        // blake2b-512 of 0..2^16 as 32 byte little endian integers
+        assert_eq!(
+            config.table_checksum(),
+            "99cf6d54e6348b19377d8cbe4034d797405ed0ae620e1a2cbb74901e031e7d27\
+             f42d567e0f2a7f5c46477fa2910bb2290eb38ce1044721895ec85c9aa44d7949"
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_gates_acceptance_edge_cases() {
+        let p = GOLDILOCKS_MODULUS;
+        let mut cases = vec![];
+        for value in [0, 1, p - 1, p, p + 1, u64::MAX] {
+            for delta in -1..=1 {
+                cases.push(range_case(value, delta));
+            }
+        }
+
+        let mut witnesses = vec![
+            Witness::Honest,
+            Witness::NonCanonical,
+            Witness::ShiftR(1),
+            Witness::ShiftR(-1),
+            Witness::ShiftQ(1),
+            Witness::ShiftQ(-1),
+        ];
+        for bits in (8..=80).step_by(8) {
+            for delta in -1..=1 {
+                witnesses.push(Witness::LimbBoundary { bits, delta });
+            }
+        }
+        // operands at p - 1, products near 2^128, and results just above p and 2^64
+        let operands = [
+            (p - 1, p - 1, p - 1),
+            (u64::MAX, u64::MAX, u64::MAX),
+            (p - 1, 1, 6),
+            (1 << 32, 1 << 32, 0),
+            (1 << 32, 1 << 32, p - 1),
+            (0, 0, 0),
+        ];
+        for (a, b, c) in operands {
+            for witness in witnesses.iter() {
+                cases.push(base_case(a, b, c, witness));
+                cases.push(ext_case(
+                    [a, b],
+                    [b, c],
+                    [c, a],
+                    [witness, &Witness::Honest],
+                ));
+                cases.push(ext_case(
+                    [a, b],
+                    [b, c],
+                    [c, a],
+                    [&Witness::Honest, witness],
+                ));
+            }
+        }
+
+        for table_mode in [TableMode::Bits16, TableMode::Bits8] {
+            for (case, accepted) in cases.iter().zip(accepted(&cases, table_mode)) {
+                assert_eq!(accepted, case.is_valid(), "{table_mode:?} {case:?}");
+            }
+        }
+        // the non-canonical remainder p + 5 of (p - 1) * 1 + 6 and the range check of p itself
+        // used to be accepted
+        let regressions = [
+            base_case(p - 1, 1, 6, &Witness::NonCanonical),
+            range_case(p, 1),
+        ];
+        assert_eq!(accepted(&regressions, TableMode::Bits8), [false, false]);
     }
 }
@@ -1,19 +1,27 @@
 use halo2_proofs::halo2curves::bn256::G1Affine;
 use halo2_proofs::plonk::keygen_pk;
 use halo2_proofs::plonk::keygen_vk;
-use halo2_proofs::plonk::verify_proof;
 use halo2_proofs::plonk::ProvingKey;
 use halo2_proofs::poly::kzg::commitment::ParamsKZG;
-use halo2_proofs::transcript::TranscriptWriterBuffer;
 use halo2_proofs::{
+    circuit::Layouter,
+    dev::MockProver,
     halo2curves::bn256::{Bn256, Fr},
-    plonk::{create_proof, Circuit},
+    plonk::{Circuit, ConstraintSystem, Error},
 };
 use halo2_solidity_verifier::encode_calldata;
 use halo2_solidity_verifier::BatchOpenScheme::Bdfg21;
-use halo2_solidity_verifier::Keccak256Transcript;
 use halo2_solidity_verifier::{compile_solidity, Evm, SolidityGenerator};
 use rand::RngCore;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::arithmetic_chip::{TableMode, TableModeConfig};
+use crate::plonky2_verifier::verifier_api::{create_and_verify_proof, min_k};
 
 pub fn test_contract_size(k: u32, circuit: &impl Circuit<Fr>) {
     let mut rng = rand::thread_rng();
@@ -59,37 +67,85 @@ pub fn create_proof_checked(
     pk: &ProvingKey<G1Affine>,
     circuit: impl Circuit<Fr>,
     instances: &[Fr],
-    mut rng: impl RngCore,
+    rng: impl RngCore,
 ) -> Vec<u8> {
-    use halo2_proofs::poly::kzg::{
-        multiopen::{ProverSHPLONK, VerifierSHPLONK},
-        strategy::SingleStrategy,
-    };
-
-    let proof = {
-        let mut transcript = Keccak256Transcript::new(Vec::new());
-        create_proof::<_, ProverSHPLONK<_>, _, _, _, _>(
-            params,
-            pk,
-            &[circuit],
-            &[&[instances]],
-            &mut rng,
-            &mut transcript,
-        )
-        .unwrap();
-        transcript.finalize()
-    };
-
-    let result = {
-        let mut transcript = Keccak256Transcript::new(proof.as_slice());
-        verify_proof::<_, VerifierSHPLONK<_>, _, _, SingleStrategy<_>>(
-            params,
-            pk.get_vk(),
-            SingleStrategy::new(params),
-            &[&[instances]],
-            &mut transcript,
-        )
-    };
+    let (proof, result) = create_and_verify_proof(params, pk, circuit, instances, rng).unwrap();
     assert!(result.is_ok());
     proof
 }
+
+/// `C` configured with `TableMode::Bits8`, for circuits whose `configure` is their config's
+/// with the default table. The constraints only differ in the width of the limbs, so the
+/// circuit fits a much smaller `k`.
+#[derive(Clone, Debug)]
+pub struct SmallTable<C>(pub C);
+
+impl<C> Circuit<Fr> for SmallTable<C>
+where
+    C: Circuit<Fr>,
+    C::Config: TableModeConfig<Fr>,
+{
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        C::Config::configure_with_table_mode(meta, TableMode::Bits8)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// Runs the mock prover on `circuit` at the smallest `k` it fits in, so tests don't pay for
+/// rows their circuits don't use. `k` is taken from `verifier_api::min_k`, which synthesizes
+/// the circuit once, so the mock prover runs a single time.
+pub fn mock_prover_at_min_k<C: Circuit<Fr>>(
+    circuit: &C,
+    instances: Vec<Vec<Fr>>,
+) -> Result<MockProver<Fr>, Error> {
+    let longest = instances.iter().max_by_key(|column| column.len());
+    let k = min_k(circuit, longest.map_or(&[], Vec::as_slice))?;
+    MockProver::run(k, circuit, instances)
+}
+
+/// `mock_prover_at_min_k` of `SmallTable(circuit)`, for tests that keep using `circuit`.
+pub fn mock_prove_small_table<C>(
+    circuit: &C,
+    instances: Vec<Vec<Fr>>,
+) -> Result<MockProver<Fr>, Error>
+where
+    C: Circuit<Fr> + Clone,
+    C::Config: TableModeConfig<Fr>,
+{
+    mock_prover_at_min_k(&SmallTable(circuit.clone()), instances)
+}
+
+/// Directory of its own under `std::env::temp_dir()`, removed when dropped, so tests running
+/// in parallel don't write to the same files and failing ones don't leave them behind.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("{name}-{}-{count}", process::id()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
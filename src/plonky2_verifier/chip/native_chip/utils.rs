@@ -3,6 +3,7 @@ use halo2wrong_maingate::fe_to_big;
 use num_bigint::BigUint;
 use num_integer::Integer;
 use plonky2::field::{
+    extension::Extendable,
     goldilocks_field::GoldilocksField,
     types::{Field, PrimeField64 as _},
 };
@@ -22,6 +23,15 @@ pub fn goldilocks_to_fe<F: PrimeField>(x: GoldilocksField) -> F {
     F::from(x.to_canonical_u64())
 }
 
+/// Inverse of `a + b * w` in the quadratic extension `w^2 = W` the circuit works in,
+/// `(a - b * w) / (a^2 - W * b^2)`. Panics on zero.
+pub fn goldilocks_ext_inverse(x: [GoldilocksField; 2]) -> [GoldilocksField; 2] {
+    let [a, b] = x;
+    let w = <GoldilocksField as Extendable<2>>::W;
+    let norm_inv = (a.square() - w * b.square()).inverse();
+    [a * norm_inv, -b * norm_inv]
+}
+
 pub fn goldilocks_decompose<F: PrimeField>(x: F) -> [F; 4] {
     let mut limbs = vec![];
     let mut x = fe_to_big(x);
@@ -34,3 +44,27 @@ pub fn goldilocks_decompose<F: PrimeField>(x: F) -> [F; 4] {
     }
     limbs.try_into().unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::{
+        extension::quadratic::QuadraticExtension,
+        goldilocks_field::GoldilocksField,
+        types::{Field, Sample},
+    };
+
+    use super::goldilocks_ext_inverse;
+
+    #[test]
+    fn test_goldilocks_ext_inverse() {
+        for _ in 0..100 {
+            let x = QuadraticExtension::<GoldilocksField>::rand();
+            assert_eq!(goldilocks_ext_inverse(x.0), x.inverse().0);
+        }
+        let one = [GoldilocksField::ONE, GoldilocksField::ZERO];
+        assert_eq!(goldilocks_ext_inverse(one), one);
+        // pure `w` terms, where the norm only depends on `W`
+        let w = [GoldilocksField::ZERO, GoldilocksField::ONE];
+        assert_eq!(goldilocks_ext_inverse(w), QuadraticExtension(w).inverse().0);
+    }
+}
@@ -35,6 +35,18 @@ impl ArithmeticGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for ArithmeticGateConstrainer {
+    fn id(&self) -> String {
+        format!("ArithmeticGate {{ num_ops: {} }}", self.num_ops)
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops
+    }
+
+    fn max_degree(&self) -> usize {
+        3
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -74,7 +86,9 @@ impl<F: PrimeField> CustomGateConstrainer<F> for ArithmeticGateConstrainer {
 #[cfg(test)]
 mod tests {
     use super::ArithmeticGateConstrainer;
-    use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
+    use crate::plonky2_verifier::chip::plonk::gates::gate_test::{
+        test_custom_gate, wire_layout_configs,
+    };
     use plonky2::{gates::arithmetic_base::ArithmeticGate, plonk::circuit_data::CircuitConfig};
 
     #[test]
@@ -84,6 +98,31 @@ mod tests {
         let halo2_gate = ArithmeticGateConstrainer {
             num_ops: plonky2_gate.num_ops,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_arithmetic_gate_wire_layout() {
+        for config in wire_layout_configs() {
+            let plonky2_gate = ArithmeticGate::new_from_config(&config);
+            for i in 0..plonky2_gate.num_ops {
+                assert_eq!(
+                    ArithmeticGateConstrainer::wires_ith_multiplicand_0(i),
+                    ArithmeticGate::wire_ith_multiplicand_0(i)
+                );
+                assert_eq!(
+                    ArithmeticGateConstrainer::wires_ith_multiplicand_1(i),
+                    ArithmeticGate::wire_ith_multiplicand_1(i)
+                );
+                assert_eq!(
+                    ArithmeticGateConstrainer::wires_ith_addend(i),
+                    ArithmeticGate::wire_ith_addend(i)
+                );
+                assert_eq!(
+                    ArithmeticGateConstrainer::wires_ith_output(i),
+                    ArithmeticGate::wire_ith_output(i)
+                );
+            }
+        }
     }
 }
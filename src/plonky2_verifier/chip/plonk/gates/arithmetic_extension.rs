@@ -34,6 +34,18 @@ impl ArithmeticExtensionGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for ArithmeticExtensionGateConstrainer {
+    fn id(&self) -> String {
+        format!("ArithmeticExtensionGate {{ num_ops: {} }}", self.num_ops)
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * self.num_ops
+    }
+
+    fn max_degree(&self) -> usize {
+        3
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -85,7 +97,9 @@ impl<F: PrimeField> CustomGateConstrainer<F> for ArithmeticExtensionGateConstrai
 #[cfg(test)]
 mod tests {
 
-    use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
+    use crate::plonky2_verifier::chip::plonk::gates::gate_test::{
+        test_custom_gate, wire_layout_configs,
+    };
     use plonky2::{
         gates::arithmetic_extension::ArithmeticExtensionGate, plonk::circuit_data::CircuitConfig,
     };
@@ -99,6 +113,32 @@ mod tests {
         let halo2_gate = ArithmeticExtensionGateConstrainer {
             num_ops: plonky2_gate.num_ops,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_arithmetic_extension_gate_wire_layout() {
+        type Gate = ArithmeticExtensionGate<2>;
+        for config in wire_layout_configs() {
+            let plonky2_gate = Gate::new_from_config(&config);
+            for i in 0..plonky2_gate.num_ops {
+                assert_eq!(
+                    ArithmeticExtensionGateConstrainer::wires_ith_multiplicand_0(i),
+                    Gate::wires_ith_multiplicand_0(i)
+                );
+                assert_eq!(
+                    ArithmeticExtensionGateConstrainer::wires_ith_multiplicand_1(i),
+                    Gate::wires_ith_multiplicand_1(i)
+                );
+                assert_eq!(
+                    ArithmeticExtensionGateConstrainer::wires_ith_addend(i),
+                    Gate::wires_ith_addend(i)
+                );
+                assert_eq!(
+                    ArithmeticExtensionGateConstrainer::wires_ith_output(i),
+                    Gate::wires_ith_output(i)
+                );
+            }
+        }
     }
 }
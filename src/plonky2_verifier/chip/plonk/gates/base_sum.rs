@@ -26,6 +26,19 @@ impl BaseSumGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for BaseSumGateConstrainer {
+    fn id(&self) -> String {
+        format!("BaseSumGate {{ num_limbs: {} }} + Base: 2", self.num_limbs)
+    }
+
+    fn num_constraints(&self) -> usize {
+        1 + self.num_limbs
+    }
+
+    fn max_degree(&self) -> usize {
+        // every limb is a base 2 digit, i.e. limb * (limb - 1) = 0
+        2
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -64,13 +77,23 @@ impl<F: PrimeField> CustomGateConstrainer<F> for BaseSumGateConstrainer {
 #[cfg(test)]
 mod tests {
     use super::BaseSumGateConstrainer;
-    use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
+    use crate::plonky2_verifier::chip::plonk::gates::{
+        gate_test::{test_custom_gate, test_custom_gate_with_wires, wire_layout_configs},
+        CustomGateConstrainer, CustomGateRef,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr;
     use plonky2::{
-        field::goldilocks_field::GoldilocksField, gates::base_sum::BaseSumGate,
+        field::{
+            extension::Extendable,
+            goldilocks_field::GoldilocksField,
+            types::{Field, Field64},
+        },
+        gates::{base_sum::BaseSumGate, gate::Gate},
         plonk::circuit_data::CircuitConfig,
     };
 
     type F = GoldilocksField;
+    type FE = <F as Extendable<2>>::Extension;
 
     #[test]
     fn test_base_sum_gate() {
@@ -79,6 +102,48 @@ mod tests {
         let halo2_gate = BaseSumGateConstrainer {
             num_limbs: plonky2_gate.num_limbs,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_base_sum_gate_64_limbs() {
+        let plonky2_gate = BaseSumGate::<2>::new(64);
+        let halo2_gate = CustomGateRef::<Fr>::from_id(&plonky2_gate.id()).unwrap();
+        assert_eq!(halo2_gate.0.id(), plonky2_gate.id().trim_end());
+        test_custom_gate(plonky2_gate, BaseSumGateConstrainer { num_limbs: 64 });
+
+        // 64 limbs can encode values up to 2^64 - 1, past the modulus. The sum is only
+        // constrained modulo p, as in plonky2, so `x` and `x + p` decompose to the same wire.
+        let plonky2_gate = BaseSumGate::<2>::new(64);
+        let sum = F::from_canonical_u64(u64::MAX - F::ORDER);
+        let wires = [FE::from(sum)]
+            .into_iter()
+            .chain((0..64).map(|_| FE::ONE))
+            .collect::<Vec<_>>();
+        let halo2_gate = BaseSumGateConstrainer { num_limbs: 64 };
+        test_custom_gate_with_wires(plonky2_gate, halo2_gate, wires);
+    }
+
+    #[test]
+    fn test_base_sum_gate_wire_layout() {
+        assert_eq!(BaseSumGateConstrainer::WIRE_SUM, BaseSumGate::<2>::WIRE_SUM);
+        assert_eq!(
+            BaseSumGateConstrainer::START_LIMBS,
+            BaseSumGate::<2>::START_LIMBS
+        );
+        let plonky2_gates = wire_layout_configs()
+            .iter()
+            .map(BaseSumGate::<2>::new_from_config::<F>)
+            .chain([BaseSumGate::<2>::new(64)]);
+        for plonky2_gate in plonky2_gates {
+            let halo2_gate = BaseSumGateConstrainer {
+                num_limbs: plonky2_gate.num_limbs,
+            };
+            assert_eq!(halo2_gate.limbs(), plonky2_gate.limbs());
+            assert_eq!(
+                halo2_gate.limbs().end,
+                Gate::<F, 2>::num_wires(&plonky2_gate)
+            );
+        }
     }
 }
@@ -16,12 +16,30 @@ pub struct ConstantGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for ConstantGateConstrainer {
+    fn id(&self) -> String {
+        format!("ConstantGate {{ num_consts: {} }}", self.num_consts)
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_consts
+    }
+
+    fn max_degree(&self) -> usize {
+        1
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
         main_gate_config: &GoldilocksChipConfig<F>,
-        local_constants: &[crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue<F, 2>],
-        local_wires: &[crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue<F, 2>],
+        local_constants: &[crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue<
+            F,
+            2,
+        >],
+        local_wires: &[crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue<
+            F,
+            2,
+        >],
         _public_inputs_hash: &crate::plonky2_verifier::types::assigned::AssignedHashValues<F>,
     ) -> Result<
         Vec<crate::plonky2_verifier::types::assigned::AssignedExtensionFieldValue<F, 2>>,
@@ -1,7 +1,6 @@
 use crate::plonky2_verifier::context::RegionCtx;
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    dev::MockProver,
     halo2curves::bn256::Fr,
     plonk::{Circuit, ConstraintSystem},
 };
@@ -14,14 +13,17 @@ use plonky2::{
     },
     gates::gate::Gate,
     hash::hash_types::HashOut,
-    plonk::vars::EvaluationVars,
+    plonk::{circuit_data::CircuitConfig, vars::EvaluationVars},
 };
 
 use super::CustomGateConstrainer;
 use crate::plonky2_verifier::{
     chip::{
         goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
-        native_chip::all_chip::AllChipConfig,
+        native_chip::{
+            all_chip::AllChipConfig,
+            test_utils::{mock_prover_at_min_k, SmallTable},
+        },
     },
     types::assigned::{AssignedExtensionFieldValue, AssignedHashValues},
 };
@@ -55,7 +57,7 @@ fn assign_quadratic_extensions(
             let b_assigned = goldilocks_chip
                 .assign_value(ctx, Value::known(goldilocks_to_fr(x.0[1])))
                 .unwrap();
-            AssignedExtensionFieldValue([a_assigned, b_assigned])
+            AssignedExtensionFieldValue::new([a_assigned, b_assigned])
         })
         .collect::<Vec<_>>()
 }
@@ -74,9 +76,7 @@ fn assign_hash_values(
                 .unwrap()
         })
         .collect::<Vec<_>>();
-    AssignedHashValues {
-        elements: elements.try_into().unwrap(),
-    }
+    AssignedHashValues::from_vec(elements).unwrap()
 }
 
 impl<'a, Gate: CustomGateConstrainer<Fr>> Circuit<Fr> for TestCircuit<'a, Gate> {
@@ -136,10 +136,10 @@ impl<'a, Gate: CustomGateConstrainer<Fr>> Circuit<Fr> for TestCircuit<'a, Gate>
                     .zip(output_expected.iter())
                     .for_each(|(a, b)| {
                         goldilocks_chip
-                            .assert_equal(&mut ctx, &a.0[0], &b.0[0])
+                            .assert_equal(&mut ctx, a.real(), b.real())
                             .unwrap();
                         goldilocks_chip
-                            .assert_equal(&mut ctx, &a.0[1], &b.0[1])
+                            .assert_equal(&mut ctx, a.imag(), b.imag())
                             .unwrap();
                     });
                 println!("final ctx:{}", ctx.offset());
@@ -154,9 +154,18 @@ impl<'a, Gate: CustomGateConstrainer<Fr>> Circuit<Fr> for TestCircuit<'a, Gate>
 pub fn test_custom_gate<PG: Gate<F, D>, HG: CustomGateConstrainer<Fr>>(
     plonky2_gate: PG,
     halo2_gate: HG,
-    k: u32,
 ) {
     let wires = FE::rand_vec(plonky2_gate.num_wires());
+    test_custom_gate_with_wires(plonky2_gate, halo2_gate, wires);
+}
+
+/// Like `test_custom_gate`, but evaluates both gates on the given wires instead of random ones.
+pub fn test_custom_gate_with_wires<PG: Gate<F, D>, HG: CustomGateConstrainer<Fr>>(
+    plonky2_gate: PG,
+    halo2_gate: HG,
+    wires: Vec<FE>,
+) {
+    assert_eq!(wires.len(), plonky2_gate.num_wires());
     let constants = FE::rand_vec(plonky2_gate.num_constants());
     let public_inputs_hash = HashOut::<F>::rand();
     let evaluation_vars = EvaluationVars::<F, D> {
@@ -165,12 +174,29 @@ pub fn test_custom_gate<PG: Gate<F, D>, HG: CustomGateConstrainer<Fr>>(
         public_inputs_hash: &public_inputs_hash,
     };
     let output: Vec<QuadraticExtension<F>> = plonky2_gate.eval_unfiltered(evaluation_vars);
+    assert_eq!(halo2_gate.num_constraints(), plonky2_gate.num_constraints());
+    assert_eq!(output.len(), plonky2_gate.num_constraints());
     let circuit = TestCircuit {
         gate: halo2_gate,
         evaluation_vars,
         output,
     };
-    MockProver::run(k, &circuit, vec![vec![]])
+    mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
         .unwrap()
         .assert_satisfied();
 }
+
+/// Configs to build plonky2's gates from in the wire layout tests. The number of operations a gate
+/// packs depends on the routed wires, so they range from a narrow config to the standard ones.
+pub fn wire_layout_configs() -> Vec<CircuitConfig> {
+    let standard = CircuitConfig::standard_recursion_config();
+    vec![
+        CircuitConfig {
+            num_wires: 40,
+            num_routed_wires: 20,
+            ..standard.clone()
+        },
+        standard,
+        CircuitConfig::standard_ecc_config(),
+    ]
+}
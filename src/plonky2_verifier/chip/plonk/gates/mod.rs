@@ -3,8 +3,8 @@ use std::ops::Range;
 use crate::plonky2_verifier::context::RegionCtx;
 use halo2_proofs::halo2curves::ff::PrimeField;
 use halo2_proofs::plonk::Error;
-use plonky2::field::types::Field;
 use plonky2::{field::goldilocks_field::GoldilocksField, gates::gate::GateRef};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use self::arithmetic_extension::ArithmeticExtensionGateConstrainer;
 use self::base_sum::BaseSumGateConstrainer;
@@ -25,9 +25,10 @@ use crate::plonky2_verifier::chip::goldilocks_extension_algebra_chip::{
 };
 use crate::plonky2_verifier::chip::goldilocks_extension_chip::GoldilocksExtensionChip;
 use crate::plonky2_verifier::types::assigned::{AssignedExtensionFieldValue, AssignedHashValues};
+use crate::plonky2_verifier::types::common_data::CommonDataError;
 
 /// Placeholder value to indicate that a gate doesn't use a selector polynomial.
-const UNUSED_SELECTOR: usize = u32::MAX as usize;
+pub(crate) const UNUSED_SELECTOR: usize = u32::MAX as usize;
 
 pub mod arithmetic;
 pub mod arithmetic_extension;
@@ -48,6 +49,16 @@ pub mod gate_test;
 pub trait CustomGateConstrainer<F: PrimeField>:
     CustomGateConstrainerClone<F> + Send + Sync
 {
+    /// Plonky2 gate id this constrainer mirrors, e.g. `"ArithmeticGate { num_ops: 20 }"`.
+    fn id(&self) -> String;
+
+    /// Number of constraints `eval_unfiltered_constraint` returns. Must match the plonky2 gate.
+    fn num_constraints(&self) -> usize;
+
+    /// Degree of the constraints in the local wires and constants. Must match the plonky2
+    /// gate's `degree()`, and fit in the quotient degree factor of the circuit.
+    fn max_degree(&self) -> usize;
+
     fn get_local_ext_algebra(
         &self,
         local_wires: &[AssignedExtensionFieldValue<F, 2>],
@@ -84,6 +95,10 @@ pub trait CustomGateConstrainer<F: PrimeField>:
     /// In Plonky2, each custom gate's constraint is multiplied by filtering polynomial
     /// `j`th gate's constraint is filtered by f_j(x) = \prod_{k=0, k \neq j}^{n-1}(f(x) - k) where
     /// f(g^i) = j if jth gate is used in ith row
+    ///
+    /// `selector_terms` holds `k - f(\zeta)` for every gate `k` of the group, followed by the
+    /// unused selector term when there is more than one group. They are shared by all gates of
+    /// the group, `filter_index` is this gate's position in it.
     fn eval_filtered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -91,30 +106,19 @@ pub trait CustomGateConstrainer<F: PrimeField>:
         mut local_constants: &[AssignedExtensionFieldValue<F, 2>],
         local_wires: &[AssignedExtensionFieldValue<F, 2>],
         public_inputs_hash: &AssignedHashValues<F>,
-        row: usize,
-        selector_index: usize,
-        group_range: Range<usize>,
+        filter_index: usize,
+        selector_terms: &[AssignedExtensionFieldValue<F, 2>],
         num_selectors: usize,
         combined_gate_constraints: &mut [AssignedExtensionFieldValue<F, 2>],
     ) -> Result<(), Error> {
         let goldilocks_extension_chip = self.goldilocks_extension_chip(goldilocks_chip_config);
-        // f(\zeta)
-        let f_zeta = &local_constants[selector_index];
-        // \prod_{k=0, k \neq j}^{n-1}(f(\zeta) - k)
-        let terms = group_range
-            .filter(|&i| i != row)
-            .chain((num_selectors > 1).then_some(UNUSED_SELECTOR))
-            .map(|i| {
-                let k = goldilocks_extension_chip.constant_extension(
-                    ctx,
-                    &[
-                        GoldilocksField::from_canonical_u64(i as u64),
-                        GoldilocksField::ZERO,
-                    ],
-                )?;
-                goldilocks_extension_chip.sub_extension(ctx, &k, &f_zeta)
-            })
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, 2>>, Error>>()?;
+        // \prod_{k=0, k \neq j}^{n-1}(k - f(\zeta))
+        let terms = selector_terms
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| *k != filter_index)
+            .map(|(_, term)| term.clone())
+            .collect::<Vec<AssignedExtensionFieldValue<F, 2>>>();
         let filter = goldilocks_extension_chip.mul_many_extension(ctx, terms)?;
 
         local_constants = &local_constants[num_selectors..];
@@ -125,30 +129,45 @@ pub trait CustomGateConstrainer<F: PrimeField>:
             local_wires,
             public_inputs_hash,
         )?;
-        for (acc, c) in combined_gate_constraints.iter_mut().zip(gate_constraints) {
-            *acc = goldilocks_extension_chip.mul_add_extension(ctx, &filter, &c, acc)?;
+        // extra constraints would be dropped by the accumulation and left unconstrained
+        if gate_constraints.len() > combined_gate_constraints.len() {
+            return Err(Error::Synthesis);
         }
-        Ok(())
+        goldilocks_extension_chip.mul_add_many_extension(
+            ctx,
+            &filter,
+            &gate_constraints,
+            combined_gate_constraints,
+        )
     }
 }
 
 #[derive(Clone)]
 pub struct CustomGateRef<F: PrimeField>(pub Box<dyn CustomGateConstrainer<F>>);
 
-impl<F: PrimeField> From<&GateRef<GoldilocksField, 2>> for CustomGateRef<F> {
-    fn from(value: &GateRef<GoldilocksField, 2>) -> Self {
-        match value.0.id().as_str().trim_end() {
+impl<F: PrimeField> CustomGateRef<F> {
+    /// Looks up the constrainer for a plonky2 gate id. Returns `None` for gates
+    /// that have no in-circuit constrainer yet, e.g. `CosetInterpolationGate`, so inner
+    /// circuits using them are rejected rather than verified against partial constraints.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let gate = match id.trim_end() {
             "ArithmeticGate { num_ops: 20 }" => Self(Box::new(ArithmeticGateConstrainer {
-                num_ops: value.0.num_ops(),
+                num_ops: 20,
+            })),
+            "ArithmeticGate { num_ops: 1 }" => Self(Box::new(ArithmeticGateConstrainer {
+                num_ops: 1,
             })),
             "PublicInputGate" => Self(Box::new(PublicInputGateConstrainer)),
             "NoopGate" => Self(Box::new(NoopGateConstrainer)),
             "ConstantGate { num_consts: 2 }" => Self(Box::new(ConstantGateConstrainer {
-                num_consts: value.0.num_constants(),
+                num_consts: 2,
             })),
             "BaseSumGate { num_limbs: 63 } + Base: 2" => {
                 Self(Box::new(BaseSumGateConstrainer { num_limbs: 63 }))
             },
+            "BaseSumGate { num_limbs: 64 } + Base: 2" => {
+                Self(Box::new(BaseSumGateConstrainer { num_limbs: 64 }))
+            },
             "PoseidonGate(PhantomData<plonky2_field::goldilocks_field::GoldilocksField>)<WIDTH=12>" => {
                 Self(Box::new(PoseidonGateConstrainer))
             },
@@ -194,11 +213,71 @@ impl<F: PrimeField> From<&GateRef<GoldilocksField, 2>> for CustomGateRef<F> {
                     num_limbs: 4
                 }))
             },
-            s => {
-                println!("{s}");
-                unimplemented!()
-            }
+            _ => return None,
+        };
+        Some(gate)
+    }
+
+    /// Checks that the constrainer emits as many constraints as the plonky2 gate it mirrors.
+    pub fn check_num_constraints(
+        &self,
+        gate: &GateRef<GoldilocksField, 2>,
+    ) -> Result<(), CommonDataError> {
+        let expected = gate.0.num_constraints();
+        let actual = self.0.num_constraints();
+        if actual != expected {
+            return Err(CommonDataError::GateConstraintCountMismatch {
+                gate: self.0.id(),
+                expected,
+                actual,
+            });
         }
+        Ok(())
+    }
+
+    /// Checks that the constrainer has the same degree as the plonky2 gate it mirrors.
+    pub fn check_degree(&self, gate: &GateRef<GoldilocksField, 2>) -> Result<(), CommonDataError> {
+        let expected = gate.0.degree();
+        let actual = self.0.max_degree();
+        if actual != expected {
+            return Err(CommonDataError::GateDegreeMismatch {
+                gate: self.0.id(),
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Fails for gates without a constrainer, or whose constrainer doesn't match the plonky2 gate
+/// in number of constraints or degree.
+impl<F: PrimeField> TryFrom<&GateRef<GoldilocksField, 2>> for CustomGateRef<F> {
+    type Error = CommonDataError;
+
+    fn try_from(value: &GateRef<GoldilocksField, 2>) -> Result<Self, Self::Error> {
+        let id = value.0.id();
+        let Some(gate) = Self::from_id(&id) else {
+            log::error!("no constrainer for plonky2 gate {}", id.trim_end());
+            return Err(CommonDataError::UnsupportedGate(id.trim_end().to_string()));
+        };
+        gate.check_num_constraints(value)?;
+        gate.check_degree(value)?;
+        Ok(gate)
+    }
+}
+
+/// Gates are serialized by their plonky2 id.
+impl<F: PrimeField> Serialize for CustomGateRef<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.id())
+    }
+}
+
+impl<'de, F: PrimeField> Deserialize<'de> for CustomGateRef<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Self::from_id(&id).ok_or_else(|| de::Error::custom(format!("unsupported gate: {id}")))
     }
 }
 
@@ -221,3 +300,263 @@ impl<F: PrimeField> Clone for Box<dyn CustomGateConstrainer<F>> {
         self.clone_box()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::sync::Once;
+
+    use halo2_proofs::{halo2curves::bn256::Fr, plonk::Error};
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{
+            arithmetic_base::ArithmeticGate, coset_interpolation::CosetInterpolationGate,
+            gate::GateRef,
+        },
+        plonk::circuit_data::CircuitConfig,
+    };
+
+    use super::{arithmetic::ArithmeticGateConstrainer, CustomGateConstrainer, CustomGateRef};
+    use crate::plonky2_verifier::{
+        chip::goldilocks_chip::GoldilocksChipConfig,
+        context::RegionCtx,
+        fixtures::{
+            generate_proof_tuple, generate_random_access_proof_tuple, generate_tiny_proof_tuple,
+            generate_two_random_access_gates_proof_tuple,
+        },
+        types::{
+            assigned::{AssignedExtensionFieldValue, AssignedHashValues},
+            common_data::{CommonData, CommonDataError},
+        },
+    };
+
+    /// Claims one more constraint than the plonky2 gate it mirrors.
+    #[derive(Clone)]
+    struct WrongLengthConstrainer(ArithmeticGateConstrainer);
+
+    impl CustomGateConstrainer<Fr> for WrongLengthConstrainer {
+        fn id(&self) -> String {
+            self.0.id()
+        }
+
+        fn num_constraints(&self) -> usize {
+            self.0.num_ops + 1
+        }
+
+        fn max_degree(&self) -> usize {
+            self.0.max_degree()
+        }
+
+        fn eval_unfiltered_constraint(
+            &self,
+            ctx: &mut RegionCtx<'_, Fr>,
+            goldilocks_chip_config: &GoldilocksChipConfig<Fr>,
+            local_constants: &[AssignedExtensionFieldValue<Fr, 2>],
+            local_wires: &[AssignedExtensionFieldValue<Fr, 2>],
+            public_inputs_hash: &AssignedHashValues<Fr>,
+        ) -> Result<Vec<AssignedExtensionFieldValue<Fr, 2>>, Error> {
+            let mut constraints = self.0.eval_unfiltered_constraint(
+                ctx,
+                goldilocks_chip_config,
+                local_constants,
+                local_wires,
+                public_inputs_hash,
+            )?;
+            constraints.push(constraints[0].clone());
+            Ok(constraints)
+        }
+    }
+
+    /// Constrains like the arithmetic gate, but claims a degree above any quotient degree factor
+    /// plonky2 builds circuits with.
+    #[derive(Clone)]
+    struct OverDegreeConstrainer(ArithmeticGateConstrainer);
+
+    impl CustomGateConstrainer<Fr> for OverDegreeConstrainer {
+        fn id(&self) -> String {
+            self.0.id()
+        }
+
+        fn num_constraints(&self) -> usize {
+            self.0.num_constraints()
+        }
+
+        fn max_degree(&self) -> usize {
+            9
+        }
+
+        fn eval_unfiltered_constraint(
+            &self,
+            ctx: &mut RegionCtx<'_, Fr>,
+            goldilocks_chip_config: &GoldilocksChipConfig<Fr>,
+            local_constants: &[AssignedExtensionFieldValue<Fr, 2>],
+            local_wires: &[AssignedExtensionFieldValue<Fr, 2>],
+            public_inputs_hash: &AssignedHashValues<Fr>,
+        ) -> Result<Vec<AssignedExtensionFieldValue<Fr, 2>>, Error> {
+            self.0.eval_unfiltered_constraint(
+                ctx,
+                goldilocks_chip_config,
+                local_constants,
+                local_wires,
+                public_inputs_hash,
+            )
+        }
+    }
+
+    fn arithmetic_gate() -> GateRef<GoldilocksField, 2> {
+        GateRef::new(ArithmeticGate::new_from_config(
+            &CircuitConfig::standard_recursion_config(),
+        ))
+    }
+
+    thread_local! {
+        static LOGS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+
+    /// Records the logs of the thread they're emitted on.
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let log = format!("{} {}", record.level(), record.args());
+            LOGS.with(|logs| logs.borrow_mut().push(log));
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Runs `f`, returning the logs it emitted. The logger is global but the logs are kept per
+    /// thread, and every test runs on its own thread, so tests don't see each other's logs.
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+        static LOGGER: CapturingLogger = CapturingLogger;
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        LOGS.with(|logs| logs.borrow_mut().clear());
+        let result = f();
+        (result, LOGS.with(|logs| logs.take()))
+    }
+
+    #[test]
+    fn test_unknown_gate_is_logged() {
+        let gate = GateRef::<GoldilocksField, 2>::new(ArithmeticGate { num_ops: 3 });
+        let (result, logs) = capture_logs(|| CustomGateRef::<Fr>::try_from(&gate));
+        match result {
+            Err(CommonDataError::UnsupportedGate(id)) => {
+                assert_eq!(id, "ArithmeticGate { num_ops: 3 }")
+            }
+            _ => panic!("expected an unsupported gate"),
+        }
+        assert_eq!(
+            logs,
+            ["ERROR no constrainer for plonky2 gate ArithmeticGate { num_ops: 3 }"]
+        );
+    }
+
+    #[test]
+    fn test_gate_num_constraints_match_plonky2() {
+        let gate = arithmetic_gate();
+        CustomGateRef::<Fr>::try_from(&gate)
+            .unwrap()
+            .check_num_constraints(&gate)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_wrong_length_constrainer_is_rejected() {
+        let gate = arithmetic_gate();
+        let wrong = CustomGateRef::<Fr>(Box::new(WrongLengthConstrainer(
+            ArithmeticGateConstrainer { num_ops: 20 },
+        )));
+        let err = wrong.check_num_constraints(&gate).unwrap_err();
+        assert!(matches!(
+            err,
+            CommonDataError::GateConstraintCountMismatch {
+                expected: 20,
+                actual: 21,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_gate_degrees_match_plonky2() {
+        for (_, _, cd) in [
+            generate_proof_tuple(),
+            generate_random_access_proof_tuple(),
+            generate_two_random_access_gates_proof_tuple(),
+        ] {
+            for gate in cd.gates.iter() {
+                let constrainer = CustomGateRef::<Fr>::try_from(gate).unwrap();
+                assert_eq!(
+                    constrainer.0.max_degree(),
+                    gate.0.degree(),
+                    "{}",
+                    gate.0.id()
+                );
+                constrainer.check_degree(gate).unwrap();
+            }
+        }
+
+        let gate = arithmetic_gate();
+        let over_degree =
+            CustomGateRef::<Fr>(Box::new(OverDegreeConstrainer(ArithmeticGateConstrainer {
+                num_ops: 20,
+            })));
+        assert!(matches!(
+            over_degree.check_degree(&gate).unwrap_err(),
+            CommonDataError::GateDegreeMismatch {
+                expected: 3,
+                actual: 9,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_over_degree_constrainer_is_rejected() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let mut common_data = CommonData::<Fr>::from(cd);
+        assert_eq!(common_data.quotient_degree_factor, 8);
+        common_data.validate().unwrap();
+
+        let index = common_data
+            .gates
+            .iter()
+            .position(|gate| gate.0.id() == "ArithmeticGate { num_ops: 20 }")
+            .unwrap();
+        common_data.gates[index] =
+            CustomGateRef(Box::new(OverDegreeConstrainer(ArithmeticGateConstrainer {
+                num_ops: 20,
+            })));
+        // the id is unchanged, so only the degree gives the constrainer away
+        common_data.check_gate_order().unwrap();
+        let err = common_data.validate().unwrap_err();
+        assert_eq!(
+            err,
+            CommonDataError::GateDegreeExceedsQuotientDegree {
+                gate: "ArithmeticGate { num_ops: 20 }".to_string(),
+                degree: 9,
+                quotient_degree_factor: 8,
+            }
+        );
+        assert!(matches!(Error::from(err), Error::Transcript(_)));
+    }
+
+    #[test]
+    fn test_coset_interpolation_gate_is_unsupported() {
+        // without a constrainer for the gate, the shifted evaluation point and the barycentric
+        // sums would go unchecked, so the gate must not resolve to any constrainer
+        let gate = GateRef::<GoldilocksField, 2>::new(
+            CosetInterpolationGate::<GoldilocksField, 2>::new(4),
+        );
+        assert!(gate.0.id().starts_with("CosetInterpolationGate"));
+        assert!(CustomGateRef::<Fr>::from_id(&gate.0.id()).is_none());
+    }
+}
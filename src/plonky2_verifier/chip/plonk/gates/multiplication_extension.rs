@@ -31,6 +31,18 @@ impl MulExtensionGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for MulExtensionGateConstrainer {
+    fn id(&self) -> String {
+        format!("MulExtensionGate {{ num_ops: {} }}", self.num_ops)
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * self.num_ops
+    }
+
+    fn max_degree(&self) -> usize {
+        3
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -74,7 +86,9 @@ impl<F: PrimeField> CustomGateConstrainer<F> for MulExtensionGateConstrainer {
 #[cfg(test)]
 mod tests {
     use super::MulExtensionGateConstrainer;
-    use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
+    use crate::plonky2_verifier::chip::plonk::gates::gate_test::{
+        test_custom_gate, wire_layout_configs,
+    };
     use plonky2::{
         gates::multiplication_extension::MulExtensionGate, plonk::circuit_data::CircuitConfig,
     };
@@ -86,6 +100,28 @@ mod tests {
         let halo2_gate = MulExtensionGateConstrainer {
             num_ops: plonky2_gate.num_ops,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_mul_extension_gate_wire_layout() {
+        type Gate = MulExtensionGate<2>;
+        for config in wire_layout_configs() {
+            let plonky2_gate = Gate::new_from_config(&config);
+            for i in 0..plonky2_gate.num_ops {
+                assert_eq!(
+                    MulExtensionGateConstrainer::wires_ith_multiplicand_0(i),
+                    Gate::wires_ith_multiplicand_0(i)
+                );
+                assert_eq!(
+                    MulExtensionGateConstrainer::wires_ith_multiplicand_1(i),
+                    Gate::wires_ith_multiplicand_1(i)
+                );
+                assert_eq!(
+                    MulExtensionGateConstrainer::wires_ith_output(i),
+                    Gate::wires_ith_output(i)
+                );
+            }
+        }
     }
 }
@@ -1,3 +1,5 @@
+use std::iter;
+
 use crate::plonky2_verifier::context::RegionCtx;
 use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
 use plonky2::{
@@ -456,33 +458,24 @@ impl PoseidonGateConstrainer {
     ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
         debug_assert!(row < T);
         let goldilocks_extension_chip = self.goldilocks_extension_chip(goldilocks_chip_config);
-        let mut res = goldilocks_extension_chip.zero_extension(ctx)?;
-
-        for i in 0..T {
-            let c = goldilocks_extension_chip.constant_extension(
-                ctx,
-                &[
-                    GoldilocksField::from_canonical_u64(MDS_MATRIX_CIRC[i]),
-                    GoldilocksField::ZERO,
-                ],
-            )?;
-            res = goldilocks_extension_chip.mul_add_extension(
-                ctx,
-                &c,
-                &state[(i + row) % T],
-                &res,
-            )?;
-        }
-        let c = goldilocks_extension_chip.constant_extension(
-            ctx,
-            &[
-                GoldilocksField::from_canonical_u64(MDS_MATRIX_DIAG[row]),
-                GoldilocksField::ZERO,
-            ],
-        )?;
-        res = goldilocks_extension_chip.mul_add_extension(ctx, &c, &state[row], &res)?;
-
-        Ok(res)
+        let coeffs = MDS_MATRIX_CIRC
+            .iter()
+            .chain(iter::once(&MDS_MATRIX_DIAG[row]))
+            .map(|c| {
+                goldilocks_extension_chip.constant_extension(
+                    ctx,
+                    &[
+                        GoldilocksField::from_canonical_u64(*c),
+                        GoldilocksField::ZERO,
+                    ],
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let inputs = (0..T)
+            .map(|i| state[(i + row) % T].clone())
+            .chain(iter::once(state[row].clone()))
+            .collect::<Vec<_>>();
+        goldilocks_extension_chip.dot_product(ctx, &coeffs, &inputs)
     }
 
     fn mds_layer<F: PrimeField>(
@@ -590,6 +583,20 @@ impl PoseidonGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for PoseidonGateConstrainer {
+    fn id(&self) -> String {
+        "PoseidonGate(PhantomData<plonky2_field::goldilocks_field::GoldilocksField>)<WIDTH=12>"
+            .to_string()
+    }
+
+    fn num_constraints(&self) -> usize {
+        T * (R_F - 1) + R_P + T + 1 + 4
+    }
+
+    fn max_degree(&self) -> usize {
+        // the S-box is x^7
+        7
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -699,14 +706,49 @@ impl<F: PrimeField> CustomGateConstrainer<F> for PoseidonGateConstrainer {
 
 #[cfg(test)]
 mod tests {
-    use super::PoseidonGateConstrainer;
+    use super::{PoseidonGateConstrainer, R_F_HALF, R_P, T};
     use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::gates::poseidon::PoseidonGate;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, poseidon::PoseidonGate},
+    };
 
     #[test]
     fn test_poseidon_gate() {
         let plonky2_gate = PoseidonGate::new();
         let halo2_gate = PoseidonGateConstrainer;
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_poseidon_gate_wire_layout() {
+        type Plonky2Gate = PoseidonGate<GoldilocksField, 2>;
+        for i in 0..T {
+            assert_eq!(
+                PoseidonGateConstrainer::wire_input(i),
+                Plonky2Gate::wire_input(i)
+            );
+            assert_eq!(
+                PoseidonGateConstrainer::wire_output(i),
+                Plonky2Gate::wire_output(i)
+            );
+        }
+        assert_eq!(PoseidonGateConstrainer::WIRE_SWAP, Plonky2Gate::WIRE_SWAP);
+
+        // plonky2 keeps the helper wires private, they're laid out back to back after the swap
+        // wire and end with the gate
+        let mut wires = (0..4)
+            .map(PoseidonGateConstrainer::wire_delta)
+            .collect::<Vec<_>>();
+        for round in 1..R_F_HALF {
+            wires.extend((0..T).map(|i| PoseidonGateConstrainer::wire_full_sbox_0(round, i)));
+        }
+        wires.extend((0..R_P).map(PoseidonGateConstrainer::wire_partial_sbox));
+        for round in 0..R_F_HALF {
+            wires.extend((0..T).map(|i| PoseidonGateConstrainer::wire_full_sbox_1(round, i)));
+        }
+        let expected = (PoseidonGateConstrainer::WIRE_SWAP + 1..Plonky2Gate::new().num_wires())
+            .collect::<Vec<_>>();
+        assert_eq!(wires, expected);
     }
 }
@@ -96,6 +96,19 @@ impl PoseidonMDSGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for PoseidonMDSGateConstrainer {
+    fn id(&self) -> String {
+        "PoseidonMdsGate(PhantomData<plonky2_field::goldilocks_field::GoldilocksField>)<WIDTH=12>"
+            .to_string()
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * T
+    }
+
+    fn max_degree(&self) -> usize {
+        1
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -127,14 +140,36 @@ impl<F: PrimeField> CustomGateConstrainer<F> for PoseidonMDSGateConstrainer {
 
 #[cfg(test)]
 mod tests {
-    use super::PoseidonMDSGateConstrainer;
+    use super::{PoseidonMDSGateConstrainer, T};
     use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::gates::poseidon_mds::PoseidonMdsGate;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, poseidon_mds::PoseidonMdsGate},
+    };
 
     #[test]
     fn test_poseidon_mds_gate() {
         let plonky2_gate = PoseidonMdsGate::new();
         let halo2_gate = PoseidonMDSGateConstrainer;
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_poseidon_mds_gate_wire_layout() {
+        type Plonky2Gate = PoseidonMdsGate<GoldilocksField, 2>;
+        for i in 0..T {
+            assert_eq!(
+                PoseidonMDSGateConstrainer::wires_input(i),
+                Plonky2Gate::wires_input(i)
+            );
+            assert_eq!(
+                PoseidonMDSGateConstrainer::wires_output(i),
+                Plonky2Gate::wires_output(i)
+            );
+        }
+        assert_eq!(
+            PoseidonMDSGateConstrainer::wires_output(T - 1).end,
+            Plonky2Gate::new().num_wires()
+        );
     }
 }
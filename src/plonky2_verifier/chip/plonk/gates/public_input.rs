@@ -20,6 +20,18 @@ impl PublicInputGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for PublicInputGateConstrainer {
+    fn id(&self) -> String {
+        "PublicInputGate".to_string()
+    }
+
+    fn num_constraints(&self) -> usize {
+        4
+    }
+
+    fn max_degree(&self) -> usize {
+        1
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -30,10 +42,10 @@ impl<F: PrimeField> CustomGateConstrainer<F> for PublicInputGateConstrainer {
     ) -> Result<Vec<AssignedExtensionFieldValue<F, 2>>, Error> {
         let goldilocks_extension_chip = self.goldilocks_extension_chip(goldilocks_chip_config);
         Self::wires_public_inputs_hash()
-            .zip(public_inputs_hash.elements.clone())
+            .zip(public_inputs_hash.iter())
             .map(|(wire, hash_part)| {
                 let hash_part_ext =
-                    goldilocks_extension_chip.convert_to_extension(ctx, &hash_part)?;
+                    goldilocks_extension_chip.convert_to_extension(ctx, hash_part)?;
                 goldilocks_extension_chip.sub_extension(ctx, &local_wires[wire], &hash_part_ext)
             })
             .collect()
@@ -44,12 +56,27 @@ impl<F: PrimeField> CustomGateConstrainer<F> for PublicInputGateConstrainer {
 mod tests {
     use super::PublicInputGateConstrainer;
     use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::gates::public_input::PublicInputGate;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, public_input::PublicInputGate},
+    };
 
     #[test]
     fn test_public_input_gate() {
         let plonky2_gate = PublicInputGate;
         let halo2_gate = PublicInputGateConstrainer;
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_public_input_gate_wire_layout() {
+        assert_eq!(
+            PublicInputGateConstrainer::wires_public_inputs_hash(),
+            PublicInputGate::wires_public_inputs_hash()
+        );
+        assert_eq!(
+            PublicInputGateConstrainer::wires_public_inputs_hash().end,
+            Gate::<GoldilocksField, 2>::num_wires(&PublicInputGate)
+        );
     }
 }
@@ -76,6 +76,21 @@ impl RandomAccessGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for RandomAccessGateConstrainer {
+    fn id(&self) -> String {
+        format!(
+            "RandomAccessGate {{ bits: {}, num_copies: {}, num_extra_constants: {}, _phantom: PhantomData<plonky2_field::goldilocks_field::GoldilocksField> }}<D=2>",
+            self.bits, self.num_copies, self.num_extra_constants
+        )
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_copies * (self.bits + 2) + self.num_extra_constants
+    }
+
+    fn max_degree(&self) -> usize {
+        self.bits + 1
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -151,8 +166,14 @@ impl<F: PrimeField> CustomGateConstrainer<F> for RandomAccessGateConstrainer {
 #[cfg(test)]
 mod tests {
     use super::RandomAccessGateConstrainer;
-    use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::{gates::random_access::RandomAccessGate, plonk::circuit_data::CircuitConfig};
+    use crate::plonky2_verifier::chip::plonk::gates::gate_test::{
+        test_custom_gate, wire_layout_configs,
+    };
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, random_access::RandomAccessGate},
+        plonk::circuit_data::CircuitConfig,
+    };
 
     #[test]
     fn test_random_access_gate() {
@@ -163,6 +184,61 @@ mod tests {
             num_copies: plonky2_gate.num_copies,
             num_extra_constants: plonky2_gate.num_extra_constants,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_random_access_gate_extra_constants() {
+        let config = CircuitConfig::default();
+        let plonky2_gate = RandomAccessGate::new_from_config(&config, 4);
+        assert!(plonky2_gate.num_extra_constants > 0);
+        let halo2_gate = RandomAccessGateConstrainer {
+            bits: plonky2_gate.bits,
+            num_copies: plonky2_gate.num_copies,
+            num_extra_constants: plonky2_gate.num_extra_constants,
+        };
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_random_access_gate_wire_layout() {
+        for config in wire_layout_configs() {
+            for bits in 1..=4 {
+                let plonky2_gate =
+                    RandomAccessGate::<GoldilocksField, 2>::new_from_config(&config, bits);
+                let halo2_gate = RandomAccessGateConstrainer {
+                    bits: plonky2_gate.bits,
+                    num_copies: plonky2_gate.num_copies,
+                    num_extra_constants: plonky2_gate.num_extra_constants,
+                };
+                for copy in 0..halo2_gate.num_copies {
+                    assert_eq!(
+                        halo2_gate.wire_access_index(copy),
+                        plonky2_gate.wire_access_index(copy)
+                    );
+                    assert_eq!(
+                        halo2_gate.wire_claimed_element(copy),
+                        plonky2_gate.wire_claimed_element(copy)
+                    );
+                    for i in 0..halo2_gate.vec_size() {
+                        assert_eq!(
+                            halo2_gate.wire_list_item(i, copy),
+                            plonky2_gate.wire_list_item(i, copy)
+                        );
+                    }
+                }
+                let extra_constant_wires = (0..halo2_gate.num_extra_constants)
+                    .map(|i| (i, halo2_gate.wire_extra_constant(i)))
+                    .collect::<Vec<_>>();
+                assert_eq!(extra_constant_wires, plonky2_gate.extra_constant_wires());
+
+                // the bits are the last wires of the gate
+                let last_copy = halo2_gate.num_copies - 1;
+                assert_eq!(
+                    halo2_gate.wire_bit(halo2_gate.bits - 1, last_copy) + 1,
+                    plonky2_gate.num_wires()
+                );
+            }
+        }
     }
 }
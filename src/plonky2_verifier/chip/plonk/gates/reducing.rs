@@ -47,6 +47,18 @@ impl ReducingGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for ReducingGateConstrainer {
+    fn id(&self) -> String {
+        format!("ReducingGate {{ num_coeffs: {} }}", self.num_coeffs)
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * self.num_coeffs
+    }
+
+    fn max_degree(&self) -> usize {
+        2
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -90,7 +102,10 @@ impl<F: PrimeField> CustomGateConstrainer<F> for ReducingGateConstrainer {
 mod tests {
     use super::ReducingGateConstrainer;
     use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::gates::reducing::ReducingGate;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, reducing::ReducingGate},
+    };
 
     #[test]
     fn test_reducing_gate() {
@@ -98,6 +113,34 @@ mod tests {
         let halo2_gate = ReducingGateConstrainer {
             num_coeffs: plonky2_gate.num_coeffs,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_reducing_gate_wire_layout() {
+        type Plonky2Gate = ReducingGate<2>;
+        assert_eq!(
+            ReducingGateConstrainer::wires_output(),
+            Plonky2Gate::wires_output()
+        );
+        assert_eq!(
+            ReducingGateConstrainer::wires_alpha(),
+            Plonky2Gate::wires_alpha()
+        );
+        assert_eq!(
+            ReducingGateConstrainer::wires_old_acc(),
+            Plonky2Gate::wires_old_acc()
+        );
+        for num_coeffs in [1, 4, 43] {
+            let plonky2_gate = Plonky2Gate::new(num_coeffs);
+            let halo2_gate = ReducingGateConstrainer { num_coeffs };
+            assert_eq!(halo2_gate.wires_coeffs(), plonky2_gate.wires_coeffs());
+            // the accumulators but the last, which is the output, end the gate
+            let end = match num_coeffs {
+                1 => halo2_gate.start_accs(),
+                _ => halo2_gate.wires_accs(num_coeffs - 2).end,
+            };
+            assert_eq!(end, Gate::<GoldilocksField, 2>::num_wires(&plonky2_gate));
+        }
     }
 }
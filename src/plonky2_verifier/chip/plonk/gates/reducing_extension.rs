@@ -51,6 +51,21 @@ impl ReducingExtensionGateConstrainer {
 }
 
 impl<F: PrimeField> CustomGateConstrainer<F> for ReducingExtensionGateConstrainer {
+    fn id(&self) -> String {
+        format!(
+            "ReducingExtensionGate {{ num_coeffs: {} }}",
+            self.num_coeffs
+        )
+    }
+
+    fn num_constraints(&self) -> usize {
+        2 * self.num_coeffs
+    }
+
+    fn max_degree(&self) -> usize {
+        2
+    }
+
     fn eval_unfiltered_constraint(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -92,7 +107,10 @@ impl<F: PrimeField> CustomGateConstrainer<F> for ReducingExtensionGateConstraine
 mod tests {
     use super::ReducingExtensionGateConstrainer;
     use crate::plonky2_verifier::chip::plonk::gates::gate_test::test_custom_gate;
-    use plonky2::gates::reducing_extension::ReducingExtensionGate;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        gates::{gate::Gate, reducing_extension::ReducingExtensionGate},
+    };
 
     #[test]
     fn test_reducing_extension_gate() {
@@ -100,6 +118,28 @@ mod tests {
         let halo2_gate = ReducingExtensionGateConstrainer {
             num_coeffs: plonky2_gate.num_coeffs,
         };
-        test_custom_gate(plonky2_gate, halo2_gate, 17);
+        test_custom_gate(plonky2_gate, halo2_gate);
+    }
+
+    #[test]
+    fn test_reducing_extension_gate_wire_layout() {
+        type Plonky2Gate = ReducingExtensionGate<2>;
+        type Halo2Gate = ReducingExtensionGateConstrainer;
+        assert_eq!(Halo2Gate::wires_output(), Plonky2Gate::wires_output());
+        assert_eq!(Halo2Gate::wires_alpha(), Plonky2Gate::wires_alpha());
+        assert_eq!(Halo2Gate::wires_old_acc(), Plonky2Gate::wires_old_acc());
+        for num_coeffs in [1, 4, 32] {
+            let plonky2_gate = Plonky2Gate::new(num_coeffs);
+            let halo2_gate = Halo2Gate { num_coeffs };
+            for i in 0..num_coeffs {
+                assert_eq!(Halo2Gate::wires_coeff(i), Plonky2Gate::wires_coeff(i));
+            }
+            // the accumulators but the last, which is the output, end the gate
+            let end = match num_coeffs {
+                1 => halo2_gate.start_accs(),
+                _ => halo2_gate.wires_accs(num_coeffs - 2).end,
+            };
+            assert_eq!(end, Gate::<GoldilocksField, 2>::num_wires(&plonky2_gate));
+        }
     }
 }
@@ -1,17 +1,20 @@
+use std::{collections::VecDeque, ops::Range};
+
 use crate::plonky2_verifier::context::RegionCtx;
 use crate::plonky2_verifier::{
     chip::{
-        fri_chip::FriVerifierChip,
+        fri_chip::{FriVerifierChip, InitialPolynomialsBatches, QueryRoundProofs},
         goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
         goldilocks_extension_chip::GoldilocksExtensionChip,
         public_inputs_hasher_chip::PublicInputsHasherChip,
         transcript_chip::TranscriptChip,
     },
+    transcript_schedule::{take, transcript_schedule, Challenge, TranscriptEvent},
     types::{
         assigned::{
             AssignedExtensionFieldValue, AssignedFriChallenges, AssignedFriProofValues,
-            AssignedHashValues, AssignedProofChallenges, AssignedProofValues,
-            AssignedVerificationKeyValues,
+            AssignedHashValues, AssignedMerkleCapValues, AssignedProofChallenges,
+            AssignedProofValues, AssignedVerificationKeyValues, ShapeError,
         },
         common_data::CommonData,
         fri::FriInstanceInfo,
@@ -19,10 +22,7 @@ use crate::plonky2_verifier::{
 };
 use halo2_proofs::{halo2curves::ff::PrimeField, plonk::*};
 use halo2wrong_maingate::AssignedValue;
-use plonky2::field::{
-    goldilocks_field::GoldilocksField,
-    types::{Field, PrimeField64},
-};
+use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
 
 pub struct PlonkVerifierChip<F: PrimeField> {
     pub goldilocks_chip_config: GoldilocksChipConfig<F>,
@@ -47,9 +47,29 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         let mut public_inputs_hasher_chip =
             PublicInputsHasherChip::<F>::new(ctx, &self.goldilocks_chip_config)?;
         let outputs = public_inputs_hasher_chip.hash(ctx, public_inputs.clone(), 4)?;
-        Ok(AssignedHashValues {
-            elements: outputs.try_into().unwrap(),
-        })
+        Ok(AssignedHashValues::from_vec(outputs)?)
+    }
+
+    /// Asserts that two proofs are for the same public inputs, e.g. when both must attest to the
+    /// same statement. Public inputs of different lengths are a `ShapeError`.
+    pub fn assert_same_public_inputs(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        pis_a: &[AssignedValue<F>],
+        pis_b: &[AssignedValue<F>],
+    ) -> Result<(), Error> {
+        if pis_a.len() != pis_b.len() {
+            return Err(ShapeError {
+                expected: pis_a.len(),
+                actual: pis_b.len(),
+            }
+            .into());
+        }
+        let goldilocks_chip = self.goldilocks_chip();
+        for (a, b) in pis_a.iter().zip(pis_b.iter()) {
+            goldilocks_chip.assert_equal(ctx, a, b)?;
+        }
+        Ok(())
     }
 
     pub fn get_challenges(
@@ -59,17 +79,12 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         circuit_digest: &AssignedHashValues<F>,
         common_data: &CommonData<F>,
         assigned_proof: &AssignedProofValues<F, 2>,
-        num_challenges: usize,
     ) -> Result<AssignedProofChallenges<F, 2>, Error> {
-        let mut transcript_chip = TranscriptChip::<F>::new(ctx, &self.goldilocks_chip_config)?;
-        for e in circuit_digest.elements.iter() {
-            transcript_chip.write_scalar(ctx, &e)?;
-        }
-
-        for e in public_inputs_hash.elements.iter() {
-            transcript_chip.write_scalar(ctx, &e)?;
-        }
-
+        // a beta is squeezed per commit phase cap, so their count is checked before anything is
+        // observed
+        assigned_proof
+            .opening_proof
+            .check_commit_phase_len(&common_data.fri_params)?;
         let AssignedProofValues {
             wires_cap,
             plonk_zs_partial_products_cap,
@@ -83,67 +98,113 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
                     ..
                 },
         } = assigned_proof;
-        for hash in wires_cap.0.iter() {
-            for e in hash.elements.iter() {
-                transcript_chip.write_scalar(ctx, &e)?;
-            }
-        }
-        let plonk_betas = transcript_chip.squeeze(ctx, num_challenges)?;
-        let plonk_gammas = transcript_chip.squeeze(ctx, num_challenges)?;
+        let fri_openings = openings.to_fri_openings(common_data)?;
 
-        for hash in plonk_zs_partial_products_cap.0.iter() {
-            for e in hash.elements.iter() {
-                transcript_chip.write_scalar(ctx, &e)?;
-            }
-        }
-        let plonk_alphas = transcript_chip.squeeze(ctx, num_challenges)?;
+        // the proof by kind of observation, `transcript_schedule` decides how they interleave
+        let seeds = &common_data.challenger_seeds;
+        let mut hashes = seeds.hashes(circuit_digest, public_inputs_hash);
+        let mut constants = seeds.constants();
+        let mut caps = [wires_cap, plonk_zs_partial_products_cap, quotient_polys_cap]
+            .into_iter()
+            .chain(commit_phase_merkle_cap_values.iter())
+            .collect::<VecDeque<_>>();
+        let mut extensions = fri_openings
+            .batches
+            .iter()
+            .flat_map(|batch| batch.values.iter())
+            .chain(final_poly.0.iter())
+            .collect::<VecDeque<_>>();
+        let mut elements = VecDeque::from([pow_witness]);
 
-        for hash in quotient_polys_cap.0.iter() {
-            for e in hash.elements.iter() {
-                transcript_chip.write_scalar(ctx, &e)?;
+        let goldilocks_chip = self.goldilocks_chip();
+        let mut transcript_chip = TranscriptChip::<F>::new(ctx, &self.goldilocks_chip_config)?;
+        let mut plonk_betas = vec![];
+        let mut plonk_gammas = vec![];
+        let mut plonk_alphas = vec![];
+        let mut plonk_zeta = None;
+        let mut fri_alpha = None;
+        let mut fri_betas = vec![];
+        let mut fri_pow_response = None;
+        let mut fri_query_indices = vec![];
+        for event in transcript_schedule(common_data) {
+            match event {
+                TranscriptEvent::ObserveHash => {
+                    for hash in take(&mut hashes, 1) {
+                        transcript_chip.write_hash(ctx, hash)?;
+                    }
+                }
+                TranscriptEvent::ObserveConstants(n) => {
+                    for constant in take(&mut constants, n) {
+                        let constant = goldilocks_chip.assign_constant(ctx, constant)?;
+                        transcript_chip.write_scalar(ctx, &constant)?;
+                    }
+                }
+                TranscriptEvent::ObserveCap(_) => {
+                    for cap in take(&mut caps, 1) {
+                        transcript_chip.write_cap(ctx, cap)?;
+                    }
+                }
+                TranscriptEvent::ObserveExtension(n) => {
+                    for ext in take(&mut extensions, n) {
+                        transcript_chip.write_extension(ctx, ext)?;
+                    }
+                }
+                TranscriptEvent::ObserveElement => {
+                    for element in take(&mut elements, 1) {
+                        transcript_chip.write_scalar(ctx, element)?;
+                    }
+                }
+                TranscriptEvent::Squeeze(challenge, n) => {
+                    let values = transcript_chip.squeeze(ctx, n)?;
+                    match challenge {
+                        Challenge::PlonkBetas => plonk_betas = values,
+                        Challenge::PlonkGammas => plonk_gammas = values,
+                        Challenge::PlonkAlphas => plonk_alphas = values,
+                        Challenge::PlonkZeta => {
+                            plonk_zeta = Some(AssignedExtensionFieldValue::from_vec(values)?)
+                        }
+                        Challenge::FriAlpha => {
+                            fri_alpha = Some(AssignedExtensionFieldValue::from_vec(values)?)
+                        }
+                        Challenge::FriBeta => {
+                            fri_betas.push(AssignedExtensionFieldValue::from_vec(values)?)
+                        }
+                        Challenge::FriPowResponse => fri_pow_response = values.into_iter().next(),
+                        Challenge::FriQueryIndices => fri_query_indices = values,
+                    }
+                }
             }
         }
-        let plonk_zeta = transcript_chip.squeeze(ctx, 2)?;
 
-        let fri_openings = openings.to_fri_openings();
-
-        for v in fri_openings.batches {
-            for ext in v.values {
-                transcript_chip.write_extension(ctx, &ext)?;
-            }
+        // values the schedule didn't take are still written, so the counts report them
+        for hash in hashes {
+            transcript_chip.write_hash(ctx, hash)?;
         }
-
-        // Scaling factor to combine polynomials.
-        let fri_alpha =
-            AssignedExtensionFieldValue(transcript_chip.squeeze(ctx, 2)?.try_into().unwrap());
-
-        // Recover the random betas used in the FRI reductions.
-        let fri_betas = commit_phase_merkle_cap_values
-            .iter()
-            .map(|cap| {
-                transcript_chip.write_cap(ctx, cap)?;
-                let fri_beta = transcript_chip.squeeze(ctx, 2)?;
-                Ok(AssignedExtensionFieldValue(fri_beta.try_into().unwrap()))
-            })
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, 2>>, Error>>()?;
-
-        for ext in final_poly.0.iter() {
-            for e in ext.0.iter() {
-                transcript_chip.write_scalar(ctx, &e)?;
-            }
+        for cap in caps {
+            transcript_chip.write_cap(ctx, cap)?;
         }
+        for ext in extensions {
+            transcript_chip.write_extension(ctx, ext)?;
+        }
+        for element in elements {
+            transcript_chip.write_scalar(ctx, element)?;
+        }
+        transcript_chip.assert_counts(
+            common_data.num_transcript_observations(),
+            common_data.num_transcript_challenges(),
+        )?;
 
-        transcript_chip.write_scalar(ctx, pow_witness)?;
-        let fri_pow_response = transcript_chip.squeeze(ctx, 1)?[0].clone();
-
-        let num_fri_queries = common_data.config.fri_config.num_query_rounds;
-        let fri_query_indices = transcript_chip.squeeze(ctx, num_fri_queries)?;
-
+        // every schedule squeezes zeta, the FRI alpha and the pow response
+        let (Some(plonk_zeta), Some(fri_alpha), Some(fri_pow_response)) =
+            (plonk_zeta, fri_alpha, fri_pow_response)
+        else {
+            return Err(Error::Synthesis);
+        };
         Ok(AssignedProofChallenges {
             plonk_betas,
             plonk_gammas,
             plonk_alphas,
-            plonk_zeta: AssignedExtensionFieldValue(plonk_zeta.try_into().unwrap()),
+            plonk_zeta,
             fri_challenges: AssignedFriChallenges {
                 fri_alpha,
                 fri_betas,
@@ -161,13 +222,26 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         challenges: &AssignedProofChallenges<F, 2>,
         vk: &AssignedVerificationKeyValues<F>,
         common_data: &CommonData<F>,
+    ) -> Result<(), Error> {
+        self.verify_vanishing_poly(ctx, proof, public_inputs_hash, challenges, common_data)?;
+        self.verify_fri(ctx, proof, challenges, vk, common_data)
+    }
+
+    /// Checks the vanishing polynomial at zeta against the quotient openings.
+    pub fn verify_vanishing_poly(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &AssignedProofValues<F, 2>,
+        public_inputs_hash: &AssignedHashValues<F>,
+        challenges: &AssignedProofChallenges<F, 2>,
+        common_data: &CommonData<F>,
     ) -> Result<(), Error> {
         let goldilocks_extension_chip = GoldilocksExtensionChip::new(&self.goldilocks_chip_config);
         let one = goldilocks_extension_chip.one_extension(ctx)?;
         let local_constants = &proof.openings.constants.clone();
         let local_wires = &proof.openings.wires;
         let local_zs = &proof.openings.plonk_zs;
-        let next_zs = &proof.openings.plonk_zs_next;
+        let next_zs = proof.openings.plonk_zs_next.zs();
         let s_sigmas = &proof.openings.plonk_sigmas;
         let partial_products = &proof.openings.partial_products;
 
@@ -193,6 +267,14 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
             &challenges.plonk_alphas,
         )?;
         let quotient_polys_zeta = &proof.openings.quotient_polys;
+        // a short vector would leave the vanishing polynomial of the last challenges unchecked
+        if quotient_polys_zeta.len() != common_data.num_quotient_polys() {
+            return Err(ShapeError {
+                expected: common_data.num_quotient_polys(),
+                actual: quotient_polys_zeta.len(),
+            }
+            .into());
+        }
         let z_h_zeta = goldilocks_extension_chip.sub_extension(ctx, &zeta_pow_deg, &one)?;
         for (i, chunk) in quotient_polys_zeta
             .chunks(common_data.quotient_degree_factor)
@@ -202,24 +284,82 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
                 goldilocks_extension_chip.reduce_extension(ctx, &zeta_pow_deg, &chunk.to_vec())?;
             let computed_vanishing_poly =
                 goldilocks_extension_chip.mul_extension(ctx, &z_h_zeta, &recombined_quotient)?;
-            goldilocks_extension_chip.assert_equal_extension(
+            goldilocks_extension_chip.check_equal_extension(
                 ctx,
                 &vanishing_poly_zeta[i],
                 &computed_vanishing_poly,
             )?;
         }
 
-        let merkle_caps = &[
+        Ok(())
+    }
+
+    /// Checks the openings against the committed polynomials with FRI, with the query rounds
+    /// assigned along with `proof`, e.g. by `ProofValues::assign`.
+    pub fn verify_fri(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &AssignedProofValues<F, 2>,
+        challenges: &AssignedProofChallenges<F, 2>,
+        vk: &AssignedVerificationKeyValues<F>,
+        common_data: &CommonData<F>,
+    ) -> Result<(), Error> {
+        let query_round_proofs =
+            QueryRoundProofs::Assigned(&proof.opening_proof.query_round_proofs);
+        self.verify_fri_with_rounds(ctx, proof, query_round_proofs, challenges, vk, common_data)
+    }
+
+    /// Like `verify_fri`, for a proof whose query rounds are passed separately. Unassigned rounds
+    /// are assigned right before the round that checks them, see
+    /// `FriVerifierChip::verify_fri_proof_with_rounds`.
+    pub fn verify_fri_with_rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &AssignedProofValues<F, 2>,
+        query_round_proofs: QueryRoundProofs<'_, F, 2>,
+        challenges: &AssignedProofChallenges<F, 2>,
+        vk: &AssignedVerificationKeyValues<F>,
+        common_data: &CommonData<F>,
+    ) -> Result<(), Error> {
+        let fri = self.prepare_fri(ctx, proof, challenges, vk, common_data)?;
+        self.verify_fri_query_rounds(
+            ctx,
+            &fri,
+            proof,
+            query_round_proofs,
+            challenges,
+            0..query_round_proofs.len(),
+        )
+    }
+
+    /// Part of `verify_fri_with_rounds` shared by all query rounds. The query rounds are then
+    /// checked by `verify_fri_query_rounds`, in as many calls, and regions, as needed.
+    pub fn prepare_fri(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        proof: &AssignedProofValues<F, 2>,
+        challenges: &AssignedProofChallenges<F, 2>,
+        vk: &AssignedVerificationKeyValues<F>,
+        common_data: &CommonData<F>,
+    ) -> Result<PreparedFri<F>, Error> {
+        let goldilocks_extension_chip = GoldilocksExtensionChip::new(&self.goldilocks_chip_config);
+        // `verify_vanishing_poly` only ties the quotient openings to the other openings. They are
+        // bound to `quotient_polys_cap` by FRI: the openings at zeta, quotients included, are
+        // reduced with `fri_alpha`, and every query opens the quotient leaves against the cap and
+        // checks the reduced leaves against the reduced openings.
+        let merkle_caps = vec![
             vk.constants_sigmas_cap.clone(),
             proof.wires_cap.clone(),
             proof.plonk_zs_partial_products_cap.clone(),
             proof.quotient_polys_cap.clone(),
         ];
 
-        let g = GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR.exp_u64(
-            GoldilocksField::NEG_ONE.to_canonical_u64() / (1 << common_data.degree_bits()),
-        );
-        let zeta_next = goldilocks_extension_chip.scalar_mul(ctx, &challenges.plonk_zeta, g)?;
+        // the opening point of the next row, `g` generates the subgroup of the trace
+        let g = self
+            .goldilocks_chip()
+            .assign_subgroup_generator(ctx, common_data.degree_bits())?;
+        let g = goldilocks_extension_chip.convert_to_extension(ctx, &g)?;
+        let zeta_next = goldilocks_extension_chip.mul_extension(ctx, &challenges.plonk_zeta, &g)?;
         let fri_instance_info =
             FriInstanceInfo::new(&challenges.plonk_zeta, &zeta_next, common_data);
         let offset = self
@@ -230,14 +370,155 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
             &offset,
             common_data.fri_params.clone(),
         );
-        fri_chip.verify_fri_proof(
+        let batches = fri_chip.prepare_query_rounds(
             ctx,
-            merkle_caps,
             &challenges.fri_challenges,
-            &proof.openings.to_fri_openings(),
-            &proof.opening_proof,
+            &proof.openings.to_fri_openings(common_data)?,
             &fri_instance_info,
         )?;
-        Ok(())
+        Ok(PreparedFri {
+            fri_chip,
+            merkle_caps,
+            fri_instance_info,
+            batches,
+        })
+    }
+
+    /// Checks the query rounds `rounds` of the proof `fri` was prepared for.
+    pub fn verify_fri_query_rounds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        fri: &PreparedFri<F>,
+        proof: &AssignedProofValues<F, 2>,
+        query_round_proofs: QueryRoundProofs<'_, F, 2>,
+        challenges: &AssignedProofChallenges<F, 2>,
+        rounds: Range<usize>,
+    ) -> Result<(), Error> {
+        fri.fri_chip.verify_query_rounds(
+            ctx,
+            &fri.merkle_caps,
+            &challenges.fri_challenges,
+            &proof.opening_proof,
+            query_round_proofs,
+            rounds,
+            &fri.batches,
+            &fri.fri_instance_info,
+        )
+    }
+}
+
+/// Values of the FRI check of a proof shared by its query rounds, see
+/// `PlonkVerifierChip::prepare_fri`.
+pub struct PreparedFri<F: PrimeField> {
+    fri_chip: FriVerifierChip<F>,
+    merkle_caps: Vec<AssignedMerkleCapValues<F>>,
+    fri_instance_info: FriInstanceInfo<F, 2>,
+    batches: InitialPolynomialsBatches<F>,
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Sample};
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                arithmetic_chip::GOLDILOCKS_MODULUS,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+        types::assigned::ShapeError,
+    };
+
+    use super::PlonkVerifierChip;
+
+    /// Public inputs of two proofs, as the representatives they are assigned with.
+    #[derive(Clone, Default)]
+    struct SamePublicInputsCircuit {
+        pis_a: Vec<Fr>,
+        pis_b: Vec<Fr>,
+    }
+
+    impl Circuit<Fr> for SamePublicInputsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "same public inputs",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let mut assign = |pis: &[Fr]| {
+                        pis.iter()
+                            .map(|pi| goldilocks_chip.assign_value(ctx, Value::known(*pi)))
+                            .collect::<Result<Vec<_>, Error>>()
+                    };
+                    let pis_a = assign(&self.pis_a)?;
+                    let pis_b = assign(&self.pis_b)?;
+                    PlonkVerifierChip::construct(&config)
+                        .assert_same_public_inputs(ctx, &pis_a, &pis_b)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_assert_same_public_inputs() {
+        let pis_a = GoldilocksField::rand_vec(5)
+            .into_iter()
+            .map(goldilocks_to_fe)
+            .collect::<Vec<Fr>>();
+        let run = |pis_b: Vec<Fr>| {
+            let circuit = SamePublicInputsCircuit {
+                pis_a: pis_a.clone(),
+                pis_b,
+            };
+            mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+        };
+
+        run(pis_a.clone()).unwrap().assert_satisfied();
+        // the same elements, one of them assigned with a non-canonical representative
+        let mut pis_b = pis_a.clone();
+        pis_b[2] += Fr::from(GOLDILOCKS_MODULUS);
+        run(pis_b).unwrap().assert_satisfied();
+
+        let mut pis_b = pis_a.clone();
+        pis_b[4] += Fr::from(1u64);
+        assert!(run(pis_b).unwrap().verify().is_err());
+
+        let Err(Error::Transcript(err)) = run(pis_a[..4].to_vec()) else {
+            panic!("public inputs of different lengths");
+        };
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<ShapeError>(),
+            Some(&ShapeError {
+                expected: 5,
+                actual: 4
+            })
+        );
     }
 }
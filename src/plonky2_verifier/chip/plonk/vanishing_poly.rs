@@ -7,9 +7,10 @@ use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
 
 use crate::plonky2_verifier::{
     chip::goldilocks_extension_chip::GoldilocksExtensionChip,
-    chip::plonk::plonk_verifier_chip::PlonkVerifierChip,
+    chip::plonk::{gates::UNUSED_SELECTOR, plonk_verifier_chip::PlonkVerifierChip},
+    chip::reducing_factor_chip::ReducingFactorChip,
     types::{
-        assigned::{AssignedExtensionFieldValue, AssignedHashValues},
+        assigned::{AssignedExtensionFieldValue, AssignedHashValues, ShapeError},
         common_data::CommonData,
     },
 };
@@ -32,6 +33,19 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         gammas: &[AssignedValue<F>],
         alphas: &[AssignedValue<F>],
     ) -> Result<Vec<AssignedExtensionFieldValue<F, 2>>, Error> {
+        // the permutation argument needs one sigma per routed wire, a shorter vector would leave
+        // the remaining wires out of the copy constraints
+        if s_sigmas.len() != common_data.config.num_routed_wires {
+            return Err(ShapeError {
+                expected: common_data.config.num_routed_wires,
+                actual: s_sigmas.len(),
+            }
+            .into());
+        }
+        // selector groups pair gate indices with constrainers, checked natively as it costs no
+        // rows
+        common_data.check_gate_order()?;
+
         let goldilocks_extension_chip = GoldilocksExtensionChip::new(&self.goldilocks_chip_config);
         let max_degree = common_data.quotient_degree_factor;
         let num_prods = common_data.num_partial_products;
@@ -92,7 +106,8 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
                 denominator_values.push(denominator);
             }
 
-            // The partial products considered for this iteration of `i`.
+            // The partial products considered for this iteration of `i`. Empty when all routed
+            // wires fit in one chunk, in which case `z_x` and `z_gx` are checked directly.
             let current_partial_products = &partial_products[i * num_prods..(i + 1) * num_prods];
             // Check the quotient partial products.
             let partial_product_checks = self.check_partial_products(
@@ -114,11 +129,14 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         ]
         .concat();
 
+        // one combination per challenge, each with a fresh `ReducingFactor` as in plonky2's
+        // `reduce_with_powers_multi`
         alphas
             .iter()
             .map(|alpha| {
                 let alpha = goldilocks_extension_chip.convert_to_extension(ctx, alpha)?;
-                goldilocks_extension_chip.reduce_extension(ctx, &alpha, &vanishing_terms)
+                ReducingFactorChip::new(&self.goldilocks_chip_config, alpha)
+                    .reduce(ctx, &vanishing_terms)
             })
             .collect()
     }
@@ -134,24 +152,84 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
         let goldilocks_extension_chip = GoldilocksExtensionChip::new(&self.goldilocks_chip_config);
         let zero_extension = goldilocks_extension_chip.zero_extension(ctx)?;
         let mut all_gate_constraints = vec![zero_extension; common_data.num_gate_constraints];
-        for (i, gate) in common_data.gates.iter().enumerate() {
-            let selector_index = common_data.selectors_info.selector_indices[i];
-            gate.0.eval_filtered_constraint(
+        let num_selectors = common_data.selectors_info.num_selectors();
+        let gate_selectors = common_data
+            .selectors_info
+            .gate_selectors(common_data.gates.len())?;
+        let (selector_constants, unused_selector) = self.selector_constants(ctx, common_data)?;
+        // `k - f(zeta)` for every gate `k` of a selector group, and for the unused selector. Every
+        // gate of the group filters with all of them but its own, so they are assigned once per
+        // group instead of once per gate.
+        let selector_terms = common_data
+            .selectors_info
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(selector_index, group)| {
+                let f_zeta = &local_constants[selector_index];
+                group
+                    .clone()
+                    .map(|k| &selector_constants[k])
+                    .chain((num_selectors > 1).then_some(&unused_selector))
+                    .map(|k| goldilocks_extension_chip.sub_extension(ctx, k, f_zeta))
+                    .collect::<Result<Vec<AssignedExtensionFieldValue<F, 2>>, Error>>()
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (gate_index, selector_index) in gate_selectors {
+            let group = &common_data.selectors_info.groups[selector_index];
+            common_data.gates[gate_index].0.eval_filtered_constraint(
                 ctx,
                 &self.goldilocks_chip_config,
                 local_constants,
                 local_wires,
                 public_inputs_hash,
-                i,
-                selector_index,
-                common_data.selectors_info.groups[selector_index].clone(),
-                common_data.selectors_info.num_selectors(),
+                gate_index - group.start,
+                &selector_terms[selector_index],
+                num_selectors,
                 &mut all_gate_constraints,
             )?;
         }
         Ok(all_gate_constraints)
     }
 
+    /// The constants a selector filter can name: every gate index up to the end of the last
+    /// selector group, and the unused selector. They are assigned up front, before any group
+    /// needs them, and the constant cache of the region hands the same cells to every later
+    /// proof verified in it.
+    fn selector_constants(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        common_data: &CommonData<F>,
+    ) -> Result<
+        (
+            Vec<AssignedExtensionFieldValue<F, 2>>,
+            AssignedExtensionFieldValue<F, 2>,
+        ),
+        Error,
+    > {
+        let goldilocks_extension_chip = GoldilocksExtensionChip::new(&self.goldilocks_chip_config);
+        let constant = |ctx: &mut RegionCtx<'_, F>, k: usize| {
+            goldilocks_extension_chip.constant_extension(
+                ctx,
+                &[
+                    GoldilocksField::from_canonical_u64(k as u64),
+                    GoldilocksField::ZERO,
+                ],
+            )
+        };
+        let num_indices = common_data
+            .selectors_info
+            .groups
+            .iter()
+            .map(|group| group.end)
+            .max()
+            .unwrap_or_default();
+        let indices = (0..num_indices)
+            .map(|k| constant(ctx, k))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok((indices, constant(ctx, UNUSED_SELECTOR)?))
+    }
+
     fn eval_l_0_x(
         &self,
         ctx: &mut RegionCtx<'_, F>,
@@ -217,3 +295,110 @@ impl<F: PrimeField> PlonkVerifierChip<F> {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            goldilocks_extension_chip::GoldilocksExtensionChip,
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+            },
+        },
+        context::RegionCtx,
+        fixtures::generate_proof_tuple,
+        types::{assigned::AssignedExtensionFieldValue, common_data::CommonData},
+    };
+
+    use super::PlonkVerifierChip;
+
+    /// Assigns the selector constants of `common_data` twice in one region.
+    #[derive(Clone)]
+    struct SelectorConstantsCircuit {
+        common_data: CommonData<Fr>,
+        /// Rows taken by each assignment.
+        rows: Cell<[usize; 2]>,
+    }
+
+    impl Circuit<Fr> for SelectorConstantsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "selector constants",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let chip = PlonkVerifierChip::construct(&config);
+                    // as in `eval_gate_constraints`, the zero limb is already assigned
+                    GoldilocksExtensionChip::new(&config).zero_extension(ctx)?;
+                    let start = ctx.offset();
+                    let (indices, unused) = chip.selector_constants(ctx, &self.common_data)?;
+                    let first_rows = ctx.offset() - start;
+                    let start = ctx.offset();
+                    let (indices_again, unused_again) =
+                        chip.selector_constants(ctx, &self.common_data)?;
+                    self.rows.set([first_rows, ctx.offset() - start]);
+
+                    let cells =
+                        |x: &AssignedExtensionFieldValue<Fr, 2>| x.0.clone().map(|c| c.cell());
+                    assert_eq!(cells(&unused_again), cells(&unused));
+                    assert_eq!(indices_again.len(), indices.len());
+                    for (a, b) in indices.iter().zip(indices_again.iter()) {
+                        assert_eq!(cells(a), cells(b));
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_selector_constants_rows() {
+        let (_, _, cd) = generate_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        // the recursive fixture has several selector groups, so its filters name the unused
+        // selector too
+        assert!(common_data.selectors_info.num_selectors() > 1);
+        let num_gates = common_data.gates.len();
+        let circuit = SmallTable(SelectorConstantsCircuit {
+            common_data,
+            rows: Cell::new([0; 2]),
+        });
+        mock_prover_at_min_k(&circuit, vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+        let [first, again] = circuit.0.rows.get();
+        // a row for every gate index but zero and one for the unused selector, however many
+        // groups or gates filter with them
+        assert_eq!(first, num_gates);
+        // every later proof verified in the region reuses the cells
+        assert_eq!(again, 0);
+    }
+}
@@ -464,3 +464,21 @@ pub fn get_round_constants<const T: usize>() -> Vec<[F; T]> {
         })
         .collect()
 }
+
+/// Rows of the MDS matrix, for the runtime-sized `DynSpec`
+#[cfg(any(feature = "dyn-spec", test))]
+pub fn dyn_mds_matrix() -> Vec<Vec<F>> {
+    MDS_MATRIX
+        .iter()
+        .map(|row| row.iter().map(|e| F::from_canonical_u64(*e)).collect())
+        .collect()
+}
+
+/// Unoptimized round constants, for the runtime-sized `DynSpec`
+#[cfg(any(feature = "dyn-spec", test))]
+pub fn dyn_round_constants() -> Vec<Vec<F>> {
+    ROUND_CONSTANTS
+        .iter()
+        .map(|l| l.iter().map(|e| F::from_canonical_u64(*e)).collect())
+        .collect()
+}
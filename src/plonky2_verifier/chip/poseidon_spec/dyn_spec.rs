@@ -0,0 +1,593 @@
+//! Runtime-sized counterparts of the `spec` and `matrix` types. The chips only ever use width
+//! `SPONGE_WIDTH`, so with the `dyn-spec` feature they read their parameters from `DynSpec`
+//! instead of monomorphizing `Spec` and its matrices over the width. Lengths that the const
+//! generic types enforce in their signatures are asserted here instead.
+
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    hash::{
+        hashing::SPONGE_WIDTH,
+        poseidon::{HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS},
+    },
+};
+
+use super::constants;
+
+type F = GoldilocksField;
+
+/// `Matrix` of a runtime size, stored row by row
+#[derive(PartialEq, Debug, Clone)]
+pub struct DynMatrix(pub Vec<Vec<F>>);
+
+impl DynMatrix {
+    pub fn zero_matrix(size: usize) -> Self {
+        Self(vec![vec![F::ZERO; size]; size])
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut m = Self::zero_matrix(size);
+        for i in 0..size {
+            m.0[i][i] = F::ONE;
+        }
+        m
+    }
+
+    pub fn from_vec(vec: Vec<Vec<F>>) -> Self {
+        let n = vec.len();
+        // Expect square and well formed matrix
+        for row in vec.iter() {
+            assert_eq!(row.len(), n);
+        }
+        Self(vec)
+    }
+
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let n = self.size();
+        Self(
+            (0..n)
+                .map(|j| (0..n).map(|i| self.0[i][j]).collect())
+                .collect(),
+        )
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let n = self.size();
+        assert_eq!(other.size(), n);
+        let mut result = Self::zero_matrix(n);
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    result.0[i][j] += self.0[i][k] * other.0[k][j];
+                }
+            }
+        }
+        result
+    }
+
+    pub fn mul_vector(&self, v: &[F]) -> Vec<F> {
+        assert_eq!(v.len(), self.size());
+        self.0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(v.iter())
+                    .fold(F::ZERO, |acc, (a_i, v_i)| acc + *a_i * *v_i)
+            })
+            .collect()
+    }
+
+    /// Gauss-Jordan inversion, like `Matrix::invert` it doesn't check that the matrix is
+    /// invertible
+    pub fn invert(&self) -> Self {
+        let n = self.size();
+        let identity = Self::identity(n);
+        let mut m: Vec<Vec<F>> = self
+            .0
+            .iter()
+            .zip(identity.0.iter())
+            .map(|(v_row, u_row)| [v_row.as_slice(), u_row.as_slice()].concat())
+            .collect();
+
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    let r = m[j][i] * m[i][i].inverse();
+                    for k in 0..2 * n {
+                        let e = m[i][k];
+                        m[j][k] -= r * e;
+                    }
+                }
+            }
+        }
+
+        Self(
+            m.into_iter()
+                .enumerate()
+                .map(|(i, row)| {
+                    let pivot_inverse = row[i].inverse();
+                    row[n..].iter().map(|e| *e * pivot_inverse).collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// First column without its first element
+    pub fn w(&self) -> Vec<F> {
+        self.0.iter().skip(1).map(|row| row[0]).collect()
+    }
+
+    /// Matrix without its first row and first column
+    pub fn sub(&self) -> Self {
+        Self::from_vec(self.0.iter().skip(1).map(|row| row[1..].to_vec()).collect())
+    }
+}
+
+/// `State` of a runtime width
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynState(pub Vec<F>);
+
+impl DynState {
+    /// Zero state of `width` elements
+    pub fn new(width: usize) -> Self {
+        DynState(vec![F::ZERO; width])
+    }
+
+    /// Applies sbox for all elements of the state.
+    /// Only supports `alpha = 7` sbox case.
+    pub fn sbox_full(&mut self) {
+        for e in self.0.iter_mut() {
+            *e = e.exp_u64(7);
+        }
+    }
+
+    /// Partial round sbox applies sbox to the first element of the state.
+    /// Only supports `alpha = 7` sbox case
+    pub fn sbox_part(&mut self) {
+        self.0[0] = self.0[0].exp_u64(7);
+    }
+
+    /// Adds constants to all elements of the state
+    pub fn add_constants(&mut self, constants: &[F]) {
+        assert_eq!(constants.len(), self.0.len());
+        for (e, constant) in self.0.iter_mut().zip(constants.iter()) {
+            *e += *constant;
+        }
+    }
+
+    /// Only adds a constant to the first element of the state
+    pub fn add_constant(&mut self, constant: &F) {
+        self.0[0] += *constant;
+    }
+
+    /// Copies elements of the state
+    pub fn words(&self) -> Vec<F> {
+        self.0.clone()
+    }
+
+    /// Second element of the state is the result
+    pub fn result(&self) -> F {
+        self.0[1]
+    }
+}
+
+/// `Spec` of a runtime width
+#[derive(Debug, Clone)]
+pub struct DynSpec {
+    pub width: usize,
+    pub r_f: usize,
+    pub mds_matrices: DynMDSMatrices,
+    pub constants: DynOptimizedConstants,
+}
+
+/// `DynSpec` of plonky2's Goldilocks Poseidon, the runtime-sized `poseidon_goldilocks_spec`
+pub fn poseidon_goldilocks_spec() -> DynSpec {
+    DynSpec::new(SPONGE_WIDTH, 2 * HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS)
+}
+
+impl DynSpec {
+    /// Computes the optimized constants and sparse matrices of a `width` sized permutation
+    /// like `Spec::new`, on runtime-sized matrices. The MDS matrix and the round constants are
+    /// only given for `SPONGE_WIDTH`, the width of plonky2's Poseidon.
+    pub fn new(width: usize, r_f: usize, r_p: usize) -> Self {
+        assert_eq!(
+            width, SPONGE_WIDTH,
+            "round constants are only generated for width {SPONGE_WIDTH}"
+        );
+        let mds = DynMDSMatrix(DynMatrix::from_vec(constants::dyn_mds_matrix()));
+        let unoptimized_constants = constants::dyn_round_constants();
+        let constants = Self::calculate_optimized_constants(r_f, r_p, unoptimized_constants, &mds);
+        let (sparse_matrices, pre_sparse_mds) = Self::calculate_sparse_matrices(r_p, &mds);
+
+        Self {
+            width,
+            r_f,
+            constants,
+            mds_matrices: DynMDSMatrices {
+                mds,
+                sparse_matrices,
+                pre_sparse_mds,
+            },
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of full rounds
+    pub fn r_f(&self) -> usize {
+        self.r_f
+    }
+
+    /// Set of MDS Matrices used in permutation line
+    pub fn mds_matrices(&self) -> &DynMDSMatrices {
+        &self.mds_matrices
+    }
+
+    /// Optimised round constants
+    pub fn constants(&self) -> &DynOptimizedConstants {
+        &self.constants
+    }
+
+    /// Applies the permutation to the state natively, like `Spec::permute`
+    pub fn permute(&self, state: &mut DynState) {
+        assert_eq!(state.0.len(), self.width);
+        let r_f_half = self.r_f / 2;
+        let mds = self.mds_matrices.mds();
+        let constants = &self.constants;
+
+        // First half of the full rounds
+        state.add_constants(&constants.start[0]);
+        for constants in constants.start.iter().skip(1).take(r_f_half - 1) {
+            state.sbox_full();
+            state.add_constants(constants);
+            mds.apply(state);
+        }
+        state.sbox_full();
+        state.add_constants(constants.start.last().unwrap());
+        self.mds_matrices.pre_sparse_mds().apply(state);
+
+        // Partial rounds
+        for (constant, sparse_mds) in constants
+            .partial
+            .iter()
+            .zip(self.mds_matrices.sparse_matrices().iter())
+        {
+            state.sbox_part();
+            state.add_constant(constant);
+            sparse_mds.apply(state);
+        }
+
+        // Second half of the full rounds
+        for constants in constants.end.iter() {
+            state.sbox_full();
+            state.add_constants(constants);
+            mds.apply(state);
+        }
+        state.sbox_full();
+        mds.apply(state);
+    }
+
+    fn calculate_optimized_constants(
+        r_f: usize,
+        r_p: usize,
+        constants: Vec<Vec<F>>,
+        mds: &DynMDSMatrix,
+    ) -> DynOptimizedConstants {
+        let width = mds.0.size();
+        let inverse_mds = mds.0.invert();
+        let (number_of_rounds, r_f_half) = (r_f + r_p, r_f / 2);
+        assert_eq!(constants.len(), number_of_rounds);
+        for constants in constants.iter() {
+            assert_eq!(constants.len(), width);
+        }
+
+        // Calculate optimized constants for first half of the full rounds
+        let mut constants_start = vec![constants[0].clone()];
+        constants_start.extend(
+            constants
+                .iter()
+                .skip(1)
+                .take(r_f_half - 1)
+                .map(|constants| inverse_mds.mul_vector(constants)),
+        );
+
+        // Calculate constants for partial rounds
+        let mut acc = constants[r_f_half + r_p].clone();
+        let mut constants_partial = vec![F::ZERO; r_p];
+        for (optimized, constants) in constants_partial
+            .iter_mut()
+            .rev()
+            .zip(constants.iter().skip(r_f_half).rev().skip(r_f_half))
+        {
+            let mut tmp = inverse_mds.mul_vector(&acc);
+            *optimized = tmp[0];
+
+            tmp[0] = F::ZERO;
+            for ((acc, tmp), constant) in acc.iter_mut().zip(tmp).zip(constants.iter()) {
+                *acc = tmp + *constant
+            }
+        }
+        constants_start.push(inverse_mds.mul_vector(&acc));
+
+        // Calculate optimized constants for ending half of the full rounds
+        let constants_end = constants
+            .iter()
+            .skip(r_f_half + r_p + 1)
+            .take(r_f_half - 1)
+            .map(|constants| inverse_mds.mul_vector(constants))
+            .collect();
+
+        DynOptimizedConstants {
+            start: constants_start,
+            partial: constants_partial,
+            end: constants_end,
+        }
+    }
+
+    fn calculate_sparse_matrices(
+        r_p: usize,
+        mds: &DynMDSMatrix,
+    ) -> (Vec<DynSparseMDSMatrix>, DynMDSMatrix) {
+        let mds = mds.transpose();
+        let mut acc = mds.clone();
+        let mut sparse_matrices = (0..r_p)
+            .map(|_| {
+                let (m_prime, m_prime_prime) = acc.factorise();
+                acc = mds.mul(&m_prime);
+                m_prime_prime
+            })
+            .collect::<Vec<DynSparseMDSMatrix>>();
+
+        sparse_matrices.reverse();
+        (sparse_matrices, acc.transpose())
+    }
+}
+
+/// `OptimizedConstants` of a runtime width
+#[derive(Debug, Clone)]
+pub struct DynOptimizedConstants {
+    pub start: Vec<Vec<F>>,
+    pub partial: Vec<F>,
+    pub end: Vec<Vec<F>>,
+}
+
+impl DynOptimizedConstants {
+    /// Returns rounds constants for first part of full rounds
+    pub fn start(&self) -> &Vec<Vec<F>> {
+        &self.start
+    }
+
+    /// Returns rounds constants for partial rounds
+    pub fn partial(&self) -> &Vec<F> {
+        &self.partial
+    }
+
+    /// Returns rounds constants for second part of full rounds
+    pub fn end(&self) -> &Vec<Vec<F>> {
+        &self.end
+    }
+}
+
+/// `MDSMatrices` of a runtime width
+#[derive(Debug, Clone)]
+pub struct DynMDSMatrices {
+    pub mds: DynMDSMatrix,
+    pub pre_sparse_mds: DynMDSMatrix,
+    pub sparse_matrices: Vec<DynSparseMDSMatrix>,
+}
+
+impl DynMDSMatrices {
+    /// Returns original MDS matrix
+    pub fn mds(&self) -> &DynMDSMatrix {
+        &self.mds
+    }
+
+    /// Returns transition matrix for sparse trick
+    pub fn pre_sparse_mds(&self) -> &DynMDSMatrix {
+        &self.pre_sparse_mds
+    }
+
+    /// Returns sparse matrices for partial rounds
+    pub fn sparse_matrices(&self) -> &Vec<DynSparseMDSMatrix> {
+        &self.sparse_matrices
+    }
+}
+
+/// `MDSMatrix` of a runtime width
+#[derive(Clone, Debug)]
+pub struct DynMDSMatrix(pub DynMatrix);
+
+impl DynMDSMatrix {
+    /// Applies `DynMDSMatrix` to the state
+    pub fn apply(&self, state: &mut DynState) {
+        state.0 = self.0.mul_vector(&state.0);
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self(self.0.mul(&other.0))
+    }
+
+    fn transpose(&self) -> Self {
+        Self(self.0.transpose())
+    }
+
+    /// Factorises the MDS matrix `M` into `M'` and the sparse `M''` with `M = M' * M''`, like
+    /// `MDSMatrix::factorise`
+    fn factorise(&self) -> (Self, DynSparseMDSMatrix) {
+        let width = self.0.size();
+        let m_hat = self.0.sub();
+        let w_hat = m_hat.invert().mul_vector(&self.0.w());
+
+        // `[[1 | 0], [0 | m_hat]]`
+        let mut prime = DynMatrix::identity(width);
+        for (prime_row, hat_row) in prime.0.iter_mut().skip(1).zip(m_hat.0.iter()) {
+            prime_row[1..].copy_from_slice(hat_row);
+        }
+
+        // `[[m_0_0 | m_0_i], [w_hat | identity]]`
+        let mut prime_prime = DynMatrix::identity(width);
+        prime_prime.0[0] = self.0 .0[0].clone();
+        for (row, w) in prime_prime.0.iter_mut().skip(1).zip(w_hat.iter()) {
+            row[0] = *w
+        }
+
+        (Self(prime), Self(prime_prime).transpose().into())
+    }
+
+    /// Returns rows of the MDS matrix
+    pub fn rows(&self) -> Vec<Vec<F>> {
+        self.0 .0.clone()
+    }
+}
+
+/// `SparseMDSMatrix` of a runtime width
+#[derive(Debug, Clone)]
+pub struct DynSparseMDSMatrix {
+    pub row: Vec<F>,
+    pub col_hat: Vec<F>,
+}
+
+impl DynSparseMDSMatrix {
+    /// Returns the first row
+    pub fn row(&self) -> &[F] {
+        &self.row
+    }
+
+    /// Returns the first column without first element in the first row
+    pub fn col_hat(&self) -> &[F] {
+        &self.col_hat
+    }
+
+    /// Applies the sparse MDS matrix to the state
+    pub fn apply(&self, state: &mut DynState) {
+        assert_eq!(self.row.len(), state.0.len());
+        assert_eq!(self.col_hat.len() + 1, state.0.len());
+        let words = state.words();
+        state.0[0] = self
+            .row
+            .iter()
+            .zip(words.iter())
+            .fold(F::ZERO, |acc, (e, cell)| acc + (*e * *cell));
+
+        for ((new_word, col_el), word) in (state.0)
+            .iter_mut()
+            .skip(1)
+            .zip(self.col_hat.iter())
+            .zip(words.iter().skip(1))
+        {
+            *new_word = *col_el * words[0] + *word;
+        }
+    }
+}
+
+impl From<DynMDSMatrix> for DynSparseMDSMatrix {
+    /// Assert the form and represent an MDS matrix as a sparse MDS matrix
+    fn from(mds: DynMDSMatrix) -> Self {
+        let mds = mds.0;
+        for (i, row) in mds.0.iter().enumerate().skip(1) {
+            for (j, e) in row.iter().enumerate().skip(1) {
+                assert_eq!(*e, if i != j { F::ZERO } else { F::ONE });
+            }
+        }
+
+        DynSparseMDSMatrix {
+            row: mds.0[0].clone(),
+            col_hat: mds.0.iter().skip(1).map(|row| row[0]).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::{Field, Sample},
+        },
+        hash::{hashing::SPONGE_WIDTH, poseidon::Poseidon},
+    };
+
+    use super::{poseidon_goldilocks_spec, DynMatrix, DynSpec, DynState};
+    use crate::plonky2_verifier::chip::poseidon_spec::spec::{self, State};
+
+    const T: usize = SPONGE_WIDTH;
+
+    #[test]
+    fn test_dyn_spec_parameters_match_const_spec() {
+        let spec = spec::poseidon_goldilocks_spec();
+        let dyn_spec = poseidon_goldilocks_spec();
+        assert_eq!(dyn_spec.r_f(), spec.r_f());
+
+        let rows =
+            |rows: &[[GoldilocksField; T]]| rows.iter().map(|row| row.to_vec()).collect::<Vec<_>>();
+        let constants = spec.constants();
+        let dyn_constants = dyn_spec.constants();
+        assert_eq!(dyn_constants.start(), &rows(constants.start()));
+        assert_eq!(dyn_constants.partial(), constants.partial());
+        assert_eq!(dyn_constants.end(), &rows(constants.end()));
+
+        let matrices = spec.mds_matrices();
+        let dyn_matrices = dyn_spec.mds_matrices();
+        assert_eq!(dyn_matrices.mds().rows(), rows(&matrices.mds().rows()));
+        assert_eq!(
+            dyn_matrices.pre_sparse_mds().rows(),
+            rows(&matrices.pre_sparse_mds().rows())
+        );
+        assert_eq!(
+            dyn_matrices.sparse_matrices().len(),
+            matrices.sparse_matrices().len()
+        );
+        for (dyn_sparse, sparse) in dyn_matrices
+            .sparse_matrices()
+            .iter()
+            .zip(matrices.sparse_matrices().iter())
+        {
+            assert_eq!(dyn_sparse.row(), sparse.row());
+            assert_eq!(dyn_sparse.col_hat(), sparse.col_hat());
+        }
+    }
+
+    #[test]
+    fn test_dyn_spec_permutation_matches_const_spec() {
+        let spec = spec::poseidon_goldilocks_spec();
+        let dyn_spec = poseidon_goldilocks_spec();
+        let mut inputs = vec![[GoldilocksField::ZERO; T]];
+        inputs.extend((0..8).map(|_| GoldilocksField::rand_array::<T>()));
+        for input in inputs {
+            let mut state = State(input);
+            spec.permute(&mut state);
+            let mut dyn_state = DynState(input.to_vec());
+            dyn_spec.permute(&mut dyn_state);
+
+            assert_eq!(dyn_state.0, state.0.to_vec());
+            assert_eq!(state.0, GoldilocksField::poseidon(input));
+        }
+    }
+
+    #[test]
+    fn test_dyn_matrix_invert() {
+        let m = DynMatrix::from_vec(
+            (0..T)
+                .map(|_| GoldilocksField::rand_vec(T))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(m.mul(&m.invert()), DynMatrix::identity(T));
+    }
+
+    #[test]
+    #[should_panic(expected = "round constants are only generated for width 12")]
+    fn test_dyn_spec_rejects_other_widths() {
+        DynSpec::new(8, 8, 22);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dyn_state_rejects_wrong_length_constants() {
+        DynState::new(T).add_constants(&[GoldilocksField::ONE; T - 1]);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod constants;
+#[cfg(any(feature = "dyn-spec", test))]
+pub mod dyn_spec;
 pub mod matrix;
 pub mod spec;
@@ -1,4 +1,10 @@
-use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    hash::{
+        hashing::SPONGE_WIDTH,
+        poseidon::{HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS},
+    },
+};
 
 use super::{constants, matrix::Matrix};
 use std::ops::Index;
@@ -81,6 +87,51 @@ impl<const T: usize, const T_MINUS_ONE: usize> Spec<T, T_MINUS_ONE> {
     pub fn constants(&self) -> &OptimizedConstants<T> {
         &self.constants
     }
+
+    /// Applies the permutation to the state natively, with the optimized constants and sparse
+    /// matrices in the order `PublicInputsHasherChip::permutation` constrains them
+    pub fn permute(&self, state: &mut State<T>) {
+        let r_f_half = self.r_f / 2;
+        let mds = self.mds_matrices.mds();
+        let constants = &self.constants;
+
+        // First half of the full rounds
+        state.add_constants(&constants.start[0]);
+        for constants in constants.start.iter().skip(1).take(r_f_half - 1) {
+            state.sbox_full();
+            state.add_constants(constants);
+            mds.apply(state);
+        }
+        state.sbox_full();
+        state.add_constants(constants.start.last().unwrap());
+        self.mds_matrices.pre_sparse_mds().apply(state);
+
+        // Partial rounds
+        for (constant, sparse_mds) in constants
+            .partial
+            .iter()
+            .zip(self.mds_matrices.sparse_matrices().iter())
+        {
+            state.sbox_part();
+            state.add_constant(constant);
+            sparse_mds.apply(state);
+        }
+
+        // Second half of the full rounds
+        for constants in constants.end.iter() {
+            state.sbox_full();
+            state.add_constants(constants);
+            mds.apply(state);
+        }
+        state.sbox_full();
+        mds.apply(state);
+    }
+}
+
+/// `Spec` of plonky2's Goldilocks Poseidon. The round counts are read from plonky2, so the
+/// permutation can't drift from the one the prover hashes with.
+pub fn poseidon_goldilocks_spec() -> Spec<SPONGE_WIDTH, { SPONGE_WIDTH - 1 }> {
+    Spec::new(2 * HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS)
 }
 
 /// `OptimizedConstants` has round constants that are added each round. While
@@ -150,10 +201,10 @@ impl<const T: usize, const T_MINUS_ONE: usize> Index<usize> for MDSMatrix<T, T_M
 }
 
 impl<const T: usize, const T_MINUS_ONE: usize> MDSMatrix<T, T_MINUS_ONE> {
-    // /// Applies `MDSMatrix` to the state
-    // pub fn apply(&self, state: &mut State<T>) {
-    //     state.0 = self.0.mul_vector(&state.0);
-    // }
+    /// Applies `MDSMatrix` to the state
+    pub fn apply(&self, state: &mut State<T>) {
+        state.0 = self.0.mul_vector(&state.0);
+    }
 
     // /// Given two `T` sized vector constructs the `t * t` Cauchy matrix
     // pub(super) fn cauchy(xs: &[F; T], ys: &[F; T]) -> Self {
@@ -405,3 +456,34 @@ impl<const T: usize, const T_MINUS_ONE: usize> Spec<T, T_MINUS_ONE> {
         (sparse_matrices, acc.transpose())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::{Field, Sample},
+        },
+        hash::{
+            hashing::SPONGE_WIDTH,
+            poseidon::{Poseidon, HALF_N_FULL_ROUNDS, N_PARTIAL_ROUNDS},
+        },
+    };
+
+    use super::{poseidon_goldilocks_spec, State};
+
+    #[test]
+    fn test_poseidon_goldilocks_spec_matches_plonky2() {
+        let spec = poseidon_goldilocks_spec();
+        assert_eq!(spec.r_f(), 2 * HALF_N_FULL_ROUNDS);
+        assert_eq!(spec.constants().partial().len(), N_PARTIAL_ROUNDS);
+
+        let mut inputs = vec![[GoldilocksField::ZERO; SPONGE_WIDTH]];
+        inputs.extend((0..8).map(|_| GoldilocksField::rand_array::<SPONGE_WIDTH>()));
+        for input in inputs {
+            let mut state = State(input);
+            spec.permute(&mut state);
+            assert_eq!(state.0, GoldilocksField::poseidon(input));
+        }
+    }
+}
@@ -10,13 +10,37 @@ use crate::plonky2_verifier::context::RegionCtx;
 use super::{
     goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
     native_chip::utils::goldilocks_to_fe,
-    poseidon_spec::spec::{SparseMDSMatrix, Spec, State},
+    poseidon_spec::spec::State,
 };
 
+#[cfg(feature = "dyn-spec")]
+use super::poseidon_spec::dyn_spec::poseidon_goldilocks_spec;
+#[cfg(not(feature = "dyn-spec"))]
+use super::poseidon_spec::spec::poseidon_goldilocks_spec;
+
 const T: usize = SPONGE_WIDTH;
+#[cfg(not(feature = "dyn-spec"))]
 const T_MINUS_ONE: usize = T - 1;
 const RATE: usize = 8;
 
+#[cfg(not(feature = "dyn-spec"))]
+type ChipSpec = super::poseidon_spec::spec::Spec<T, T_MINUS_ONE>;
+#[cfg(not(feature = "dyn-spec"))]
+type ChipSparseMDSMatrix = super::poseidon_spec::spec::SparseMDSMatrix<T, T_MINUS_ONE>;
+#[cfg(not(feature = "dyn-spec"))]
+type Row = [GoldilocksField; T];
+#[cfg(not(feature = "dyn-spec"))]
+type Rows = [Row; T];
+
+#[cfg(feature = "dyn-spec")]
+type ChipSpec = super::poseidon_spec::dyn_spec::DynSpec;
+#[cfg(feature = "dyn-spec")]
+type ChipSparseMDSMatrix = super::poseidon_spec::dyn_spec::DynSparseMDSMatrix;
+#[cfg(feature = "dyn-spec")]
+type Row = Vec<GoldilocksField>;
+#[cfg(feature = "dyn-spec")]
+type Rows = Vec<Row>;
+
 /// `AssignedState` is composed of `T` sized assigned values
 #[derive(Debug, Clone)]
 pub struct AssignedState<F: PrimeField, const T: usize>(pub(super) [AssignedValue<F>; T]);
@@ -28,7 +52,7 @@ pub struct PublicInputsHasherChip<F: PrimeField> {
     state: AssignedState<F, T>,
     absorbing: Vec<AssignedValue<F>>,
     output_buffer: Vec<AssignedValue<F>>,
-    spec: Spec<T, T_MINUS_ONE>,
+    spec: ChipSpec,
     goldilocks_chip_config: GoldilocksChipConfig<F>,
 }
 
@@ -39,7 +63,7 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
         ctx: &mut RegionCtx<'_, F>,
         goldilocks_chip_config: &GoldilocksChipConfig<F>,
     ) -> Result<Self, Error> {
-        let spec = Spec::<T, T_MINUS_ONE>::new(8, 22);
+        let spec = poseidon_goldilocks_spec();
         let goldilocks_chip = GoldilocksChip::new(goldilocks_chip_config);
 
         let initial_state = State::<T>::default()
@@ -114,7 +138,7 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
         self.spec.r_f() / 2
     }
 
-    pub(super) fn constants_start(&self) -> Vec<[GoldilocksField; T]> {
+    pub(super) fn constants_start(&self) -> Vec<Row> {
         self.spec.constants().start().clone()
     }
 
@@ -122,19 +146,19 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
         self.spec.constants().partial().clone()
     }
 
-    pub(super) fn constants_end(&self) -> Vec<[GoldilocksField; T]> {
+    pub(super) fn constants_end(&self) -> Vec<Row> {
         self.spec.constants().end().clone()
     }
 
-    pub(super) fn mds(&self) -> [[GoldilocksField; T]; T] {
+    pub(super) fn mds(&self) -> Rows {
         self.spec.mds_matrices().mds().rows()
     }
 
-    pub(super) fn pre_sparse_mds(&self) -> [[GoldilocksField; T]; T] {
+    pub(super) fn pre_sparse_mds(&self) -> Rows {
         self.spec.mds_matrices().pre_sparse_mds().rows()
     }
 
-    pub(super) fn sparse_matrices(&self) -> Vec<SparseMDSMatrix<T, T_MINUS_ONE>> {
+    pub(super) fn sparse_matrices(&self) -> Vec<ChipSparseMDSMatrix> {
         self.spec.mds_matrices().sparse_matrices().clone()
     }
 }
@@ -144,8 +168,9 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
     fn sbox_full(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        constants: &[GoldilocksField; T],
+        constants: &[GoldilocksField],
     ) -> Result<(), Error> {
+        assert_eq!(constants.len(), T);
         let goldilocks_chip = self.goldilocks_chip();
         for (word, constant) in self.state.0.iter_mut().zip(constants.iter()) {
             let word2 = goldilocks_chip.mul(ctx, word, word)?;
@@ -177,8 +202,9 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
     fn absorb_with_pre_constants(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        pre_constants: &[GoldilocksField; T],
+        pre_constants: &[GoldilocksField],
     ) -> Result<(), Error> {
+        assert_eq!(pre_constants.len(), T);
         let goldilocks_chip = self.goldilocks_chip();
 
         // Add pre constants
@@ -190,11 +216,8 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
     }
 
     /// Applies MDS State multiplication
-    fn apply_mds(
-        &mut self,
-        ctx: &mut RegionCtx<'_, F>,
-        mds: &[[GoldilocksField; T]; T],
-    ) -> Result<(), Error> {
+    fn apply_mds(&mut self, ctx: &mut RegionCtx<'_, F>, mds: &[Row]) -> Result<(), Error> {
+        assert_eq!(mds.len(), T);
         let goldilocks_chip = self.goldilocks_chip();
         // Calculate new state
         let new_state = mds
@@ -225,7 +248,7 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
     fn apply_sparse_mds(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
-        mds: &SparseMDSMatrix<T, T_MINUS_ONE>,
+        mds: &ChipSparseMDSMatrix,
     ) -> Result<(), Error> {
         let goldilocks_chip = self.goldilocks_chip();
         // For the 0th word
@@ -312,6 +335,9 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
         Ok(())
     }
 
+    /// Constrains plonky2's `hash_n_to_m_no_pad`: inputs are absorbed `RATE` elements at a
+    /// time by overwriting the rate part of the state, leaving the capacity untouched, and a
+    /// trailing partial chunk only overwrites as many words as it has. No padding is applied.
     pub fn hash(
         &mut self,
         ctx: &mut RegionCtx<'_, F>,
@@ -363,3 +389,101 @@ impl<F: PrimeField> PublicInputsHasherChip<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{goldilocks_field::GoldilocksField, types::Sample},
+        hash::{hashing::hash_n_to_hash_no_pad, poseidon::PoseidonPermutation},
+    };
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+    };
+
+    use super::{PublicInputsHasherChip, RATE};
+
+    #[derive(Clone, Default)]
+    pub struct TestCircuit {
+        inputs: Vec<GoldilocksField>,
+        expected_output: [GoldilocksField; 4],
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "public inputs hasher chip",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+
+                    let inputs = self
+                        .inputs
+                        .iter()
+                        .map(|x| {
+                            goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*x)))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+
+                    let mut hasher_chip = PublicInputsHasherChip::<Fr>::new(ctx, &config)?;
+                    let outputs = hasher_chip.hash(ctx, inputs, 4)?;
+                    for (output, expected) in outputs.iter().zip(self.expected_output.iter()) {
+                        let expected = goldilocks_chip.assign_constant(ctx, *expected)?;
+                        goldilocks_chip.assert_equal(ctx, output, &expected)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_public_inputs_hash_spanning_multiple_chunks() {
+        // single partial chunk, exactly two chunks, and two chunks plus a partial one
+        for num_inputs in [4, 2 * RATE, 2 * RATE + 5] {
+            let inputs = (0..num_inputs)
+                .map(|_| GoldilocksField::rand())
+                .collect::<Vec<_>>();
+            let expected_output =
+                hash_n_to_hash_no_pad::<GoldilocksField, PoseidonPermutation>(&inputs).elements;
+            let circuit = TestCircuit {
+                inputs,
+                expected_output,
+            };
+            let instance: Vec<Fr> = vec![];
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![instance]).unwrap();
+            mock_prover.assert_satisfied();
+        }
+    }
+}
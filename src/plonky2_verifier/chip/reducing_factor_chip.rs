@@ -0,0 +1,174 @@
+use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
+
+use crate::plonky2_verifier::{context::RegionCtx, types::assigned::AssignedExtensionFieldValue};
+
+use super::{
+    goldilocks_chip::GoldilocksChipConfig, goldilocks_extension_chip::GoldilocksExtensionChip,
+};
+
+/// In-circuit `plonky2::util::reducing::ReducingFactor`: combines terms with the powers of
+/// `base`, counting them so that `shift` multiplies by `base` to the number of terms reduced
+/// since the last shift.
+#[derive(Clone, Debug)]
+pub struct ReducingFactorChip<F: PrimeField> {
+    base: AssignedExtensionFieldValue<F, 2>,
+    count: usize,
+    goldilocks_chip_config: GoldilocksChipConfig<F>,
+}
+
+impl<F: PrimeField> ReducingFactorChip<F> {
+    pub fn new(
+        goldilocks_chip_config: &GoldilocksChipConfig<F>,
+        base: AssignedExtensionFieldValue<F, 2>,
+    ) -> Self {
+        Self {
+            base,
+            count: 0,
+            goldilocks_chip_config: goldilocks_chip_config.clone(),
+        }
+    }
+
+    fn goldilocks_extension_chip(&self) -> GoldilocksExtensionChip<F> {
+        GoldilocksExtensionChip::new(&self.goldilocks_chip_config)
+    }
+
+    /// Terms reduced since the last `shift` or `reset`.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// `sum terms[i] * base^i`, like `ReducingFactor::reduce`.
+    pub fn reduce(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        terms: &[AssignedExtensionFieldValue<F, 2>],
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        self.count += terms.len();
+        self.goldilocks_extension_chip()
+            .reduce_extension(ctx, &self.base, &terms.to_vec())
+    }
+
+    /// `x * base^count`, resetting the count, like `ReducingFactor::shift`.
+    pub fn shift(
+        &mut self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedExtensionFieldValue<F, 2>,
+    ) -> Result<AssignedExtensionFieldValue<F, 2>, Error> {
+        let shifted = self
+            .goldilocks_extension_chip()
+            .shift(ctx, &self.base, self.count, x)?;
+        self.count = 0;
+        Ok(shifted)
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{
+            extension::quadratic::QuadraticExtension,
+            goldilocks_field::GoldilocksField,
+            types::{Field, Sample},
+        },
+        util::reducing::ReducingFactor,
+    };
+
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            goldilocks_extension_chip::GoldilocksExtensionChip,
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+            },
+        },
+        context::RegionCtx,
+    };
+
+    use super::ReducingFactorChip;
+
+    type FE = QuadraticExtension<GoldilocksField>;
+
+    /// Reduces `batches` one after the other with `base`, shifting the accumulated value by each
+    /// batch as plonky2 does when it reduces the openings of FRI, checking every step against
+    /// `ReducingFactor`.
+    #[derive(Clone)]
+    struct ReducingFactorCircuit {
+        base: FE,
+        batches: Vec<Vec<FE>>,
+    }
+
+    impl Circuit<Fr> for ReducingFactorCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            GoldilocksChip::new(&config).load_table(&mut layouter)?;
+            let extension_chip = GoldilocksExtensionChip::new(&config);
+            layouter.assign_region(
+                || "reducing factor",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let base = extension_chip.constant_extension(ctx, &self.base.0)?;
+                    let mut chip = ReducingFactorChip::new(&config, base);
+                    let mut native = ReducingFactor::new(self.base);
+                    let mut acc = extension_chip.zero_extension(ctx)?;
+                    let mut native_acc = FE::ZERO;
+                    for batch in self.batches.iter() {
+                        let terms = batch
+                            .iter()
+                            .map(|term| extension_chip.constant_extension(ctx, &term.0))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        let reduced = chip.reduce(ctx, &terms)?;
+                        let native_reduced = native.reduce(batch.iter());
+                        assert_eq!(chip.count(), batch.len());
+                        let expected = extension_chip.constant_extension(ctx, &native_reduced.0)?;
+                        extension_chip.assert_equal_extension(ctx, &reduced, &expected)?;
+
+                        let shifted = chip.shift(ctx, &acc)?;
+                        native_acc = native.shift(native_acc);
+                        acc = extension_chip.add_extension(ctx, &shifted, &reduced)?;
+                        native_acc += native_reduced;
+                        let expected = extension_chip.constant_extension(ctx, &native_acc.0)?;
+                        extension_chip.assert_equal_extension(ctx, &acc, &expected)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_reducing_factor_matches_plonky2() {
+        let circuit = ReducingFactorCircuit {
+            base: FE::rand(),
+            // an empty batch leaves the count, and so the next shift, at zero
+            batches: [3, 0, 1, 7].map(FE::rand_vec).to_vec(),
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+    }
+}
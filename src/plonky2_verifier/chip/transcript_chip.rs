@@ -5,11 +5,45 @@ use crate::plonky2_verifier::{
 };
 use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
 use halo2wrong_maingate::AssignedValue;
+use std::{fmt, io};
 
 use super::goldilocks_chip::GoldilocksChipConfig;
 
+/// Raised when the transcript observed or squeezed a different number of elements than
+/// the proof shape requires, i.e. a write or a squeeze is missing or duplicated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptCountMismatch {
+    pub expected_observations: usize,
+    pub observations: usize,
+    pub expected_challenges: usize,
+    pub challenges: usize,
+}
+
+impl fmt::Display for TranscriptCountMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "transcript observed {} elements and squeezed {} challenges, expected {} and {}",
+            self.observations,
+            self.challenges,
+            self.expected_observations,
+            self.expected_challenges
+        )
+    }
+}
+
+impl std::error::Error for TranscriptCountMismatch {}
+
+impl From<TranscriptCountMismatch> for Error {
+    fn from(value: TranscriptCountMismatch) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
+}
+
 pub struct TranscriptChip<N: PrimeField> {
     hasher_chip: HasherChip<N>,
+    observation_count: usize,
+    challenge_count: usize,
 }
 
 impl<N: PrimeField> TranscriptChip<N> {
@@ -19,7 +53,40 @@ impl<N: PrimeField> TranscriptChip<N> {
         goldilocks_chip_config: &GoldilocksChipConfig<N>,
     ) -> Result<Self, Error> {
         let hasher_chip = HasherChip::new(ctx, goldilocks_chip_config)?;
-        Ok(Self { hasher_chip })
+        Ok(Self {
+            hasher_chip,
+            observation_count: 0,
+            challenge_count: 0,
+        })
+    }
+
+    /// Number of elements written to the transcript so far
+    pub fn observation_count(&self) -> usize {
+        self.observation_count
+    }
+
+    /// Number of challenges squeezed from the transcript so far
+    pub fn challenge_count(&self) -> usize {
+        self.challenge_count
+    }
+
+    /// Checks the observation and challenge counts against the ones required by the proof shape
+    pub fn assert_counts(
+        &self,
+        expected_observations: usize,
+        expected_challenges: usize,
+    ) -> Result<(), TranscriptCountMismatch> {
+        if self.observation_count != expected_observations
+            || self.challenge_count != expected_challenges
+        {
+            return Err(TranscriptCountMismatch {
+                expected_observations,
+                observations: self.observation_count,
+                expected_challenges,
+                challenges: self.challenge_count,
+            });
+        }
+        Ok(())
     }
 
     /// Write scalar to the transcript
@@ -28,6 +95,7 @@ impl<N: PrimeField> TranscriptChip<N> {
         ctx: &mut RegionCtx<'_, N>,
         scalar: &AssignedValue<N>,
     ) -> Result<(), Error> {
+        self.observation_count += 1;
         self.hasher_chip.update(ctx, scalar)
     }
 
@@ -36,7 +104,7 @@ impl<N: PrimeField> TranscriptChip<N> {
         ctx: &mut RegionCtx<'_, N>,
         extension: &AssignedExtensionFieldValue<N, D>,
     ) -> Result<(), Error> {
-        for scalar in extension.0.iter() {
+        for scalar in extension.limbs().iter() {
             self.write_scalar(ctx, scalar)?;
         }
         Ok(())
@@ -47,7 +115,7 @@ impl<N: PrimeField> TranscriptChip<N> {
         ctx: &mut RegionCtx<'_, N>,
         hash: &AssignedHashValues<N>,
     ) -> Result<(), Error> {
-        for scalar in hash.elements.iter() {
+        for scalar in hash.iter() {
             self.write_scalar(ctx, scalar)?;
         }
         Ok(())
@@ -70,6 +138,7 @@ impl<N: PrimeField> TranscriptChip<N> {
         ctx: &mut RegionCtx<'_, N>,
         num_outputs: usize,
     ) -> Result<Vec<AssignedValue<N>>, Error> {
+        self.challenge_count += num_outputs;
         self.hasher_chip.squeeze(ctx, num_outputs)
     }
 }
@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, io};
 
 use halo2_proofs::{
-    circuit::{AssignedCell, Cell, Region, Value},
+    circuit::{AssignedCell, Cell, Layouter, Region, Value},
     halo2curves::ff::PrimeField,
     plonk::{Advice, Column, Error, Fixed, Selector},
 };
@@ -13,6 +13,12 @@ pub struct RegionCtx<'a, F: PrimeField> {
     region: Region<'a, F>,
     offset: usize,
     contants: HashMap<BigUint, AssignedCell<F, F>>,
+    extension_contants: HashMap<[BigUint; 2], [AssignedCell<F, F>; 2]>,
+    /// Verification checks recorded as conditions instead of being constrained, while
+    /// `record_checks` is active.
+    checks: Option<Vec<AssignedCell<F, F>>>,
+    /// Name of the region and the rows it can use, see `with_capacity`.
+    capacity: Option<(String, usize)>,
 }
 
 impl<'a, F: PrimeField> RegionCtx<'a, F> {
@@ -21,6 +27,33 @@ impl<'a, F: PrimeField> RegionCtx<'a, F> {
             region,
             offset,
             contants: HashMap::new(),
+            extension_contants: HashMap::new(),
+            checks: None,
+            capacity: None,
+        }
+    }
+
+    /// Lets the region use `usable_rows` rows, checked by `ensure_capacity` and reported under
+    /// `name`. Without a capacity, running out of rows is only caught by halo2, once the region
+    /// is placed.
+    pub fn with_capacity(mut self, name: impl Into<String>, usable_rows: usize) -> Self {
+        self.capacity = Some((name.into(), usable_rows));
+        self
+    }
+
+    /// Checks that `rows_needed` rows from the current offset fit in the capacity of the region,
+    /// if it has one. Chips call it around their bulk operations so that a region outgrowing the
+    /// circuit fails while it is laid out, before halo2 assigns a row it doesn't have.
+    pub fn ensure_capacity(&self, rows_needed: usize) -> Result<(), RegionCapacityExceeded> {
+        match &self.capacity {
+            Some((region, usable_rows)) if self.offset + rows_needed > *usable_rows => {
+                Err(RegionCapacityExceeded {
+                    region: region.clone(),
+                    rows_needed: self.offset + rows_needed,
+                    usable_rows: *usable_rows,
+                })
+            }
+            _ => Ok(()),
         }
     }
 
@@ -56,12 +89,42 @@ impl<'a, F: PrimeField> RegionCtx<'a, F> {
 
     pub fn clear_fixed(&mut self) {
         self.contants.clear();
+        self.extension_contants.clear();
     }
 
     pub fn get_fixed(&self, value: &F) -> Option<&AssignedCell<F, F>> {
         self.contants.get(&fe_to_big(*value))
     }
 
+    /// Extension constants are keyed on both limbs, `a + 0w` and `0 + aw` are different entries.
+    pub fn register_fixed_extension(&mut self, value: [F; 2], cells: [AssignedCell<F, F>; 2]) {
+        self.extension_contants.insert(value.map(fe_to_big), cells);
+    }
+
+    pub fn get_fixed_extension(&self, value: &[F; 2]) -> Option<&[AssignedCell<F, F>; 2]> {
+        self.extension_contants.get(&value.map(fe_to_big))
+    }
+
+    /// Starts recording verification checks, see `GoldilocksChip::check_equal`.
+    pub fn record_checks(&mut self) {
+        self.checks = Some(vec![]);
+    }
+
+    /// Stops recording verification checks and returns the conditions recorded so far.
+    pub fn take_checks(&mut self) -> Vec<AssignedCell<F, F>> {
+        self.checks.take().unwrap_or_default()
+    }
+
+    pub fn is_recording_checks(&self) -> bool {
+        self.checks.is_some()
+    }
+
+    pub fn push_check(&mut self, condition: AssignedCell<F, F>) {
+        if let Some(checks) = self.checks.as_mut() {
+            checks.push(condition);
+        }
+    }
+
     pub fn assign_advice<A, AR>(
         &mut self,
         annotation: A,
@@ -88,3 +151,83 @@ impl<'a, F: PrimeField> RegionCtx<'a, F> {
         self.offset += 1
     }
 }
+
+/// A region needs more rows than are left to it in the circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionCapacityExceeded {
+    pub region: String,
+    pub rows_needed: usize,
+    pub usable_rows: usize,
+}
+
+impl RegionCapacityExceeded {
+    pub fn shortfall(&self) -> usize {
+        self.rows_needed - self.usable_rows
+    }
+}
+
+impl fmt::Display for RegionCapacityExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "region `{}` needs {} rows but only {} are left to it, {} short",
+            self.region,
+            self.rows_needed,
+            self.usable_rows,
+            self.shortfall()
+        )
+    }
+}
+
+impl std::error::Error for RegionCapacityExceeded {}
+
+impl From<RegionCapacityExceeded> for Error {
+    fn from(value: RegionCapacityExceeded) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::OutOfMemory, value))
+    }
+}
+
+/// Rows of a circuit handed out to its regions in the order they are assigned, assuming the
+/// floor planner stacks them, as it does with regions sharing their columns. `None` hands out
+/// rows without checking them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RowBudget {
+    remaining: Option<usize>,
+}
+
+impl RowBudget {
+    pub fn new(usable_rows: Option<usize>) -> Self {
+        Self {
+            remaining: usable_rows,
+        }
+    }
+
+    /// Assigns the region `name` with the rows left as its capacity, see
+    /// `RegionCtx::with_capacity`, and takes the rows it used off the budget.
+    pub fn assign_region<F: PrimeField, A>(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        name: &str,
+        mut assignment: impl FnMut(&mut RegionCtx<'_, F>) -> Result<A, Error>,
+    ) -> Result<A, Error> {
+        let mut rows_used = 0;
+        let remaining = self.remaining;
+        let value = layouter.assign_region(
+            || name,
+            |region| {
+                let mut ctx = RegionCtx::new(region, 0);
+                if let Some(remaining) = remaining {
+                    ctx = ctx.with_capacity(name, remaining);
+                }
+                let value = assignment(&mut ctx)?;
+                ctx.ensure_capacity(0)?;
+                rows_used = ctx.offset();
+                Ok(value)
+            },
+        )?;
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= rows_used;
+        }
+        Ok(value)
+    }
+}
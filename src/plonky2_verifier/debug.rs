@@ -0,0 +1,114 @@
+use halo2_proofs::dev::VerifyFailure;
+
+use super::verifier_circuit::{BatchLayout, Component};
+
+/// Maps the failures of a `MockProver` run of a `BatchVerifier` to the proofs and the
+/// components that caused them, in the order they first fail. Failures outside the regions of
+/// `layout` (e.g. instance mismatches) aren't attributed.
+pub fn attribute_failures(
+    errors: &[VerifyFailure],
+    layout: &BatchLayout,
+) -> Vec<(usize, Component)> {
+    let mut attributed = vec![];
+    let failures = errors
+        .iter()
+        .filter_map(region_name)
+        .filter_map(|name| layout.find(&name));
+    for failure in failures {
+        if !attributed.contains(&failure) {
+            attributed.push(failure);
+        }
+    }
+    attributed
+}
+
+/// Name of the region a failure is located in, e.g. `proof 2: fri` from
+/// `in Region 7 ('proof 2: fri') at offset 42`.
+fn region_name(failure: &VerifyFailure) -> Option<String> {
+    let location = match failure {
+        VerifyFailure::ConstraintNotSatisfied { location, .. }
+        | VerifyFailure::Lookup { location, .. }
+        | VerifyFailure::Permutation { location, .. } => location.to_string(),
+        _ => return None,
+    };
+    let (_, name) = location.split_once("('")?;
+    let (name, _) = name.split_once("')")?;
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        halo2curves::{bn256::Fr, ff::Field as _},
+        plonk::Error,
+    };
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+    use super::attribute_failures;
+    use crate::plonky2_verifier::{
+        chip::native_chip::{test_utils::mock_prove_small_table, utils::goldilocks_to_fe},
+        fixtures::{generate_tiny_proof_tuple, mock_verifier},
+        verifier_circuit::{
+            BatchProofError, BatchVerifier, Component, PublicInputHandling, Verifier,
+            CIRCUIT_VERSION,
+        },
+    };
+
+    fn batch(tampered: Option<usize>) -> (Vec<Verifier>, Vec<Fr>) {
+        let mut verifiers = vec![];
+        let mut hashes = vec![];
+        for index in 0..4 {
+            let mut proof = generate_tiny_proof_tuple();
+            if tampered == Some(index) {
+                // a leaf of the first initial tree, it doesn't affect any challenge
+                let initial_trees_proof =
+                    &mut proof.0.proof.opening_proof.query_round_proofs[0].initial_trees_proof;
+                initial_trees_proof.evals_proofs[0].0[0] += GoldilocksField::ONE;
+            }
+            let (verifier, _) = mock_verifier(proof);
+            let trace = verifier.witness_trace().unwrap();
+            hashes.extend(trace.public_inputs_hash.map(goldilocks_to_fe::<Fr>));
+            verifiers.push(verifier);
+        }
+        (
+            verifiers,
+            [vec![Fr::from(CIRCUIT_VERSION), Fr::ONE], hashes].concat(),
+        )
+    }
+
+    #[test]
+    fn test_attribute_failures_to_proof() {
+        let (verifiers, instances) = batch(Some(2));
+        let circuit = BatchVerifier::new(verifiers).strict();
+        let prover = mock_prove_small_table(&circuit, vec![instances]).unwrap();
+        let errors = prover.verify().unwrap_err();
+
+        let attributed = attribute_failures(&errors, &circuit.layout());
+        assert_eq!(attributed, vec![(2, Component::Fri)]);
+    }
+
+    #[test]
+    fn test_attribute_invalid_proof_before_synthesis() {
+        let (mut verifiers, instances) = batch(None);
+        // the tiny proof has two public inputs, too few to be read as a pre-hashed hash
+        verifiers[1] = verifiers[1]
+            .clone()
+            .with_public_input_handling(PublicInputHandling::PreHashed);
+        let circuit = BatchVerifier::new(verifiers);
+
+        let err = circuit.witness_traces().unwrap_err();
+        assert_eq!(err.index, 1);
+
+        match mock_prove_small_table(&circuit, vec![instances]) {
+            Err(Error::Transcript(err)) => {
+                let err = err
+                    .get_ref()
+                    .unwrap()
+                    .downcast_ref::<BatchProofError>()
+                    .unwrap();
+                assert_eq!(err.index, 1);
+            }
+            _ => panic!("expected the batch to be rejected before synthesis"),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Proofs shared by the tests of the verifier modules and the benchmarks. The ones many tests
+//! share are proven once per process, every call returns a clone of the same proof.
+
+use crate::plonky2_semaphore::{
+    access_set::AccessSet, identity::Identity, wrapper::WrapperCircuit,
+};
+use crate::plonky2_verifier::{
+    bn245_poseidon::plonky2_config::{
+        standard_inner_stark_verifier_config, standard_stark_verifier_config,
+        Bn254PoseidonGoldilocksConfig,
+    },
+    chip::native_chip::{test_utils::mock_prove_small_table, utils::goldilocks_to_fe},
+    types::{common_data::CommonData, proof::ProofValues, verification_key::VerificationKeyValues},
+    verifier_circuit::{plonky2_query_indices, ProofTuple, Verifier},
+};
+use halo2_proofs::halo2curves::bn256::Fr;
+use plonky2::{
+    field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, Field64},
+    },
+    fri::reduction_strategies::FriReductionStrategy,
+    gates::noop::NoopGate,
+    hash::{
+        hashing::hash_n_to_hash_no_pad,
+        poseidon::{PoseidonHash, PoseidonPermutation},
+    },
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::CircuitConfig,
+        config::{GenericConfig, PoseidonGoldilocksConfig},
+        proof::ProofWithPublicInputs,
+    },
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::sync::OnceLock;
+
+type F = GoldilocksField;
+const D: usize = 2;
+
+type Fixture = ProofTuple<F, Bn254PoseidonGoldilocksConfig, D>;
+
+/// Recursive proof of a circuit checking `poseidon(42)`, wrapped with
+/// `Bn254PoseidonGoldilocksConfig` so it can be verified inside halo2.
+pub fn generate_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    static PROOF: OnceLock<Fixture> = OnceLock::new();
+    PROOF.get_or_init(recursive_proof_tuple).clone()
+}
+
+fn recursive_proof_tuple() -> Fixture {
+    let (inner_target, inner_data) = {
+        let hash_const =
+            hash_n_to_hash_no_pad::<F, PoseidonPermutation>(&[F::from_canonical_u64(42)]);
+        let mut builder = CircuitBuilder::<F, D>::new(standard_inner_stark_verifier_config());
+        let target = builder.add_virtual_target();
+        let expected_hash = builder.constant_hash(hash_const);
+        let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![target]);
+        builder.connect_hashes(hash, expected_hash);
+        builder.register_public_inputs(&expected_hash.elements);
+        let data = builder.build::<PoseidonGoldilocksConfig>();
+        (target, data)
+    };
+
+    let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
+    let proof_t =
+        builder.add_virtual_proof_with_pis::<PoseidonGoldilocksConfig>(&inner_data.common);
+    let vd = builder.constant_verifier_data(&inner_data.verifier_only);
+    builder.verify_proof::<PoseidonGoldilocksConfig>(&proof_t, &vd, &inner_data.common);
+    builder.register_public_inputs(&proof_t.public_inputs);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+
+    let proof = {
+        let mut pw = PartialWitness::new();
+        pw.set_target(inner_target, F::from_canonical_usize(42));
+        inner_data.prove(pw).unwrap()
+    };
+
+    let mut pw = PartialWitness::new();
+    pw.set_proof_with_pis_target(&proof_t, &proof);
+    let final_proof = data.prove(pw).unwrap();
+    (final_proof, data.verifier_only, data.common)
+}
+
+/// Semaphore signal of one of 16 identities, wrapped with `Bn254PoseidonGoldilocksConfig` like
+/// `AccessSet::verify_signal` does. The identities and the topic are fixed, so the proof only
+/// differs between processes in its blinding.
+pub fn generate_semaphore_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    static PROOF: OnceLock<Fixture> = OnceLock::new();
+    PROOF.get_or_init(semaphore_proof_tuple).clone()
+}
+
+fn semaphore_proof_tuple() -> Fixture {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    let identities = (0..16)
+        .map(|_| Identity::generate(&mut rng))
+        .collect::<Vec<_>>();
+    let access_set = AccessSet::from_identities(&identities, 0);
+    let topic = [1, 2, 3, 4].map(F::from_canonical_u64);
+    let (signal, verifier_data) = access_set.make_signal(&identities[3], topic).unwrap();
+
+    let public_inputs = access_set
+        .0
+        .cap
+        .0
+        .iter()
+        .flat_map(|h| h.elements)
+        .chain(signal.nullifier.into_iter().flatten())
+        .chain(signal.topics.into_iter().flatten())
+        .collect();
+    let proof = ProofWithPublicInputs {
+        proof: signal.proof,
+        public_inputs,
+    };
+    let wrapper_circuit = WrapperCircuit::new(standard_stark_verifier_config(), &verifier_data);
+    let wrapped_proof = wrapper_circuit.prove(&proof).unwrap();
+    let data = wrapper_circuit.data;
+    (wrapped_proof, data.verifier_only, data.common)
+}
+
+/// Proof of `y = x^2 + 1` with `x` and `y` public, small enough for `degree_bits` to stay at 3.
+/// Without FRI reductions and with few leaves per tree, it is also the fastest fixture to
+/// run through the real prover.
+pub fn generate_tiny_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    static PROOF: OnceLock<Fixture> = OnceLock::new();
+    PROOF
+        .get_or_init(generate_tiny_proof_tuple_with_config)
+        .clone()
+}
+
+/// `generate_tiny_proof_tuple` proven with any `GenericConfig`.
+pub fn generate_tiny_proof_tuple_with_config<C: GenericConfig<D, F = F>>() -> ProofTuple<F, C, D> {
+    tiny_proof_tuple(standard_stark_verifier_config())
+}
+
+/// `generate_tiny_proof_tuple` built with `config`, e.g. the FRI parameters of a preset.
+pub fn generate_tiny_proof_tuple_with_circuit_config(
+    config: CircuitConfig,
+) -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    tiny_proof_tuple(config)
+}
+
+/// `generate_tiny_proof_tuple` with 40 query rounds. Its LDE has only 64 points, so some
+/// rounds are bound to draw the same index.
+pub fn generate_duplicate_query_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let mut config = standard_stark_verifier_config();
+    config.fri_config.num_query_rounds = 40;
+    tiny_proof_tuple(config)
+}
+
+/// `generate_tiny_proof_tuple` folded with the FRI reduction schedule `[1, 0, 1]`, whose middle
+/// step commits to the folded polynomial again without folding it further.
+pub fn generate_zero_arity_step_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let mut config = standard_stark_verifier_config();
+    config.fri_config.reduction_strategy = FriReductionStrategy::Fixed(vec![1, 0, 1]);
+    tiny_proof_tuple(config)
+}
+
+fn tiny_proof_tuple<C: GenericConfig<D, F = F>>(config: CircuitConfig) -> ProofTuple<F, C, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let x_squared = builder.square(x);
+    let y = builder.add_const(x_squared, F::ONE);
+    builder.register_public_input(x);
+    builder.register_public_input(y);
+    let data = builder.build::<C>();
+    assert!(
+        data.common.degree_bits() <= 3,
+        "tiny fixture grew to degree_bits {}",
+        data.common.degree_bits()
+    );
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// `generate_tiny_proof_tuple` with its public inputs padded with `padding` zeros.
+pub fn generate_zero_padded_proof_tuple(
+    padding: usize,
+) -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
+    let x = builder.add_virtual_target();
+    let x_squared = builder.square(x);
+    let y = builder.add_const(x_squared, F::ONE);
+    builder.register_public_input(x);
+    builder.register_public_input(y);
+    let zero = builder.zero();
+    for _ in 0..padding {
+        builder.register_public_input(zero);
+    }
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof whose public inputs have the high bit set, as hashes usually do: `p - 1`, `2^63` and
+/// a random value in `[2^63, p)`.
+pub fn generate_high_bit_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let high_bit = 1u64 << 63;
+    let values = [
+        F::NEG_ONE,
+        F::from_canonical_u64(high_bit),
+        F::from_canonical_u64(rand::thread_rng().gen_range(high_bit..F::ORDER)),
+    ];
+    let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
+    let targets = builder.add_virtual_targets(values.len());
+    builder.register_public_inputs(&targets);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+
+    let mut pw = PartialWitness::new();
+    for (target, value) in targets.into_iter().zip(values) {
+        pw.set_target(target, value);
+    }
+    let proof = data.prove(pw).unwrap();
+    assert_eq!(proof.public_inputs, values);
+    (proof, data.verifier_only, data.common)
+}
+
+/// `generate_tiny_proof_tuple` with zero knowledge, so the trees of every oracle but the
+/// preprocessed one are salted.
+pub fn generate_hiding_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let config = CircuitConfig {
+        zero_knowledge: true,
+        ..standard_stark_verifier_config()
+    };
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let x_squared = builder.square(x);
+    let y = builder.add_const(x_squared, F::ONE);
+    builder.register_public_input(x);
+    builder.register_public_input(y);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert!(data.common.fri_params.hiding);
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of a circuit reading a list of 16 constants at a public index. The constants fill the
+/// extra constant wires left over by the `RandomAccessGate` before any `ConstantGate` is added.
+pub fn generate_random_access_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
+    let index = builder.add_virtual_target();
+    let list = (0..16)
+        .map(|i| builder.constant(F::from_canonical_u64(i * i + 3)))
+        .collect::<Vec<_>>();
+    let element = builder.random_access(index, list);
+    builder.register_public_input(index);
+    builder.register_public_input(element);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert!(data.common.gates.iter().any(|gate| {
+        let id = gate.0.id();
+        id.starts_with("RandomAccessGate") && !id.contains("num_extra_constants: 0")
+    }));
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(index, F::from_canonical_u64(5));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of a circuit reading a list of 2 and a list of 16 constants, so `common_data.gates` has
+/// two `RandomAccessGate`s that differ only in their parameters.
+pub fn generate_two_random_access_gates_proof_tuple(
+) -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
+    let short_index = builder.add_virtual_target();
+    let short_list = (0..2)
+        .map(|i| builder.constant(F::from_canonical_u64(i + 7)))
+        .collect::<Vec<_>>();
+    let short_element = builder.random_access(short_index, short_list);
+    let long_index = builder.add_virtual_target();
+    let long_list = (0..16)
+        .map(|i| builder.constant(F::from_canonical_u64(i * i + 3)))
+        .collect::<Vec<_>>();
+    let long_element = builder.random_access(long_index, long_list);
+    builder.register_public_input(short_index);
+    builder.register_public_input(short_element);
+    builder.register_public_input(long_index);
+    builder.register_public_input(long_element);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    let num_random_access_gates = data
+        .common
+        .gates
+        .iter()
+        .filter(|gate| gate.0.id().starts_with("RandomAccessGate"))
+        .count();
+    assert_eq!(num_random_access_gates, 2);
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(short_index, F::ONE);
+    pw.set_target(long_index, F::from_canonical_u64(5));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of `y = x^2` with only `y` public, over four wires. The single `ArithmeticGate` has
+/// one operation and there are no partial products, so every openings vector is as short as
+/// plonky2 allows.
+pub fn generate_minimal_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let config = CircuitConfig {
+        num_wires: 4,
+        num_routed_wires: 4,
+        ..standard_stark_verifier_config()
+    };
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let y = builder.mul(x, x);
+    builder.register_public_input(y);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert_eq!(data.common.num_partial_products, 0);
+    assert!(data
+        .common
+        .gates
+        .iter()
+        .any(|gate| gate.0.id() == "ArithmeticGate { num_ops: 1 }"));
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of a circuit with only constant and public input gates, routed over four wires so that
+/// every permutation chunk fits in the quotient degree and there are no partial products.
+pub fn generate_no_partial_products_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D>
+{
+    let config = CircuitConfig {
+        num_wires: 4,
+        num_routed_wires: 4,
+        ..standard_stark_verifier_config()
+    };
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let expected = builder.constant(F::from_canonical_u64(42));
+    builder.connect(x, expected);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert_eq!(data.common.num_partial_products, 0);
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(42));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of `x^2 + 1 = 10` over the standard wires, with the quotient split in
+/// `max_quotient_degree_factor` chunks. There are no public inputs, as hashing them would add a
+/// `PoseidonGate`, whose degree only fits in a factor of at least 7.
+pub fn generate_quotient_degree_factor_proof_tuple(
+    max_quotient_degree_factor: usize,
+) -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let config = CircuitConfig {
+        max_quotient_degree_factor,
+        ..standard_stark_verifier_config()
+    };
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = builder.add_virtual_target();
+    let x_squared = builder.square(x);
+    let y = builder.add_const(x_squared, F::ONE);
+    let expected = builder.constant(F::from_canonical_u64(10));
+    builder.connect(y, expected);
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert_eq!(
+        data.common.quotient_degree_factor,
+        max_quotient_degree_factor
+    );
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(3));
+    let proof = data.prove(pw).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// Proof of a circuit with no gates but the `PublicInputGate` plonky2 always adds and
+/// `NoopGate` padding. Both fit in a single selector group, so gates are filtered without the
+/// unused selector term.
+pub fn generate_noop_only_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
+    let config = CircuitConfig {
+        num_wires: 4,
+        num_routed_wires: 4,
+        ..standard_stark_verifier_config()
+    };
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    for _ in 0..4 {
+        builder.add_gate(NoopGate, vec![]);
+    }
+    let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    assert_eq!(data.common.selectors_info.num_selectors(), 1);
+    assert!(data
+        .common
+        .gates
+        .iter()
+        .all(|gate| ["NoopGate", "PublicInputGate"].contains(&gate.0.id().as_str())));
+
+    let proof = data.prove(PartialWitness::new()).unwrap();
+    (proof, data.verifier_only, data.common)
+}
+
+/// The verifier circuit of `proof` and the instances to prove it with, as every test builds it
+/// from a plonky2 proof. Tests tampering with the proof do it on the plonky2 types first.
+pub fn mock_verifier(
+    proof: ProofTuple<F, Bn254PoseidonGoldilocksConfig, D>,
+) -> (Verifier, Vec<Fr>) {
+    let (proof_with_public_inputs, vd, cd) = proof;
+    let public_inputs = proof_with_public_inputs
+        .public_inputs
+        .iter()
+        .map(|e| goldilocks_to_fe(*e))
+        .collect();
+    let verifier = Verifier::new(
+        ProofValues::<Fr, D>::from(proof_with_public_inputs.proof),
+        public_inputs,
+        VerificationKeyValues::from(vd),
+        CommonData::from(cd),
+    );
+    let instances = verifier.instances();
+    (verifier, instances)
+}
+
+/// Checks `proof` with the verifier circuit on the mock prover, like
+/// `verifier_api::verify_inside_snark_mock`, but configured with `TableMode::Bits8` at the
+/// smallest `k` the circuit fits in, which is how the tests run it.
+pub fn verify_mock(proof: ProofTuple<F, Bn254PoseidonGoldilocksConfig, D>) {
+    let query_indices = plonky2_query_indices(&proof).unwrap();
+    let (circuit, instances) = mock_verifier(proof);
+    let circuit = circuit.with_plonky2_query_indices(query_indices);
+    mock_prove_small_table(&circuit, vec![instances])
+        .unwrap()
+        .assert_satisfied();
+}
@@ -0,0 +1,636 @@
+//! Reads plonky2 proofs from the JSON a JS/wasm prover writes, circomlib-style: every field
+//! element is a decimal string, and the documents mirror plonky2's `ProofWithPublicInputs` and
+//! `VerifierOnlyCircuitData` with hashes and extension elements as nested arrays.
+//!
+//! ```text
+//! felt      "18446744069414584320"            decimal, canonical, no leading zeros
+//! ext       [felt, felt]                      coefficients of 1 and w
+//! hash      [felt, felt, felt, felt]
+//! cap       [hash, ...]
+//! merkle    { "siblings": [hash, ...] }
+//!
+//! proof.json
+//! {
+//!   "proof": {
+//!     "wires_cap": cap,
+//!     "plonk_zs_partial_products_cap": cap,
+//!     "quotient_polys_cap": cap,
+//!     "openings": {
+//!       "constants": [ext, ...], "plonk_sigmas": [ext, ...], "wires": [ext, ...],
+//!       "plonk_zs": [ext, ...], "plonk_zs_next": [ext, ...],
+//!       "partial_products": [ext, ...], "quotient_polys": [ext, ...]
+//!     },
+//!     "opening_proof": {
+//!       "commit_phase_merkle_caps": [cap, ...],
+//!       "query_round_proofs": [{
+//!         "initial_trees_proof": { "evals_proofs": [[[felt, ...], merkle], ...] },
+//!         "steps": [{ "evals": [ext, ...], "merkle_proof": merkle }, ...]
+//!       }, ...],
+//!       "final_poly": { "coeffs": [ext, ...] },
+//!       "pow_witness": felt
+//!     }
+//!   },
+//!   "public_inputs": [felt, ...]
+//! }
+//!
+//! vk.json
+//! { "constants_sigmas_cap": cap, "circuit_digest": hash }
+//! ```
+//!
+//! plonky2 can't deserialize `CommonCircuitData`, its gates are only written as ids, so the
+//! common data is read in the serialization of `CommonData`, which rebuilds the gates from
+//! their ids. Numbers in it are plain JSON numbers.
+//!
+//! Errors name the offending value by its path, e.g. `proof.proof.openings.wires[3][1]`.
+
+use std::{fmt, io};
+
+use halo2_proofs::{halo2curves::bn256::Fr, plonk::Error};
+use plonky2::field::{
+    goldilocks_field::GoldilocksField,
+    types::{Field, Field64},
+};
+use serde_json::Value;
+
+use super::{
+    bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+    chip::native_chip::utils::goldilocks_to_fe,
+    types::{
+        common_data::CommonData,
+        proof::{
+            FriInitialTreeProofValues, FriProofValues, FriQueryRoundValues, FriQueryStepValues,
+            MerkleProofValues, OpeningSetValues, PolynomialCoeffsExtValues, ProofValues,
+        },
+        verification_key::VerificationKeyValues,
+        ExtensionFieldValue, FormatVersion, HashValues, MerkleCapValues,
+    },
+    verifier_circuit::{ProofTuple, Verifier},
+};
+
+/// A value of a JSON document that can't be read, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// Path of the value, starting with the document: `proof`, `vk` or `common`.
+    pub path: String,
+    pub reason: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<JsonError> for Error {
+    fn from(value: JsonError) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
+}
+
+/// A plonky2 proof with its verification key and common data, in the value types the
+/// verifier circuit is built from.
+#[derive(Clone)]
+pub struct ProofTupleValues {
+    pub proof: ProofValues<Fr, 2>,
+    pub public_inputs: Vec<GoldilocksField>,
+    pub vk: VerificationKeyValues<Fr>,
+    pub common_data: CommonData<Fr>,
+}
+
+impl ProofTupleValues {
+    /// Reads the documents of the module docs. Field elements must be canonical, and the common
+    /// data must pass `CommonData::validate`.
+    pub fn from_json(
+        proof_json: &str,
+        vk_json: &str,
+        common_json: &str,
+    ) -> Result<Self, JsonError> {
+        let common_data = read_common_data(common_json)?;
+
+        let proof_json = parse("proof", proof_json)?;
+        let root = Node::root("proof", &proof_json);
+        let public_inputs_node = root.field("public_inputs")?;
+        let public_inputs = public_inputs_node.map(Node::element)?;
+        if public_inputs.len() != common_data.num_public_inputs {
+            return Err(public_inputs_node.error(format!(
+                "{} public inputs, the common data expects {}",
+                public_inputs.len(),
+                common_data.num_public_inputs
+            )));
+        }
+        let proof = read_proof(&root.field("proof")?)?;
+
+        let vk_json = parse("vk", vk_json)?;
+        let root = Node::root("vk", &vk_json);
+        let vk = VerificationKeyValues {
+            format_version: FormatVersion,
+            constants_sigmas_cap: read_cap(&root.field("constants_sigmas_cap")?)?,
+            circuit_digest: read_hash(&root.field("circuit_digest")?)?,
+        };
+
+        Ok(Self {
+            proof,
+            public_inputs,
+            vk,
+            common_data,
+        })
+    }
+
+    pub fn verifier(self) -> Verifier {
+        let public_inputs = self
+            .public_inputs
+            .iter()
+            .map(|e| goldilocks_to_fe(*e))
+            .collect();
+        Verifier::new(self.proof, public_inputs, self.vk, self.common_data)
+    }
+}
+
+impl From<ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>> for ProofTupleValues {
+    fn from(value: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>) -> Self {
+        let (proof_with_public_inputs, vd, cd) = value;
+        Self {
+            proof: ProofValues::from(proof_with_public_inputs.proof),
+            public_inputs: proof_with_public_inputs.public_inputs,
+            vk: VerificationKeyValues::from(vd),
+            common_data: CommonData::from(cd),
+        }
+    }
+}
+
+fn parse(document: &str, json: &str) -> Result<Value, JsonError> {
+    serde_json::from_str(json).map_err(|err| JsonError {
+        path: document.to_string(),
+        reason: err.to_string(),
+    })
+}
+
+fn read_common_data(json: &str) -> Result<CommonData<Fr>, JsonError> {
+    let error = |path: String, reason: String| JsonError { path, reason };
+    let common_data: CommonData<Fr> =
+        serde_json::from_str(json).map_err(|err| error("common".to_string(), err.to_string()))?;
+    // serde reads any `u64` into a Goldilocks element
+    if let Some((i, k)) = common_data
+        .k_is
+        .iter()
+        .enumerate()
+        .find(|(_, k)| k.0 >= GoldilocksField::ORDER)
+    {
+        return Err(error(format!("common.k_is[{i}]"), non_canonical(k.0)));
+    }
+    common_data
+        .validate()
+        .map_err(|err| error("common".to_string(), err.to_string()))?;
+    Ok(common_data)
+}
+
+fn non_canonical(value: impl fmt::Display) -> String {
+    format!(
+        "{value} is not a canonical Goldilocks element, it must be below {}",
+        GoldilocksField::ORDER
+    )
+}
+
+/// A JSON value and its path in the document.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn root(document: &str, value: &'a Value) -> Self {
+        Self {
+            value,
+            path: document.to_string(),
+        }
+    }
+
+    fn error(&self, reason: String) -> JsonError {
+        JsonError {
+            path: self.path.clone(),
+            reason,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> JsonError {
+        let found = match self.value {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        };
+        self.error(format!("expected {expected}, found {found}"))
+    }
+
+    fn field(&self, name: &str) -> Result<Node<'a>, JsonError> {
+        let object = self
+            .value
+            .as_object()
+            .ok_or_else(|| self.unexpected("an object"))?;
+        let value = object
+            .get(name)
+            .ok_or_else(|| self.error(format!("missing field `{name}`")))?;
+        Ok(Node {
+            value,
+            path: format!("{}.{name}", self.path),
+        })
+    }
+
+    fn items(&self) -> Result<Vec<Node<'a>>, JsonError> {
+        let array = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.unexpected("an array"))?;
+        Ok(array
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Node {
+                value,
+                path: format!("{}[{i}]", self.path),
+            })
+            .collect())
+    }
+
+    fn items_array<const N: usize>(&self) -> Result<[Node<'a>; N], JsonError> {
+        let items = self.items()?;
+        let len = items.len();
+        items
+            .try_into()
+            .map_err(|_| self.error(format!("expected {N} elements, found {len}")))
+    }
+
+    fn map<T>(
+        &self,
+        read: impl Fn(&Node<'a>) -> Result<T, JsonError>,
+    ) -> Result<Vec<T>, JsonError> {
+        self.items()?.iter().map(read).collect()
+    }
+
+    /// A field element, as a canonical decimal string. JSON numbers are refused, JS loses
+    /// precision above 2^53.
+    fn element(&self) -> Result<GoldilocksField, JsonError> {
+        let digits = self
+            .value
+            .as_str()
+            .ok_or_else(|| self.unexpected("a decimal string"))?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.error(format!("`{digits}` is not a decimal number")));
+        }
+        if digits.len() > 1 && digits.starts_with('0') {
+            return Err(self.error(format!("`{digits}` has leading zeros")));
+        }
+        match digits.parse::<u64>() {
+            Ok(value) if value < GoldilocksField::ORDER => {
+                Ok(GoldilocksField::from_canonical_u64(value))
+            }
+            _ => Err(self.error(non_canonical(digits))),
+        }
+    }
+
+    fn elements<const N: usize>(&self) -> Result<[GoldilocksField; N], JsonError> {
+        let items = self.items_array::<N>()?;
+        let mut elements = [GoldilocksField::ZERO; N];
+        for (element, item) in elements.iter_mut().zip(items.iter()) {
+            *element = item.element()?;
+        }
+        Ok(elements)
+    }
+}
+
+fn read_hash(node: &Node) -> Result<HashValues<Fr>, JsonError> {
+    Ok(HashValues::new(node.elements()?))
+}
+
+fn read_cap(node: &Node) -> Result<MerkleCapValues<Fr>, JsonError> {
+    Ok(MerkleCapValues(node.map(read_hash)?))
+}
+
+fn read_extension(node: &Node) -> Result<ExtensionFieldValue<Fr, 2>, JsonError> {
+    Ok(ExtensionFieldValue::new(node.elements()?))
+}
+
+fn read_extensions(node: &Node) -> Result<Vec<ExtensionFieldValue<Fr, 2>>, JsonError> {
+    node.map(read_extension)
+}
+
+fn read_merkle_proof(node: &Node) -> Result<MerkleProofValues<Fr>, JsonError> {
+    Ok(MerkleProofValues {
+        siblings: node.field("siblings")?.map(read_hash)?,
+    })
+}
+
+fn read_openings(node: &Node) -> Result<OpeningSetValues<Fr, 2>, JsonError> {
+    Ok(OpeningSetValues {
+        constants: read_extensions(&node.field("constants")?)?,
+        plonk_sigmas: read_extensions(&node.field("plonk_sigmas")?)?,
+        wires: read_extensions(&node.field("wires")?)?,
+        plonk_zs: read_extensions(&node.field("plonk_zs")?)?,
+        plonk_zs_next: read_extensions(&node.field("plonk_zs_next")?)?,
+        partial_products: read_extensions(&node.field("partial_products")?)?,
+        quotient_polys: read_extensions(&node.field("quotient_polys")?)?,
+    })
+}
+
+fn read_query_round(node: &Node) -> Result<FriQueryRoundValues<Fr, 2>, JsonError> {
+    let evals_proofs = node
+        .field("initial_trees_proof")?
+        .field("evals_proofs")?
+        .map(|pair| {
+            let [evals, merkle_proof] = pair.items_array()?;
+            Ok((evals.map(Node::element)?, read_merkle_proof(&merkle_proof)?))
+        })?;
+    let steps = node.field("steps")?.map(|step| {
+        Ok(FriQueryStepValues {
+            evals: read_extensions(&step.field("evals")?)?,
+            merkle_proof: read_merkle_proof(&step.field("merkle_proof")?)?,
+        })
+    })?;
+    Ok(FriQueryRoundValues {
+        initial_trees_proof: FriInitialTreeProofValues { evals_proofs },
+        steps,
+    })
+}
+
+fn read_proof(node: &Node) -> Result<ProofValues<Fr, 2>, JsonError> {
+    let opening_proof = node.field("opening_proof")?;
+    Ok(ProofValues {
+        wires_cap: read_cap(&node.field("wires_cap")?)?,
+        plonk_zs_partial_products_cap: read_cap(&node.field("plonk_zs_partial_products_cap")?)?,
+        quotient_polys_cap: read_cap(&node.field("quotient_polys_cap")?)?,
+        openings: read_openings(&node.field("openings")?)?,
+        opening_proof: FriProofValues {
+            commit_phase_merkle_cap_values: opening_proof
+                .field("commit_phase_merkle_caps")?
+                .map(read_cap)?,
+            query_round_proofs: opening_proof
+                .field("query_round_proofs")?
+                .map(read_query_round)?,
+            final_poly: PolynomialCoeffsExtValues(read_extensions(
+                &opening_proof.field("final_poly")?.field("coeffs")?,
+            )?),
+            pow_witness: opening_proof.field("pow_witness")?.element()?,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use plonky2::field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field64, PrimeField64},
+    };
+    use serde_json::{json, Value};
+
+    use super::{JsonError, ProofTupleValues};
+    use crate::plonky2_verifier::{
+        chip::native_chip::test_utils::mock_prove_small_table,
+        fixtures::{
+            generate_hiding_proof_tuple, generate_high_bit_proof_tuple, generate_tiny_proof_tuple,
+        },
+        types::{proof::MerkleProofValues, ExtensionFieldValue, HashValues, MerkleCapValues},
+    };
+
+    fn element(e: &GoldilocksField) -> Value {
+        json!(e.to_canonical_u64().to_string())
+    }
+
+    fn elements(elements: &[GoldilocksField]) -> Value {
+        elements.iter().map(element).collect()
+    }
+
+    fn hash(hash: &HashValues<Fr>) -> Value {
+        elements(&hash.elements)
+    }
+
+    fn cap(cap: &MerkleCapValues<Fr>) -> Value {
+        cap.0.iter().map(hash).collect()
+    }
+
+    fn extensions(values: &[ExtensionFieldValue<Fr, 2>]) -> Value {
+        values
+            .iter()
+            .map(|value| elements(&value.elements))
+            .collect()
+    }
+
+    fn merkle_proof(proof: &MerkleProofValues<Fr>) -> Value {
+        json!({ "siblings": proof.siblings.iter().map(hash).collect::<Value>() })
+    }
+
+    /// Writes `values` in the schema of the module docs, as the wasm prover does.
+    fn to_json(values: &ProofTupleValues) -> (Value, Value, String) {
+        let proof = &values.proof;
+        let openings = &proof.openings;
+        let fri_proof = &proof.opening_proof;
+        let query_round_proofs = fri_proof
+            .query_round_proofs
+            .iter()
+            .map(|round| {
+                let evals_proofs = round
+                    .initial_trees_proof
+                    .evals_proofs
+                    .iter()
+                    .map(|(evals, proof)| json!([elements(evals), merkle_proof(proof)]))
+                    .collect::<Value>();
+                let steps = round
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        json!({
+                            "evals": extensions(&step.evals),
+                            "merkle_proof": merkle_proof(&step.merkle_proof),
+                        })
+                    })
+                    .collect::<Value>();
+                json!({
+                    "initial_trees_proof": { "evals_proofs": evals_proofs },
+                    "steps": steps,
+                })
+            })
+            .collect::<Value>();
+        let proof_json = json!({
+            "proof": {
+                "wires_cap": cap(&proof.wires_cap),
+                "plonk_zs_partial_products_cap": cap(&proof.plonk_zs_partial_products_cap),
+                "quotient_polys_cap": cap(&proof.quotient_polys_cap),
+                "openings": {
+                    "constants": extensions(&openings.constants),
+                    "plonk_sigmas": extensions(&openings.plonk_sigmas),
+                    "wires": extensions(&openings.wires),
+                    "plonk_zs": extensions(&openings.plonk_zs),
+                    "plonk_zs_next": extensions(&openings.plonk_zs_next),
+                    "partial_products": extensions(&openings.partial_products),
+                    "quotient_polys": extensions(&openings.quotient_polys),
+                },
+                "opening_proof": {
+                    "commit_phase_merkle_caps": fri_proof
+                        .commit_phase_merkle_cap_values
+                        .iter()
+                        .map(cap)
+                        .collect::<Value>(),
+                    "query_round_proofs": query_round_proofs,
+                    "final_poly": { "coeffs": extensions(&fri_proof.final_poly.0) },
+                    "pow_witness": element(&fri_proof.pow_witness),
+                },
+            },
+            "public_inputs": elements(&values.public_inputs),
+        });
+        let vk_json = json!({
+            "constants_sigmas_cap": cap(&values.vk.constants_sigmas_cap),
+            "circuit_digest": hash(&values.vk.circuit_digest),
+        });
+        let common_json = serde_json::to_string(&values.common_data).unwrap();
+        (proof_json, vk_json, common_json)
+    }
+
+    fn from_json(proof: &Value, vk: &Value, common: &str) -> Result<ProofTupleValues, JsonError> {
+        ProofTupleValues::from_json(&proof.to_string(), &vk.to_string(), common)
+    }
+
+    /// `testdata/json`, the tiny fixture as the wasm prover writes it.
+    fn fixture_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/json")
+    }
+
+    fn record_fixture() {
+        let dir = fixture_dir();
+        let (proof, vk, common) = to_json(&ProofTupleValues::from(generate_tiny_proof_tuple()));
+        fs::create_dir_all(&dir).unwrap();
+        let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap();
+        fs::write(dir.join("proof.json"), pretty(&proof)).unwrap();
+        fs::write(dir.join("vk.json"), pretty(&vk)).unwrap();
+        fs::write(dir.join("common.json"), common).unwrap();
+    }
+
+    fn read_fixture() -> (Value, Value, String) {
+        let dir = fixture_dir();
+        let read = |name: &str| {
+            fs::read_to_string(dir.join(name)).unwrap_or_else(|err| {
+                panic!("testdata/json/{name}: {err}, record it with `make record-json-fixtures`")
+            })
+        };
+        (
+            serde_json::from_str(&read("proof.json")).unwrap(),
+            serde_json::from_str(&read("vk.json")).unwrap(),
+            read("common.json"),
+        )
+    }
+
+    #[test]
+    fn test_json_fixture_verifies() {
+        if env::var_os("JSON_RECORD_FIXTURES").is_some() {
+            record_fixture();
+        }
+        let (proof, vk, common) = read_fixture();
+        let values = from_json(&proof, &vk, &common).unwrap();
+        // the test writer agrees with the files
+        assert_eq!(to_json(&values), (proof, vk, common));
+
+        let circuit = values.verifier();
+        mock_prove_small_table(&circuit, vec![circuit.instances()])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn test_json_round_trip_verifies() {
+        // the high bit fixture has elements close to the modulus
+        for proof_tuple in [
+            generate_hiding_proof_tuple(),
+            generate_high_bit_proof_tuple(),
+        ] {
+            let (proof, vk, common) = to_json(&ProofTupleValues::from(proof_tuple));
+            let values = from_json(&proof, &vk, &common).unwrap();
+            assert_eq!(to_json(&values), (proof, vk, common));
+
+            let circuit = values.verifier();
+            mock_prove_small_table(&circuit, vec![circuit.instances()])
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_json_errors_name_the_offending_value() {
+        let (proof, vk, common) = read_fixture();
+        let evals_proof = "/proof/opening_proof/query_round_proofs/0/initial_trees_proof/\
+                           evals_proofs/1/1";
+        for (pointer, value, path, reason) in [
+            (
+                "/proof/opening_proof/pow_witness",
+                json!(GoldilocksField::ORDER.to_string()),
+                "proof.proof.opening_proof.pow_witness",
+                "is not a canonical Goldilocks element",
+            ),
+            (
+                "/public_inputs/0",
+                json!(1),
+                "proof.public_inputs[0]",
+                "expected a decimal string, found a number",
+            ),
+            (
+                "/proof/openings/wires/3/1",
+                json!("007"),
+                "proof.proof.openings.wires[3][1]",
+                "has leading zeros",
+            ),
+            (
+                "/proof/openings/wires/3/0",
+                json!("-1"),
+                "proof.proof.openings.wires[3][0]",
+                "is not a decimal number",
+            ),
+            (
+                "/proof/wires_cap/0",
+                json!(["1", "2", "3"]),
+                "proof.proof.wires_cap[0]",
+                "expected 4 elements, found 3",
+            ),
+            (
+                evals_proof,
+                json!({}),
+                "proof.proof.opening_proof.query_round_proofs[0].initial_trees_proof\
+                 .evals_proofs[1][1]",
+                "missing field `siblings`",
+            ),
+        ] {
+            let mut proof = proof.clone();
+            *proof.pointer_mut(pointer).unwrap() = value;
+            let err = from_json(&proof, &vk, &common).err().unwrap();
+            assert_eq!(err.path, path);
+            assert!(err.reason.contains(reason), "{err}");
+        }
+
+        let mut extra_input = proof.clone();
+        extra_input["public_inputs"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!("0"));
+        let err = from_json(&extra_input, &vk, &common).err().unwrap();
+        assert_eq!(err.path, "proof.public_inputs");
+        assert!(err.reason.contains("the common data expects"), "{err}");
+
+        let mut bad_vk = vk.clone();
+        bad_vk["circuit_digest"] = json!("0");
+        let err = from_json(&proof, &bad_vk, &common).err().unwrap();
+        assert_eq!(err.path, "vk.circuit_digest");
+        assert_eq!(err.reason, "expected an array, found a string");
+
+        let mut bad_common = serde_json::from_str::<Value>(&common).unwrap();
+        bad_common["k_is"][0] = json!(GoldilocksField::ORDER);
+        let err = from_json(&proof, &vk, &bad_common.to_string())
+            .err()
+            .unwrap();
+        assert_eq!(err.path, "common.k_is[0]");
+
+        let err = from_json(&proof, &vk, "{").err().unwrap();
+        assert_eq!(err.path, "common");
+    }
+}
@@ -1,6 +1,13 @@
 pub mod bn245_poseidon;
 pub mod chip;
 pub mod context;
+pub mod debug;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+pub mod json;
+pub mod presets;
+pub mod transcript_schedule;
 pub mod types;
 pub mod verifier_api;
 pub mod verifier_circuit;
+pub mod witness_trace;
@@ -0,0 +1,314 @@
+//! FRI parameters for proofs verified by the halo2 verifier. Most of the verifier circuit checks
+//! the FRI query rounds, so for a target security level the cheapest aggregated proof comes from
+//! the fewest query rounds, paid for by the plonky2 prover with a larger LDE and more grinding.
+
+use anyhow::{ensure, Result};
+use halo2_proofs::halo2curves::bn256::Fr;
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    fri::FriConfig,
+    gates::noop::NoopGate,
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+};
+
+use super::{
+    bn245_poseidon::plonky2_config::{
+        standard_stark_verifier_config, Bn254PoseidonGoldilocksConfig,
+    },
+    chip::native_chip::utils::goldilocks_to_fe,
+    types::{common_data::CommonData, proof::ProofValues, verification_key::VerificationKeyValues},
+    verifier_api::{min_k, used_rows},
+    verifier_circuit::{Verifier, SUPPORTED_EXTENSION_DEGREE},
+};
+
+/// Number of Merkle trees the initial FRI polynomials are committed to: constants and sigmas,
+/// wires, Z and partial products, and quotient polynomials.
+const NUM_INITIAL_TREES: usize = 4;
+
+/// Upper bound on the bits of security of the challenges, drawn from the extension field of
+/// order `p^D < 2^(64 D)`.
+pub const MAX_SECURITY_BITS: usize = 64 * SUPPORTED_EXTENSION_DEGREE - 1;
+
+/// Conjectured soundness of FRI in bits, as plonky2 accounts it: every query round adds
+/// `rate_bits` bits and grinding adds `proof_of_work_bits`.
+pub fn fri_security_bits(config: &FriConfig) -> usize {
+    let bits = config.rate_bits * config.num_query_rounds + config.proof_of_work_bits as usize;
+    bits.min(MAX_SECURITY_BITS)
+}
+
+/// Trade-off between the cost of the plonky2 prover and of the verifier circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Cheapest verifier circuit: a rate of 1/16 and 20 bits of grinding, so the plonky2 prover
+    /// pays for an LDE twice the size of `Balanced` and 16 times the grinding.
+    Fast,
+    /// plonky2's standard parameters: a rate of 1/8 and 16 bits of grinding.
+    Balanced,
+    /// `Balanced` without crediting the grinding, so the query rounds alone reach the target.
+    Conservative,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Fast, Preset::Balanced, Preset::Conservative];
+
+    fn rate_bits(self) -> usize {
+        match self {
+            Preset::Fast => 4,
+            Preset::Balanced | Preset::Conservative => 3,
+        }
+    }
+
+    fn proof_of_work_bits(self) -> u32 {
+        match self {
+            Preset::Fast => 20,
+            Preset::Balanced | Preset::Conservative => 16,
+        }
+    }
+
+    /// Bits of security the preset counts from grinding.
+    fn credited_proof_of_work_bits(self) -> usize {
+        match self {
+            Preset::Fast | Preset::Balanced => self.proof_of_work_bits() as usize,
+            Preset::Conservative => 0,
+        }
+    }
+
+    /// Recommends the FRI parameters of a proof of `2^degree_bits` rows reaching
+    /// `security_bits` of conjectured security, with at least one query round even if the
+    /// credited grinding alone reaches the target. The cap height and the reduction strategy
+    /// are those of `standard_stark_verifier_config`, the FRI chip only folds with arity 2.
+    pub fn recommend(self, security_bits: usize, degree_bits: usize) -> Recommendation {
+        assert!(
+            security_bits <= MAX_SECURITY_BITS,
+            "security is bounded by the extension field to {MAX_SECURITY_BITS} bits"
+        );
+        let rate_bits = self.rate_bits();
+        assert!(
+            degree_bits + rate_bits <= GoldilocksField::TWO_ADICITY,
+            "LDE of 2^{} rows exceeds the two-adicity of Goldilocks",
+            degree_bits + rate_bits
+        );
+        let num_query_rounds = security_bits
+            .saturating_sub(self.credited_proof_of_work_bits())
+            .div_ceil(rate_bits)
+            .max(1);
+        let fri_config = FriConfig {
+            rate_bits,
+            proof_of_work_bits: self.proof_of_work_bits(),
+            num_query_rounds,
+            ..standard_stark_verifier_config().fri_config
+        };
+        Recommendation {
+            preset: self,
+            security_bits: fri_security_bits(&fri_config),
+            merkle_hashes: merkle_hashes(&fri_config, degree_bits),
+            degree_bits,
+            fri_config,
+        }
+    }
+}
+
+/// FRI parameters recommended by a `Preset`, with the cost they put on the verifier circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recommendation {
+    pub preset: Preset,
+    pub fri_config: FriConfig,
+    pub degree_bits: usize,
+    /// Conjectured security of `fri_config`, see `fri_security_bits`. At least the target.
+    pub security_bits: usize,
+    /// Merkle path hashes the verifier circuit checks over all query rounds. They dominate its
+    /// rows, so presets are compared by them; `outer_circuit_size` gives the rows and `k`.
+    pub merkle_hashes: usize,
+}
+
+/// Size of the verifier circuit of a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OuterCircuitSize {
+    /// Rows the verifier circuit assigns, see `verifier_api::used_rows`.
+    pub rows: usize,
+    /// Smallest degree the verifier circuit fits in, see `verifier_api::min_k`.
+    pub k: u32,
+}
+
+impl Recommendation {
+    /// `standard_stark_verifier_config` with the recommended FRI parameters.
+    pub fn circuit_config(&self) -> CircuitConfig {
+        CircuitConfig {
+            security_bits: self.security_bits,
+            fri_config: self.fri_config.clone(),
+            ..standard_stark_verifier_config()
+        }
+    }
+
+    /// Predicts the size of the verifier circuit of proofs with the recommended parameters, by
+    /// proving a circuit of `2^degree_bits` rows of no-op gates with them and synthesizing its
+    /// verifier circuit once. The gates of an actual circuit add their constraints on top of
+    /// it, which are few rows next to the query rounds.
+    pub fn outer_circuit_size(&self) -> Result<OuterCircuitSize> {
+        let mut builder = CircuitBuilder::<GoldilocksField, 2>::new(self.circuit_config());
+        let x = builder.add_virtual_target();
+        builder.register_public_input(x);
+        // building adds at least the public input gate, which takes the degree past the half
+        while builder.num_gates() < 1 << self.degree_bits.saturating_sub(1) {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+        ensure!(
+            data.common.degree_bits() == self.degree_bits,
+            "the smallest circuit with these parameters has degree_bits {}, not {}",
+            data.common.degree_bits(),
+            self.degree_bits
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, GoldilocksField::ONE);
+        let proof = data.prove(pw)?;
+        let instances = proof
+            .public_inputs
+            .iter()
+            .map(|e| goldilocks_to_fe(*e))
+            .collect::<Vec<Fr>>();
+        let circuit = Verifier::new(
+            ProofValues::<Fr, 2>::from(proof.proof),
+            instances.clone(),
+            VerificationKeyValues::from(data.verifier_only),
+            CommonData::from(data.common),
+        );
+        Ok(OuterCircuitSize {
+            rows: used_rows(&circuit, &instances)?,
+            k: min_k(&circuit, &instances)?,
+        })
+    }
+}
+
+/// `Preset::Balanced` recommendation, plonky2's standard parameters at 100 bits.
+pub fn recommend(security_bits: usize, degree_bits: usize) -> Recommendation {
+    Preset::Balanced.recommend(security_bits, degree_bits)
+}
+
+/// Merkle path hashes of all query rounds: the initial trees have `lde_bits - cap_height`
+/// levels, and each reduction shortens the next tree by its arity bits.
+fn merkle_hashes(config: &FriConfig, degree_bits: usize) -> usize {
+    let fri_params = config.fri_params(degree_bits, false);
+    let height = fri_params.lde_bits() - config.cap_height;
+    let mut per_query = NUM_INITIAL_TREES * height;
+    let mut reduced_bits = 0;
+    for arity_bits in fri_params.reduction_arity_bits.iter() {
+        reduced_bits += arity_bits;
+        per_query += height - reduced_bits;
+    }
+    per_query * config.num_query_rounds
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{fri::FriConfig, plonk::circuit_data::CircuitConfig};
+
+    use super::{fri_security_bits, recommend, Preset, MAX_SECURITY_BITS};
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::{
+            standard_inner_stark_verifier_config, standard_stark_verifier_config,
+        },
+        fixtures::{generate_tiny_proof_tuple_with_circuit_config, verify_mock},
+    };
+
+    #[test]
+    fn test_security_of_standard_configs() {
+        // plonky2's standard recursion config: 28 rounds at rate 1/8 and 16 bits of grinding
+        for config in [
+            CircuitConfig::standard_recursion_config(),
+            standard_inner_stark_verifier_config(),
+            standard_stark_verifier_config(),
+        ] {
+            assert_eq!(fri_security_bits(&config.fri_config), 100);
+            assert_eq!(config.security_bits, 100);
+        }
+
+        let mut config = standard_stark_verifier_config().fri_config;
+        config.num_query_rounds = 100;
+        assert_eq!(fri_security_bits(&config), MAX_SECURITY_BITS);
+    }
+
+    #[test]
+    fn test_recommend_reaches_target() {
+        // the balanced preset is plonky2's standard config at 100 bits
+        let recommendation = recommend(100, 12);
+        assert_eq!(
+            recommendation.circuit_config().fri_config,
+            standard_stark_verifier_config().fri_config
+        );
+
+        for security_bits in [80, 100, 112, 127] {
+            for degree_bits in [3, 12, 20] {
+                let recommendations =
+                    Preset::ALL.map(|preset| preset.recommend(security_bits, degree_bits));
+                for recommendation in recommendations.iter() {
+                    let config = &recommendation.fri_config;
+                    assert!(recommendation.security_bits >= security_bits);
+                    // the fewest rounds reaching the target with the bits the preset credits
+                    let credited = |num_query_rounds: usize| match recommendation.preset {
+                        Preset::Conservative => config.rate_bits * num_query_rounds,
+                        Preset::Fast | Preset::Balanced => fri_security_bits(&FriConfig {
+                            num_query_rounds,
+                            ..config.clone()
+                        }),
+                    };
+                    assert!(credited(config.num_query_rounds) >= security_bits);
+                    assert!(credited(config.num_query_rounds - 1) < security_bits);
+                }
+                let [fast, balanced, conservative] = recommendations;
+                assert!(fast.merkle_hashes < balanced.merkle_hashes);
+                assert!(balanced.merkle_hashes < conservative.merkle_hashes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recommend_at_least_one_query_round() {
+        for security_bits in [0, 10, 16, 20] {
+            for preset in Preset::ALL {
+                let recommendation = preset.recommend(security_bits, 12);
+                assert!(recommendation.fri_config.num_query_rounds >= 1);
+                assert!(recommendation.security_bits >= security_bits);
+            }
+        }
+        // the grinding alone reaches the target
+        assert_eq!(
+            Preset::Fast.recommend(20, 12).fri_config.num_query_rounds,
+            1
+        );
+        assert_eq!(
+            Preset::Balanced
+                .recommend(16, 12)
+                .fri_config
+                .num_query_rounds,
+            1
+        );
+    }
+
+    #[test]
+    fn test_outer_circuit_size() {
+        let sizes =
+            Preset::ALL.map(|preset| preset.recommend(100, 3).outer_circuit_size().unwrap());
+        for size in sizes.iter() {
+            assert!(size.rows < 1 << size.k);
+        }
+        // fewer Merkle hashes, fewer rows
+        let [fast, balanced, conservative] = sizes;
+        assert!(fast.rows < balanced.rows);
+        assert!(balanced.rows < conservative.rows);
+    }
+
+    #[test]
+    fn test_preset_proofs_halo2_mock() {
+        for preset in Preset::ALL {
+            let recommendation = preset.recommend(100, 3);
+            let proof =
+                generate_tiny_proof_tuple_with_circuit_config(recommendation.circuit_config());
+            assert_eq!(proof.2.config.fri_config, recommendation.fri_config);
+            assert_eq!(proof.2.degree_bits(), recommendation.degree_bits);
+            verify_mock(proof);
+        }
+    }
+}
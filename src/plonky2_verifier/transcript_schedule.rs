@@ -0,0 +1,397 @@
+//! Order in which the verifier writes a proof to the Fiat-Shamir transcript and squeezes its
+//! challenges. `PlonkVerifierChip::get_challenges` and `WitnessTrace` both replay the schedule
+//! instead of spelling out their own sequence of writes, and the expected transcript counts are
+//! summed from it, so none of them can diverge from the others.
+
+use std::collections::VecDeque;
+
+use halo2_proofs::halo2curves::ff::PrimeField;
+use plonky2::{field::goldilocks_field::GoldilocksField, hash::hash_types::NUM_HASH_OUT_ELTS};
+use serde::{Deserialize, Serialize};
+
+use super::{types::common_data::CommonData, verifier_circuit::SUPPORTED_EXTENSION_DEGREE};
+
+/// A value the challenger absorbs before the first commitment of the proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengerSeed {
+    CircuitDigest,
+    PublicInputsHash,
+    /// Constant elements, e.g. the domain separator of a recursion-specific challenger
+    Elements(Vec<GoldilocksField>),
+}
+
+/// Seeds of the challenger, in the order it absorbs them. They have to mirror how the inner
+/// prover initialized its challenger, or every challenge differs. Defaults to plonky2's
+/// `get_challenges`: the circuit digest, then the public inputs hash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengerSeeds(pub Vec<ChallengerSeed>);
+
+impl Default for ChallengerSeeds {
+    fn default() -> Self {
+        Self(vec![
+            ChallengerSeed::CircuitDigest,
+            ChallengerSeed::PublicInputsHash,
+        ])
+    }
+}
+
+impl ChallengerSeeds {
+    /// Hashes the `ObserveHash` events take, in the order of the seeds.
+    pub fn hashes<T: Clone>(&self, circuit_digest: T, public_inputs_hash: T) -> VecDeque<T> {
+        self.0
+            .iter()
+            .filter_map(|seed| match seed {
+                ChallengerSeed::CircuitDigest => Some(circuit_digest.clone()),
+                ChallengerSeed::PublicInputsHash => Some(public_inputs_hash.clone()),
+                ChallengerSeed::Elements(_) => None,
+            })
+            .collect()
+    }
+
+    /// Elements the `ObserveConstants` events take, in the order of the seeds.
+    pub fn constants(&self) -> VecDeque<GoldilocksField> {
+        self.0
+            .iter()
+            .flat_map(|seed| match seed {
+                ChallengerSeed::Elements(elements) => elements.as_slice(),
+                _ => &[][..],
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// Challenge derived by a `TranscriptEvent::Squeeze`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Challenge {
+    PlonkBetas,
+    PlonkGammas,
+    PlonkAlphas,
+    PlonkZeta,
+    FriAlpha,
+    /// Beta of the next FRI reduction, squeezed after its commit phase cap
+    FriBeta,
+    FriPowResponse,
+    FriQueryIndices,
+}
+
+/// A step of the transcript of a proof verification. Observations take the next values of their
+/// kind from the proof, so only the schedule decides how the kinds interleave.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// Observes the next hash seed, the circuit digest or the public inputs hash.
+    ObserveHash,
+    /// Observes the next `n` constant elements of a `ChallengerSeed::Elements`.
+    ObserveConstants(usize),
+    /// Observes the next Merkle cap, of `n` hashes: the wires, Z and partial products and
+    /// quotient caps, then the commit phase cap of each FRI reduction.
+    ObserveCap(usize),
+    /// Observes the next `n` extension elements: the openings at zeta, at `g * zeta`, then the
+    /// coefficients of the final polynomial.
+    ObserveExtension(usize),
+    /// Observes the proof-of-work witness.
+    ObserveElement,
+    /// Squeezes `n` elements for a challenge.
+    Squeeze(Challenge, usize),
+}
+
+impl TranscriptEvent {
+    /// Number of field elements the event writes to the transcript.
+    pub fn num_observations(&self) -> usize {
+        match self {
+            TranscriptEvent::ObserveHash => NUM_HASH_OUT_ELTS,
+            TranscriptEvent::ObserveConstants(n) => *n,
+            TranscriptEvent::ObserveCap(n) => n * NUM_HASH_OUT_ELTS,
+            TranscriptEvent::ObserveExtension(n) => n * SUPPORTED_EXTENSION_DEGREE,
+            TranscriptEvent::ObserveElement => 1,
+            TranscriptEvent::Squeeze(..) => 0,
+        }
+    }
+
+    /// Number of challenges the event squeezes.
+    pub fn num_challenges(&self) -> usize {
+        match self {
+            TranscriptEvent::Squeeze(_, n) => *n,
+            _ => 0,
+        }
+    }
+}
+
+/// Transcript of the verification of a proof of `common_data`'s shape, in the order of plonky2's
+/// `ProofWithPublicInputs::get_challenges`.
+pub fn transcript_schedule<F: PrimeField>(common_data: &CommonData<F>) -> Vec<TranscriptEvent> {
+    use TranscriptEvent::*;
+
+    let num_challenges = common_data.config.num_challenges;
+    let cap_len = 1 << common_data.config.fri_config.cap_height;
+    let extension = SUPPORTED_EXTENSION_DEGREE;
+    let zeta_openings = common_data.num_preprocessed_polys()
+        + common_data.config.num_wires
+        + common_data.num_zs_partial_products_polys()
+        + common_data.num_quotient_polys();
+
+    let mut schedule = common_data
+        .challenger_seeds
+        .0
+        .iter()
+        .map(|seed| match seed {
+            ChallengerSeed::CircuitDigest | ChallengerSeed::PublicInputsHash => ObserveHash,
+            ChallengerSeed::Elements(elements) => ObserveConstants(elements.len()),
+        })
+        .collect::<Vec<_>>();
+    schedule.extend([
+        ObserveCap(cap_len),
+        Squeeze(Challenge::PlonkBetas, num_challenges),
+        Squeeze(Challenge::PlonkGammas, num_challenges),
+        ObserveCap(cap_len),
+        Squeeze(Challenge::PlonkAlphas, num_challenges),
+        ObserveCap(cap_len),
+        Squeeze(Challenge::PlonkZeta, extension),
+        // a batch of openings per point, as `OpeningSet::to_fri_openings`
+        ObserveExtension(zeta_openings),
+        ObserveExtension(num_challenges),
+        Squeeze(Challenge::FriAlpha, extension),
+    ]);
+    for _ in common_data.fri_params.reduction_arity_bits.iter() {
+        schedule.extend([ObserveCap(cap_len), Squeeze(Challenge::FriBeta, extension)]);
+    }
+    schedule.extend([
+        ObserveExtension(common_data.fri_params.final_poly_len()),
+        ObserveElement,
+        Squeeze(Challenge::FriPowResponse, 1),
+        Squeeze(
+            Challenge::FriQueryIndices,
+            common_data.config.fri_config.num_query_rounds,
+        ),
+    ]);
+    schedule
+}
+
+/// Takes the next `n` values of `queue`, or what's left of it. A short proof is written as far
+/// as it goes, so the transcript counts report the missing values.
+pub(crate) fn take<T>(queue: &mut VecDeque<T>, n: usize) -> impl Iterator<Item = T> + '_ {
+    queue.drain(..n.min(queue.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use plonky2::{
+        field::{
+            extension::Extendable,
+            goldilocks_field::GoldilocksField,
+            types::{Field, PrimeField64},
+        },
+        hash::merkle_tree::MerkleCap,
+        iop::challenger::Challenger,
+        plonk::config::{GenericConfig, Hasher},
+    };
+
+    use super::{transcript_schedule, Challenge, ChallengerSeed, TranscriptEvent};
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+        chip::native_chip::{test_utils::mock_prove_small_table, utils::goldilocks_to_fe},
+        fixtures::{generate_proof_tuple, generate_tiny_proof_tuple, mock_verifier},
+        types::{
+            common_data::CommonData, proof::ProofValues, verification_key::VerificationKeyValues,
+        },
+        verifier_circuit::{ProofTuple, Verifier},
+        witness_trace::WitnessTrace,
+    };
+
+    type F = GoldilocksField;
+    type C = Bn254PoseidonGoldilocksConfig;
+    type H = <C as GenericConfig<2>>::Hasher;
+
+    /// plonky2's challenger, recording what it's asked for as `TranscriptEvent`s.
+    struct RecordingChallenger {
+        challenger: Challenger<F, H>,
+        events: Vec<TranscriptEvent>,
+    }
+
+    impl RecordingChallenger {
+        fn observe_hash<OH: Hasher<F>>(&mut self, hash: OH::Hash) {
+            self.events.push(TranscriptEvent::ObserveHash);
+            self.challenger.observe_hash::<OH>(hash);
+        }
+
+        fn observe_elements(&mut self, elements: &[F]) {
+            self.events
+                .push(TranscriptEvent::ObserveConstants(elements.len()));
+            self.challenger.observe_elements(elements);
+        }
+
+        fn observe_cap(&mut self, cap: &MerkleCap<F, H>) {
+            self.events.push(TranscriptEvent::ObserveCap(cap.0.len()));
+            self.challenger.observe_cap(cap);
+        }
+
+        fn observe_extension_elements(&mut self, elements: &[<F as Extendable<2>>::Extension]) {
+            self.events
+                .push(TranscriptEvent::ObserveExtension(elements.len()));
+            self.challenger.observe_extension_elements::<2>(elements);
+        }
+
+        fn observe_element(&mut self, element: F) {
+            self.events.push(TranscriptEvent::ObserveElement);
+            self.challenger.observe_element(element);
+        }
+
+        fn get_n_challenges(&mut self, challenge: Challenge, n: usize) -> Vec<F> {
+            self.events.push(TranscriptEvent::Squeeze(challenge, n));
+            self.challenger.get_n_challenges(n)
+        }
+    }
+
+    /// Replays plonky2's `ProofWithPublicInputs::get_challenges` on a recording challenger,
+    /// after absorbing `domain_separator` if there is one. Returns the events and the raw query
+    /// index challenges.
+    fn record_get_challenges(
+        proof_tuple: &ProofTuple<F, C, 2>,
+        domain_separator: &[F],
+    ) -> (Vec<TranscriptEvent>, Vec<F>) {
+        let (proof_with_public_inputs, vd, cd) = proof_tuple;
+        let proof = &proof_with_public_inputs.proof;
+        let num_challenges = cd.config.num_challenges;
+        let mut challenger = RecordingChallenger {
+            challenger: Challenger::new(),
+            events: vec![],
+        };
+
+        if !domain_separator.is_empty() {
+            challenger.observe_elements(domain_separator);
+        }
+        challenger.observe_hash::<H>(vd.circuit_digest);
+        challenger.observe_hash::<<C as GenericConfig<2>>::InnerHasher>(
+            proof_with_public_inputs.get_public_inputs_hash(),
+        );
+        challenger.observe_cap(&proof.wires_cap);
+        challenger.get_n_challenges(Challenge::PlonkBetas, num_challenges);
+        challenger.get_n_challenges(Challenge::PlonkGammas, num_challenges);
+        challenger.observe_cap(&proof.plonk_zs_partial_products_cap);
+        challenger.get_n_challenges(Challenge::PlonkAlphas, num_challenges);
+        challenger.observe_cap(&proof.quotient_polys_cap);
+        // `get_extension_challenge` squeezes `D` elements
+        challenger.get_n_challenges(Challenge::PlonkZeta, 2);
+
+        // `observe_openings(&openings.to_fri_openings())`
+        let openings = &proof.openings;
+        let zeta_batch = [
+            openings.constants.as_slice(),
+            openings.plonk_sigmas.as_slice(),
+            openings.wires.as_slice(),
+            openings.plonk_zs.as_slice(),
+            openings.partial_products.as_slice(),
+            openings.quotient_polys.as_slice(),
+        ]
+        .concat();
+        challenger.observe_extension_elements(&zeta_batch);
+        challenger.observe_extension_elements(&openings.plonk_zs_next);
+
+        // `Challenger::fri_challenges`
+        let fri_proof = &proof.opening_proof;
+        challenger.get_n_challenges(Challenge::FriAlpha, 2);
+        for cap in fri_proof.commit_phase_merkle_caps.iter() {
+            challenger.observe_cap(cap);
+            challenger.get_n_challenges(Challenge::FriBeta, 2);
+        }
+        challenger.observe_extension_elements(&fri_proof.final_poly.coeffs);
+        challenger.observe_element(fri_proof.pow_witness);
+        challenger.get_n_challenges(Challenge::FriPowResponse, 1);
+        let num_query_rounds = cd.config.fri_config.num_query_rounds;
+        let query_indices =
+            challenger.get_n_challenges(Challenge::FriQueryIndices, num_query_rounds);
+        (challenger.events, query_indices)
+    }
+
+    #[test]
+    fn test_schedule_matches_plonky2_transcript() {
+        for proof_tuple in [generate_tiny_proof_tuple(), generate_proof_tuple()] {
+            let (events, query_indices) = record_get_challenges(&proof_tuple, &[]);
+            let (verifier, _) = mock_verifier(proof_tuple.clone());
+            let common_data = verifier.common_data();
+            assert_eq!(transcript_schedule(common_data), events);
+
+            // the replay squeezes the query indices plonky2 opened the proof at
+            let lde_size = 1 << common_data.fri_params.lde_bits();
+            let (proof_with_public_inputs, vd, cd) = proof_tuple;
+            let compressed = proof_with_public_inputs
+                .compress(&vd.circuit_digest, &cd)
+                .unwrap();
+            assert_eq!(
+                query_indices
+                    .iter()
+                    .map(|index| index.to_canonical_u64() as usize % lde_size)
+                    .collect::<Vec<_>>(),
+                compressed.proof.opening_proof.query_round_proofs.indices
+            );
+
+            // and so does the trace, driven by the schedule
+            let trace = verifier.witness_trace().unwrap();
+            assert_eq!(trace.challenges.fri_query_indices, query_indices);
+        }
+    }
+
+    #[test]
+    fn test_challenger_seeds_mirror_the_inner_challenger() {
+        // a recursion circuit whose challenger absorbs a domain separator before anything else
+        let proof_tuple = generate_tiny_proof_tuple();
+        let domain_separator = vec![F::from_canonical_u64(0x5eed), F::ONE];
+        let (events, query_indices) = record_get_challenges(&proof_tuple, &domain_separator);
+        let (proof_with_public_inputs, vd, cd) = proof_tuple;
+        let common_data = CommonData::<Fr>::from(cd)
+            .with_challenger_seeds(vec![
+                ChallengerSeed::Elements(domain_separator),
+                ChallengerSeed::CircuitDigest,
+                ChallengerSeed::PublicInputsHash,
+            ])
+            .unwrap();
+        assert_eq!(transcript_schedule(&common_data), events);
+
+        let instances = proof_with_public_inputs
+            .public_inputs
+            .iter()
+            .map(|e| goldilocks_to_fe(*e))
+            .collect::<Vec<Fr>>();
+        let proof = ProofValues::from(proof_with_public_inputs.proof);
+        let vk = VerificationKeyValues::from(vd);
+        let trace = WitnessTrace::new(&proof, &instances, &vk, &common_data).unwrap();
+        assert_eq!(trace.challenges.fri_query_indices, query_indices);
+
+        // synthesis checks the challenges of the circuit against the trace
+        let verifier_circuit = Verifier::new(proof, instances, vk, common_data);
+        let prover =
+            mock_prove_small_table(&verifier_circuit, vec![verifier_circuit.instances()]).unwrap();
+        // plonky2 proved with its default seeds, so the proof doesn't verify under these
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_transcript_counts_sum_the_schedule() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let cap_len = 4 << common_data.config.fri_config.cap_height;
+        let num_reductions = common_data.fri_params.reduction_arity_bits.len();
+        let num_challenges = common_data.config.num_challenges;
+
+        // digests, caps, extension openings and final polynomial, pow witness
+        let openings = common_data.num_preprocessed_polys()
+            + common_data.config.num_wires
+            + common_data.num_zs_partial_products_polys()
+            + common_data.num_quotient_polys()
+            + num_challenges;
+        let final_poly = common_data.fri_params.final_poly_len();
+        assert_eq!(
+            common_data.num_transcript_observations(),
+            8 + (3 + num_reductions) * cap_len + 2 * (openings + final_poly) + 1
+        );
+        // plonk challenges, zeta, FRI alpha and betas, pow response and query indices
+        assert_eq!(
+            common_data.num_transcript_challenges(),
+            3 * num_challenges
+                + 2 * (2 + num_reductions)
+                + 1
+                + common_data.config.fri_config.num_query_rounds
+        );
+    }
+}
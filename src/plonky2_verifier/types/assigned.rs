@@ -1,29 +1,148 @@
-use halo2_proofs::halo2curves::ff::PrimeField;
+use std::{fmt, io};
+
+use halo2_proofs::{circuit::Value, halo2curves::ff::PrimeField, plonk::Error};
 use halo2wrong_maingate::AssignedValue;
+use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+use super::{
+    common_data::{CommonData, FriParams},
+    fri::{FriOracleInfo, FriPolynomialInfo, SALT_SIZE},
+    proof::{
+        FriInitialTreeProofValues, FriProofValues, FriQueryRoundValues, FriQueryStepValues,
+        MerkleProofValues, OpeningSetValues, PolynomialCoeffsExtValues, ProofValues,
+    },
+    ExtensionFieldValue, HashValues, MerkleCapValues,
+};
+use crate::plonky2_verifier::{
+    chip::{
+        goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+        native_chip::utils::fe_to_goldilocks,
+    },
+    context::RegionCtx,
+};
+
+/// Number of assigned values does not match the shape of the type built from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeError {
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} assigned values, got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+impl From<ShapeError> for Error {
+    fn from(value: ShapeError) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
+}
+
+pub(crate) fn to_array<T, const N: usize>(values: Vec<T>) -> Result<[T; N], ShapeError> {
+    let actual = values.len();
+    values.try_into().map_err(|_| ShapeError {
+        expected: N,
+        actual,
+    })
+}
 
 #[derive(Clone)]
 pub struct AssignedHashValues<F: PrimeField> {
     pub elements: [AssignedValue<F>; 4],
 }
 
+impl<F: PrimeField> AssignedHashValues<F> {
+    pub fn from_vec(elements: Vec<AssignedValue<F>>) -> Result<Self, ShapeError> {
+        Ok(Self {
+            elements: to_array(elements)?,
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AssignedValue<F>> {
+        self.elements.iter()
+    }
+}
+
 #[derive(Clone)]
 pub struct AssignedMerkleCapValues<F: PrimeField>(pub Vec<AssignedHashValues<F>>);
 
 #[derive(Clone, Debug)]
 pub struct AssignedExtensionFieldValue<F: PrimeField, const D: usize>(pub [AssignedValue<F>; D]);
 
+impl<F: PrimeField, const D: usize> AssignedExtensionFieldValue<F, D> {
+    pub fn new(limbs: [AssignedValue<F>; D]) -> Self {
+        Self(limbs)
+    }
+
+    pub fn from_vec(limbs: Vec<AssignedValue<F>>) -> Result<Self, ShapeError> {
+        Ok(Self(to_array(limbs)?))
+    }
+
+    pub fn limbs(&self) -> &[AssignedValue<F>; D] {
+        &self.0
+    }
+}
+
+impl<F: PrimeField> AssignedExtensionFieldValue<F, 2> {
+    /// Coefficient of `1`.
+    pub fn real(&self) -> &AssignedValue<F> {
+        &self.0[0]
+    }
+
+    /// Coefficient of `w`, where `w^2 = 7`.
+    pub fn imag(&self) -> &AssignedValue<F> {
+        &self.0[1]
+    }
+}
+
 pub struct AssignedOpeningSetValues<F: PrimeField, const D: usize> {
     pub constants: Vec<AssignedExtensionFieldValue<F, D>>,
     pub plonk_sigmas: Vec<AssignedExtensionFieldValue<F, D>>,
     pub wires: Vec<AssignedExtensionFieldValue<F, D>>,
     pub plonk_zs: Vec<AssignedExtensionFieldValue<F, D>>,
-    pub plonk_zs_next: Vec<AssignedExtensionFieldValue<F, D>>,
+    pub plonk_zs_next: ZetaNextOpenings<F, D>,
     pub partial_products: Vec<AssignedExtensionFieldValue<F, D>>,
     pub quotient_polys: Vec<AssignedExtensionFieldValue<F, D>>,
 }
 
 impl<F: PrimeField, const D: usize> AssignedOpeningSetValues<F, D> {
-    pub(crate) fn to_fri_openings(&self) -> AssignedFriOpenings<F, D> {
+    /// Groups the openings into the batches `FriInstanceInfo::new` opens at zeta and at
+    /// `g * zeta`, in the order of `OpeningSet::to_fri_openings`. Every group is checked against
+    /// the number of polynomials `common_data` commits to, so openings of the wrong shape can't
+    /// shift polynomials into a neighbouring group.
+    pub(crate) fn to_fri_openings(
+        &self,
+        common_data: &CommonData<F>,
+    ) -> Result<AssignedFriOpenings<F, D>, ShapeError> {
+        let num_challenges = common_data.config.num_challenges;
+        for (values, expected) in [
+            (&self.constants, common_data.constants_range().len()),
+            (&self.plonk_sigmas, common_data.sigmas_range().len()),
+            (&self.wires, common_data.config.num_wires),
+            (&self.plonk_zs, num_challenges),
+            (&self.plonk_zs_next.0, num_challenges),
+            (
+                &self.partial_products,
+                num_challenges * common_data.num_partial_products,
+            ),
+            (&self.quotient_polys, common_data.num_quotient_polys()),
+        ] {
+            if values.len() != expected {
+                return Err(ShapeError {
+                    expected,
+                    actual: values.len(),
+                });
+            }
+        }
+
         let zeta_batch = AssignedFriOpeningBatch {
             values: [
                 self.constants.as_slice(),
@@ -35,15 +154,75 @@ impl<F: PrimeField, const D: usize> AssignedOpeningSetValues<F, D> {
             ]
             .concat(),
         };
-        let zeta_next_batch = AssignedFriOpeningBatch {
-            values: self.plonk_zs_next.clone(),
-        };
-        AssignedFriOpenings {
-            batches: vec![zeta_batch, zeta_next_batch],
+        Ok(AssignedFriOpenings {
+            batches: vec![zeta_batch, self.plonk_zs_next.batch()],
+        })
+    }
+}
+
+/// Openings at `g * zeta`. plonky2 only opens the `Z` polynomials there, so the only way to
+/// build these is from the `plonk_zs_next` of an opening set, and the polynomials of their FRI
+/// batch are the `Z` polynomials of `common_data`.
+pub struct ZetaNextOpenings<F: PrimeField, const D: usize>(Vec<AssignedExtensionFieldValue<F, D>>);
+
+impl<F: PrimeField, const D: usize> ZetaNextOpenings<F, D> {
+    pub fn assign(
+        config: &GoldilocksChipConfig<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        opening_set_values: &OpeningSetValues<F, D>,
+    ) -> Result<Self, Error> {
+        let plonk_zs_next =
+            ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.plonk_zs_next)?;
+        Ok(Self(plonk_zs_next))
+    }
+
+    /// Polynomials opened at `g * zeta`, in the order of the openings.
+    pub fn polynomials(common_data: &CommonData<F>) -> Vec<FriPolynomialInfo> {
+        common_data.fri_zs_polys()
+    }
+
+    pub fn zs(&self) -> &[AssignedExtensionFieldValue<F, D>] {
+        &self.0
+    }
+
+    fn batch(&self) -> AssignedFriOpeningBatch<F, D> {
+        AssignedFriOpeningBatch {
+            values: self.0.clone(),
         }
     }
 }
 
+/// Little-endian bits of a Merkle leaf index. They are only obtained by decomposing an assigned
+/// index or by dropping low bits of other index bits, so the cap entry and the path a Merkle
+/// proof is checked with always come from the same index.
+#[derive(Clone, Debug)]
+pub struct AssignedIndexBits<F: PrimeField>(Vec<AssignedValue<F>>);
+
+impl<F: PrimeField> AssignedIndexBits<F> {
+    /// Decomposes `index` and keeps its `num_bits` low bits.
+    pub fn decompose(
+        goldilocks_chip: &GoldilocksChip<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        index: &AssignedValue<F>,
+        num_bits: usize,
+    ) -> Result<Self, Error> {
+        let mut bits = goldilocks_chip.to_bits(ctx, index, 64)?;
+        bits.truncate(num_bits);
+        Ok(Self(bits))
+    }
+
+    pub fn bits(&self) -> &[AssignedValue<F>] {
+        &self.0
+    }
+
+    /// Splits off the `num_bits` low bits, returning them along with the bits of
+    /// `index >> num_bits`.
+    pub fn split_low(&self, num_bits: usize) -> (&[AssignedValue<F>], Self) {
+        let (low, high) = self.0.split_at(num_bits);
+        (low, Self(high.to_vec()))
+    }
+}
+
 #[derive(Clone)]
 pub struct AssignedMerkleProofValues<F: PrimeField> {
     pub siblings: Vec<AssignedHashValues<F>>,
@@ -64,9 +243,35 @@ impl<F: PrimeField> AssignedFriInitialTreeProofValues<F> {
         self.unsalted_evals(oracle_index, salted)[poly_index].clone()
     }
 
+    /// Checks that there is a leaf per oracle, salted according to `hiding`. A proof whose
+    /// hiding flag doesn't match the common data is rejected here instead of having its salt
+    /// read as evaluations, or its evaluations as salt.
+    pub(crate) fn check_leaf_lens(
+        &self,
+        oracles: &[FriOracleInfo],
+        hiding: bool,
+    ) -> Result<(), ShapeError> {
+        if self.evals_proofs.len() != oracles.len() {
+            return Err(ShapeError {
+                expected: oracles.len(),
+                actual: self.evals_proofs.len(),
+            });
+        }
+        for ((evals, _), oracle) in self.evals_proofs.iter().zip(oracles.iter()) {
+            let expected = oracle.leaf_len(hiding);
+            if evals.len() != expected {
+                return Err(ShapeError {
+                    expected,
+                    actual: evals.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     fn unsalted_evals(&self, oracle_index: usize, salted: bool) -> &[AssignedValue<F>] {
         let evals = &self.evals_proofs[oracle_index].0;
-        let salt_size = if salted { 4 } else { 0 };
+        let salt_size = if salted { SALT_SIZE } else { 0 };
         &evals[..evals.len() - salt_size]
     }
 }
@@ -91,11 +296,35 @@ pub struct AssignedPolynomialCoeffsExtValues<F: PrimeField, const D: usize>(
 #[derive(Clone)]
 pub struct AssignedFriProofValues<F: PrimeField, const D: usize> {
     pub commit_phase_merkle_cap_values: Vec<AssignedMerkleCapValues<F>>,
+    /// Empty after `FriProofValues::assign_without_query_rounds`.
     pub query_round_proofs: Vec<AssignedFriQueryRoundValues<F, D>>,
     pub final_poly: AssignedPolynomialCoeffsExtValues<F, D>,
     pub pow_witness: AssignedValue<F>,
 }
 
+impl<F: PrimeField, const D: usize> AssignedFriProofValues<F, D> {
+    /// Checks that there is a commit phase cap per reduction. The transcript squeezes a beta
+    /// per cap, so an extra cap would shift the pow response and the query indices, and the
+    /// proof would fail in FRI instead of here.
+    pub(crate) fn check_commit_phase_len(&self, fri_params: &FriParams) -> Result<(), ShapeError> {
+        check_commit_phase_len(self.commit_phase_merkle_cap_values.len(), fri_params)
+    }
+}
+
+pub(crate) fn check_commit_phase_len(
+    num_caps: usize,
+    fri_params: &FriParams,
+) -> Result<(), ShapeError> {
+    let expected = fri_params.reduction_arity_bits.len();
+    if num_caps != expected {
+        return Err(ShapeError {
+            expected,
+            actual: num_caps,
+        });
+    }
+    Ok(())
+}
+
 pub struct AssignedProofValues<F: PrimeField, const D: usize> {
     pub wires_cap: AssignedMerkleCapValues<F>,
     pub plonk_zs_partial_products_cap: AssignedMerkleCapValues<F>,
@@ -140,3 +369,594 @@ pub struct AssignedProofChallenges<F: PrimeField, const D: usize> {
     pub plonk_zeta: AssignedExtensionFieldValue<F, D>,
     pub fri_challenges: AssignedFriChallenges<F, D>,
 }
+
+/// The value behind `value`, or `None` while it's unknown, e.g. during keygen.
+pub(crate) fn known<T>(value: Value<T>) -> Option<T> {
+    let mut known = None;
+    value.map(|value| known = Some(value));
+    known
+}
+
+fn witness<F: PrimeField>(value: &AssignedValue<F>) -> Option<GoldilocksField> {
+    known(value.value().map(|value| fe_to_goldilocks(*value)))
+}
+
+fn witnesses<F: PrimeField>(values: &[AssignedValue<F>]) -> Option<Vec<GoldilocksField>> {
+    values.iter().map(witness).collect()
+}
+
+fn witness_array<F: PrimeField, const N: usize>(
+    values: &[AssignedValue<F>; N],
+) -> Option<[GoldilocksField; N]> {
+    let mut elements = [GoldilocksField::ZERO; N];
+    for (element, value) in elements.iter_mut().zip(values.iter()) {
+        *element = witness(value)?;
+    }
+    Some(elements)
+}
+
+fn extension_witnesses<F: PrimeField, const D: usize>(
+    values: &[AssignedExtensionFieldValue<F, D>],
+) -> Option<Vec<ExtensionFieldValue<F, D>>> {
+    values
+        .iter()
+        .map(AssignedExtensionFieldValue::witness)
+        .collect()
+}
+
+// Witness extraction. Each `witness` returns the values the structure was assigned from, so
+// they can be snapshotted (`ProofValues` is serde) and assigned again without being recomputed
+// from the plonky2 proof. They are `None` when the witness is unknown.
+
+impl<F: PrimeField> AssignedHashValues<F> {
+    pub fn witness(&self) -> Option<HashValues<F>> {
+        witness_array(&self.elements).map(HashValues::new)
+    }
+}
+
+impl<F: PrimeField> AssignedMerkleCapValues<F> {
+    pub fn witness(&self) -> Option<MerkleCapValues<F>> {
+        let hashes = self.0.iter().map(AssignedHashValues::witness);
+        hashes.collect::<Option<Vec<_>>>().map(MerkleCapValues)
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedExtensionFieldValue<F, D> {
+    pub fn witness(&self) -> Option<ExtensionFieldValue<F, D>> {
+        witness_array(&self.0).map(ExtensionFieldValue::new)
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedOpeningSetValues<F, D> {
+    pub fn witness(&self) -> Option<OpeningSetValues<F, D>> {
+        Some(OpeningSetValues {
+            constants: extension_witnesses(&self.constants)?,
+            plonk_sigmas: extension_witnesses(&self.plonk_sigmas)?,
+            wires: extension_witnesses(&self.wires)?,
+            plonk_zs: extension_witnesses(&self.plonk_zs)?,
+            plonk_zs_next: extension_witnesses(self.plonk_zs_next.zs())?,
+            partial_products: extension_witnesses(&self.partial_products)?,
+            quotient_polys: extension_witnesses(&self.quotient_polys)?,
+        })
+    }
+}
+
+impl<F: PrimeField> AssignedMerkleProofValues<F> {
+    pub fn witness(&self) -> Option<MerkleProofValues<F>> {
+        let siblings = self.siblings.iter().map(AssignedHashValues::witness);
+        Some(MerkleProofValues {
+            siblings: siblings.collect::<Option<Vec<_>>>()?,
+        })
+    }
+}
+
+impl<F: PrimeField> AssignedFriInitialTreeProofValues<F> {
+    pub fn witness(&self) -> Option<FriInitialTreeProofValues<F>> {
+        let evals_proofs = self
+            .evals_proofs
+            .iter()
+            .map(|(evals, merkle_proof)| Some((witnesses(evals)?, merkle_proof.witness()?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(FriInitialTreeProofValues { evals_proofs })
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedFriQueryStepValues<F, D> {
+    pub fn witness(&self) -> Option<FriQueryStepValues<F, D>> {
+        Some(FriQueryStepValues {
+            evals: extension_witnesses(&self.evals)?,
+            merkle_proof: self.merkle_proof.witness()?,
+        })
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedFriQueryRoundValues<F, D> {
+    pub fn witness(&self) -> Option<FriQueryRoundValues<F, D>> {
+        let steps = self.steps.iter().map(AssignedFriQueryStepValues::witness);
+        Some(FriQueryRoundValues {
+            initial_trees_proof: self.initial_trees_proof.witness()?,
+            steps: steps.collect::<Option<Vec<_>>>()?,
+        })
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedFriProofValues<F, D> {
+    /// Only holds the query rounds that were assigned with the proof, so none after
+    /// `FriProofValues::assign_without_query_rounds`.
+    pub fn witness(&self) -> Option<FriProofValues<F, D>> {
+        let commit_phase_merkle_cap_values = self
+            .commit_phase_merkle_cap_values
+            .iter()
+            .map(AssignedMerkleCapValues::witness)
+            .collect::<Option<Vec<_>>>()?;
+        let query_round_proofs = self
+            .query_round_proofs
+            .iter()
+            .map(AssignedFriQueryRoundValues::witness)
+            .collect::<Option<Vec<_>>>()?;
+        Some(FriProofValues {
+            commit_phase_merkle_cap_values,
+            query_round_proofs,
+            final_poly: PolynomialCoeffsExtValues(extension_witnesses(&self.final_poly.0)?),
+            pow_witness: witness(&self.pow_witness)?,
+        })
+    }
+}
+
+impl<F: PrimeField, const D: usize> AssignedProofValues<F, D> {
+    pub fn witness(&self) -> Option<ProofValues<F, D>> {
+        Some(ProofValues {
+            wires_cap: self.wires_cap.witness()?,
+            plonk_zs_partial_products_cap: self.plonk_zs_partial_products_cap.witness()?,
+            quotient_polys_cap: self.quotient_polys_cap.witness()?,
+            openings: self.openings.witness()?,
+            opening_proof: self.opening_proof.witness()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{
+            extension::quadratic::QuadraticExtension,
+            goldilocks_field::GoldilocksField,
+            types::{Field, PrimeField64},
+        },
+        plonk::proof::Proof,
+        util::reverse_bits,
+    };
+
+    use super::{AssignedExtensionFieldValue, AssignedHashValues, ShapeError};
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::{fe_to_goldilocks, goldilocks_to_fe},
+            },
+        },
+        context::RegionCtx,
+        fixtures::{
+            generate_hiding_proof_tuple, generate_minimal_proof_tuple,
+            generate_no_partial_products_proof_tuple, generate_proof_tuple,
+            generate_tiny_proof_tuple, mock_verifier,
+        },
+        types::{
+            common_data::CommonData,
+            fri::FriInstanceInfo,
+            proof::{OpeningSetValues, ProofValues},
+            ExtensionFieldValue,
+        },
+        verifier_circuit::ProofTuple,
+        witness_trace::WitnessTrace,
+    };
+
+    type FE = QuadraticExtension<GoldilocksField>;
+
+    #[derive(Clone, Default)]
+    struct TestCircuit;
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "assigned constructors",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let values = (0..5)
+                        .map(|i| goldilocks_chip.assign_value(ctx, Value::known(Fr::from(i))))
+                        .collect::<Result<Vec<_>, Error>>()?;
+
+                    let hash = AssignedHashValues::from_vec(values[..4].to_vec()).unwrap();
+                    assert_eq!(hash.iter().count(), 4);
+                    assert_eq!(
+                        AssignedHashValues::from_vec(values.clone()).err(),
+                        Some(ShapeError {
+                            expected: 4,
+                            actual: 5
+                        })
+                    );
+
+                    let ext = AssignedExtensionFieldValue::<Fr, 2>::from_vec(values[..2].to_vec())
+                        .unwrap();
+                    goldilocks_chip.assert_equal(ctx, ext.real(), &values[0])?;
+                    goldilocks_chip.assert_equal(ctx, ext.imag(), &values[1])?;
+                    assert_eq!(
+                        AssignedExtensionFieldValue::<Fr, 2>::from_vec(values[..1].to_vec()).err(),
+                        Some(ShapeError {
+                            expected: 2,
+                            actual: 1
+                        })
+                    );
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fallible_constructors() {
+        let mock_prover = mock_prover_at_min_k(&SmallTable(TestCircuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    fn native_extension(value: &AssignedExtensionFieldValue<Fr, 2>) -> FE {
+        let mut limbs = [GoldilocksField::ZERO; 2];
+        for (limb, assigned) in limbs.iter_mut().zip(value.limbs().iter()) {
+            assigned.value().map(|v| *limb = fe_to_goldilocks(*v));
+        }
+        QuadraticExtension(limbs)
+    }
+
+    fn reduce(values: &[FE], alpha: FE) -> FE {
+        values
+            .iter()
+            .rev()
+            .fold(FE::ZERO, |acc, value| acc * alpha + *value)
+    }
+
+    /// Batches the openings of a plonky2 proof with `to_fri_openings` and replays plonky2's
+    /// `fri_combine_initial` on them. The prover built its FRI polynomial from
+    /// `OpeningSet::to_fri_openings`, so the combination only matches the first folded
+    /// evaluation of every query if both group the openings the same way.
+    #[derive(Clone)]
+    struct FriOpeningsCircuit {
+        proof: Proof<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>,
+        common_data: CommonData<Fr>,
+        trace: WitnessTrace,
+        /// `(oracle_index, polynomial_index)` of the polynomials plonky2 opens at `g * zeta`.
+        plonky2_zeta_next_polys: Vec<(usize, usize)>,
+    }
+
+    impl FriOpeningsCircuit {
+        fn new(proof_tuple: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>) -> Self {
+            let (verifier, _) = mock_verifier(proof_tuple.clone());
+            let common_data = verifier.common_data().clone();
+            let trace = verifier.witness_trace().unwrap();
+            let (proof_with_public_inputs, _, cd) = proof_tuple;
+            let proof = proof_with_public_inputs.proof;
+            // the second batch of plonky2's own FRI instance is the one opened at `g * zeta`
+            let zeta = QuadraticExtension(trace.challenges.plonk_zeta);
+            let plonky2_zeta_next_polys = cd.get_fri_instance(zeta).batches[1]
+                .polynomials
+                .iter()
+                .map(|poly| (poly.oracle_index, poly.polynomial_index))
+                .collect();
+            Self {
+                proof,
+                common_data,
+                trace,
+                plonky2_zeta_next_polys,
+            }
+        }
+
+        /// Combines `batches` over the polynomials of the matching batches of `instance`.
+        fn check_combined_openings(&self, batches: &[Vec<FE>], instance: &FriInstanceInfo<Fr, 2>) {
+            let common_data = &self.common_data;
+            let fri_params = &common_data.fri_params;
+            let lde_bits = fri_params.lde_bits();
+            let alpha = QuadraticExtension(self.trace.challenges.fri_alpha);
+            let zeta = QuadraticExtension(self.trace.challenges.plonk_zeta);
+            let g = GoldilocksField::primitive_root_of_unity(common_data.degree_bits());
+            let points = [zeta, QuadraticExtension([g, GoldilocksField::ZERO]) * zeta];
+            let polys = instance
+                .batches
+                .iter()
+                .map(|batch| batch.polynomials.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(batches.len(), polys.len());
+
+            let opening_proof = &self.proof.opening_proof;
+            for (round_proof, index) in opening_proof
+                .query_round_proofs
+                .iter()
+                .zip(self.trace.challenges.fri_query_indices.iter())
+            {
+                let x_index = (index.to_canonical_u64() % (1 << lde_bits)) as usize;
+                let subgroup_x = GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR
+                    * GoldilocksField::primitive_root_of_unity(lde_bits)
+                        .exp_u64(reverse_bits(x_index, lde_bits) as u64);
+                let subgroup_x = QuadraticExtension([subgroup_x, GoldilocksField::ZERO]);
+
+                let mut sum = FE::ZERO;
+                for ((openings, polys), point) in batches.iter().zip(polys.iter()).zip(points) {
+                    assert_eq!(openings.len(), polys.len());
+                    // salts are appended to the leaves, so they never shift a polynomial
+                    let evals = polys
+                        .iter()
+                        .map(|poly| {
+                            let leaf =
+                                &round_proof.initial_trees_proof.evals_proofs[poly.oracle_index].0;
+                            QuadraticExtension([leaf[poly.polynomial_index], GoldilocksField::ZERO])
+                        })
+                        .collect::<Vec<_>>();
+                    let numerator = reduce(&evals, alpha) - reduce(openings, alpha);
+                    sum =
+                        sum * alpha.exp_u64(polys.len() as u64) + numerator / (subgroup_x - point);
+                }
+
+                let expected = match fri_params.reduction_arity_bits.first() {
+                    Some(arity_bits) => {
+                        round_proof.steps[0].evals[x_index & ((1 << arity_bits) - 1)]
+                    }
+                    None => opening_proof.final_poly.eval(subgroup_x),
+                };
+                assert_eq!(sum, expected);
+            }
+        }
+    }
+
+    impl Circuit<Fr> for FriOpeningsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "fri openings",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    // the points only matter to the native combination, which uses its own
+                    let zeta = self.trace.challenges.plonk_zeta.map(|e| {
+                        goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(e)))
+                    });
+                    let [real, imag] = zeta;
+                    let zeta = AssignedExtensionFieldValue::new([real?, imag?]);
+                    let instance = FriInstanceInfo::new(&zeta, &zeta, &self.common_data);
+                    let zeta_next_polys = instance.batches[1]
+                        .polynomials
+                        .iter()
+                        .map(|poly| (poly.oracle_index, poly.polynomial_index))
+                        .collect::<Vec<_>>();
+                    assert_eq!(zeta_next_polys, self.plonky2_zeta_next_polys);
+
+                    let openings = OpeningSetValues::from(self.proof.openings.clone());
+                    let assigned = OpeningSetValues::assign(&config, ctx, &openings)?;
+                    let batches = assigned
+                        .to_fri_openings(&self.common_data)?
+                        .batches
+                        .iter()
+                        .map(|batch| batch.values.iter().map(native_extension).collect())
+                        .collect::<Vec<Vec<FE>>>();
+                    assert_eq!(batches[1].len(), zeta_next_polys.len());
+                    self.check_combined_openings(&batches, &instance);
+
+                    let mut missing_wire = openings;
+                    missing_wire.wires.pop();
+                    let assigned = OpeningSetValues::assign(&config, ctx, &missing_wire)?;
+                    let num_wires = self.common_data.config.num_wires;
+                    assert_eq!(
+                        assigned.to_fri_openings(&self.common_data).err(),
+                        Some(ShapeError {
+                            expected: num_wires,
+                            actual: num_wires - 1
+                        })
+                    );
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fri_openings_match_plonky2_batches() {
+        for proof_tuple in [
+            generate_proof_tuple(),
+            generate_tiny_proof_tuple(),
+            generate_hiding_proof_tuple(),
+            generate_minimal_proof_tuple(),
+            generate_no_partial_products_proof_tuple(),
+        ] {
+            let circuit = FriOpeningsCircuit::new(proof_tuple);
+            let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+            mock_prover.assert_satisfied();
+        }
+    }
+
+    /// Assigns a whole proof, query rounds included, and snapshots its witness.
+    #[derive(Clone)]
+    struct WitnessSnapshotCircuit {
+        proof: ProofValues<Fr, 2>,
+    }
+
+    impl Circuit<Fr> for WitnessSnapshotCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "witness snapshot",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let assigned = ProofValues::assign(&config, ctx, &self.proof)?;
+                    // the witness is unknown while the floor planner measures the region
+                    let json = assigned
+                        .witness()
+                        .map(|snapshot| serde_json::to_string(&snapshot).unwrap());
+                    let cached: ProofValues<Fr, 2> = match &json {
+                        Some(json) => {
+                            assert_eq!(*json, serde_json::to_string(&self.proof).unwrap());
+                            serde_json::from_str(json).unwrap()
+                        }
+                        None => self.proof.clone(),
+                    };
+
+                    // the cells assigned from the cached values hold the same witness
+                    let reassigned = ProofValues::assign(&config, ctx, &cached)?;
+                    let reassigned_json = reassigned
+                        .witness()
+                        .map(|snapshot| serde_json::to_string(&snapshot).unwrap());
+                    assert_eq!(reassigned_json, json);
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_witness_snapshot_reproduces_assignments() {
+        let (proof_with_public_inputs, _, _) = generate_tiny_proof_tuple();
+        let circuit = WitnessSnapshotCircuit {
+            proof: ProofValues::from(proof_with_public_inputs.proof),
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+    }
+
+    /// Assigns the same extension values one by one and with `assign_many`, constraining the
+    /// two assignments equal.
+    #[derive(Clone)]
+    struct AssignManyCircuit {
+        values: Vec<ExtensionFieldValue<Fr, 2>>,
+        /// Rows taken by each assignment.
+        rows: Cell<[usize; 2]>,
+    }
+
+    impl Circuit<Fr> for AssignManyCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "assign many",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let one_by_one = self
+                        .values
+                        .iter()
+                        .map(|value| ExtensionFieldValue::assign(&config, ctx, value))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let one_by_one_rows = ctx.offset();
+                    let many = ExtensionFieldValue::assign_many(&config, ctx, &self.values)?;
+                    self.rows
+                        .set([one_by_one_rows, ctx.offset() - one_by_one_rows]);
+
+                    assert_eq!(many.len(), one_by_one.len());
+                    for (a, b) in one_by_one.iter().zip(many.iter()) {
+                        for (a, b) in a.limbs().iter().zip(b.limbs().iter()) {
+                            goldilocks_chip.assert_equal(ctx, a, b)?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_many_matches_assign() {
+        let (proof_with_public_inputs, _, _) = generate_tiny_proof_tuple();
+        let openings = OpeningSetValues::<Fr, 2>::from(proof_with_public_inputs.proof.openings);
+        // the repeated wire openings are assigned once per occurrence
+        let values = [
+            openings.wires.clone(),
+            openings.wires,
+            openings.quotient_polys,
+        ]
+        .concat();
+        let circuit = SmallTable(AssignManyCircuit {
+            values,
+            rows: Cell::new([0; 2]),
+        });
+        let mock_prover = mock_prover_at_min_k(&circuit, vec![vec![]]).unwrap();
+        mock_prover.assert_satisfied();
+        let [one_by_one, many] = circuit.0.rows.get();
+        assert_eq!(many, one_by_one);
+        assert_eq!(many, 2 * circuit.0.values.len());
+    }
+}
@@ -1,12 +1,24 @@
-use std::ops::{Range, RangeFrom};
+use std::{
+    fmt, io,
+    ops::{Range, RangeFrom},
+};
 
-use crate::plonky2_verifier::{chip::plonk::gates::CustomGateRef, types::fri::FriOracleInfo};
+use crate::plonky2_verifier::{
+    chip::plonk::gates::CustomGateRef,
+    transcript_schedule::{transcript_schedule, ChallengerSeed, ChallengerSeeds, TranscriptEvent},
+    types::fri::FriOracleInfo,
+};
 
-use super::{fri::FriPolynomialInfo, to_goldilocks};
-use halo2_proofs::halo2curves::ff::PrimeField;
-use plonky2::{field::goldilocks_field::GoldilocksField, plonk::circuit_data::CommonCircuitData};
+use super::{fri::FriPolynomialInfo, FormatVersion};
+use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    gates::gate::GateRef,
+    plonk::circuit_data::CommonCircuitData,
+};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct FriConfig {
     /// `rate = 2^{-rate_bits}`.
     pub rate_bits: usize,
@@ -20,7 +32,7 @@ pub struct FriConfig {
     pub num_query_rounds: usize,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct CircuitConfig {
     pub num_wires: usize,
     pub num_routed_wires: usize,
@@ -39,7 +51,7 @@ pub struct CircuitConfig {
     pub fri_config: FriConfig,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct FriParams {
     pub config: FriConfig,
     pub hiding: bool,
@@ -51,9 +63,17 @@ impl FriParams {
     pub fn lde_bits(&self) -> usize {
         self.degree_bits + self.config.rate_bits
     }
+
+    pub fn total_arities(&self) -> usize {
+        self.reduction_arity_bits.iter().sum()
+    }
+
+    pub fn final_poly_len(&self) -> usize {
+        1 << (self.degree_bits - self.total_arities())
+    }
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct SelectorsInfo {
     pub selector_indices: Vec<usize>,
     pub groups: Vec<Range<usize>>,
@@ -63,10 +83,217 @@ impl SelectorsInfo {
     pub fn num_selectors(&self) -> usize {
         self.groups.len()
     }
+
+    /// `(gate index, selector index)` of every gate, group by group. plonky2 lists the gates of
+    /// a group contiguously but does not promise the groups come in gate order, so the mapping
+    /// is checked in both directions instead of derived from positions: every gate points to an
+    /// existing group containing it, and the groups hold no other gates.
+    pub fn gate_selectors(&self, num_gates: usize) -> Result<Vec<(usize, usize)>, CommonDataError> {
+        if self.selector_indices.len() != num_gates {
+            return Err(CommonDataError::SelectorCountMismatch {
+                expected: num_gates,
+                actual: self.selector_indices.len(),
+            });
+        }
+        for (gate, &selector_index) in self.selector_indices.iter().enumerate() {
+            let group = self.groups.get(selector_index).ok_or(
+                CommonDataError::SelectorIndexOutOfRange {
+                    gate,
+                    selector_index,
+                    num_selectors: self.num_selectors(),
+                },
+            )?;
+            if !group.contains(&gate) {
+                return Err(CommonDataError::GateOutsideSelectorGroup {
+                    gate,
+                    selector_index,
+                    group: group.clone(),
+                });
+            }
+        }
+        // each gate lies in its own group, so the groups hold exactly the gates pointing to
+        // them iff their sizes add up
+        let grouped = self.groups.iter().map(|group| group.len()).sum::<usize>();
+        if grouped != num_gates {
+            return Err(CommonDataError::GroupSizeMismatch {
+                expected: num_gates,
+                actual: grouped,
+            });
+        }
+        Ok(self
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(selector_index, group)| {
+                group.clone().map(move |gate| (gate, selector_index))
+            })
+            .collect())
+    }
+}
+
+/// Common data the verifier circuit would mis-handle: circuit shapes plonky2 cannot produce,
+/// or gates whose constrainer disagrees with plonky2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommonDataError {
+    /// The Merkle cap is taller than the smallest tree committed to in FRI.
+    CapHeightExceedsTreeHeight {
+        cap_height: usize,
+        tree_height: usize,
+    },
+    /// The FRI reductions fold more bits than the LDE domain has.
+    ReductionExceedsDomain {
+        total_arities: usize,
+        lde_bits: usize,
+    },
+    /// A plonky2 gate, named by its id, has no in-circuit constrainer.
+    UnsupportedGate(String),
+    /// A gate constrainer emits a different number of constraints than its plonky2 gate.
+    GateConstraintCountMismatch {
+        gate: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// A gate constrainer has a different degree than its plonky2 gate.
+    GateDegreeMismatch {
+        gate: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// A gate's constraints are of higher degree than the quotient polynomial is split for, so
+    /// the quotient chunks could not absorb them.
+    GateDegreeExceedsQuotientDegree {
+        gate: String,
+        degree: usize,
+        quotient_degree_factor: usize,
+    },
+    /// The gates were not converted one to one, so selectors would filter the wrong constrainer.
+    GateCountMismatch { expected: usize, actual: usize },
+    /// The constrainer at `index` mirrors a different gate, or the same gate with different
+    /// parameters.
+    GateMismatch {
+        index: usize,
+        expected: String,
+        actual: String,
+    },
+    /// `selector_indices` does not have one entry per gate.
+    SelectorCountMismatch { expected: usize, actual: usize },
+    /// A gate points to a selector group that does not exist.
+    SelectorIndexOutOfRange {
+        gate: usize,
+        selector_index: usize,
+        num_selectors: usize,
+    },
+    /// A gate points to a selector group whose range does not contain it.
+    GateOutsideSelectorGroup {
+        gate: usize,
+        selector_index: usize,
+        group: Range<usize>,
+    },
+    /// The selector groups hold more gates than the circuit has.
+    GroupSizeMismatch { expected: usize, actual: usize },
+    /// The challenger doesn't absorb this seed, so the challenges aren't bound to it and a
+    /// proof could be replayed for another verifying key or other public inputs.
+    MissingChallengerSeed(ChallengerSeed),
+}
+
+impl fmt::Display for CommonDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CapHeightExceedsTreeHeight {
+                cap_height,
+                tree_height,
+            } => write!(
+                f,
+                "cap height {cap_height} exceeds the height {tree_height} of the smallest FRI tree"
+            ),
+            Self::ReductionExceedsDomain {
+                total_arities,
+                lde_bits,
+            } => write!(
+                f,
+                "FRI reductions fold {total_arities} bits of a domain of {lde_bits} bits"
+            ),
+            Self::UnsupportedGate(id) => write!(f, "no constrainer for plonky2 gate {id}"),
+            Self::GateConstraintCountMismatch {
+                gate,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{gate} has {actual} constraints in circuit, expected {expected}"
+            ),
+            Self::GateDegreeMismatch {
+                gate,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{gate} has degree {actual} in circuit, expected {expected}"
+            ),
+            Self::GateDegreeExceedsQuotientDegree {
+                gate,
+                degree,
+                quotient_degree_factor,
+            } => write!(
+                f,
+                "{gate} has degree {degree}, above quotient degree factor {quotient_degree_factor}"
+            ),
+            Self::GateCountMismatch { expected, actual } => {
+                write!(f, "{actual} gate constrainers for {expected} plonky2 gates")
+            }
+            Self::GateMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "gate {index} is constrained as {actual}, expected {expected}"
+            ),
+            Self::SelectorCountMismatch { expected, actual } => {
+                write!(f, "{actual} selector indices for {expected} gates")
+            }
+            Self::SelectorIndexOutOfRange {
+                gate,
+                selector_index,
+                num_selectors,
+            } => write!(
+                f,
+                "gate {gate} uses selector {selector_index}, out of {num_selectors} selectors"
+            ),
+            Self::GateOutsideSelectorGroup {
+                gate,
+                selector_index,
+                group,
+            } => write!(
+                f,
+                "gate {gate} uses selector {selector_index}, whose group is {group:?}"
+            ),
+            Self::GroupSizeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "selector groups hold {actual} gates, expected {expected}"
+                )
+            }
+            Self::MissingChallengerSeed(seed) => {
+                write!(f, "the challenger is not seeded with the {seed:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommonDataError {}
+
+impl From<CommonDataError> for Error {
+    fn from(value: CommonDataError) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CommonData<F: PrimeField> {
+    pub format_version: FormatVersion,
+
     pub config: CircuitConfig,
 
     pub fri_params: FriParams,
@@ -74,6 +301,10 @@ pub struct CommonData<F: PrimeField> {
     /// The types of gates used in this circuit, along with their prefixes.
     pub gates: Vec<CustomGateRef<F>>,
 
+    /// plonky2 id of each gate, recorded when its constrainer was constructed. Selector groups
+    /// refer to gates by index, so the constrainers must stay in this order.
+    pub gate_ids: Vec<String>,
+
     /// Information on the circuit's selector polynomials.
     pub selectors_info: SelectorsInfo,
 
@@ -93,6 +324,11 @@ pub struct CommonData<F: PrimeField> {
 
     /// The number of partial products needed to compute the `Z` polynomials.
     pub num_partial_products: usize,
+
+    /// What the challenger absorbs before the proof, plonky2's order unless set with
+    /// `with_challenger_seeds`.
+    #[serde(default)]
+    pub challenger_seeds: ChallengerSeeds,
 }
 
 /// Holds the Merkle tree index and blinding flag of a set of polynomials used in FRI.
@@ -122,6 +358,315 @@ impl PlonkOracle {
 }
 
 impl<F: PrimeField> CommonData<F> {
+    /// Builds common data for a circuit that was never run through plonky2, deriving the
+    /// remaining fields the way `CircuitBuilder::build` does. All gates share one selector
+    /// group and are assumed to use every constant wire, so the selectors and constants differ
+    /// from what plonky2 would pick for the same gates.
+    pub fn new(
+        config: CircuitConfig,
+        degree_bits: usize,
+        reduction_arity_bits: Vec<usize>,
+        gates: Vec<CustomGateRef<F>>,
+        num_public_inputs: usize,
+    ) -> Self {
+        let quotient_degree_factor = config.max_quotient_degree_factor;
+        let num_gate_constraints = gates
+            .iter()
+            .map(|gate| gate.0.num_constraints())
+            .max()
+            .unwrap_or(0);
+        let gate_ids = gates.iter().map(|gate| gate.0.id()).collect();
+        let selectors_info = SelectorsInfo {
+            selector_indices: vec![0; gates.len()],
+            groups: vec![0..gates.len()],
+        };
+        let num_constants = selectors_info.num_selectors() + config.num_constants;
+        // `get_unique_coset_shifts`
+        let k_is = GoldilocksField::MULTIPLICATIVE_GROUP_GENERATOR
+            .powers()
+            .take(config.num_routed_wires)
+            .collect();
+        // `num_partial_products`: the last chunk of the permutation product is checked
+        // against `Z(gx)` itself
+        let num_partial_products = config.num_routed_wires.div_ceil(quotient_degree_factor) - 1;
+        Self {
+            format_version: FormatVersion,
+            fri_params: FriParams {
+                config: config.fri_config.clone(),
+                hiding: config.zero_knowledge,
+                degree_bits,
+                reduction_arity_bits,
+            },
+            config,
+            gates,
+            gate_ids,
+            selectors_info,
+            quotient_degree_factor,
+            num_gate_constraints,
+            num_constants,
+            num_public_inputs,
+            k_is,
+            num_partial_products,
+            challenger_seeds: ChallengerSeeds::default(),
+        }
+    }
+
+    /// Seeds the challenger like a prover whose challenger doesn't start as plonky2's, e.g. one
+    /// absorbing a domain separator first. The seeds must include the circuit digest and the
+    /// public inputs hash.
+    pub fn with_challenger_seeds(
+        mut self,
+        seeds: Vec<ChallengerSeed>,
+    ) -> Result<Self, CommonDataError> {
+        self.challenger_seeds = ChallengerSeeds(seeds);
+        self.check_challenger_seeds()?;
+        Ok(self)
+    }
+
+    /// Checks that the challenger absorbs the circuit digest and the public inputs hash, which
+    /// bind the challenges to the verifying key and the public inputs.
+    pub fn check_challenger_seeds(&self) -> Result<(), CommonDataError> {
+        for seed in [
+            ChallengerSeed::CircuitDigest,
+            ChallengerSeed::PublicInputsHash,
+        ] {
+            if !self.challenger_seeds.0.contains(&seed) {
+                return Err(CommonDataError::MissingChallengerSeed(seed));
+            }
+        }
+        Ok(())
+    }
+
+    /// Mirrors plonky2's rule that every tree committed to in FRI, down to the last
+    /// commit phase tree, has at least `cap_height` levels, and checks the selector mapping
+    /// before any gate is filtered with it and the gate degrees against the quotient degree
+    /// factor, and the challenger seeds with `check_challenger_seeds`.
+    pub fn validate(&self) -> Result<(), CommonDataError> {
+        let lde_bits = self.fri_params.lde_bits();
+        let total_arities = self.fri_params.total_arities();
+        let tree_height =
+            lde_bits
+                .checked_sub(total_arities)
+                .ok_or(CommonDataError::ReductionExceedsDomain {
+                    total_arities,
+                    lde_bits,
+                })?;
+        let cap_height = self.fri_params.config.cap_height;
+        if cap_height > tree_height {
+            return Err(CommonDataError::CapHeightExceedsTreeHeight {
+                cap_height,
+                tree_height,
+            });
+        }
+        self.selectors_info.gate_selectors(self.gates.len())?;
+        self.check_gate_degrees()?;
+        self.check_challenger_seeds()?;
+        Ok(())
+    }
+
+    /// Checks that the constraints of every gate fit in `quotient_degree_factor`, which plonky2
+    /// guarantees for its own gates but not for constrainers registered outside of it.
+    pub fn check_gate_degrees(&self) -> Result<(), CommonDataError> {
+        for gate in self.gates.iter() {
+            let degree = gate.0.max_degree();
+            if degree > self.quotient_degree_factor {
+                return Err(CommonDataError::GateDegreeExceedsQuotientDegree {
+                    gate: gate.0.id(),
+                    degree,
+                    quotient_degree_factor: self.quotient_degree_factor,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `gates` holds one constrainer per plonky2 gate, in the same order. Gates of
+    /// the same type with different parameters (e.g. two `RandomAccessGate`s) have distinct
+    /// ids, so they must stay distinct entries.
+    pub fn check_gates(
+        &self,
+        gates: &[GateRef<GoldilocksField, 2>],
+    ) -> Result<(), CommonDataError> {
+        let gate_ids = gates
+            .iter()
+            .map(|gate| gate.0.id().trim_end().to_string())
+            .collect::<Vec<_>>();
+        self.check_gate_ids(&gate_ids)
+    }
+
+    /// Lists the fields `self` and `other` differ in as `field: self != other`, e.g. to compare
+    /// the common data passed to the verifier with the one of the circuit that produced the
+    /// proof. Empty if they match.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut diff = vec![];
+        let mut compare = |field: &str, a: &dyn fmt::Debug, b: &dyn fmt::Debug| {
+            let (a, b) = (format!("{a:?}"), format!("{b:?}"));
+            if a != b {
+                diff.push(format!("{field}: {a} != {b}"));
+            }
+        };
+
+        let (config, other_config) = (&self.config, &other.config);
+        compare(
+            "config.num_wires",
+            &config.num_wires,
+            &other_config.num_wires,
+        );
+        compare(
+            "config.num_routed_wires",
+            &config.num_routed_wires,
+            &other_config.num_routed_wires,
+        );
+        compare(
+            "config.num_constants",
+            &config.num_constants,
+            &other_config.num_constants,
+        );
+        compare(
+            "config.use_base_arithmetic_gate",
+            &config.use_base_arithmetic_gate,
+            &other_config.use_base_arithmetic_gate,
+        );
+        compare(
+            "config.security_bits",
+            &config.security_bits,
+            &other_config.security_bits,
+        );
+        compare(
+            "config.num_challenges",
+            &config.num_challenges,
+            &other_config.num_challenges,
+        );
+        compare(
+            "config.zero_knowledge",
+            &config.zero_knowledge,
+            &other_config.zero_knowledge,
+        );
+        compare(
+            "config.max_quotient_degree_factor",
+            &config.max_quotient_degree_factor,
+            &other_config.max_quotient_degree_factor,
+        );
+        compare(
+            "config.fri_config",
+            &config.fri_config,
+            &other_config.fri_config,
+        );
+
+        let (fri_params, other_fri_params) = (&self.fri_params, &other.fri_params);
+        let (fri_config, other_fri_config) = (&fri_params.config, &other_fri_params.config);
+        compare(
+            "fri_params.config.rate_bits",
+            &fri_config.rate_bits,
+            &other_fri_config.rate_bits,
+        );
+        compare(
+            "fri_params.config.cap_height",
+            &fri_config.cap_height,
+            &other_fri_config.cap_height,
+        );
+        compare(
+            "fri_params.config.proof_of_work_bits",
+            &fri_config.proof_of_work_bits,
+            &other_fri_config.proof_of_work_bits,
+        );
+        compare(
+            "fri_params.config.num_query_rounds",
+            &fri_config.num_query_rounds,
+            &other_fri_config.num_query_rounds,
+        );
+        compare(
+            "fri_params.hiding",
+            &fri_params.hiding,
+            &other_fri_params.hiding,
+        );
+        compare(
+            "fri_params.degree_bits",
+            &fri_params.degree_bits,
+            &other_fri_params.degree_bits,
+        );
+        compare(
+            "fri_params.reduction_arity_bits",
+            &fri_params.reduction_arity_bits,
+            &other_fri_params.reduction_arity_bits,
+        );
+
+        // gate by gate, a missing gate would shift the whole list
+        compare(
+            "gate_ids.len()",
+            &self.gate_ids.len(),
+            &other.gate_ids.len(),
+        );
+        for (i, (id, other_id)) in self.gate_ids.iter().zip(other.gate_ids.iter()).enumerate() {
+            compare(&format!("gate_ids[{i}]"), id, other_id);
+        }
+        compare(
+            "selectors_info.selector_indices",
+            &self.selectors_info.selector_indices,
+            &other.selectors_info.selector_indices,
+        );
+        compare(
+            "selectors_info.groups",
+            &self.selectors_info.groups,
+            &other.selectors_info.groups,
+        );
+        compare(
+            "quotient_degree_factor",
+            &self.quotient_degree_factor,
+            &other.quotient_degree_factor,
+        );
+        compare(
+            "num_gate_constraints",
+            &self.num_gate_constraints,
+            &other.num_gate_constraints,
+        );
+        compare("num_constants", &self.num_constants, &other.num_constants);
+        compare(
+            "num_public_inputs",
+            &self.num_public_inputs,
+            &other.num_public_inputs,
+        );
+        compare("k_is", &self.k_is, &other.k_is);
+        compare(
+            "num_partial_products",
+            &self.num_partial_products,
+            &other.num_partial_products,
+        );
+        compare(
+            "challenger_seeds",
+            &self.challenger_seeds,
+            &other.challenger_seeds,
+        );
+        diff
+    }
+
+    /// Checks that the constrainers are still in the order of the recorded `gate_ids`, e.g.
+    /// after `gates` was modified or deserialized.
+    pub fn check_gate_order(&self) -> Result<(), CommonDataError> {
+        self.check_gate_ids(&self.gate_ids)
+    }
+
+    fn check_gate_ids(&self, gate_ids: &[String]) -> Result<(), CommonDataError> {
+        if self.gates.len() != gate_ids.len() {
+            return Err(CommonDataError::GateCountMismatch {
+                expected: gate_ids.len(),
+                actual: self.gates.len(),
+            });
+        }
+        for (index, (constrainer, expected)) in self.gates.iter().zip(gate_ids.iter()).enumerate() {
+            let expected = expected.clone();
+            let actual = constrainer.0.id();
+            if actual != expected {
+                return Err(CommonDataError::GateMismatch {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub const fn degree_bits(&self) -> usize {
         self.fri_params.degree_bits
     }
@@ -199,6 +744,25 @@ impl<F: PrimeField> CommonData<F> {
         .concat()
     }
 
+    /// Number of field elements the verifier writes to the transcript, from the circuit
+    /// digest up to the proof-of-work witness.
+    pub fn num_transcript_observations(&self) -> usize {
+        transcript_schedule(self)
+            .iter()
+            .map(TranscriptEvent::num_observations)
+            .sum()
+    }
+
+    /// Number of challenges the verifier squeezes from the transcript.
+    pub fn num_transcript_challenges(&self) -> usize {
+        transcript_schedule(self)
+            .iter()
+            .map(TranscriptEvent::num_challenges)
+            .sum()
+    }
+
+    /// Oracles opened in FRI, in commitment order. Whether an oracle is actually salted also
+    /// depends on `fri_params.hiding`, see `FriOracleInfo::salted`.
     pub fn fri_oracles(&self) -> Vec<FriOracleInfo> {
         vec![
             FriOracleInfo {
@@ -221,9 +785,21 @@ impl<F: PrimeField> CommonData<F> {
     }
 }
 
+/// Panics on common data `CommonData::from_plonky2` rejects.
 impl<F: PrimeField> From<CommonCircuitData<GoldilocksField, 2>> for CommonData<F> {
     fn from(value: CommonCircuitData<GoldilocksField, 2>) -> Self {
-        Self {
+        Self::from_plonky2(value).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl<F: PrimeField> CommonData<F> {
+    /// Converts plonky2's common data, failing on gates the verifier has no constrainer for,
+    /// or whose constrainers don't match them.
+    pub fn from_plonky2(
+        value: CommonCircuitData<GoldilocksField, 2>,
+    ) -> Result<Self, CommonDataError> {
+        let common_data = Self {
+            format_version: FormatVersion,
             config: CircuitConfig {
                 num_wires: value.config.num_wires,
                 num_routed_wires: value.config.num_routed_wires,
@@ -243,7 +819,12 @@ impl<F: PrimeField> From<CommonCircuitData<GoldilocksField, 2>> for CommonData<F
             gates: value
                 .gates
                 .iter()
-                .map(|gate| CustomGateRef::from(gate))
+                .map(CustomGateRef::try_from)
+                .collect::<Result<_, _>>()?,
+            gate_ids: value
+                .gates
+                .iter()
+                .map(|gate| gate.0.id().trim_end().to_string())
                 .collect(),
             fri_params: FriParams {
                 config: FriConfig {
@@ -264,8 +845,436 @@ impl<F: PrimeField> From<CommonCircuitData<GoldilocksField, 2>> for CommonData<F
             num_gate_constraints: value.num_gate_constraints,
             num_constants: value.num_constants,
             num_public_inputs: value.num_public_inputs,
-            k_is: value.k_is.iter().map(|e| to_goldilocks(*e)).collect(),
+            k_is: value.k_is,
             num_partial_products: value.num_partial_products,
+            challenger_seeds: ChallengerSeeds::default(),
+        };
+        common_data.check_gate_order()?;
+        common_data.check_gate_degrees()?;
+        Ok(common_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitConfig, CommonData, CommonDataError, FriConfig, SelectorsInfo};
+    use crate::plonky2_verifier::{
+        chip::plonk::gates::CustomGateRef,
+        fixtures::{
+            generate_hiding_proof_tuple, generate_proof_tuple, generate_tiny_proof_tuple,
+            generate_two_random_access_gates_proof_tuple,
+        },
+        transcript_schedule::{ChallengerSeed, ChallengerSeeds},
+        types::verification_key::VerificationKeyValues,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+    #[test]
+    fn test_common_data_serde_round_trip() {
+        let (_, vd, cd) = generate_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let json = serde_json::to_string(&common_data).unwrap();
+        let decoded: CommonData<Fr> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        assert_eq!(decoded.k_is, common_data.k_is);
+        assert_eq!(
+            decoded.fri_params.lde_bits(),
+            common_data.fri_params.lde_bits()
+        );
+        assert_eq!(
+            decoded.gates.iter().map(|g| g.0.id()).collect::<Vec<_>>(),
+            common_data
+                .gates
+                .iter()
+                .map(|g| g.0.id())
+                .collect::<Vec<_>>()
+        );
+
+        let vk = VerificationKeyValues::<Fr>::from(vd);
+        let json = serde_json::to_string(&vk).unwrap();
+        let decoded: VerificationKeyValues<Fr> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.circuit_digest.elements, vk.circuit_digest.elements);
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_fri_oracles_match_hiding_proof_leaves() {
+        let (proof, _, cd) = generate_hiding_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let hiding = common_data.fri_params.hiding;
+        assert!(hiding);
+
+        let oracles = common_data.fri_oracles();
+        // the preprocessed oracle is never salted, the others are in a hiding proof
+        let salted = oracles
+            .iter()
+            .map(|oracle| oracle.salted(hiding))
+            .collect::<Vec<_>>();
+        assert_eq!(salted, [false, true, true, true]);
+        for round in proof.proof.opening_proof.query_round_proofs.iter() {
+            let leaves = &round.initial_trees_proof.evals_proofs;
+            assert_eq!(leaves.len(), oracles.len());
+            for ((leaf, _), oracle) in leaves.iter().zip(oracles.iter()) {
+                assert_eq!(leaf.len(), oracle.leaf_len(hiding));
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_cap_height() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let mut common_data = CommonData::<Fr>::from(cd);
+        assert_eq!(common_data.validate(), Ok(()));
+
+        let fri_params = &common_data.fri_params;
+        let tree_height = fri_params.lde_bits() - fri_params.total_arities();
+        common_data.fri_params.config.cap_height = tree_height + 1;
+        assert_eq!(
+            common_data.validate(),
+            Err(CommonDataError::CapHeightExceedsTreeHeight {
+                cap_height: tree_height + 1,
+                tree_height,
+            })
+        );
+    }
+
+    #[test]
+    fn test_challenger_seeds_bind_digest_and_public_inputs() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let domain_separator = ChallengerSeed::Elements(vec![GoldilocksField::ONE]);
+        assert!(common_data
+            .clone()
+            .with_challenger_seeds(vec![
+                domain_separator.clone(),
+                ChallengerSeed::CircuitDigest,
+                ChallengerSeed::PublicInputsHash,
+            ])
+            .is_ok());
+
+        for (seeds, missing) in [
+            (vec![], ChallengerSeed::CircuitDigest),
+            (
+                vec![domain_separator, ChallengerSeed::PublicInputsHash],
+                ChallengerSeed::CircuitDigest,
+            ),
+            (
+                vec![ChallengerSeed::CircuitDigest],
+                ChallengerSeed::PublicInputsHash,
+            ),
+        ] {
+            assert_eq!(
+                common_data
+                    .clone()
+                    .with_challenger_seeds(seeds.clone())
+                    .err(),
+                Some(CommonDataError::MissingChallengerSeed(missing.clone()))
+            );
+            // deserialized common data skips `with_challenger_seeds`
+            let mut deserialized = common_data.clone();
+            deserialized.challenger_seeds = ChallengerSeeds(seeds);
+            assert_eq!(
+                deserialized.validate(),
+                Err(CommonDataError::MissingChallengerSeed(missing))
+            );
         }
     }
+
+    #[test]
+    fn test_check_gates_keeps_gate_parameters() {
+        let (_, _, cd) = generate_two_random_access_gates_proof_tuple();
+        let mut common_data = CommonData::<Fr>::from(cd.clone());
+        assert_eq!(common_data.check_gates(&cd.gates), Ok(()));
+
+        // swapping the two random access constrainers keeps the gate types but not the parameters
+        let random_access = common_data
+            .gates
+            .iter()
+            .enumerate()
+            .filter(|(_, gate)| gate.0.id().starts_with("RandomAccessGate"))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let [first, second] = random_access[..] else {
+            panic!("expected two random access gates, got {random_access:?}");
+        };
+        common_data.gates.swap(first, second);
+        assert_eq!(
+            common_data.check_gates(&cd.gates),
+            Err(CommonDataError::GateMismatch {
+                index: first,
+                expected: cd.gates[first].0.id().trim_end().to_string(),
+                actual: cd.gates[second].0.id().trim_end().to_string(),
+            })
+        );
+
+        common_data.gates.pop();
+        assert_eq!(
+            common_data.check_gates(&cd.gates),
+            Err(CommonDataError::GateCountMismatch {
+                expected: cd.gates.len(),
+                actual: cd.gates.len() - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_gate_order_catches_shuffled_gates() {
+        let (_, _, cd) = generate_proof_tuple();
+        let mut common_data = CommonData::<Fr>::from(cd.clone());
+        assert_eq!(common_data.check_gate_order(), Ok(()));
+        assert_eq!(
+            common_data.gate_ids,
+            cd.gates
+                .iter()
+                .map(|gate| gate.0.id().trim_end().to_string())
+                .collect::<Vec<_>>()
+        );
+
+        // e.g. a registry handing out the constrainers in registration order
+        let last = common_data.gates.len() - 1;
+        common_data.gates.swap(0, last);
+        assert_eq!(
+            common_data.check_gate_order(),
+            Err(CommonDataError::GateMismatch {
+                index: 0,
+                expected: common_data.gate_ids[0].clone(),
+                actual: common_data.gate_ids[last].clone(),
+            })
+        );
+
+        // the order survives a serde round trip, so a reordered file is caught as well
+        let json = serde_json::to_string(&common_data).unwrap();
+        let decoded: CommonData<Fr> = serde_json::from_str(&json).unwrap();
+        assert!(decoded.check_gate_order().is_err());
+    }
+
+    #[test]
+    fn test_gate_selectors_follow_groups_not_positions() {
+        let (_, _, cd) = generate_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let selectors_info = &common_data.selectors_info;
+        assert!(selectors_info.num_selectors() > 1);
+        let num_gates = common_data.gates.len();
+        let pairs = selectors_info.gate_selectors(num_gates).unwrap();
+        assert_eq!(pairs.len(), num_gates);
+
+        // the same mapping with the groups listed back to front, as a prover ordering its
+        // selector polynomials differently would: every gate keeps its group, only the
+        // selector indices move
+        let num_selectors = selectors_info.num_selectors();
+        let permuted = SelectorsInfo {
+            selector_indices: selectors_info
+                .selector_indices
+                .iter()
+                .map(|selector_index| num_selectors - 1 - selector_index)
+                .collect(),
+            groups: selectors_info.groups.iter().rev().cloned().collect(),
+        };
+        let mut permuted_pairs = permuted
+            .gate_selectors(num_gates)
+            .unwrap()
+            .into_iter()
+            .map(|(gate, selector_index)| (gate, num_selectors - 1 - selector_index))
+            .collect::<Vec<_>>();
+        permuted_pairs.sort();
+        assert_eq!(permuted_pairs, pairs);
+        let permuted_common_data = CommonData {
+            selectors_info: permuted,
+            ..common_data.clone()
+        };
+        assert_eq!(permuted_common_data.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_gate_selectors_rejects_invalid_mappings() {
+        let (_, _, cd) = generate_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        let num_gates = common_data.gates.len();
+        let num_selectors = common_data.selectors_info.num_selectors();
+
+        let mut out_of_range = common_data.clone();
+        out_of_range.selectors_info.selector_indices[0] = num_selectors;
+        assert_eq!(
+            out_of_range.validate(),
+            Err(CommonDataError::SelectorIndexOutOfRange {
+                gate: 0,
+                selector_index: num_selectors,
+                num_selectors,
+            })
+        );
+
+        // gate 0 is always in the first group, so pointing it at the last one is wrong
+        let mut outside = common_data.clone();
+        outside.selectors_info.selector_indices[0] = num_selectors - 1;
+        assert_eq!(
+            outside.validate(),
+            Err(CommonDataError::GateOutsideSelectorGroup {
+                gate: 0,
+                selector_index: num_selectors - 1,
+                group: common_data.selectors_info.groups[num_selectors - 1].clone(),
+            })
+        );
+
+        let mut overlapping = common_data.clone();
+        overlapping.selectors_info.groups[num_selectors - 1].start -= 1;
+        assert!(matches!(
+            overlapping.validate(),
+            Err(CommonDataError::GroupSizeMismatch { expected, actual })
+                if expected == num_gates && actual == num_gates + 1
+        ));
+
+        let mut truncated = common_data;
+        truncated.selectors_info.selector_indices.pop();
+        assert_eq!(
+            truncated.validate(),
+            Err(CommonDataError::SelectorCountMismatch {
+                expected: num_gates,
+                actual: num_gates - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_lists_mismatched_fields() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let common_data = CommonData::<Fr>::from(cd);
+        assert!(common_data.diff(&common_data.clone()).is_empty());
+
+        // e.g. the verifier assumes another degree and FRI rate than the prover used
+        let mut other = common_data.clone();
+        other.config.num_challenges += 1;
+        other.fri_params.config.rate_bits += 1;
+        other.fri_params.degree_bits += 1;
+        other.gate_ids[0] = "NoopGate".to_string();
+        other.gate_ids.push("NoopGate".to_string());
+
+        let num_challenges = common_data.config.num_challenges;
+        let rate_bits = common_data.fri_params.config.rate_bits;
+        let degree_bits = common_data.fri_params.degree_bits;
+        let num_gates = common_data.gate_ids.len();
+        assert_eq!(
+            common_data.diff(&other),
+            vec![
+                format!(
+                    "config.num_challenges: {num_challenges} != {}",
+                    num_challenges + 1
+                ),
+                format!(
+                    "fri_params.config.rate_bits: {rate_bits} != {}",
+                    rate_bits + 1
+                ),
+                format!(
+                    "fri_params.degree_bits: {degree_bits} != {}",
+                    degree_bits + 1
+                ),
+                format!("gate_ids.len(): {num_gates} != {}", num_gates + 1),
+                format!("gate_ids[0]: {:?} != \"NoopGate\"", common_data.gate_ids[0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_new_matches_plonky2_common_data() {
+        let (_, _, cd) = generate_tiny_proof_tuple();
+        let gates = cd
+            .gates
+            .iter()
+            .map(CustomGateRef::try_from)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let common_data = CommonData::<Fr>::from(cd.clone());
+        let synthetic = CommonData::<Fr>::new(
+            common_data.config.clone(),
+            cd.fri_params.degree_bits,
+            cd.fri_params.reduction_arity_bits.clone(),
+            gates,
+            cd.num_public_inputs,
+        );
+        assert_eq!(synthetic.check_gates(&cd.gates), Ok(()));
+        assert_eq!(synthetic.k_is, common_data.k_is);
+        assert_eq!(
+            synthetic.quotient_degree_factor,
+            common_data.quotient_degree_factor
+        );
+        assert_eq!(
+            synthetic.num_partial_products,
+            common_data.num_partial_products
+        );
+        assert_eq!(
+            synthetic.num_gate_constraints,
+            common_data.num_gate_constraints
+        );
+        assert_eq!(synthetic.fri_params.hiding, common_data.fri_params.hiding);
+        assert_eq!(
+            synthetic.fri_params.final_poly_len(),
+            common_data.fri_params.final_poly_len()
+        );
+        assert_eq!(synthetic.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_synthetic_common_data() {
+        let config = CircuitConfig {
+            num_wires: 4,
+            num_routed_wires: 4,
+            num_constants: 2,
+            use_base_arithmetic_gate: true,
+            security_bits: 100,
+            num_challenges: 2,
+            zero_knowledge: false,
+            max_quotient_degree_factor: 8,
+            fri_config: FriConfig {
+                rate_bits: 3,
+                cap_height: 0,
+                proof_of_work_bits: 16,
+                num_query_rounds: 28,
+            },
+        };
+        let gates = [
+            "NoopGate",
+            "ConstantGate { num_consts: 2 }",
+            "PublicInputGate",
+        ]
+        .into_iter()
+        .map(|id| CustomGateRef::from_id(id).unwrap())
+        .collect();
+        let common_data = CommonData::<Fr>::new(config.clone(), 3, vec![], gates, 4);
+        assert_eq!(common_data.validate(), Ok(()));
+        assert_eq!(common_data.degree(), 8);
+        assert_eq!(common_data.fri_params.final_poly_len(), 8);
+        // every permutation chunk fits in the quotient degree
+        assert_eq!(common_data.num_partial_products, 0);
+        assert_eq!(common_data.selectors_info.num_selectors(), 1);
+        assert_eq!(common_data.constants_range(), 0..3);
+        assert_eq!(common_data.sigmas_range(), 3..7);
+        assert_eq!(common_data.num_quotient_polys(), 16);
+        // PublicInputGate has the most constraints, one per hash element
+        assert_eq!(common_data.num_gate_constraints, 4);
+
+        let common_data = CommonData::<Fr>::new(
+            CircuitConfig {
+                num_wires: 135,
+                num_routed_wires: 80,
+                ..config
+            },
+            12,
+            vec![4, 4],
+            vec![],
+            0,
+        );
+        assert_eq!(common_data.num_partial_products, 9);
+        assert_eq!(common_data.fri_params.final_poly_len(), 16);
+        assert_eq!(common_data.k_is.len(), 80);
+    }
+
+    #[test]
+    fn test_common_data_rejects_unknown_format_version() {
+        let mut json = serde_json::to_value(CommonData::<Fr>::default()).unwrap();
+        json["format_version"] = 0.into();
+        let err = serde_json::from_value::<CommonData<Fr>>(json)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("unsupported format version"));
+    }
 }
@@ -2,14 +2,33 @@ use std::ops::Range;
 
 use halo2_proofs::halo2curves::ff::PrimeField;
 
-use super::{assigned::AssignedExtensionFieldValue, common_data::CommonData};
+use super::{
+    assigned::{AssignedExtensionFieldValue, ZetaNextOpenings},
+    common_data::CommonData,
+};
+
+/// Number of random elements appended to each leaf of a salted tree.
+pub const SALT_SIZE: usize = 4;
 
 #[derive(Copy, Clone)]
 pub struct FriOracleInfo {
     pub num_polys: usize,
+    /// Whether the oracle is blinded when the proof is hiding, see `PlonkOracle`.
     pub blinding: bool,
 }
 
+impl FriOracleInfo {
+    /// Whether the leaves of the oracle's tree end with a salt.
+    pub fn salted(&self, hiding: bool) -> bool {
+        hiding && self.blinding
+    }
+
+    /// Length of the oracle's leaves, salt included.
+    pub fn leaf_len(&self, hiding: bool) -> usize {
+        self.num_polys + if self.salted(hiding) { SALT_SIZE } else { 0 }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct FriPolynomialInfo {
     /// Index into `FriInstanceInfo`'s `oracles` list.
@@ -61,7 +80,7 @@ impl<F: PrimeField, const D: usize> FriInstanceInfo<F, D> {
         // The Z polynomials are also opened at g * zeta.
         let zeta_next_batch = FriBatchInfo {
             point: zeta_next.clone(),
-            polynomials: common_data.fri_zs_polys(),
+            polynomials: ZetaNextOpenings::<F, D>::polynomials(common_data),
         };
 
         let openings = vec![zeta_batch, zeta_next_batch];
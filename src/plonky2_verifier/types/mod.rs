@@ -11,6 +11,7 @@ use plonky2::{
     field::goldilocks_field::GoldilocksField,
     hash::{hash_types::HashOut, merkle_tree::MerkleCap},
 };
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use self::assigned::{AssignedExtensionFieldValue, AssignedHashValues, AssignedMerkleCapValues};
 
@@ -24,17 +25,51 @@ pub mod fri;
 pub mod proof;
 pub mod verification_key;
 
-pub fn to_goldilocks(e: GoldilocksField) -> GoldilocksField {
-    GoldilocksField::from_canonical_u64(e.0)
+/// Version tag stored in serialized verifier artifacts (`CommonData`, `VerificationKeyValues`).
+/// Loading an artifact written with a different version fails instead of silently producing
+/// a verifier for the wrong circuit shape.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FormatVersion;
+
+impl FormatVersion {
+    pub const CURRENT: u32 = 2;
 }
 
-#[derive(Clone, Debug, Default)]
+impl Serialize for FormatVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(Self::CURRENT)
+    }
+}
+
+impl<'de> Deserialize<'de> for FormatVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = u32::deserialize(deserializer)?;
+        if version != Self::CURRENT {
+            return Err(de::Error::custom(format!(
+                "unsupported format version {version}, expected {}",
+                Self::CURRENT
+            )));
+        }
+        Ok(FormatVersion)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct HashValues<F: PrimeField> {
     pub elements: [GoldilocksField; 4],
+    #[serde(skip)]
     _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField> HashValues<F> {
+    pub fn new(elements: [GoldilocksField; 4]) -> Self {
+        Self {
+            elements,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn assign(
         config: &GoldilocksChipConfig<F>,
         ctx: &mut RegionCtx<'_, F>,
@@ -72,18 +107,12 @@ impl<F: PrimeField> HashValues<F> {
 
 impl<F: PrimeField> From<HashOut<GoldilocksField>> for HashValues<F> {
     fn from(value: HashOut<GoldilocksField>) -> Self {
-        let mut elements = [GoldilocksField::ZERO; 4];
-        for (to, from) in elements.iter_mut().zip(value.elements.iter()) {
-            *to = to_goldilocks(*from);
-        }
-        HashValues {
-            elements,
-            _marker: PhantomData,
-        }
+        HashValues::new(value.elements)
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct MerkleCapValues<F: PrimeField>(pub Vec<HashValues<F>>);
 
 impl<F: PrimeField> MerkleCapValues<F> {
@@ -121,10 +150,37 @@ impl<F: PrimeField> From<MerkleCap<GoldilocksField, Bn254PoseidonHash>> for Merk
     }
 }
 
+/// Serde for `[GoldilocksField; D]` with a generic `D`, which serde only implements for
+/// arrays of a concrete length.
+mod goldilocks_array {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, const D: usize>(
+        elements: &[GoldilocksField; D],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        elements.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, De: Deserializer<'de>, const D: usize>(
+        deserializer: De,
+    ) -> Result<[GoldilocksField; D], De::Error> {
+        let elements = Vec::<GoldilocksField>::deserialize(deserializer)?;
+        let len = elements.len();
+        elements
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &format!("{D} elements").as_str()))
+    }
+}
+
 /// Contains a extension field value
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ExtensionFieldValue<F: PrimeField, const D: usize> {
+    #[serde(with = "goldilocks_array")]
     pub elements: [GoldilocksField; D],
+    #[serde(skip)]
     _marker: PhantomData<F>,
 }
 
@@ -138,6 +194,13 @@ impl<F: PrimeField, const D: usize> Default for ExtensionFieldValue<F, D> {
 }
 
 impl<F: PrimeField, const D: usize> ExtensionFieldValue<F, D> {
+    pub fn new(elements: [GoldilocksField; D]) -> Self {
+        Self {
+            elements,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn assign(
         config: &GoldilocksChipConfig<F>,
         ctx: &mut RegionCtx<'_, F>,
@@ -148,26 +211,47 @@ impl<F: PrimeField, const D: usize> ExtensionFieldValue<F, D> {
             .elements
             .iter()
             .map(|v| goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*v))))
-            .collect::<Result<Vec<AssignedValue<F>>, Error>>()?
-            .try_into()
-            .unwrap();
-        Ok(AssignedExtensionFieldValue(elements))
+            .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
+        Ok(AssignedExtensionFieldValue::from_vec(elements)?)
+    }
+
+    /// Assigns `values` in one pass over their limbs, in the cells `assign` would use for each
+    /// value in turn. Equal values are not merged: unlike constants, which `assign_constant`
+    /// shares through the region's constant cache, they are witnesses, so merging them would
+    /// make the layout, and with it the verifying key, depend on the proof.
+    pub fn assign_many(
+        config: &GoldilocksChipConfig<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        values: &[Self],
+    ) -> Result<Vec<AssignedExtensionFieldValue<F, D>>, Error> {
+        let goldilocks_chip = GoldilocksChip::new(config);
+        let limbs = values
+            .iter()
+            .flat_map(|value| value.elements.iter())
+            .map(|v| goldilocks_chip.assign_value(ctx, Value::known(goldilocks_to_fe(*v))))
+            .collect::<Result<Vec<AssignedValue<F>>, Error>>()?;
+        Ok(limbs
+            .chunks_exact(D)
+            .map(|limbs| AssignedExtensionFieldValue::from_vec(limbs.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?)
     }
 }
 
 impl<F: PrimeField> From<[GoldilocksField; 2]> for ExtensionFieldValue<F, 2> {
     fn from(value: [GoldilocksField; 2]) -> Self {
-        let mut elements = vec![];
-        for from in value.iter() {
-            elements.push(to_goldilocks(*from));
-        }
-        ExtensionFieldValue {
-            elements: elements.try_into().unwrap(),
-            _marker: PhantomData,
-        }
+        ExtensionFieldValue::new(value)
     }
 }
 
+/// Assigns `hash` as constants, e.g. an expected root an assigned hash is bound to.
+pub fn assign_hashout_constant<F: PrimeField>(
+    config: &GoldilocksChipConfig<F>,
+    ctx: &mut RegionCtx<'_, F>,
+    hash: HashOut<GoldilocksField>,
+) -> Result<AssignedHashValues<F>, Error> {
+    HashValues::assign_constant(config, ctx, &HashValues::from(hash))
+}
+
 pub fn to_extension_field_values<F: PrimeField>(
     extension_fields: Vec<<GoldilocksField as Extendable<2>>::Extension>,
 ) -> Vec<ExtensionFieldValue<F, 2>> {
@@ -176,3 +260,120 @@ pub fn to_extension_field_values<F: PrimeField>(
         .map(|e| ExtensionFieldValue::from(e.0))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::{bn256::Fr, ff::Field as _},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{goldilocks_field::GoldilocksField, types::Field},
+        hash::hash_types::HashOut,
+    };
+
+    use super::{assign_hashout_constant, ExtensionFieldValue, HashValues};
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                arithmetic_chip::GOLDILOCKS_MODULUS,
+                test_utils::{mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+            plonk::plonk_verifier_chip::PlonkVerifierChip,
+        },
+        context::RegionCtx,
+        fixtures::generate_tiny_proof_tuple,
+    };
+
+    #[test]
+    fn test_non_canonical_elements_keep_their_value() {
+        // plonky2 may hold an element as its canonical representative plus p
+        let one = GoldilocksField(GOLDILOCKS_MODULUS + 1);
+        assert_eq!(one, GoldilocksField::ONE);
+
+        let hash = HashValues::<Fr>::from(HashOut { elements: [one; 4] });
+        assert_eq!(hash.elements.map(goldilocks_to_fe::<Fr>), [Fr::ONE; 4]);
+        let extension = ExtensionFieldValue::<Fr, 2>::from([GoldilocksField::ZERO, one]);
+        assert_eq!(
+            extension.elements.map(goldilocks_to_fe::<Fr>),
+            [Fr::ZERO, Fr::ONE]
+        );
+    }
+
+    /// Hashes `public_inputs` in-circuit and binds the hash to the constant `expected`, as a
+    /// recursive verifier binds the public inputs hash of an inner proof to an expected root.
+    #[derive(Clone, Default)]
+    struct ExpectedRootCircuit {
+        public_inputs: Vec<GoldilocksField>,
+        expected: HashOut<GoldilocksField>,
+    }
+
+    impl Circuit<Fr> for ExpectedRootCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let goldilocks_chip = GoldilocksChip::new(&config);
+            goldilocks_chip.load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "expected root",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let public_inputs = self
+                        .public_inputs
+                        .iter()
+                        .map(|e| Value::known(goldilocks_to_fe(*e)))
+                        .map(|e| goldilocks_chip.assign_value(ctx, e))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let hash = PlonkVerifierChip::construct(&config)
+                        .get_public_inputs_hash(ctx, &public_inputs)?;
+                    let expected = assign_hashout_constant(&config, ctx, self.expected)?;
+                    for (actual, expected) in hash.iter().zip(expected.iter()) {
+                        goldilocks_chip.assert_equal(ctx, actual, expected)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_public_inputs_hash_bound_to_expected_root() {
+        let (proof_with_public_inputs, _, _) = generate_tiny_proof_tuple();
+        let expected = proof_with_public_inputs.get_public_inputs_hash();
+        let circuit = ExpectedRootCircuit {
+            public_inputs: proof_with_public_inputs.public_inputs,
+            expected,
+        };
+        mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+
+        let mut other_root = expected;
+        other_root.elements[3] += GoldilocksField::ONE;
+        let circuit = ExpectedRootCircuit {
+            expected: other_root,
+            ..circuit
+        };
+        let mock_prover = mock_prover_at_min_k(&SmallTable(circuit), vec![vec![]]).unwrap();
+        assert!(mock_prover.verify().is_err());
+    }
+}
@@ -5,14 +5,13 @@ use crate::plonky2_verifier::chip::goldilocks_chip::{GoldilocksChip, GoldilocksC
 use crate::plonky2_verifier::chip::native_chip::utils::goldilocks_to_fe;
 
 use super::assigned::{
-    AssignedExtensionFieldValue, AssignedFriInitialTreeProofValues, AssignedFriProofValues,
+    check_commit_phase_len, AssignedFriInitialTreeProofValues, AssignedFriProofValues,
     AssignedFriQueryRoundValues, AssignedFriQueryStepValues, AssignedHashValues,
     AssignedMerkleCapValues, AssignedMerkleProofValues, AssignedOpeningSetValues,
-    AssignedPolynomialCoeffsExtValues,
-};
-use super::{
-    to_extension_field_values, to_goldilocks, ExtensionFieldValue, HashValues, MerkleCapValues,
+    AssignedPolynomialCoeffsExtValues, AssignedProofValues, ShapeError, ZetaNextOpenings,
 };
+use super::common_data::FriParams;
+use super::{to_extension_field_values, ExtensionFieldValue, HashValues, MerkleCapValues};
 use crate::plonky2_verifier::context::RegionCtx;
 use halo2_proofs::circuit::Value;
 use halo2_proofs::halo2curves::ff::PrimeField;
@@ -21,7 +20,6 @@ use halo2wrong_maingate::AssignedValue;
 use itertools::Itertools;
 use plonky2::field::extension::quadratic::QuadraticExtension;
 use plonky2::field::polynomial::PolynomialCoeffs;
-use plonky2::field::types::Field;
 use plonky2::fri::proof::{FriProof, FriQueryRound};
 use plonky2::hash::merkle_proofs::MerkleProof;
 use plonky2::plonk::proof::{OpeningSet, Proof};
@@ -29,8 +27,10 @@ use plonky2::{
     field::goldilocks_field::GoldilocksField,
     fri::proof::{FriInitialTreeProof, FriQueryStep},
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct OpeningSetValues<F: PrimeField, const D: usize> {
     pub constants: Vec<ExtensionFieldValue<F, D>>,
     pub plonk_sigmas: Vec<ExtensionFieldValue<F, D>>,
@@ -61,41 +61,17 @@ impl<F: PrimeField, const D: usize> OpeningSetValues<F, D> {
         ctx: &mut RegionCtx<'_, F>,
         opening_set_values: &Self,
     ) -> Result<AssignedOpeningSetValues<F, D>, Error> {
-        let constants = opening_set_values
-            .constants
-            .iter()
-            .map(|c| ExtensionFieldValue::assign(config, ctx, c))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let plonk_sigmas = opening_set_values
-            .plonk_sigmas
-            .iter()
-            .map(|s| ExtensionFieldValue::assign(config, ctx, s))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let wires = opening_set_values
-            .wires
-            .iter()
-            .map(|w| ExtensionFieldValue::assign(config, ctx, w))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let plonk_zs = opening_set_values
-            .plonk_zs
-            .iter()
-            .map(|z| ExtensionFieldValue::assign(config, ctx, z))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let plonk_zs_next = opening_set_values
-            .plonk_zs_next
-            .iter()
-            .map(|z_next| ExtensionFieldValue::assign(config, ctx, z_next))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let partial_products = opening_set_values
-            .partial_products
-            .iter()
-            .map(|p| ExtensionFieldValue::assign(config, ctx, p))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
-        let quotient_polys = opening_set_values
-            .quotient_polys
-            .iter()
-            .map(|q| ExtensionFieldValue::assign(config, ctx, q))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
+        let constants =
+            ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.constants)?;
+        let plonk_sigmas =
+            ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.plonk_sigmas)?;
+        let wires = ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.wires)?;
+        let plonk_zs = ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.plonk_zs)?;
+        let plonk_zs_next = ZetaNextOpenings::assign(config, ctx, opening_set_values)?;
+        let partial_products =
+            ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.partial_products)?;
+        let quotient_polys =
+            ExtensionFieldValue::assign_many(config, ctx, &opening_set_values.quotient_polys)?;
         Ok(AssignedOpeningSetValues {
             constants,
             plonk_sigmas,
@@ -108,7 +84,8 @@ impl<F: PrimeField, const D: usize> OpeningSetValues<F, D> {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct MerkleProofValues<F: PrimeField> {
     pub siblings: Vec<HashValues<F>>,
 }
@@ -139,7 +116,8 @@ impl<F: PrimeField> From<MerkleProof<GoldilocksField, Bn254PoseidonHash>> for Me
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct FriInitialTreeProofValues<F: PrimeField> {
     pub evals_proofs: Vec<(Vec<GoldilocksField>, MerkleProofValues<F>)>,
 }
@@ -152,19 +130,16 @@ impl<F: PrimeField> From<FriInitialTreeProof<GoldilocksField, Bn254PoseidonHash>
             .evals_proofs
             .iter()
             .map(|(evals, proofs)| {
-                let evals_values: Vec<GoldilocksField> = evals
-                    .iter()
-                    .map(|f| GoldilocksField::from_canonical_u64(f.0))
-                    .collect();
                 let proofs_values = MerkleProofValues::from(proofs.clone());
-                (evals_values, proofs_values)
+                (evals.clone(), proofs_values)
             })
             .collect();
         FriInitialTreeProofValues { evals_proofs }
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct FriQueryStepValues<F: PrimeField, const D: usize> {
     pub evals: Vec<ExtensionFieldValue<F, D>>,
     pub merkle_proof: MerkleProofValues<F>,
@@ -176,11 +151,7 @@ impl<F: PrimeField, const D: usize> FriQueryStepValues<F, D> {
         ctx: &mut RegionCtx<'_, F>,
         fri_query_step_values: &Self,
     ) -> Result<AssignedFriQueryStepValues<F, D>, Error> {
-        let evals = fri_query_step_values
-            .evals
-            .iter()
-            .map(|v| ExtensionFieldValue::assign(config, ctx, v))
-            .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?;
+        let evals = ExtensionFieldValue::assign_many(config, ctx, &fri_query_step_values.evals)?;
         let merkle_proof = AssignedMerkleProofValues {
             siblings: fri_query_step_values
                 .merkle_proof
@@ -213,7 +184,8 @@ impl<F: PrimeField> From<FriQueryStep<GoldilocksField, Bn254PoseidonHash, 2>>
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct FriQueryRoundValues<F: PrimeField, const D: usize> {
     pub initial_trees_proof: FriInitialTreeProofValues<F>,
     pub steps: Vec<FriQueryStepValues<F, D>>,
@@ -279,7 +251,8 @@ impl<F: PrimeField, const D: usize> FriQueryRoundValues<F, D> {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PolynomialCoeffsExtValues<F: PrimeField, const D: usize>(
     pub Vec<ExtensionFieldValue<F, D>>,
 );
@@ -305,16 +278,13 @@ impl<F: PrimeField, const D: usize> PolynomialCoeffsExtValues<F, D> {
         polynomial_coeffs_ext_values: &Self,
     ) -> Result<AssignedPolynomialCoeffsExtValues<F, D>, Error> {
         Ok(AssignedPolynomialCoeffsExtValues(
-            polynomial_coeffs_ext_values
-                .0
-                .iter()
-                .map(|v| ExtensionFieldValue::assign(config, ctx, v))
-                .collect::<Result<Vec<AssignedExtensionFieldValue<F, D>>, Error>>()?,
+            ExtensionFieldValue::assign_many(config, ctx, &polynomial_coeffs_ext_values.0)?,
         ))
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct FriProofValues<F: PrimeField, const D: usize> {
     pub commit_phase_merkle_cap_values: Vec<MerkleCapValues<F>>,
     pub query_round_proofs: Vec<FriQueryRoundValues<F, D>>,
@@ -336,30 +306,48 @@ impl<F: PrimeField> From<FriProof<GoldilocksField, Bn254PoseidonHash, 2>> for Fr
                 .map(|proof| FriQueryRoundValues::from(proof.clone()))
                 .collect_vec(),
             final_poly: PolynomialCoeffsExtValues::from(value.final_poly),
-            pow_witness: to_goldilocks(value.pow_witness),
+            pow_witness: value.pow_witness,
         }
     }
 }
 
 // check constant
 impl<F: PrimeField, const D: usize> FriProofValues<F, D> {
+    /// Native counterpart of `AssignedFriProofValues::check_commit_phase_len`.
+    pub(crate) fn check_commit_phase_len(&self, fri_params: &FriParams) -> Result<(), ShapeError> {
+        check_commit_phase_len(self.commit_phase_merkle_cap_values.len(), fri_params)
+    }
+
+    /// Assigns the whole FRI proof, query rounds included, up front.
     pub fn assign(
         config: &GoldilocksChipConfig<F>,
         ctx: &mut RegionCtx<'_, F>,
         fri_proof_values: &Self,
     ) -> Result<AssignedFriProofValues<F, D>, Error> {
-        let commit_phase_merkle_cap_values = fri_proof_values
-            .commit_phase_merkle_cap_values
-            .iter()
-            .map(|merkle_cap_values| MerkleCapValues::assign(config, ctx, merkle_cap_values))
-            .collect::<Result<Vec<AssignedMerkleCapValues<F>>, Error>>()?;
-        let query_round_proofs = fri_proof_values
+        let mut assigned = Self::assign_without_query_rounds(config, ctx, fri_proof_values)?;
+        assigned.query_round_proofs = fri_proof_values
             .query_round_proofs
             .iter()
             .map(|fri_query_round_values| {
                 FriQueryRoundValues::assign(config, ctx, fri_query_round_values)
             })
             .collect::<Result<Vec<AssignedFriQueryRoundValues<F, D>>, Error>>()?;
+        Ok(assigned)
+    }
+
+    /// Like `assign`, but leaves `query_round_proofs` empty. The transcript doesn't observe the
+    /// query rounds, so they can be assigned by `FriVerifierChip::verify_fri_proof_with_rounds`
+    /// right before the round that checks them, instead of all being held until the FRI check.
+    pub fn assign_without_query_rounds(
+        config: &GoldilocksChipConfig<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        fri_proof_values: &Self,
+    ) -> Result<AssignedFriProofValues<F, D>, Error> {
+        let commit_phase_merkle_cap_values = fri_proof_values
+            .commit_phase_merkle_cap_values
+            .iter()
+            .map(|merkle_cap_values| MerkleCapValues::assign(config, ctx, merkle_cap_values))
+            .collect::<Result<Vec<AssignedMerkleCapValues<F>>, Error>>()?;
         let final_poly =
             PolynomialCoeffsExtValues::assign(config, ctx, &fri_proof_values.final_poly)?;
         let goldilocks_chip = GoldilocksChip::new(config);
@@ -369,14 +357,15 @@ impl<F: PrimeField, const D: usize> FriProofValues<F, D> {
         )?;
         Ok(AssignedFriProofValues {
             commit_phase_merkle_cap_values,
-            query_round_proofs,
+            query_round_proofs: vec![],
             final_poly,
             pow_witness,
         })
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ProofValues<F: PrimeField, const D: usize> {
     pub wires_cap: MerkleCapValues<F>,
     pub plonk_zs_partial_products_cap: MerkleCapValues<F>,
@@ -401,3 +390,30 @@ impl<F: PrimeField> From<Proof<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2
         }
     }
 }
+
+impl<F: PrimeField, const D: usize> ProofValues<F, D> {
+    /// Assigns the whole proof up front, like the verifier did before it assigned the FRI query
+    /// rounds while checking them. `Verifier` assigns the caps and openings itself and leaves the
+    /// query rounds to `PlonkVerifierChip::verify_fri_with_rounds`.
+    pub fn assign(
+        config: &GoldilocksChipConfig<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        proof_values: &Self,
+    ) -> Result<AssignedProofValues<F, D>, Error> {
+        Ok(AssignedProofValues {
+            wires_cap: MerkleCapValues::assign(config, ctx, &proof_values.wires_cap)?,
+            plonk_zs_partial_products_cap: MerkleCapValues::assign(
+                config,
+                ctx,
+                &proof_values.plonk_zs_partial_products_cap,
+            )?,
+            quotient_polys_cap: MerkleCapValues::assign(
+                config,
+                ctx,
+                &proof_values.quotient_polys_cap,
+            )?,
+            openings: OpeningSetValues::assign(config, ctx, &proof_values.openings)?,
+            opening_proof: FriProofValues::assign(config, ctx, &proof_values.opening_proof)?,
+        })
+    }
+}
@@ -1,21 +1,46 @@
 use crate::plonky2_verifier::{
     bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
-    types::{HashValues, MerkleCapValues},
+    chip::goldilocks_chip::GoldilocksChipConfig,
+    context::RegionCtx,
+    types::{assigned::AssignedVerificationKeyValues, FormatVersion, HashValues, MerkleCapValues},
 };
-use halo2_proofs::halo2curves::ff::PrimeField;
+use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
 use plonky2::plonk::circuit_data::VerifierOnlyCircuitData;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKeyValues<F: PrimeField> {
+    pub format_version: FormatVersion,
     pub constants_sigmas_cap: MerkleCapValues<F>,
     pub circuit_digest: HashValues<F>,
 }
 
+impl<F: PrimeField> VerificationKeyValues<F> {
+    /// Assigns the verification key as fixed constants, so the verifier circuit only accepts
+    /// proofs of the circuit this key was generated for.
+    pub fn assign_constant(
+        config: &GoldilocksChipConfig<F>,
+        ctx: &mut RegionCtx<'_, F>,
+        vk: &Self,
+    ) -> Result<AssignedVerificationKeyValues<F>, Error> {
+        Ok(AssignedVerificationKeyValues {
+            constants_sigmas_cap: MerkleCapValues::assign_constant(
+                config,
+                ctx,
+                &vk.constants_sigmas_cap,
+            )?,
+            circuit_digest: HashValues::assign_constant(config, ctx, &vk.circuit_digest)?,
+        })
+    }
+}
+
 impl<F: PrimeField> From<VerifierOnlyCircuitData<Bn254PoseidonGoldilocksConfig, 2>>
     for VerificationKeyValues<F>
 {
     fn from(value: VerifierOnlyCircuitData<Bn254PoseidonGoldilocksConfig, 2>) -> Self {
         VerificationKeyValues {
+            format_version: FormatVersion,
             constants_sigmas_cap: MerkleCapValues::from(value.constants_sigmas_cap),
             circuit_digest: HashValues::from(value.circuit_digest),
         }
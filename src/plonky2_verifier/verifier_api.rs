@@ -1,23 +1,63 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
 use std::time::Instant;
 
 use super::bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig;
 use super::types::{
     common_data::CommonData, proof::ProofValues, verification_key::VerificationKeyValues,
 };
-use super::verifier_circuit::{ProofTuple, Verifier};
-use crate::plonky2_verifier::chip::native_chip::test_utils::create_proof_checked;
+use super::verifier_circuit::{
+    circuit_instances, plonky2_query_indices, ProofTuple, PublicInputHandling, Verifier,
+    CIRCUIT_VERSION, SUPPORTED_EXTENSION_DEGREE,
+};
+use crate::compat::v0::wrap_proof;
+use crate::plonky2_verifier::chip::native_chip::arithmetic_chip::GOLDILOCKS_MODULUS;
 use crate::plonky2_verifier::chip::native_chip::utils::goldilocks_to_fe;
+use crate::plonky2_verifier::chip::plonk::gates::CustomGateRef;
+use crate::plonky2_verifier::chip::{
+    goldilocks_chip::GoldilocksChipConfig, hasher_chip::HasherChip,
+};
+use crate::plonky2_verifier::context::RegionCtx;
 use colored::Colorize;
+use halo2_proofs::circuit::Value;
 use halo2_proofs::dev::MockProver;
-use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
-use halo2_proofs::plonk::{keygen_pk, keygen_vk};
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_proofs::halo2curves::ff::PrimeField;
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Any, Assigned, Assignment, Challenge,
+    Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Instance, ProvingKey, Selector,
+    VerifyingKey,
+};
+use halo2_proofs::poly::commitment::Params;
 use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use halo2_proofs::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+use halo2_proofs::transcript::TranscriptWriterBuffer;
+use halo2_proofs::SerdeFormat;
 use halo2_solidity_verifier::compile_solidity;
 use halo2_solidity_verifier::encode_calldata;
 use halo2_solidity_verifier::BatchOpenScheme::Bdfg21;
 use halo2_solidity_verifier::Evm;
+use halo2_solidity_verifier::Keccak256Transcript;
 use halo2_solidity_verifier::SolidityGenerator;
+use halo2wrong_maingate::AssignedValue;
 use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::gates::arithmetic_extension::ArithmeticExtensionGate;
+use plonky2::gates::multiplication_extension::MulExtensionGate;
+use plonky2::plonk::circuit_data::{
+    CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData,
+};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
+use plonky2::util::serialization::DefaultGateSerializer;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 
 fn report_elapsed(now: Instant) {
     println!(
@@ -28,141 +68,3088 @@ fn report_elapsed(now: Instant) {
     );
 }
 
-/// Public API for generating Halo2 proof for Plonky2 verifier circuit
-/// feed Plonky2 proof, `VerifierOnlyCircuitData`, `CommonCircuitData`
-/// This runs only mock prover for constraint check
-pub fn verify_inside_snark_mock(
-    degree: u32,
-    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>,
-) {
-    let (proof_with_public_inputs, vd, cd) = proof;
-    // proof_with_public_inputs -> ProofValues type
-    let proof = ProofValues::<Fr, 2>::from(proof_with_public_inputs.proof);
-    let instances = proof_with_public_inputs
-        .public_inputs
-        .iter()
-        .map(|e| goldilocks_to_fe(*e))
-        .collect::<Vec<Fr>>();
-    // let instances = vec![];
-    let vk = VerificationKeyValues::from(vd.clone());
-    let common_data = CommonData::from(cd);
-    let verifier_circuit = Verifier::new(proof, instances.clone(), vk, common_data);
-    let prover = MockProver::run(degree, &verifier_circuit, vec![instances.clone()]).unwrap();
-    prover.assert_satisfied();
+/// Generates KZG params for circuits of size `2^k`.
+/// If `seed` is given, the setup is derived deterministically from it. This is only meant for
+/// tests and fixtures which need reproducible verifier contracts, since the toxic waste is known.
+pub fn gen_srs(k: u32, seed: Option<u64>) -> ParamsKZG<Bn256> {
+    match seed {
+        Some(seed) => ParamsKZG::<Bn256>::setup(k, ChaCha20Rng::seed_from_u64(seed)),
+        None => ParamsKZG::<Bn256>::setup(k, rand::thread_rng()),
+    }
 }
 
-/// Public API for generating Halo2 proof for Plonky2 verifier circuit
-/// feed Plonky2 proof, `VerifierOnlyCircuitData`, `CommonCircuitData`
-/// This runs real prover and generates valid SNARK proof, generates EVM verifier and runs the verifier
-pub fn verify_inside_snark(
-    degree: u32,
-    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>,
-) {
-    let (proof_with_public_inputs, vd, cd) = proof;
-    let proof = ProofValues::<Fr, 2>::from(proof_with_public_inputs.proof);
-    let instances = proof_with_public_inputs
-        .public_inputs
-        .iter()
-        .map(|e| goldilocks_to_fe(*e))
-        .collect::<Vec<Fr>>();
-    let vk = VerificationKeyValues::from(vd.clone());
-    let common_data = CommonData::from(cd);
-    // runs mock prover
-    let circuit = Verifier::new(proof, instances.clone(), vk, common_data);
-    let mock_prover = MockProver::run(degree, &circuit, vec![instances.clone()]).unwrap();
-    mock_prover.assert_satisfied();
-    println!("{}", "Mock prover passes".white().bold());
-    // generates halo2 solidity verifier
-    let mut rng = rand::thread_rng();
-    let param = ParamsKZG::<Bn256>::setup(degree, &mut rng);
-    let vk = keygen_vk(&param, &circuit).unwrap();
-    let pk = keygen_pk(&param, vk.clone(), &circuit).unwrap();
-    let generator = SolidityGenerator::new(&param, &vk, Bdfg21, instances.len());
-    let (verifier_solidity, vk_solidity) = generator.render_separately().unwrap();
-    let mut evm = Evm::default();
-    let verifier_creation_code = compile_solidity(&verifier_solidity);
-    let verifier_address = evm.create(verifier_creation_code);
-    let vk_creation_code = compile_solidity(&vk_solidity);
-    let vk_address = evm.create(vk_creation_code);
-    // generates SNARK proof and runs EVM verifier
-    println!("{}", "Starting finalization phase".red().bold());
-    let now = Instant::now();
-    let proof = create_proof_checked(&param, &pk, circuit.clone(), &instances, &mut rng);
-    println!("{}", "SNARK proof generated successfully!".white().bold());
-    report_elapsed(now);
-    let calldata = encode_calldata(Some(vk_address.into()), &proof, &instances);
-    let (gas_cost, _output) = evm.call(verifier_address, calldata);
-    println!("Gas cost: {}", gas_cost);
+/// Largest degree `min_k` tries.
+const MAX_K: u32 = 26;
+
+/// Returns the smallest `k` for which `circuit` can be synthesized with `instances`. The
+/// circuit is synthesized once, without witnesses checked or any table of size `2^k`, and `k`
+/// is derived from the rows it assigns and the blinding rows of its constraint system.
+pub fn min_k<C: Circuit<Fr>>(circuit: &C, instances: &[Fr]) -> Result<u32, Error> {
+    k_for_rows::<C>(used_rows(circuit, instances)?)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{verify_inside_snark, verify_inside_snark_mock};
-    use crate::plonky2_verifier::{
-        bn245_poseidon::plonky2_config::{
-            standard_inner_stark_verifier_config, standard_stark_verifier_config,
-            Bn254PoseidonGoldilocksConfig,
-        },
-        verifier_circuit::ProofTuple,
-    };
-    use plonky2::{
-        field::{goldilocks_field::GoldilocksField, types::Field},
-        hash::{
-            hashing::hash_n_to_hash_no_pad,
-            poseidon::{PoseidonHash, PoseidonPermutation},
-        },
-        iop::witness::{PartialWitness, WitnessWrite},
-        plonk::{circuit_builder::CircuitBuilder, config::PoseidonGoldilocksConfig},
+/// Returns the smallest `k` for which `rows` rows of `C`, e.g. counted by `used_rows`, fit
+/// next to the blinding rows of its constraint system.
+pub fn k_for_rows<C: Circuit<Fr>>(rows: usize) -> Result<u32, Error> {
+    let cs = constraint_system::<C>();
+    (1..=MAX_K)
+        .find(|k| {
+            let n = 1usize << k;
+            n >= cs.minimum_rows() && n - (cs.blinding_factors() + 1) >= rows
+        })
+        .ok_or(Error::NotEnoughRowsAvailable { current_k: MAX_K })
+}
+
+/// Rows `circuit` assigns when synthesized with `instances`, counted like `min_k` in a single
+/// synthesis.
+pub fn used_rows<C: Circuit<Fr>>(circuit: &C, instances: &[Fr]) -> Result<usize, Error> {
+    let cs = constraint_system::<C>();
+    let mut counter = RowCounter::default();
+    C::FloorPlanner::synthesize(
+        &mut counter,
+        circuit,
+        C::configure(&mut ConstraintSystem::default()),
+        cs.constants().clone(),
+    )?;
+    Ok(counter.rows.max(instances.len()))
+}
+
+/// Records the rows a synthesis assigns, ignoring the values.
+#[derive(Default)]
+struct RowCounter {
+    rows: usize,
+}
+
+impl RowCounter {
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+    }
+}
+
+impl Assignment<Fr> for RowCounter {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<Fr>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fr>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        _: Column<Any>,
+        left_row: usize,
+        _: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.touch(left_row.max(right_row));
+        Ok(())
+    }
+
+    // fills the rest of a table column, whatever the number of rows
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<Fr>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<Fr> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// Errors of the proving entry points. The refusals of the API are variants of their own, so
+/// callers can match them instead of downcasting a halo2 error.
+#[derive(Debug)]
+pub enum VerifierApiError {
+    Halo2(Error),
+    /// `MemoryGuard` refused the degree.
+    ResourceLimit(ResourceLimit),
+    /// Keygen ran out of rows at `2^k`, `EvmVerifier::setup_with_retry` tries one degree up.
+    NotEnoughRows {
+        k: u32,
+    },
+    MaxKExceeded(MaxKExceeded),
+    Srs(SrsError),
+    VersionMismatch(ArtifactVersionMismatch),
+    PublicInputsMismatch(PublicInputsMismatch),
+}
+
+impl fmt::Display for VerifierApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Halo2(err) => write!(f, "{err}"),
+            Self::ResourceLimit(err) => write!(f, "{err}"),
+            Self::NotEnoughRows { k } => {
+                write!(f, "the circuit doesn't fit in 2^{k} rows")
+            }
+            Self::MaxKExceeded(err) => write!(f, "{err}"),
+            Self::Srs(err) => write!(f, "{err}"),
+            Self::VersionMismatch(err) => write!(f, "{err}"),
+            Self::PublicInputsMismatch(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifierApiError {}
+
+impl From<Error> for VerifierApiError {
+    fn from(value: Error) -> Self {
+        Self::Halo2(value)
+    }
+}
+
+impl From<ResourceLimit> for VerifierApiError {
+    fn from(value: ResourceLimit) -> Self {
+        Self::ResourceLimit(value)
+    }
+}
+
+impl From<MaxKExceeded> for VerifierApiError {
+    fn from(value: MaxKExceeded) -> Self {
+        Self::MaxKExceeded(value)
+    }
+}
+
+impl From<SrsError> for VerifierApiError {
+    fn from(value: SrsError) -> Self {
+        Self::Srs(value)
+    }
+}
+
+impl From<ArtifactVersionMismatch> for VerifierApiError {
+    fn from(value: ArtifactVersionMismatch) -> Self {
+        Self::VersionMismatch(value)
+    }
+}
+
+impl From<PublicInputsMismatch> for VerifierApiError {
+    fn from(value: PublicInputsMismatch) -> Self {
+        Self::PublicInputsMismatch(value)
+    }
+}
+
+/// Generates the verifying and proving keys of `circuit`, unless `MemoryGuard::default()`
+/// estimates they don't fit in memory.
+pub fn gen_pk(
+    param: &ParamsKZG<Bn256>,
+    circuit: &impl Circuit<Fr>,
+) -> Result<ProvingKey<G1Affine>, VerifierApiError> {
+    gen_pk_with_guard(param, circuit, MemoryGuard::default())
+}
+
+/// `gen_pk` checking the memory estimate against `guard`.
+pub fn gen_pk_with_guard<C: Circuit<Fr>>(
+    param: &ParamsKZG<Bn256>,
+    circuit: &C,
+    guard: MemoryGuard,
+) -> Result<ProvingKey<G1Affine>, VerifierApiError> {
+    guard.check::<C>(param.k())?;
+    keygen(param, circuit)
+}
+
+fn keygen(
+    param: &ParamsKZG<Bn256>,
+    circuit: &impl Circuit<Fr>,
+) -> Result<ProvingKey<G1Affine>, VerifierApiError> {
+    let not_enough_rows = |err| match err {
+        Error::NotEnoughRowsAvailable { current_k } => {
+            VerifierApiError::NotEnoughRows { k: current_k }
+        }
+        err => VerifierApiError::Halo2(err),
     };
+    let vk = keygen_vk(param, circuit).map_err(not_enough_rows)?;
+    keygen_pk(param, vk, circuit).map_err(not_enough_rows)
+}
 
-    type F = GoldilocksField;
-    const D: usize = 2;
+/// Creates a proof and runs the native verifier on it. Fails only if the proof can't be
+/// created, the result of the verification is returned along with the proof.
+pub fn create_and_verify_proof(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: impl Circuit<Fr>,
+    instances: &[Fr],
+    rng: impl RngCore,
+) -> Result<(Vec<u8>, Result<(), Error>), Error> {
+    let proof = create_proof_bytes(params, pk, circuit, instances, rng)?;
+    let result = verify_proof_bytes(params, pk.get_vk(), &proof, instances);
+    Ok((proof, result))
+}
 
-    fn generate_proof_tuple() -> ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> {
-        let (inner_target, inner_data) = {
-            let hash_const =
-                hash_n_to_hash_no_pad::<F, PoseidonPermutation>(&[F::from_canonical_u64(42)]);
-            let mut builder = CircuitBuilder::<F, D>::new(standard_inner_stark_verifier_config());
-            let target = builder.add_virtual_target();
-            let expected_hash = builder.constant_hash(hash_const);
-            let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![target]);
-            builder.connect_hashes(hash, expected_hash);
-            builder.register_public_inputs(&expected_hash.elements);
-            let data = builder.build::<PoseidonGoldilocksConfig>();
-            (target, data)
-        };
+/// Creates a proof with the transcript the EVM verifier reads.
+pub fn create_proof_bytes(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: impl Circuit<Fr>,
+    instances: &[Fr],
+    mut rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Keccak256Transcript::new(Vec::new());
+    create_proof::<_, ProverSHPLONK<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[instances]],
+        &mut rng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+/// Verifies a proof created by `create_proof_bytes` with the native verifier.
+pub fn verify_proof_bytes(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instances: &[Fr],
+) -> Result<(), Error> {
+    let mut transcript = Keccak256Transcript::new(proof);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, SingleStrategy<_>>(
+        params,
+        vk,
+        SingleStrategy::new(params),
+        &[&[instances]],
+        &mut transcript,
+    )
+}
+
+/// Size of a serialized `Fr`, and of a polynomial coefficient or evaluation.
+const FR_BYTES: u64 = 32;
+
+fn constraint_system<C: Circuit<Fr>>() -> ConstraintSystem<Fr> {
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+    cs
+}
+
+/// Rough estimate, in bytes, of the peak memory of generating the keys and a proof of a circuit
+/// with constraint system `cs` at `2^k` rows. It counts the params, the values and coefficients
+/// of every committed column, the fixed and permutation cosets the proving key keeps over the
+/// extended domain, and the FFT scratch of the quotient evaluation.
+pub fn estimate_memory(k: u32, cs: &ConstraintSystem<Fr>) -> u64 {
+    let n = 1u64 << k;
+    let quotient_bits = (cs.degree() as u64 - 1)
+        .next_power_of_two()
+        .trailing_zeros();
+    let extended_n = n << quotient_bits;
+
+    let num_permutation_columns = cs.permutation().get_columns().len() as u64;
+    // each lookup commits to its permuted input, permuted table and product
+    let num_committed_columns = (cs.num_advice_columns()
+        + cs.num_instance_columns()
+        + cs.num_fixed_columns()
+        + 3 * cs.lookups().len()) as u64
+        // sigmas and, at most one per column, grand products
+        + 2 * num_permutation_columns;
+    let num_cosets = cs.num_fixed_columns() as u64 + num_permutation_columns;
 
-        let mut builder = CircuitBuilder::<F, D>::new(standard_stark_verifier_config());
-        let proof_t =
-            builder.add_virtual_proof_with_pis::<PoseidonGoldilocksConfig>(&inner_data.common);
-        let vd = builder.constant_verifier_data(&inner_data.verifier_only);
-        builder.verify_proof::<PoseidonGoldilocksConfig>(&proof_t, &vd, &inner_data.common);
-        builder.register_public_inputs(&proof_t.public_inputs);
-        let data = builder.build::<Bn254PoseidonGoldilocksConfig>();
+    let params = 2 * n * G1_RAW_BYTES;
+    let columns = 2 * num_committed_columns * n * FR_BYTES;
+    let cosets = num_cosets * extended_n * FR_BYTES;
+    let fft_scratch = 2 * extended_n * FR_BYTES;
+    params + columns + cosets + fft_scratch
+}
+
+#[cfg(feature = "sysinfo")]
+fn available_memory() -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    Some(system.available_memory())
+}
+
+#[cfg(not(feature = "sysinfo"))]
+fn available_memory() -> Option<u64> {
+    None
+}
+
+/// Memory check run by the proving entry points before the params and keys are generated, so a
+/// mistyped degree fails fast instead of exhausting the memory of the machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryGuard {
+    /// Largest accepted estimate, in bytes. Defaults to the available system memory, which is
+    /// only known with the `sysinfo` feature. Without either, nothing is refused.
+    pub max_bytes: Option<u64>,
+    /// Proceeds whatever the estimate.
+    pub force: bool,
+}
 
-        let proof = {
-            let mut pw = PartialWitness::new();
-            pw.set_target(inner_target, F::from_canonical_usize(42));
-            inner_data.prove(pw).unwrap()
+impl MemoryGuard {
+    /// Checks `estimate_memory` of the constraint system of `C` at `2^k` rows against the
+    /// limit. Only the configuration of `C` is needed, so it runs before anything is synthesized.
+    pub fn check<C: Circuit<Fr>>(&self, k: u32) -> Result<(), ResourceLimit> {
+        if self.force {
+            return Ok(());
+        }
+        let limit_bytes = match self.max_bytes.or_else(available_memory) {
+            Some(limit_bytes) => limit_bytes,
+            None => return Ok(()),
         };
+        let estimated_bytes = estimate_memory(k, &constraint_system::<C>());
+        if estimated_bytes > limit_bytes {
+            return Err(ResourceLimit {
+                k,
+                estimated_bytes,
+                limit_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// `MemoryGuard` refused to prove a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceLimit {
+    pub k: u32,
+    pub estimated_bytes: u64,
+    pub limit_bytes: u64,
+}
 
-        let mut pw = PartialWitness::new();
-        pw.set_proof_with_pis_target(&proof_t, &proof);
-        let final_proof = data.prove(pw).unwrap();
-        let proof: ProofTuple<F, Bn254PoseidonGoldilocksConfig, D> =
-            (final_proof, data.verifier_only, data.common);
-        proof
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MIB: u64 = 1 << 20;
+        write!(
+            f,
+            "proving at k = {} needs about {} MiB, more than the limit of {} MiB, \
+             set `force: true` on the `MemoryGuard` to proceed anyway",
+            self.k,
+            self.estimated_bytes / MIB,
+            self.limit_bytes / MIB
+        )
     }
+}
 
-    #[test]
-    fn test_recursive_halo2_mock() {
-        let proof = generate_proof_tuple();
-        verify_inside_snark_mock(19, proof);
+impl std::error::Error for ResourceLimit {}
+
+/// Where the blinding factors of an outer proof come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProvingMode {
+    /// Blinding factors from the thread RNG.
+    #[default]
+    Randomized,
+    /// Blinding factors derived from the seed, so proving the same circuit twice gives the same
+    /// proof and runs differ only in the circuit. This is only meant for benchmarks and tests:
+    /// the blinding is known to anyone with the seed, so the proof is not zero knowledge. The
+    /// `production` feature removes the variant outside of the tests.
+    #[cfg(any(test, not(feature = "production")))]
+    Deterministic(u64),
+}
+
+impl ProvingMode {
+    /// RNG the blinding factors are drawn from.
+    pub fn rng(self) -> Box<dyn RngCore> {
+        match self {
+            ProvingMode::Randomized => Box::new(rand::thread_rng()),
+            #[cfg(any(test, not(feature = "production")))]
+            ProvingMode::Deterministic(seed) => {
+                log::warn!("proving with deterministic blinding, the proof is not zero knowledge");
+                Box::new(ChaCha20Rng::seed_from_u64(seed))
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_recursive_halo2_proof() {
-        let proof = generate_proof_tuple();
-        verify_inside_snark(19, proof);
+/// Proves `circuit` with the transcript the EVM verifier reads.
+pub fn gen_proof(
+    param: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: Verifier,
+) -> Result<Vec<u8>, Error> {
+    gen_proof_with_mode(param, pk, circuit, ProvingMode::Randomized)
+}
+
+/// `gen_proof` with the blinding factors taken from `mode`.
+pub fn gen_proof_with_mode(
+    param: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: Verifier,
+    mode: ProvingMode,
+) -> Result<Vec<u8>, Error> {
+    let instances = circuit.instances();
+    create_proof_bytes(param, pk, circuit, &instances, mode.rng())
+}
+
+/// Versions of the build an artifact was produced by.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactMetadata {
+    /// `CIRCUIT_VERSION` of the verifier circuit.
+    pub circuit_version: u64,
+    /// `PLONKY2_VERSION`, if the build knew it.
+    pub plonky2_version: Option<String>,
+}
+
+impl ArtifactMetadata {
+    /// Metadata of the running build.
+    pub fn current() -> Self {
+        Self {
+            circuit_version: CIRCUIT_VERSION,
+            plonky2_version: PLONKY2_VERSION.map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for ArtifactMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circuit version {}", self.circuit_version)?;
+        match &self.plonky2_version {
+            Some(version) => write!(f, ", plonky2 {version}"),
+            None => write!(f, ", unknown plonky2 version"),
+        }
+    }
+}
+
+/// A halo2 proof of the verifier circuit with its instances, and the versions it was proven
+/// with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofArtifact {
+    /// The proof, as `gen_proof` returns it.
+    pub proof: Vec<u8>,
+    pub instances: Vec<Fr>,
+    pub metadata: ArtifactMetadata,
+}
+
+impl ProofArtifact {
+    /// Proves `circuit` with `gen_proof`, recording the versions of the running build.
+    pub fn prove(
+        param: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: Verifier,
+    ) -> Result<Self, Error> {
+        let instances = circuit.instances();
+        let proof = gen_proof(param, pk, circuit)?;
+        Ok(Self {
+            proof,
+            instances,
+            metadata: ArtifactMetadata::current(),
+        })
+    }
+
+    /// Checks the proof with `verify_proof_bytes`. An artifact of another `CIRCUIT_VERSION`
+    /// is rejected with `VerifierApiError::VersionMismatch` before the proof is read, naming
+    /// the versions of both builds.
+    pub fn verify(
+        &self,
+        param: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+    ) -> Result<(), VerifierApiError> {
+        let current = ArtifactMetadata::current();
+        if self.metadata.circuit_version != current.circuit_version {
+            return Err(ArtifactVersionMismatch {
+                artifact: self.metadata.clone(),
+                current,
+            }
+            .into());
+        }
+        Ok(verify_proof_bytes(param, vk, &self.proof, &self.instances)?)
+    }
+}
+
+/// A `ProofArtifact` from a build of another `CIRCUIT_VERSION`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArtifactVersionMismatch {
+    pub artifact: ArtifactMetadata,
+    pub current: ArtifactMetadata,
+}
+
+impl fmt::Display for ArtifactVersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the artifact was proven with {}, the verifier is {}",
+            self.artifact, self.current
+        )
+    }
+}
+
+impl std::error::Error for ArtifactVersionMismatch {}
+
+/// Magic bytes of a snarkjs `.ptau` file, which has to be converted to halo2 params first.
+const PTAU_MAGIC: &[u8; 4] = b"ptau";
+
+/// Uncompressed sizes of the points in a halo2 params file.
+const G1_RAW_BYTES: u64 = 64;
+const G2_RAW_BYTES: u64 = 128;
+
+/// Reasons an externally supplied SRS is rejected by `load_srs_from_ptau`.
+#[derive(Debug)]
+pub enum SrsError {
+    Io(io::Error),
+    /// The file is a snarkjs `.ptau`, not yet converted to the halo2 params layout.
+    UnconvertedPtau,
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// The file size doesn't match the number of G1 and G2 points of a `2^k` SRS.
+    PointCountMismatch {
+        k: u32,
+        expected_len: u64,
+        len: u64,
+    },
+    DegreeTooLow {
+        available: u32,
+        requested: u32,
+    },
+}
+
+impl fmt::Display for SrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read SRS: {err}"),
+            Self::UnconvertedPtau => write!(
+                f,
+                "snarkjs .ptau files must be converted to halo2 params before loading"
+            ),
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(f, "SRS blake2b checksum is {actual}, expected {expected}")
+            }
+            Self::PointCountMismatch {
+                k,
+                expected_len,
+                len,
+            } => write!(
+                f,
+                "SRS of degree 2^{k} should take {expected_len} bytes, found {len}"
+            ),
+            Self::DegreeTooLow {
+                available,
+                requested,
+            } => write!(f, "SRS supports k up to {available}, requested {requested}"),
+        }
+    }
+}
+
+impl std::error::Error for SrsError {}
+
+impl From<io::Error> for SrsError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Reader hashing the bytes read through it with blake2b, so a file is checksummed in the pass
+/// that parses it.
+struct Blake2bReader<R> {
+    inner: R,
+    state: blake2b_simd::State,
+}
+
+impl<R: Read> Read for Blake2bReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.state.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+/// Loads KZG params from a Powers-of-Tau SRS converted to the halo2 params layout (e.g. the
+/// hermez/perpetual-powers-of-tau files produced by `halo2-kzg-srs`), and downsizes them to
+/// `2^k`. `checksum` is the hex encoded blake2b-512 digest of the whole file. The file is
+/// streamed once, hashed while its points are parsed, so it is never held in memory next to the
+/// params, and the params are only returned if the digest matches.
+pub fn load_srs_from_ptau(
+    path: impl AsRef<Path>,
+    k: u32,
+    checksum: &str,
+) -> Result<ParamsKZG<Bn256>, SrsError> {
+    let file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut reader = Blake2bReader {
+        inner: BufReader::new(file),
+        state: blake2b_simd::State::new(),
+    };
+    let mut header = [0; 4];
+    reader.read_exact(&mut header)?;
+    if &header == PTAU_MAGIC {
+        return Err(SrsError::UnconvertedPtau);
+    }
+
+    // `k` followed by the monomial and lagrange G1 bases, then `g2` and `s_g2`
+    let srs_k = u32::from_le_bytes(header);
+    let expected_len = 1u64
+        .checked_shl(srs_k)
+        .and_then(|n| n.checked_mul(2 * G1_RAW_BYTES))
+        .and_then(|g1_len| g1_len.checked_add(4 + 2 * G2_RAW_BYTES));
+    if expected_len != Some(len) {
+        return Err(SrsError::PointCountMismatch {
+            k: srs_k,
+            expected_len: expected_len.unwrap_or(u64::MAX),
+            len,
+        });
+    }
+    if srs_k < k {
+        return Err(SrsError::DegreeTooLow {
+            available: srs_k,
+            requested: k,
+        });
+    }
+
+    // `read_custom` reads `k` itself, so the header is put back in front of the points
+    let params = ParamsKZG::<Bn256>::read_custom(
+        &mut header.as_slice().chain(&mut reader),
+        SerdeFormat::RawBytes,
+    );
+    // the rest of the file goes into the digest even if a point failed to parse, so a corrupted
+    // file is reported as such
+    io::copy(&mut reader, &mut io::sink())?;
+    let actual = reader.state.finalize().to_hex().to_string();
+    if !actual.eq_ignore_ascii_case(checksum) {
+        return Err(SrsError::ChecksumMismatch {
+            expected: checksum.to_string(),
+            actual,
+        });
+    }
+
+    let mut params = params?;
+    if params.k() > k {
+        params.downsize(k);
+    }
+    Ok(params)
+}
+
+/// Generates the deployment code of the EVM verifier and of the separately deployed
+/// verifying key contract, in this order.
+pub fn gen_evm_verifier(
+    param: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instances: usize,
+) -> (Vec<u8>, Vec<u8>) {
+    let generator = SolidityGenerator::new(param, vk, Bdfg21, num_instances);
+    let (verifier_solidity, vk_solidity) = generator.render_separately().unwrap();
+    (
+        compile_solidity(&verifier_solidity),
+        compile_solidity(&vk_solidity),
+    )
+}
+
+/// Solidity source of `Plonky2VerifierWrapper`, see `gen_evm_verifier_wrapper`.
+const EVM_VERIFIER_WRAPPER_TEMPLATE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IHalo2Verifier {
+    function verifyProof(address vk, bytes calldata proof, uint256[] calldata instances)
+        external
+        returns (bool);
+}
+
+contract Plonky2VerifierWrapper {
+    uint256 internal constant GOLDILOCKS_MODULUS = 0xffffffff00000001;
+    uint256 internal constant NUM_INSTANCES = {{NUM_INSTANCES}};
+    uint256 internal constant CIRCUIT_VERSION = {{CIRCUIT_VERSION}};
+
+    address public immutable verifier;
+    address public immutable vk;
+
+    constructor(address verifier_, address vk_) {
+        verifier = verifier_;
+        vk = vk_;
+    }
+
+    /// Number of Goldilocks public inputs of the plonky2 proof.
+    function numInstances() external pure returns (uint256) {
+        return NUM_INSTANCES;
+    }
+
+    /// Version of the verifier circuit, passed to the verifier as the first instance.
+    function circuitVersion() external pure returns (uint256) {
+        return CIRCUIT_VERSION;
+    }
+
+    /// Length of `packedPublicInputs` expected by `verify`.
+    function numPackedPublicInputs() public pure returns (uint256) {
+        return (NUM_INSTANCES + 2) / 3;
+    }
+
+    /// Packs `elements` into `elements[0] + elements[1] * p + elements[2] * p^2`.
+    function packGoldilocks(uint64[3] memory elements) public pure returns (uint256 packed) {
+        for (uint256 i = 3; i > 0; i--) {
+            require(elements[i - 1] < GOLDILOCKS_MODULUS, "non-canonical goldilocks element");
+            packed = packed * GOLDILOCKS_MODULUS + elements[i - 1];
+        }
+    }
+
+    /// Inverse of `packGoldilocks`. Every limb is reduced modulo p, so a word that isn't below
+    /// p^3, i.e. that no canonical elements pack to, reverts instead of being truncated.
+    function unpackGoldilocks(uint256 packed) public pure returns (uint64[3] memory elements) {
+        for (uint256 i = 0; i < 3; i++) {
+            elements[i] = uint64(packed % GOLDILOCKS_MODULUS);
+            packed /= GOLDILOCKS_MODULUS;
+        }
+        require(packed == 0, "packed value out of range");
+    }
+
+    /// Verifies `proof` against public inputs packed three per word. The last word is padded
+    /// with zeros.
+    function verify(bytes calldata proof, uint256[] calldata packedPublicInputs)
+        external
+        returns (bool)
+    {
+        require(
+            packedPublicInputs.length == numPackedPublicInputs(),
+            "wrong number of packed public inputs"
+        );
+        uint256[] memory instances = new uint256[](NUM_INSTANCES + 1);
+        instances[0] = CIRCUIT_VERSION;
+        for (uint256 i = 0; i < packedPublicInputs.length; i++) {
+            uint64[3] memory elements = unpackGoldilocks(packedPublicInputs[i]);
+            for (uint256 j = 0; j < 3; j++) {
+                if (3 * i + j < NUM_INSTANCES) {
+                    instances[1 + 3 * i + j] = elements[j];
+                } else {
+                    require(elements[j] == 0, "non-zero padding");
+                }
+            }
+        }
+        return IHalo2Verifier(verifier).verifyProof(vk, proof, instances);
+    }
+}
+"#;
+
+/// Generates the Solidity source of a contract wrapping the verifier from `gen_evm_verifier`.
+/// It takes the public inputs packed three Goldilocks elements per word the way
+/// `ArithmeticChip::pack` does, see `pack_goldilocks_instances`. The contract is deployed with
+/// the addresses of the verifier and of the verifying key contract as constructor arguments,
+/// and passes `CIRCUIT_VERSION` to the verifier ahead of the `num_instances` public inputs.
+pub fn gen_evm_verifier_wrapper(num_instances: usize) -> String {
+    EVM_VERIFIER_WRAPPER_TEMPLATE
+        .replace("{{NUM_INSTANCES}}", &num_instances.to_string())
+        .replace("{{CIRCUIT_VERSION}}", &CIRCUIT_VERSION.to_string())
+}
+
+/// A public input that is not a canonical Goldilocks element, i.e. not below the modulus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonCanonicalGoldilocks {
+    pub index: usize,
+    pub value: u64,
+}
+
+impl fmt::Display for NonCanonicalGoldilocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "public input {} is {}, not a canonical goldilocks element",
+            self.index, self.value
+        )
+    }
+}
+
+impl std::error::Error for NonCanonicalGoldilocks {}
+
+/// Packs public inputs into the words taken by the `gen_evm_verifier_wrapper` contract. Like
+/// the contract's `packGoldilocks`, it rejects values that are not below the modulus, since
+/// `p + a` would pack to the same word as `a` with the next element incremented.
+pub fn pack_goldilocks_instances(public_inputs: &[u64]) -> Result<Vec<Fr>, NonCanonicalGoldilocks> {
+    if let Some((index, value)) = public_inputs
+        .iter()
+        .enumerate()
+        .find(|(_, value)| **value >= GOLDILOCKS_MODULUS)
+    {
+        return Err(NonCanonicalGoldilocks {
+            index,
+            value: *value,
+        });
+    }
+    Ok(pack_canonical_goldilocks(public_inputs))
+}
+
+/// `pack_goldilocks_instances` of values known to be canonical.
+fn pack_canonical_goldilocks(public_inputs: &[u64]) -> Vec<Fr> {
+    let modulus = Fr::from(GOLDILOCKS_MODULUS);
+    public_inputs
+        .chunks(3)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(Fr::from(0), |acc, e| acc * modulus + Fr::from(*e))
+        })
+        .collect()
+}
+
+/// Calldata of `Plonky2VerifierWrapper.verify(proof, packedPublicInputs)`.
+pub fn encode_wrapper_calldata(proof: &[u8], packed_public_inputs: &[Fr]) -> Vec<u8> {
+    let word = |value: usize| {
+        let mut word = [0u8; 32];
+        word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+        word
+    };
+    let padded_proof_len = (proof.len() + 31) / 32 * 32;
+
+    let mut calldata = function_selector("verify(bytes,uint256[])").to_vec();
+    calldata.extend(word(0x40));
+    calldata.extend(word(0x40 + 0x20 + padded_proof_len));
+    calldata.extend(word(proof.len()));
+    calldata.extend(proof);
+    calldata.resize(calldata.len() + padded_proof_len - proof.len(), 0);
+    calldata.extend(word(packed_public_inputs.len()));
+    for value in packed_public_inputs {
+        calldata.extend(value.to_repr().iter().rev());
+    }
+    calldata
+}
+
+fn function_selector(signature: &str) -> [u8; 4] {
+    Keccak256::digest(signature.as_bytes())[..4]
+        .try_into()
+        .unwrap()
+}
+
+/// `GenericConfig`s of the proofs passed to `verify_bytes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigKind {
+    /// `Bn254PoseidonGoldilocksConfig`, whose hasher is verified in-circuit.
+    Bn254PoseidonGoldilocks,
+    /// plonky2's `PoseidonGoldilocksConfig`. There is no in-circuit Goldilocks Poseidon
+    /// transcript, so these proofs are wrapped into a `Bn254PoseidonGoldilocks` proof first,
+    /// see `compat::v0::wrap_proof`.
+    PoseidonGoldilocks,
+}
+
+/// Serializations of the proofs passed to `verify_bytes_with_encoding`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofEncoding {
+    /// `ProofWithPublicInputs::to_bytes`.
+    #[default]
+    Uncompressed,
+    /// `CompressedProofWithPublicInputs::to_bytes`. The proof is decompressed before it is
+    /// verified, see `decompress_proof`.
+    Compressed,
+}
+
+/// Reasons `verify_bytes` can't decide whether a proof is valid.
+#[derive(Debug)]
+pub enum VerifyBytesError {
+    Deserialize(String),
+    /// The common circuit data was built for another extension degree than
+    /// `SUPPORTED_EXTENSION_DEGREE`.
+    UnsupportedExtensionDegree(usize),
+    /// A gate of the common circuit data has no in-circuit constrainer. Gate ids change between
+    /// plonky2 releases, so this is usually a proof from another version than `PLONKY2_VERSION`.
+    UnsupportedGate(String),
+    /// The proof was read but plonky2's verifier rejects it. `verify_bytes` returns `Ok(false)`
+    /// for it.
+    InvalidProof(anyhow::Error),
+    /// A `PoseidonGoldilocks` proof that verifies couldn't be wrapped.
+    Wrap(anyhow::Error),
+    /// The verifier circuit couldn't be synthesized or proven. Invalid plonky2 proofs are
+    /// rejected before, so this is a proof of another circuit than `pk`'s or a verifier bug.
+    Halo2(Error),
+}
+
+impl fmt::Display for VerifyBytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize(err) => write!(f, "failed to deserialize: {err}"),
+            Self::UnsupportedExtensionDegree(degree) => write!(
+                f,
+                "extension degree {degree} is not supported, expected {SUPPORTED_EXTENSION_DEGREE}"
+            ),
+            Self::UnsupportedGate(id) => match PLONKY2_VERSION {
+                Some(version) => write!(
+                    f,
+                    "unsupported gate `{id}`, was the proof generated with plonky2 {version}?"
+                ),
+                None => write!(f, "unsupported gate `{id}`"),
+            },
+            Self::InvalidProof(err) => write!(f, "the proof doesn't verify: {err}"),
+            Self::Wrap(err) => write!(f, "failed to wrap the proof: {err}"),
+            Self::Halo2(err) => write!(f, "failed to prove the verifier circuit: {err:?}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyBytesError {}
+
+/// Version of plonky2 the verifier was built against, as locked in this crate's `Cargo.lock`.
+/// `None` when the crate is built as a dependency, see `build.rs`.
+pub const PLONKY2_VERSION: Option<&str> = option_env!("PLONKY2_VERSION");
+
+/// Verifies a serialized plonky2 proof inside the halo2 verifier circuit, proving the circuit
+/// with `pk` and checking the halo2 proof in-process. `param` and `pk` are set up once, by
+/// `EvmVerifier::setup` of a proof `read_proof_bytes` gives for the same plonky2 circuit, and
+/// reused for every proof. Returns `Ok(false)` if the plonky2 proof is well-formed but doesn't
+/// verify, or if the halo2 proof doesn't verify, and an error for input that can't be read or
+/// a circuit the verifier doesn't support.
+pub fn verify_bytes(
+    param: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    proof: &[u8],
+    common: &[u8],
+    verifier_only: &[u8],
+    config: ConfigKind,
+) -> Result<bool, VerifyBytesError> {
+    verify_bytes_with_encoding(
+        param,
+        pk,
+        proof,
+        common,
+        verifier_only,
+        config,
+        ProofEncoding::Uncompressed,
+        ProvingMode::Randomized,
+    )
+}
+
+/// `verify_bytes` for a proof serialized with `encoding`, proving with the blinding factors
+/// of `mode`.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_bytes_with_encoding(
+    param: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    proof: &[u8],
+    common: &[u8],
+    verifier_only: &[u8],
+    config: ConfigKind,
+    encoding: ProofEncoding,
+    mode: ProvingMode,
+) -> Result<bool, VerifyBytesError> {
+    let proof_tuple = match read_proof_bytes(proof, common, verifier_only, config, encoding) {
+        Err(VerifyBytesError::InvalidProof(_)) => return Ok(false),
+        proof_tuple => proof_tuple?,
+    };
+    let circuit = EvmVerifier::circuit(proof_tuple).with_k(param.k());
+    let instances = circuit.instances();
+    let (_, result) = create_and_verify_proof(param, pk, circuit, &instances, mode.rng())
+        .map_err(VerifyBytesError::Halo2)?;
+    Ok(result.is_ok())
+}
+
+/// Deserializes a plonky2 proof of `config` into the proof tuple the verifier circuit takes.
+/// `common` is serialized with plonky2's `DefaultGateSerializer`. The proof is checked with
+/// plonky2's verifier, a proof that doesn't verify is an `InvalidProof` error.
+/// `PoseidonGoldilocks` proofs are wrapped, so the tuple is the wrapper circuit's.
+pub fn read_proof_bytes(
+    proof: &[u8],
+    common: &[u8],
+    verifier_only: &[u8],
+    config: ConfigKind,
+    encoding: ProofEncoding,
+) -> Result<
+    ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+    VerifyBytesError,
+> {
+    match config {
+        ConfigKind::Bn254PoseidonGoldilocks => {
+            let proof_tuple = deserialize_proof_tuple(proof, common, verifier_only, encoding)?;
+            verify_natively(&proof_tuple)?;
+            Ok(proof_tuple)
+        }
+        ConfigKind::PoseidonGoldilocks => {
+            let proof_tuple = deserialize_proof_tuple::<PoseidonGoldilocksConfig>(
+                proof,
+                common,
+                verifier_only,
+                encoding,
+            )?;
+            verify_natively(&proof_tuple)?;
+            wrap_proof(proof_tuple).map_err(VerifyBytesError::Wrap)
+        }
+    }
+}
+
+fn deserialize_proof_tuple<C: GenericConfig<2, F = GoldilocksField>>(
+    proof: &[u8],
+    common: &[u8],
+    verifier_only: &[u8],
+    encoding: ProofEncoding,
+) -> Result<ProofTuple<GoldilocksField, C, 2>, VerifyBytesError> {
+    let cd = CommonCircuitData::<GoldilocksField, 2>::from_bytes(
+        common.to_vec(),
+        &DefaultGateSerializer,
+    )
+    .map_err(|err| {
+        VerifyBytesError::Deserialize(match PLONKY2_VERSION {
+            Some(version) => format!("common circuit data, expected plonky2 {version}: {err:?}"),
+            None => format!("common circuit data: {err:?}"),
+        })
+    })?;
+    // the proof layout depends on the extension degree, so check it before reading the proof
+    if let Some(degree) = extension_degree(&cd) {
+        return Err(VerifyBytesError::UnsupportedExtensionDegree(degree));
+    }
+    if let Some(gate) = cd
+        .gates
+        .iter()
+        .find(|gate| CustomGateRef::<Fr>::from_id(&gate.0.id()).is_none())
+    {
+        return Err(VerifyBytesError::UnsupportedGate(
+            gate.0.id().trim_end().to_string(),
+        ));
+    }
+    let vd = VerifierOnlyCircuitData::<C, 2>::from_bytes(verifier_only.to_vec())
+        .map_err(|err| VerifyBytesError::Deserialize(format!("verifier data: {err:?}")))?;
+    let deserialize_err = |err| VerifyBytesError::Deserialize(format!("proof: {err:?}"));
+    let proof = match encoding {
+        ProofEncoding::Uncompressed => {
+            ProofWithPublicInputs::<GoldilocksField, C, 2>::from_bytes(proof.to_vec(), &cd)
+                .map_err(deserialize_err)?
+        }
+        ProofEncoding::Compressed => {
+            let proof = CompressedProofWithPublicInputs::<GoldilocksField, C, 2>::from_bytes(
+                proof.to_vec(),
+                &cd,
+            )
+            .map_err(deserialize_err)?;
+            decompress_proof(proof, &vd, &cd)?
+        }
+    };
+    Ok((proof, vd, cd))
+}
+
+/// Runs plonky2's verifier on `proof_tuple`, which tells a well-formed proof that doesn't verify
+/// apart from input that can't be read.
+fn verify_natively<C: GenericConfig<2, F = GoldilocksField>>(
+    proof_tuple: &ProofTuple<GoldilocksField, C, 2>,
+) -> Result<(), VerifyBytesError> {
+    let (proof, vd, cd) = proof_tuple;
+    VerifierCircuitData {
+        verifier_only: vd.clone(),
+        common: cd.clone(),
+    }
+    .verify(proof.clone())
+    .map_err(VerifyBytesError::InvalidProof)
+}
+
+/// Decompresses a proof of plonky2's `ProofWithPublicInputs::compress`. Compression stores the
+/// query round of an index drawn several times once, decompression gives every query its own
+/// round again, so the circuit checks duplicate indices as it does in uncompressed proofs.
+pub fn decompress_proof<C: GenericConfig<2, F = GoldilocksField>>(
+    proof: CompressedProofWithPublicInputs<GoldilocksField, C, 2>,
+    vd: &VerifierOnlyCircuitData<C, 2>,
+    cd: &CommonCircuitData<GoldilocksField, 2>,
+) -> Result<ProofWithPublicInputs<GoldilocksField, C, 2>, VerifyBytesError> {
+    proof
+        .decompress(&vd.circuit_digest, cd)
+        .map_err(|err| VerifyBytesError::Deserialize(format!("compressed proof: {err:?}")))
+}
+
+/// Returns the extension degree `cd` was built for if it isn't `SUPPORTED_EXTENSION_DEGREE`.
+///
+/// plonky2 doesn't serialize the extension degree, and reading common data of another degree
+/// with `D = 2` succeeds. The degree is recovered from the extension gates instead: the circuit
+/// builder sizes them with `new_from_config`, so their `num_ops` tells the degrees apart.
+fn extension_degree(cd: &CommonCircuitData<GoldilocksField, 2>) -> Option<usize> {
+    let config = &cd.config;
+    // `num_ops` of each extension gate as built for every Goldilocks extension degree, the
+    // supported one first so that it wins when two degrees size a gate alike
+    let arithmetic_ops = [
+        (
+            2,
+            ArithmeticExtensionGate::<2>::new_from_config(config).num_ops,
+        ),
+        (
+            1,
+            ArithmeticExtensionGate::<1>::new_from_config(config).num_ops,
+        ),
+        (
+            4,
+            ArithmeticExtensionGate::<4>::new_from_config(config).num_ops,
+        ),
+        (
+            5,
+            ArithmeticExtensionGate::<5>::new_from_config(config).num_ops,
+        ),
+    ];
+    let mul_ops = [
+        (2, MulExtensionGate::<2>::new_from_config(config).num_ops),
+        (1, MulExtensionGate::<1>::new_from_config(config).num_ops),
+        (4, MulExtensionGate::<4>::new_from_config(config).num_ops),
+        (5, MulExtensionGate::<5>::new_from_config(config).num_ops),
+    ];
+    cd.gates.iter().find_map(|gate| {
+        let gate = gate.0.as_any();
+        let (num_ops, ops_by_degree) =
+            if let Some(gate) = gate.downcast_ref::<ArithmeticExtensionGate<2>>() {
+                (gate.num_ops, &arithmetic_ops)
+            } else if let Some(gate) = gate.downcast_ref::<MulExtensionGate<2>>() {
+                (gate.num_ops, &mul_ops)
+            } else {
+                return None;
+            };
+        ops_by_degree
+            .iter()
+            .find(|(_, ops)| *ops == num_ops)
+            .map(|(degree, _)| *degree)
+            .filter(|degree| *degree != SUPPORTED_EXTENSION_DEGREE)
+    })
+}
+
+/// How the contract an EVM verifier is called through takes the instances.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstancePacking {
+    /// One Goldilocks element per word, as the verifier from `gen_evm_verifier` takes them.
+    #[default]
+    Unpacked,
+    /// Three Goldilocks elements per word, as the wrapper from `gen_evm_verifier_wrapper` takes
+    /// them, see `pack_goldilocks_instances`.
+    Goldilocks3,
+}
+
+/// Instances the EVM verifier is deployed for.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceLayout {
+    /// Number of Goldilocks public inputs of the plonky2 proof.
+    pub num_instances: usize,
+    pub packing: InstancePacking,
+}
+
+impl InstanceLayout {
+    /// Number of instances of the verifier circuit, the public inputs preceded by
+    /// `CIRCUIT_VERSION`.
+    pub fn num_circuit_instances(&self) -> usize {
+        self.num_instances + 1
+    }
+
+    /// Instances of the verifier circuit of `proof_tuple` laid out by `options`, one vector
+    /// per instance column. They are computed natively, the public inputs hash with plonky2's
+    /// Poseidon, so nothing is synthesized:
+    /// - `Unpacked`, the instances the halo2 proof carries and the verifier from
+    ///   `gen_evm_verifier` takes, `CIRCUIT_VERSION` first,
+    /// - `Goldilocks3`, the packed words the wrapper from `gen_evm_verifier_wrapper` takes,
+    ///   which it unpacks and prefixes with `CIRCUIT_VERSION` itself.
+    ///
+    /// `EvmVerifier` builds its circuits from the `Unpacked` ones, so the proofs of `gen_proof`
+    /// carry exactly these.
+    pub fn instances_for(
+        proof_tuple: &ProofTuple<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            SUPPORTED_EXTENSION_DEGREE,
+        >,
+        options: InstanceOptions,
+    ) -> Vec<Vec<Fr>> {
+        let elements = verifier_elements(&proof_tuple.0, options.public_input_handling);
+        match options.packing {
+            InstancePacking::Unpacked => {
+                let instances = elements
+                    .into_iter()
+                    .map(goldilocks_to_fe)
+                    .collect::<Vec<Fr>>();
+                vec![circuit_instances(&instances)]
+            }
+            InstancePacking::Goldilocks3 => {
+                let elements = elements
+                    .iter()
+                    .map(|e| e.to_canonical_u64())
+                    .collect::<Vec<_>>();
+                vec![pack_canonical_goldilocks(&elements)]
+            }
+        }
+    }
+}
+
+/// How `InstanceLayout::instances_for` lays out the instances of a proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstanceOptions {
+    pub public_input_handling: PublicInputHandling,
+    pub packing: InstancePacking,
+}
+
+/// Goldilocks elements the verifier circuit of `proof_with_public_inputs` exposes with
+/// `public_input_handling`, after `CIRCUIT_VERSION`.
+fn verifier_elements(
+    proof_with_public_inputs: &ProofWithPublicInputs<
+        GoldilocksField,
+        Bn254PoseidonGoldilocksConfig,
+        SUPPORTED_EXTENSION_DEGREE,
+    >,
+    public_input_handling: PublicInputHandling,
+) -> Vec<GoldilocksField> {
+    match public_input_handling {
+        PublicInputHandling::Raw => proof_with_public_inputs.public_inputs.clone(),
+        PublicInputHandling::PreHashed => proof_with_public_inputs
+            .get_public_inputs_hash()
+            .elements
+            .to_vec(),
+        PublicInputHandling::RawWithHashInstance => {
+            let public_inputs_hash = proof_with_public_inputs.get_public_inputs_hash();
+            let public_inputs = proof_with_public_inputs.public_inputs.iter();
+            public_inputs
+                .chain(&public_inputs_hash.elements)
+                .copied()
+                .collect()
+        }
+    }
+}
+
+/// Describes the artifacts `EvmVerifier::export` writes, as stored in `manifest.json`. Hashes
+/// are hex encoded Keccak256.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub k: u32,
+    /// Hash of the verifying key serialized in `SerdeFormat::RawBytes`. The key commits to the
+    /// fixed columns and the permutation, so it identifies the circuit for the SRS.
+    pub vk_hash: String,
+    pub instance_layout: InstanceLayout,
+    /// `CIRCUIT_VERSION` of the circuit `vk` was generated for.
+    pub circuit_version: u64,
+    /// `PLONKY2_VERSION` of the build that exported the verifier, if it knew it.
+    pub plonky2_version: Option<String>,
+    pub solc_version: String,
+    /// Hash of `deployment.bin` decoded, the code the verifier is deployed with.
+    pub deployment_code_hash: String,
+    /// Hash of `wrapper_deployment.bin` decoded, for a `Goldilocks3` layout.
+    pub wrapper_deployment_code_hash: Option<String>,
+}
+
+impl Manifest {
+    pub const FILE_NAME: &'static str = "manifest.json";
+}
+
+fn keccak256_hex(bytes: &[u8]) -> String {
+    hex::encode(Keccak256::digest(bytes))
+}
+
+/// Runs `solc` with `args` on `source`, returning its stdout.
+fn run_solc(args: &[&str], source: &str) -> io::Result<String> {
+    let mut solc = Command::new("solc")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    solc.stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(source.as_bytes())?;
+    let output = solc.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("solc exited with {}", output.status),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Code of the last contract in `source`, `output` being `--bin` for the deployment code, as
+/// `compile_solidity` compiles it, or `--bin-runtime` for the runtime code.
+fn compile_solidity_code(source: &str, output: &str) -> io::Result<Vec<u8>> {
+    let output = run_solc(&[output, "--optimize", "-"], source)?;
+    let code = output.split_ascii_whitespace().last().unwrap_or_default();
+    hex::decode(code).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Proving setup of the verifier circuit.
+pub struct EvmVerifier;
+
+impl EvmVerifier {
+    /// Builds the verifier circuit of `proof`, picks the smallest `k` it fits in and generates
+    /// fresh KZG params and the proving key for it. Prove with `gen_proof`, and generate the
+    /// EVM verifier from `pk.get_vk()` with `gen_evm_verifier`.
+    pub fn setup(
+        proof: ProofTuple<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            SUPPORTED_EXTENSION_DEGREE,
+        >,
+    ) -> Result<(ParamsKZG<Bn256>, ProvingKey<G1Affine>, Verifier), VerifierApiError> {
+        Self::setup_with_guard(proof, MemoryGuard::default())
+    }
+
+    /// `setup` checking the memory estimate at the chosen `k` against `guard`. `min_k` only
+    /// counts the rows of a synthesis, so the check still runs before anything of size `2^k`.
+    pub fn setup_with_guard(
+        proof: ProofTuple<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            SUPPORTED_EXTENSION_DEGREE,
+        >,
+        guard: MemoryGuard,
+    ) -> Result<(ParamsKZG<Bn256>, ProvingKey<G1Affine>, Verifier), VerifierApiError> {
+        let circuit = Self::circuit(proof);
+        let k = min_k(&circuit, &circuit.instances())?;
+        guard.check::<Verifier>(k)?;
+        let param = gen_srs(k, None);
+        let pk = keygen(&param, &circuit)?;
+        Ok((param, pk, circuit))
+    }
+
+    /// `setup` from `initial_k` instead of the smallest `k` the mock prover finds. Whenever
+    /// keygen runs out of rows, it's retried one degree up until `max_k`. The params are
+    /// generated at every degree, or downsized from `srs` if it reaches the degree.
+    pub fn setup_with_retry(
+        proof: ProofTuple<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            SUPPORTED_EXTENSION_DEGREE,
+        >,
+        initial_k: u32,
+        max_k: u32,
+        srs: Option<&ParamsKZG<Bn256>>,
+        guard: MemoryGuard,
+    ) -> Result<
+        (
+            ParamsKZG<Bn256>,
+            ProvingKey<G1Affine>,
+            Verifier,
+            ProvingStats,
+        ),
+        VerifierApiError,
+    > {
+        let circuit = Self::circuit(proof);
+        let mut attempted_k = vec![];
+        for k in initial_k..=max_k {
+            guard.check::<Verifier>(k)?;
+            let param = match srs {
+                Some(srs) if srs.k() < k => {
+                    return Err(SrsError::DegreeTooLow {
+                        available: srs.k(),
+                        requested: k,
+                    }
+                    .into())
+                }
+                Some(srs) => {
+                    let mut param = srs.clone();
+                    if param.k() > k {
+                        param.downsize(k);
+                    }
+                    param
+                }
+                None => gen_srs(k, None),
+            };
+            attempted_k.push(k);
+            match keygen(&param, &circuit) {
+                Ok(pk) => {
+                    let stats = ProvingStats {
+                        attempted_k,
+                        final_k: k,
+                    };
+                    return Ok((param, pk, circuit, stats));
+                }
+                Err(VerifierApiError::NotEnoughRows { .. }) => {
+                    log::warn!("the verifier circuit doesn't fit in 2^{k} rows")
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(MaxKExceeded { attempted_k, max_k }.into())
+    }
+
+    fn circuit(
+        proof: ProofTuple<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            SUPPORTED_EXTENSION_DEGREE,
+        >,
+    ) -> Verifier {
+        let query_indices = plonky2_query_indices(&proof);
+        let mut instances = InstanceLayout::instances_for(&proof, InstanceOptions::default());
+        let (proof_with_public_inputs, vd, cd) = proof;
+        // `Verifier` exposes `CIRCUIT_VERSION` before the instances it's given
+        let instances = instances.remove(0).split_off(1);
+        let verifier = Verifier::new(
+            ProofValues::<Fr, 2>::from(proof_with_public_inputs.proof),
+            instances,
+            VerificationKeyValues::from(vd),
+            CommonData::from(cd),
+        );
+        match query_indices {
+            Some(indices) => verifier.with_plonky2_query_indices(indices),
+            None => verifier,
+        }
+    }
+
+    /// Writes the EVM verifier of `vk` to `dir` for review before deployment:
+    /// - `verifier.sol` and `vk.sol`, the sources `gen_evm_verifier` compiles. The generator
+    ///   only emits Solidity, with the verification itself in inline assembly, so there's no
+    ///   standalone Yul source,
+    /// - `deployment.bin` and `vk_deployment.bin`, the hex encoded deployment code,
+    /// - `runtime.bin`, the hex encoded runtime code of the verifier,
+    /// - `wrapper.sol` and `wrapper_deployment.bin`, the contract from
+    ///   `gen_evm_verifier_wrapper` and its deployment code, if `layout` packs the instances
+    ///   with `Goldilocks3`,
+    /// - `manifest.json`, the returned `Manifest`.
+    ///
+    /// `dir` is created if needed and existing artifacts in it are overwritten. A missing or
+    /// failing `solc` is returned as an error.
+    pub fn export(
+        dir: &Path,
+        srs: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        layout: &InstanceLayout,
+    ) -> io::Result<Manifest> {
+        fs::create_dir_all(dir)?;
+        let generator = SolidityGenerator::new(srs, vk, Bdfg21, layout.num_circuit_instances());
+        let (verifier_solidity, vk_solidity) = generator
+            .render_separately()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        fs::write(dir.join("verifier.sol"), &verifier_solidity)?;
+        fs::write(dir.join("vk.sol"), &vk_solidity)?;
+
+        let deployment_code = compile_solidity_code(&verifier_solidity, "--bin")?;
+        fs::write(dir.join("deployment.bin"), hex::encode(&deployment_code))?;
+        let vk_deployment_code = compile_solidity_code(&vk_solidity, "--bin")?;
+        fs::write(
+            dir.join("vk_deployment.bin"),
+            hex::encode(vk_deployment_code),
+        )?;
+        let runtime_code = compile_solidity_code(&verifier_solidity, "--bin-runtime")?;
+        fs::write(dir.join("runtime.bin"), hex::encode(runtime_code))?;
+
+        let wrapper_deployment_code_hash = match layout.packing {
+            InstancePacking::Unpacked => None,
+            InstancePacking::Goldilocks3 => {
+                let wrapper_solidity = gen_evm_verifier_wrapper(layout.num_instances);
+                fs::write(dir.join("wrapper.sol"), &wrapper_solidity)?;
+                let wrapper_deployment_code = compile_solidity_code(&wrapper_solidity, "--bin")?;
+                fs::write(
+                    dir.join("wrapper_deployment.bin"),
+                    hex::encode(&wrapper_deployment_code),
+                )?;
+                Some(keccak256_hex(&wrapper_deployment_code))
+            }
+        };
+
+        let solc_version = run_solc(&["--version"], "")?;
+        let manifest = Manifest {
+            k: srs.k(),
+            vk_hash: keccak256_hex(&vk.to_bytes(SerdeFormat::RawBytes)),
+            instance_layout: layout.clone(),
+            circuit_version: CIRCUIT_VERSION,
+            plonky2_version: PLONKY2_VERSION.map(str::to_string),
+            solc_version: solc_version.lines().last().unwrap_or_default().to_string(),
+            deployment_code_hash: keccak256_hex(&deployment_code),
+            wrapper_deployment_code_hash,
+        };
+        fs::write(
+            dir.join(Manifest::FILE_NAME),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        Ok(manifest)
+    }
+}
+
+/// Degrees `EvmVerifier::setup_with_retry` went through.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProvingStats {
+    /// Every `k` keygen was attempted at, in order, the final one included.
+    pub attempted_k: Vec<u32>,
+    /// `k` of the params and the proving key.
+    pub final_k: u32,
+}
+
+/// Keygen ran out of rows at every degree `EvmVerifier::setup_with_retry` was allowed to try.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxKExceeded {
+    pub attempted_k: Vec<u32>,
+    pub max_k: u32,
+}
+
+impl fmt::Display for MaxKExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the verifier circuit doesn't fit in 2^{} rows, tried k = {:?}",
+            self.max_k, self.attempted_k
+        )
+    }
+}
+
+impl std::error::Error for MaxKExceeded {}
+
+/// Public API for generating Halo2 proof for Plonky2 verifier circuit
+/// feed Plonky2 proof, `VerifierOnlyCircuitData`, `CommonCircuitData`
+/// This runs only mock prover for constraint check
+pub fn verify_inside_snark_mock(
+    degree: u32,
+    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+) {
+    let verifier_circuit = EvmVerifier::circuit(proof).with_k(degree);
+    let instances = verifier_circuit.instances();
+    let prover = MockProver::run(degree, &verifier_circuit, vec![instances]).unwrap();
+    prover.assert_satisfied();
+}
+
+/// Public inputs of a proof that differ from the expected ones by more than trailing zeros.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputsMismatch {
+    pub expected: Vec<GoldilocksField>,
+    pub actual: Vec<GoldilocksField>,
+}
+
+impl fmt::Display for PublicInputsMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "public inputs {:?} don't match {:?} up to trailing zeros",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for PublicInputsMismatch {}
+
+/// Whether `a` and `b` are equal once their trailing zeros are stripped, so a proof of a circuit
+/// padding its public inputs to a fixed length matches the unpadded ones.
+pub fn eq_up_to_trailing_zeros(a: &[GoldilocksField], b: &[GoldilocksField]) -> bool {
+    fn strip(values: &[GoldilocksField]) -> &[GoldilocksField] {
+        let len = values
+            .iter()
+            .rposition(|v| !v.is_zero())
+            .map_or(0, |i| i + 1);
+        &values[..len]
+    }
+    strip(a) == strip(b)
+}
+
+/// `verify_inside_snark_mock`, then checks the public inputs of the proof against `expected`
+/// ignoring trailing zeros, failing with `VerifierApiError::PublicInputsMismatch`.
+pub fn verify_inside_snark_mock_with_public_inputs(
+    degree: u32,
+    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+    expected: &[GoldilocksField],
+) -> Result<(), VerifierApiError> {
+    let actual = proof.0.public_inputs.clone();
+    verify_inside_snark_mock(degree, proof);
+    if !eq_up_to_trailing_zeros(&actual, expected) {
+        return Err(PublicInputsMismatch {
+            expected: expected.to_vec(),
+            actual,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Public API for generating Halo2 proof for Plonky2 verifier circuit
+/// feed Plonky2 proof, `VerifierOnlyCircuitData`, `CommonCircuitData`
+/// This runs real prover and generates valid SNARK proof, generates EVM verifier and runs the verifier
+///
+/// Fails with `VerifierApiError::ResourceLimit` if `MemoryGuard::default()` refuses `degree`,
+/// checked before the mock prover or anything else of size `2^degree` runs, and with
+/// `ConstraintSystemFailure` if the mock prover isn't satisfied.
+pub fn verify_inside_snark(
+    degree: u32,
+    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+) -> Result<(), VerifierApiError> {
+    verify_inside_snark_with_mode(degree, proof, ProvingMode::Randomized)
+}
+
+/// `verify_inside_snark` with the blinding factors of the SNARK proof taken from `mode`.
+pub fn verify_inside_snark_with_mode(
+    degree: u32,
+    proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+    mode: ProvingMode,
+) -> Result<(), VerifierApiError> {
+    let rng = mode.rng();
+    MemoryGuard::default().check::<Verifier>(degree)?;
+    let circuit = EvmVerifier::circuit(proof).with_k(degree);
+    let instances = circuit.instances();
+    // runs mock prover
+    let mock_prover = MockProver::run(degree, &circuit, vec![instances.clone()])?;
+    if let Err(failures) = mock_prover.verify() {
+        for failure in failures.iter() {
+            log::error!("{failure}");
+        }
+        return Err(Error::ConstraintSystemFailure.into());
+    }
+    println!("{}", "Mock prover passes".white().bold());
+    // generates halo2 solidity verifier
+    let param = gen_srs(degree, None);
+    let vk = keygen_vk(&param, &circuit)?;
+    let pk = keygen_pk(&param, vk.clone(), &circuit)?;
+    let (verifier_creation_code, vk_creation_code) = gen_evm_verifier(&param, &vk, instances.len());
+    let mut evm = Evm::default();
+    let verifier_address = evm.create(verifier_creation_code);
+    let vk_address = evm.create(vk_creation_code);
+    // generates SNARK proof and runs EVM verifier
+    println!("{}", "Starting finalization phase".red().bold());
+    let now = Instant::now();
+    let (proof, result) = create_and_verify_proof(&param, &pk, circuit.clone(), &instances, rng)?;
+    result?;
+    println!("{}", "SNARK proof generated successfully!".white().bold());
+    report_elapsed(now);
+    let calldata = encode_calldata(Some(vk_address.into()), &proof, &instances);
+    let (gas_cost, _output) = evm.call(verifier_address, calldata);
+    println!("Gas cost: {}", gas_cost);
+    Ok(())
+}
+
+/// Constrains the Poseidon hash over Bn254 of `inputs`, the sponge of `Bn254PoseidonHash` that
+/// the verifier's transcript and Merkle proofs run on, and returns its first `num_outputs`
+/// elements. `Bn254PoseidonHash::hash_no_pad` of the same inputs gives the first 4. The
+/// inputs are taken as they are, range check them first if they are not known to be
+/// canonical Goldilocks elements.
+pub fn poseidon_hash<F: PrimeField>(
+    ctx: &mut RegionCtx<'_, F>,
+    config: &GoldilocksChipConfig<F>,
+    inputs: &[AssignedValue<F>],
+    num_outputs: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    HasherChip::new(ctx, config)?.hash(ctx, inputs.to_vec(), num_outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{min_k, verify_inside_snark, verify_inside_snark_mock};
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+        chip::native_chip::{arithmetic_chip::TableMode, test_utils::mock_prove_small_table},
+        fixtures::{
+            generate_hiding_proof_tuple, generate_high_bit_proof_tuple,
+            generate_minimal_proof_tuple, generate_no_partial_products_proof_tuple,
+            generate_noop_only_proof_tuple, generate_proof_tuple,
+            generate_quotient_degree_factor_proof_tuple, generate_random_access_proof_tuple,
+            generate_tiny_proof_tuple, generate_two_random_access_gates_proof_tuple, mock_verifier,
+            verify_mock,
+        },
+        verifier_circuit::{ProofTuple, CIRCUIT_VERSION},
+    };
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    /// The lookup table of the arithmetic chip takes `2^16` rows, so no circuit fits below this.
+    const MIN_K: u32 = TableMode::Bits16.min_k();
+
+    /// The verifier as it's deployed, with the 16-bit table, on the tiny fixture.
+    #[test]
+    fn test_tiny_halo2_mock() {
+        let proof = generate_tiny_proof_tuple();
+        let circuit = super::EvmVerifier::circuit(proof.clone());
+        let k = min_k(&circuit, &circuit.instances()).unwrap();
+        verify_inside_snark_mock(k, proof);
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_recursive_halo2_mock() {
+        let proof = generate_proof_tuple();
+        verify_inside_snark_mock(19, proof);
+    }
+
+    #[test]
+    fn test_recursive_halo2_mock_rejects_other_vk() {
+        use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+        let mut proof = generate_tiny_proof_tuple();
+        // the vk is fixed in the circuit, so this pins the verifier to a different circuit
+        proof.1.circuit_digest.elements[0] += GoldilocksField::ONE;
+        let (verifier_circuit, instances) = mock_verifier(proof);
+        let prover = mock_prove_small_table(&verifier_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_poseidon_hash_matches_native() {
+        use super::poseidon_hash;
+        use crate::plonky2_verifier::{
+            bn245_poseidon::plonky2_config::Bn254PoseidonHash,
+            chip::{
+                goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+                native_chip::{all_chip::AllChipConfig, utils::goldilocks_to_fe},
+            },
+            context::RegionCtx,
+        };
+        use halo2_proofs::{
+            circuit::{floor_planner::V1, Layouter, Value},
+            halo2curves::bn256::Fr,
+            plonk::{Circuit, ConstraintSystem, Error},
+        };
+        use plonky2::{
+            field::types::{Field, Sample},
+            plonk::config::Hasher,
+        };
+
+        #[derive(Clone, Default)]
+        struct PoseidonHashCircuit {
+            inputs: Vec<GoldilocksField>,
+            expected: Vec<GoldilocksField>,
+        }
+
+        impl Circuit<Fr> for PoseidonHashCircuit {
+            type Config = GoldilocksChipConfig<Fr>;
+            type FloorPlanner = V1;
+
+            fn without_witnesses(&self) -> Self {
+                self.clone()
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+                GoldilocksChip::configure(&all_chip_config)
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fr>,
+            ) -> Result<(), Error> {
+                let goldilocks_chip = GoldilocksChip::new(&config);
+                goldilocks_chip.load_table(&mut layouter)?;
+                layouter.assign_region(
+                    || "poseidon hash",
+                    |region| {
+                        let ctx = &mut RegionCtx::new(region, 0);
+                        let inputs = self
+                            .inputs
+                            .iter()
+                            .map(|e| Value::known(goldilocks_to_fe(*e)))
+                            .map(|e| goldilocks_chip.assign_value(ctx, e))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        let outputs = poseidon_hash(ctx, &config, &inputs, self.expected.len())?;
+                        assert_eq!(outputs.len(), self.expected.len());
+                        for (output, expected) in outputs.iter().zip(self.expected.iter()) {
+                            let expected = goldilocks_chip.assign_constant(ctx, *expected)?;
+                            goldilocks_chip.assert_equal(ctx, output, &expected)?;
+                        }
+                        Ok(())
+                    },
+                )?;
+                Ok(())
+            }
+        }
+
+        // empty, within one rate and across absorptions
+        for num_inputs in [0, 1, 8, 9, 20] {
+            let inputs = GoldilocksField::rand_vec(num_inputs);
+            let expected = Bn254PoseidonHash::hash_no_pad(&inputs).elements.to_vec();
+            let circuit = PoseidonHashCircuit {
+                inputs: inputs.clone(),
+                expected,
+            };
+            mock_prove_small_table(&circuit, vec![vec![]])
+                .unwrap()
+                .assert_satisfied();
+
+            let mut expected = circuit.expected.clone();
+            expected[0] += GoldilocksField::ONE;
+            let circuit = PoseidonHashCircuit { inputs, expected };
+            let prover = mock_prove_small_table(&circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err(), "{num_inputs} inputs");
+        }
+    }
+
+    #[test]
+    fn test_tampered_proofs_match_plonky2_verify() {
+        use plonky2::{
+            field::{extension::quadratic::QuadraticExtension, types::Field},
+            plonk::{circuit_data::VerifierCircuitData, proof::ProofWithPublicInputs},
+        };
+
+        type Tamper =
+            fn(&mut ProofWithPublicInputs<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>);
+        let cases: [(&str, Tamper); 5] = [
+            ("valid", |_| {}),
+            ("wire opening", |proof| {
+                proof.proof.openings.wires[0] += QuadraticExtension::<GoldilocksField>::ONE
+            }),
+            ("wires cap", |proof| {
+                proof.proof.wires_cap.0[0].elements[0] += GoldilocksField::ONE
+            }),
+            ("pow witness", |proof| {
+                proof.proof.opening_proof.pow_witness += GoldilocksField::ONE
+            }),
+            ("public input", |proof| {
+                proof.public_inputs[0] += GoldilocksField::ONE
+            }),
+        ];
+
+        let (proof_with_public_inputs, vd, cd) = generate_tiny_proof_tuple();
+        let verifier_data = VerifierCircuitData {
+            verifier_only: vd.clone(),
+            common: cd.clone(),
+        };
+        for (location, tamper) in cases {
+            let mut proof_with_public_inputs = proof_with_public_inputs.clone();
+            tamper(&mut proof_with_public_inputs);
+            let plonky2_accepts = verifier_data
+                .verify(proof_with_public_inputs.clone())
+                .is_ok();
+            assert_eq!(plonky2_accepts, location == "valid", "{location}");
+
+            let (circuit, instances) =
+                mock_verifier((proof_with_public_inputs, vd.clone(), cd.clone()));
+            // a tamper may already fail synthesis, e.g. at the proof of work check
+            let satisfied = mock_prove_small_table(&circuit, vec![instances])
+                .map_or(false, |prover| prover.verify().is_ok());
+            assert_eq!(satisfied, plonky2_accepts, "{location}");
+        }
+    }
+
+    #[test]
+    fn test_instances_for_match_circuit_instances() {
+        use super::{
+            pack_goldilocks_instances, EvmVerifier, InstanceLayout, InstanceOptions,
+            InstancePacking,
+        };
+        use crate::plonky2_verifier::{
+            chip::native_chip::utils::{fe_to_goldilocks, goldilocks_to_fe},
+            types::{
+                common_data::CommonData, proof::ProofValues,
+                verification_key::VerificationKeyValues,
+            },
+            verifier_circuit::{PublicInputHandling, Verifier},
+        };
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use plonky2::field::types::PrimeField64;
+
+        let options = |public_input_handling, packing| InstanceOptions {
+            public_input_handling,
+            packing,
+        };
+        let proof_tuple = generate_tiny_proof_tuple();
+        let expected = InstanceLayout::instances_for(&proof_tuple, InstanceOptions::default());
+        let circuit = EvmVerifier::circuit(proof_tuple.clone());
+        assert_eq!(vec![circuit.instances()], expected);
+
+        let (proof_with_public_inputs, vd, cd) = proof_tuple.clone();
+        let public_inputs_hash = proof_with_public_inputs
+            .get_public_inputs_hash()
+            .elements
+            .map(goldilocks_to_fe::<Fr>);
+        for (public_input_handling, instances) in [
+            (
+                PublicInputHandling::Raw,
+                vec![Fr::from(3u64), Fr::from(10u64)],
+            ),
+            (PublicInputHandling::PreHashed, public_inputs_hash.to_vec()),
+            (
+                PublicInputHandling::RawWithHashInstance,
+                [Fr::from(3u64), Fr::from(10u64)]
+                    .into_iter()
+                    .chain(public_inputs_hash)
+                    .collect(),
+            ),
+        ] {
+            let expected = InstanceLayout::instances_for(
+                &proof_tuple,
+                options(public_input_handling, InstancePacking::Unpacked),
+            );
+            assert_eq!(expected[0][0], Fr::from(CIRCUIT_VERSION));
+            assert_eq!(expected[0][1..], instances);
+
+            // the wrapper takes the same elements, three to a word and without the version
+            let packed = InstanceLayout::instances_for(
+                &proof_tuple,
+                options(public_input_handling, InstancePacking::Goldilocks3),
+            );
+            let elements = instances
+                .iter()
+                .map(|e| fe_to_goldilocks(*e).to_canonical_u64())
+                .collect::<Vec<_>>();
+            assert_eq!(packed, vec![pack_goldilocks_instances(&elements).unwrap()]);
+
+            let circuit = Verifier::new(
+                ProofValues::<Fr, 2>::from(proof_with_public_inputs.proof.clone()),
+                instances,
+                VerificationKeyValues::from(vd.clone()),
+                CommonData::from(cd.clone()),
+            )
+            .with_public_input_handling(public_input_handling);
+            assert_eq!(vec![circuit.instances()], expected);
+            mock_prove_small_table(&circuit, expected)
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_raw_with_hash_instance() {
+        use super::{
+            circuit_instances, pack_goldilocks_instances, InstanceLayout, InstanceOptions,
+            InstancePacking,
+        };
+        use crate::plonky2_verifier::{
+            bn245_poseidon::plonky2_config::Bn254PoseidonHash,
+            chip::native_chip::utils::{fe_to_goldilocks, goldilocks_to_fe},
+            types::{
+                common_data::CommonData, proof::ProofValues,
+                verification_key::VerificationKeyValues,
+            },
+            verifier_circuit::{PublicInputHandling, Verifier},
+        };
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use plonky2::{field::types::PrimeField64, plonk::config::Hasher};
+
+        let proof_tuple = generate_tiny_proof_tuple();
+        let options = |packing| InstanceOptions {
+            public_input_handling: PublicInputHandling::RawWithHashInstance,
+            packing,
+        };
+        let expected =
+            InstanceLayout::instances_for(&proof_tuple, options(InstancePacking::Unpacked));
+        let packed =
+            InstanceLayout::instances_for(&proof_tuple, options(InstancePacking::Goldilocks3));
+        let (proof_with_public_inputs, vd, cd) = proof_tuple;
+        let public_inputs = proof_with_public_inputs.public_inputs.clone();
+        let instances = expected[0][1..].to_vec();
+        let native_hash = Bn254PoseidonHash::hash_no_pad(&public_inputs).elements;
+        let to_fe = |elements: &[GoldilocksField]| -> Vec<Fr> {
+            elements.iter().map(|e| goldilocks_to_fe(*e)).collect()
+        };
+        let (raw_instances, hash_instances) = instances.split_at(public_inputs.len());
+        assert_eq!(raw_instances, to_fe(&public_inputs));
+        assert_eq!(hash_instances, to_fe(&native_hash));
+
+        let verifier = |instances: Vec<Fr>| {
+            Verifier::new(
+                ProofValues::<Fr, 2>::from(proof_with_public_inputs.proof.clone()),
+                instances,
+                VerificationKeyValues::from(vd.clone()),
+                CommonData::from(cd.clone()),
+            )
+            .with_public_input_handling(PublicInputHandling::RawWithHashInstance)
+        };
+        let circuit = verifier(instances.clone());
+        mock_prove_small_table(&circuit, expected.clone())
+            .unwrap()
+            .assert_satisfied();
+
+        // the exposed hash doesn't match the one computed in-circuit, either because the
+        // instance column or the assigned hash is corrupted
+        let mut corrupted = instances.clone();
+        *corrupted.last_mut().unwrap() += Fr::from(1u64);
+        let prover = mock_prove_small_table(&circuit, vec![circuit_instances(&corrupted)]).unwrap();
+        assert!(prover.verify().is_err());
+        let circuit = verifier(corrupted);
+        let prover = mock_prove_small_table(&circuit, vec![circuit.instances()]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the hash elements are canonical, so the wrapper packs them with the public inputs
+        let layout = InstanceLayout {
+            num_instances: instances.len(),
+            packing: InstancePacking::Goldilocks3,
+        };
+        assert_eq!(layout.num_circuit_instances(), expected[0].len());
+        let elements = instances
+            .iter()
+            .map(|e| fe_to_goldilocks(*e).to_canonical_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(packed, vec![pack_goldilocks_instances(&elements).unwrap()]);
+        assert_eq!(packed[0].len(), (layout.num_instances + 2) / 3);
+    }
+
+    #[test]
+    fn test_quotient_openings_are_bound_to_cap() {
+        use plonky2::field::{
+            extension::quadratic::QuadraticExtension, goldilocks_field::GoldilocksField,
+            types::Field,
+        };
+
+        let mut proof = generate_tiny_proof_tuple();
+        let (verifier, _) = mock_verifier(proof.clone());
+        let trace = verifier.witness_trace().unwrap();
+
+        // shifts the first two quotient chunks so that `q_0 + zeta^n * q_1`, and with it the
+        // vanishing polynomial check, is unchanged. Only FRI can catch it.
+        let zeta_pow_deg =
+            QuadraticExtension(trace.challenges.plonk_zeta).exp_power_of_2(proof.2.degree_bits());
+        let delta = QuadraticExtension::<GoldilocksField>::ONE;
+        let quotient_polys = &mut proof.0.proof.openings.quotient_polys;
+        assert!(proof.2.quotient_degree_factor >= 2);
+        quotient_polys[0] += delta * zeta_pow_deg;
+        quotient_polys[1] -= delta;
+
+        // zeta is squeezed before the openings are observed
+        let (circuit, instances) = mock_verifier(proof);
+        let tampered_trace = circuit.witness_trace().unwrap();
+        assert_eq!(
+            tampered_trace.challenges.plonk_zeta,
+            trace.challenges.plonk_zeta
+        );
+
+        let prover = mock_prove_small_table(&circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_short_sigma_openings_are_rejected() {
+        use crate::plonky2_verifier::types::assigned::ShapeError;
+        use halo2_proofs::plonk::Error;
+
+        let mut proof = generate_tiny_proof_tuple();
+        let num_routed_wires = proof.2.config.num_routed_wires;
+        // moves the last sigma opening into the wires, so the transcript length is unchanged
+        let openings = &mut proof.0.proof.openings;
+        let sigma = openings.plonk_sigmas.pop().unwrap();
+        openings.wires.push(sigma);
+        let (verifier_circuit, instances) = mock_verifier(proof);
+        let err = mock_prove_small_table(&verifier_circuit, vec![instances])
+            .err()
+            .unwrap();
+        let Error::Transcript(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        let shape = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ShapeError>())
+            .unwrap();
+        assert_eq!(shape.expected, num_routed_wires);
+        assert_eq!(shape.actual + 1, shape.expected);
+    }
+
+    #[test]
+    fn test_public_inputs_up_to_trailing_zeros() {
+        use super::{
+            eq_up_to_trailing_zeros, verify_inside_snark_mock_with_public_inputs,
+            PublicInputsMismatch, VerifierApiError,
+        };
+        use crate::plonky2_verifier::fixtures::generate_zero_padded_proof_tuple;
+        use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+        let unpadded = generate_tiny_proof_tuple();
+        let padded = generate_zero_padded_proof_tuple(3);
+        let expected = unpadded.0.public_inputs.clone();
+        assert_eq!(padded.0.public_inputs.len(), expected.len() + 3);
+        assert!(eq_up_to_trailing_zeros(&padded.0.public_inputs, &expected));
+
+        verify_inside_snark_mock_with_public_inputs(19, unpadded, &expected).unwrap();
+        let padded_expected = [expected.clone(), vec![GoldilocksField::ZERO; 5]].concat();
+        verify_inside_snark_mock_with_public_inputs(19, padded.clone(), &padded_expected).unwrap();
+
+        // only zeros are padding
+        let mut other = expected.clone();
+        other.push(GoldilocksField::ONE);
+        let actual = padded.0.public_inputs.clone();
+        let err = verify_inside_snark_mock_with_public_inputs(19, padded, &other).unwrap_err();
+        let VerifierApiError::PublicInputsMismatch(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(
+            err,
+            PublicInputsMismatch {
+                expected: other,
+                actual
+            }
+        );
+        assert!(!eq_up_to_trailing_zeros(
+            &[GoldilocksField::ZERO, GoldilocksField::ONE],
+            &[]
+        ));
+    }
+
+    #[test]
+    fn test_tiny_circuit_halo2_mock() {
+        let proof = generate_tiny_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_zero_arity_step_halo2_mock() {
+        use crate::plonky2_verifier::fixtures::generate_zero_arity_step_proof_tuple;
+        use plonky2::plonk::circuit_data::VerifierCircuitData;
+
+        let (proof, vd, cd) = generate_zero_arity_step_proof_tuple();
+        assert_eq!(cd.fri_params.reduction_arity_bits, vec![1, 0, 1]);
+        let verifier_data = VerifierCircuitData {
+            verifier_only: vd.clone(),
+            common: cd.clone(),
+        };
+        verifier_data.verify(proof.clone()).unwrap();
+        verify_mock((proof, vd, cd));
+    }
+
+    #[test]
+    fn test_no_partial_products_halo2_mock() {
+        let proof = generate_no_partial_products_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_non_hiding_proof_halo2_mock() {
+        use crate::plonky2_verifier::types::{assigned::ShapeError, fri::SALT_SIZE};
+        use halo2_proofs::plonk::Error;
+
+        let proof = generate_tiny_proof_tuple();
+        assert!(!proof.2.config.zero_knowledge);
+        assert!(!proof.2.fri_params.hiding);
+        verify_mock(proof);
+
+        // reading the leaves with the wrong hiding flag misaligns the openings, which is
+        // rejected at the first salted oracle
+        for (hiding, mut proof) in [
+            (false, generate_tiny_proof_tuple()),
+            (true, generate_hiding_proof_tuple()),
+        ] {
+            assert_eq!(proof.2.fri_params.hiding, hiding);
+            proof.2.fri_params.hiding = !hiding;
+            let (circuit, instances) = mock_verifier(proof);
+            match mock_prove_small_table(&circuit, vec![instances]) {
+                Err(Error::Transcript(err)) => {
+                    let err = err.get_ref().unwrap().downcast_ref::<ShapeError>().unwrap();
+                    let (longer, shorter) = if hiding {
+                        (err.actual, err.expected)
+                    } else {
+                        (err.expected, err.actual)
+                    };
+                    assert_eq!(longer - shorter, SALT_SIZE);
+                }
+                _ => panic!("expected the leaves to be rejected"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_hiding_proof_halo2_mock() {
+        let proof = generate_hiding_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_random_access_extra_constants_halo2_mock() {
+        let proof = generate_random_access_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_minimal_circuit_halo2_mock() {
+        let proof = generate_minimal_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_quotient_degree_factor_4_halo2_mock() {
+        // the quotient polynomials are recombined and the partial products chunked by 4
+        let proof = generate_quotient_degree_factor_proof_tuple(4);
+        assert_eq!(
+            proof.2.num_partial_products,
+            proof.2.config.num_routed_wires.div_ceil(4) - 1
+        );
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_single_selector_group_halo2_mock() {
+        // a single selector group drops the unused selector term from every filter
+        let proof = generate_noop_only_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_two_random_access_gates_halo2_mock() {
+        let proof = generate_two_random_access_gates_proof_tuple();
+        verify_mock(proof);
+    }
+
+    #[test]
+    fn test_out_of_range_selector_is_rejected() {
+        use crate::plonky2_verifier::types::common_data::CommonDataError;
+        use halo2_proofs::plonk::Error;
+
+        let mut proof = generate_two_random_access_gates_proof_tuple();
+        let selectors_info = &mut proof.2.selectors_info;
+        let num_selectors = selectors_info.num_selectors();
+        let last = proof.2.gates.len() - 1;
+        selectors_info.selector_indices[last] = num_selectors;
+        let (verifier_circuit, instances) = mock_verifier(proof);
+        let err = mock_prove_small_table(&verifier_circuit, vec![instances])
+            .err()
+            .unwrap();
+        let Error::Transcript(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(
+            err.get_ref()
+                .and_then(|e| e.downcast_ref::<CommonDataError>()),
+            Some(&CommonDataError::SelectorIndexOutOfRange {
+                gate: last,
+                selector_index: num_selectors,
+                num_selectors,
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_srs_from_ptau() {
+        use super::{gen_srs, load_srs_from_ptau, SrsError};
+        use crate::plonky2_verifier::chip::native_chip::test_utils::TempDir;
+        use halo2_proofs::{poly::commitment::Params, SerdeFormat};
+
+        let dir = TempDir::new("srs-test").unwrap();
+        let path = dir.path().join("params.srs");
+        let mut bytes = vec![];
+        gen_srs(4, Some(1))
+            .write_custom(&mut bytes, SerdeFormat::RawBytes)
+            .unwrap();
+        std::fs::write(&path, &bytes).unwrap();
+        let checksum = blake2b_simd::blake2b(&bytes).to_hex().to_string();
+
+        let params = load_srs_from_ptau(&path, 3, &checksum).unwrap();
+        assert_eq!(params.k(), 3);
+        assert!(matches!(
+            load_srs_from_ptau(&path, 3, &"00".repeat(64)),
+            Err(SrsError::ChecksumMismatch { .. })
+        ));
+        // a corrupted point is reported as a checksum mismatch, whether it parses or not
+        let mut corrupted = bytes.clone();
+        corrupted[4] ^= 1;
+        std::fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(
+            load_srs_from_ptau(&path, 3, &checksum),
+            Err(SrsError::ChecksumMismatch { .. })
+        ));
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            load_srs_from_ptau(&path, 5, &checksum),
+            Err(SrsError::DegreeTooLow {
+                available: 4,
+                requested: 5
+            })
+        ));
+
+        // one G1 point short
+        let truncated = &bytes[..bytes.len() - 64];
+        std::fs::write(&path, truncated).unwrap();
+        let checksum = blake2b_simd::blake2b(truncated).to_hex().to_string();
+        assert!(matches!(
+            load_srs_from_ptau(&path, 3, &checksum),
+            Err(SrsError::PointCountMismatch { k: 4, .. })
+        ));
+
+        std::fs::write(&path, b"ptau\x01\x00\x00\x00").unwrap();
+        assert!(matches!(
+            load_srs_from_ptau(&path, 3, &checksum),
+            Err(SrsError::UnconvertedPtau)
+        ));
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_verify_bytes() {
+        use super::{verify_bytes, ConfigKind, EvmVerifier};
+        use plonky2::{
+            field::{extension::quadratic::QuadraticExtension, types::Field},
+            util::serialization::DefaultGateSerializer,
+        };
+
+        let (proof, vd, cd) = generate_tiny_proof_tuple();
+        let (param, pk, _) = EvmVerifier::setup((proof.clone(), vd.clone(), cd.clone())).unwrap();
+        let common = cd.to_bytes(&DefaultGateSerializer).unwrap();
+        let verifier_only = vd.to_bytes().unwrap();
+        // the keys are reused across proofs
+        for _ in 0..2 {
+            let verified = verify_bytes(
+                &param,
+                &pk,
+                &proof.to_bytes(),
+                &common,
+                &verifier_only,
+                ConfigKind::Bn254PoseidonGoldilocks,
+            )
+            .unwrap();
+            assert!(verified);
+        }
+
+        // a tampered proof still reads, it just doesn't verify
+        let mut tampered = proof;
+        tampered.proof.openings.wires[0] += QuadraticExtension::<GoldilocksField>::ONE;
+        let verified = verify_bytes(
+            &param,
+            &pk,
+            &tampered.to_bytes(),
+            &common,
+            &verifier_only,
+            ConfigKind::Bn254PoseidonGoldilocks,
+        )
+        .unwrap();
+        assert!(!verified);
+    }
+
+    #[test]
+    fn test_read_proof_bytes_rejects_tampered_proof() {
+        use super::{read_proof_bytes, ConfigKind, ProofEncoding, VerifyBytesError};
+        use plonky2::{
+            field::{extension::quadratic::QuadraticExtension, types::Field},
+            util::serialization::DefaultGateSerializer,
+        };
+
+        let (mut proof, vd, cd) = generate_tiny_proof_tuple();
+        proof.proof.openings.wires[0] += QuadraticExtension::<GoldilocksField>::ONE;
+        let err = read_proof_bytes(
+            &proof.to_bytes(),
+            &cd.to_bytes(&DefaultGateSerializer).unwrap(),
+            &vd.to_bytes().unwrap(),
+            ConfigKind::Bn254PoseidonGoldilocks,
+            ProofEncoding::Uncompressed,
+        )
+        .unwrap_err();
+        assert!(matches!(err, VerifyBytesError::InvalidProof(_)), "{err}");
+    }
+
+    #[test]
+    fn test_duplicate_query_indices() {
+        use super::{decompress_proof, deserialize_proof_tuple, ProofEncoding};
+        use crate::plonky2_verifier::fixtures::generate_duplicate_query_proof_tuple;
+        use plonky2::{field::types::PrimeField64, util::serialization::DefaultGateSerializer};
+        use std::collections::HashSet;
+
+        let (proof, vd, cd) = generate_duplicate_query_proof_tuple();
+        let (verifier, _) = mock_verifier((proof.clone(), vd.clone(), cd.clone()));
+        let trace = verifier.witness_trace().unwrap();
+        let lde_size = 1 << cd.fri_params.lde_bits();
+        let indices = trace
+            .challenges
+            .fri_query_indices
+            .iter()
+            .map(|index| index.to_canonical_u64() % lde_size)
+            .collect::<Vec<_>>();
+        let distinct = indices.iter().collect::<HashSet<_>>().len();
+        assert!(
+            distinct < indices.len(),
+            "no duplicate query index in {indices:?}"
+        );
+
+        // uncompressed, plonky2 keeps a round per query
+        assert_eq!(
+            proof.proof.opening_proof.query_round_proofs.len(),
+            indices.len()
+        );
+        verify_mock((proof.clone(), vd.clone(), cd.clone()));
+
+        // compressed, the initial tree proofs are stored once per distinct index and every
+        // round is restored on decompression
+        let compressed = proof.clone().compress(&vd.circuit_digest, &cd).unwrap();
+        let compressed_rounds = &compressed.proof.opening_proof.query_round_proofs;
+        assert_eq!(compressed_rounds.indices.len(), indices.len());
+        assert_eq!(compressed_rounds.initial_trees_proofs.len(), distinct);
+        let bytes = compressed.to_bytes();
+        assert_eq!(decompress_proof(compressed, &vd, &cd).unwrap(), proof);
+
+        let common = cd.to_bytes(&DefaultGateSerializer).unwrap();
+        let (decompressed, vd, cd) = deserialize_proof_tuple::<Bn254PoseidonGoldilocksConfig>(
+            &bytes,
+            &common,
+            &vd.to_bytes().unwrap(),
+            ProofEncoding::Compressed,
+        )
+        .unwrap();
+        assert_eq!(decompressed, proof);
+        verify_mock((decompressed, vd, cd));
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_verify_bytes_poseidon_goldilocks_config() {
+        use super::{read_proof_bytes, verify_bytes, ConfigKind, EvmVerifier, ProofEncoding};
+        use crate::plonky2_verifier::fixtures::generate_tiny_proof_tuple_with_config;
+        use plonky2::{
+            plonk::config::PoseidonGoldilocksConfig, util::serialization::DefaultGateSerializer,
+        };
+
+        let (proof, vd, cd) = generate_tiny_proof_tuple_with_config::<PoseidonGoldilocksConfig>();
+        let proof = proof.to_bytes();
+        let common = cd.to_bytes(&DefaultGateSerializer).unwrap();
+        let verifier_only = vd.to_bytes().unwrap();
+        let config = ConfigKind::PoseidonGoldilocks;
+        let wrapped = read_proof_bytes(
+            &proof,
+            &common,
+            &verifier_only,
+            config,
+            ProofEncoding::Uncompressed,
+        )
+        .unwrap();
+        let (param, pk, _) = EvmVerifier::setup(wrapped).unwrap();
+        let verified = verify_bytes(&param, &pk, &proof, &common, &verifier_only, config).unwrap();
+        assert!(verified);
+    }
+
+    #[test]
+    fn test_read_proof_bytes_wraps_poseidon_goldilocks_proofs() {
+        use super::{read_proof_bytes, ConfigKind, ProofEncoding};
+        use crate::plonky2_verifier::fixtures::generate_tiny_proof_tuple_with_config;
+        use plonky2::{
+            plonk::config::PoseidonGoldilocksConfig, util::serialization::DefaultGateSerializer,
+        };
+
+        let (proof, vd, cd) = generate_tiny_proof_tuple_with_config::<PoseidonGoldilocksConfig>();
+        let (wrapped, _, _) = read_proof_bytes(
+            &proof.to_bytes(),
+            &cd.to_bytes(&DefaultGateSerializer).unwrap(),
+            &vd.to_bytes().unwrap(),
+            ConfigKind::PoseidonGoldilocks,
+            ProofEncoding::Uncompressed,
+        )
+        .unwrap();
+        assert_eq!(wrapped.public_inputs, proof.public_inputs);
+    }
+
+    #[test]
+    fn test_verify_bytes_rejects_other_extension_degree() {
+        use super::{read_proof_bytes, ConfigKind, ProofEncoding, VerifyBytesError};
+        use plonky2::{
+            gates::{arithmetic_extension::ArithmeticExtensionGate, gate::GateRef},
+            util::serialization::DefaultGateSerializer,
+        };
+
+        let (proof, vd, mut cd) = generate_tiny_proof_tuple();
+        // lay out the extension arithmetic gate the way a `D = 4` circuit builder does
+        let num_ops = ArithmeticExtensionGate::<4>::new_from_config(&cd.config).num_ops;
+        cd.gates
+            .retain(|gate| !gate.0.as_any().is::<ArithmeticExtensionGate<2>>());
+        cd.gates
+            .push(GateRef::new(ArithmeticExtensionGate::<2> { num_ops }));
+        let common = cd.to_bytes(&DefaultGateSerializer).unwrap();
+        let err = read_proof_bytes(
+            &proof.to_bytes(),
+            &common,
+            &vd.to_bytes().unwrap(),
+            ConfigKind::Bn254PoseidonGoldilocks,
+            ProofEncoding::Uncompressed,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            VerifyBytesError::UnsupportedExtensionDegree(4)
+        ));
+    }
+
+    #[test]
+    fn test_verify_bytes_rejects_unknown_gate() {
+        use super::{
+            read_proof_bytes, ConfigKind, ProofEncoding, VerifyBytesError, PLONKY2_VERSION,
+        };
+        use plonky2::{
+            gates::{arithmetic_base::ArithmeticGate, gate::GateRef},
+            util::serialization::DefaultGateSerializer,
+        };
+
+        let (proof, vd, mut cd) = generate_tiny_proof_tuple();
+        // a gate layout the dispatch doesn't know, like the ids of another plonky2 release
+        cd.gates.push(GateRef::new(ArithmeticGate { num_ops: 3 }));
+        let common = cd.to_bytes(&DefaultGateSerializer).unwrap();
+        let err = read_proof_bytes(
+            &proof.to_bytes(),
+            &common,
+            &vd.to_bytes().unwrap(),
+            ConfigKind::Bn254PoseidonGoldilocks,
+            ProofEncoding::Uncompressed,
+        )
+        .unwrap_err();
+        // the error names the plonky2 version of the build, when it's known
+        if let Some(version) = PLONKY2_VERSION {
+            assert!(err.to_string().contains(version), "{err}");
+        }
+        match err {
+            VerifyBytesError::UnsupportedGate(id) => {
+                assert_eq!(id, "ArithmeticGate { num_ops: 3 }")
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_proof_artifact() {
+        use super::{ArtifactMetadata, EvmVerifier, ProofArtifact, VerifierApiError};
+
+        let (param, pk, circuit) = EvmVerifier::setup(generate_tiny_proof_tuple()).unwrap();
+        let artifact = ProofArtifact::prove(&param, &pk, circuit).unwrap();
+        assert_eq!(artifact.metadata, ArtifactMetadata::current());
+        artifact.verify(&param, pk.get_vk()).unwrap();
+
+        let mut other_version = artifact.clone();
+        other_version.metadata.circuit_version += 1;
+        let err = other_version.verify(&param, pk.get_vk()).unwrap_err();
+        let VerifierApiError::VersionMismatch(mismatch) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(mismatch.artifact, other_version.metadata);
+        assert_eq!(mismatch.current, ArtifactMetadata::current());
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_recursive_halo2_proof() {
+        let proof = generate_proof_tuple();
+        verify_inside_snark(19, proof).unwrap();
+    }
+
+    /// `PoseidonGoldilocksConfig` proofs are verified through a wrapper circuit, see
+    /// `compat::v0`, which is only worth it while verifying the wrapped proof costs more.
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_bn254_config_takes_fewer_rows_than_wrapped_proof() {
+        use super::{used_rows, EvmVerifier};
+        use crate::{
+            compat::v0::wrap_proof,
+            plonky2_verifier::fixtures::generate_tiny_proof_tuple_with_config,
+        };
+        use plonky2::plonk::config::PoseidonGoldilocksConfig;
+
+        fn rows(proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>) -> usize {
+            let circuit = EvmVerifier::circuit(proof);
+            used_rows(&circuit, &circuit.instances()).unwrap()
+        }
+
+        let bn254 = rows(generate_tiny_proof_tuple());
+        let wrapped = rows(
+            wrap_proof(generate_tiny_proof_tuple_with_config::<
+                PoseidonGoldilocksConfig,
+            >())
+            .unwrap(),
+        );
+        assert!(
+            bn254 < wrapped,
+            "the BN254 Poseidon proof takes {bn254} rows to verify, the wrapped one {wrapped}"
+        );
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_tiny_circuit_halo2_proof() {
+        let proof = generate_tiny_proof_tuple();
+        verify_inside_snark(19, proof).unwrap();
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_evm_verifier_export() {
+        use super::{
+            gen_evm_verifier, gen_evm_verifier_wrapper, keccak256_hex, EvmVerifier, InstanceLayout,
+            InstancePacking, Manifest,
+        };
+        use crate::plonky2_verifier::chip::native_chip::test_utils::TempDir;
+        use halo2_proofs::poly::commitment::Params;
+        use halo2_solidity_verifier::compile_solidity;
+
+        let proof = generate_tiny_proof_tuple();
+        let num_instances = proof.0.public_inputs.len();
+        let (param, pk, _) = EvmVerifier::setup(proof).unwrap();
+        let layout = InstanceLayout {
+            num_instances,
+            packing: InstancePacking::Goldilocks3,
+        };
+        let dir = TempDir::new("evm-export-test").unwrap();
+        let manifest = EvmVerifier::export(dir.path(), &param, pk.get_vk(), &layout).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        let read_back: Manifest = serde_json::from_str(&read(Manifest::FILE_NAME)).unwrap();
+        assert_eq!(read_back, manifest);
+        assert_eq!(manifest.k, param.k());
+        assert_eq!(manifest.instance_layout, layout);
+        assert_eq!(manifest.circuit_version, CIRCUIT_VERSION);
+
+        let (verifier_creation_code, vk_creation_code) =
+            gen_evm_verifier(&param, pk.get_vk(), layout.num_circuit_instances());
+        assert_eq!(
+            hex::decode(read("deployment.bin")).unwrap(),
+            verifier_creation_code
+        );
+        assert_eq!(
+            hex::decode(read("vk_deployment.bin")).unwrap(),
+            vk_creation_code
+        );
+        // the runtime code is what the deployment code returns, so it's part of it
+        let runtime_code = hex::decode(read("runtime.bin")).unwrap();
+        assert!(verifier_creation_code
+            .windows(runtime_code.len())
+            .any(|window| window == runtime_code));
+
+        let wrapper_solidity = read("wrapper.sol");
+        assert_eq!(wrapper_solidity, gen_evm_verifier_wrapper(num_instances));
+        let wrapper_creation_code = hex::decode(read("wrapper_deployment.bin")).unwrap();
+        assert_eq!(wrapper_creation_code, compile_solidity(&wrapper_solidity));
+        assert_eq!(
+            manifest.wrapper_deployment_code_hash,
+            Some(keccak256_hex(&wrapper_creation_code))
+        );
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_evm_verifier_setup() {
+        use super::verify_proof_bytes;
+        use super::{gen_proof, EvmVerifier};
+        use halo2_proofs::{dev::MockProver, plonk::Error, poly::commitment::Params};
+
+        let (param, pk, circuit) = EvmVerifier::setup(generate_tiny_proof_tuple()).unwrap();
+        let instances = circuit.instances();
+        // `k` is minimal
+        if param.k() > MIN_K {
+            let result = MockProver::run(param.k() - 1, &circuit, vec![instances.clone()]);
+            assert!(matches!(result, Err(Error::NotEnoughRowsAvailable { .. })));
+        }
+
+        let proof = gen_proof(&param, &pk, circuit).unwrap();
+        verify_proof_bytes(&param, pk.get_vk(), &proof, &instances).unwrap();
+    }
+
+    #[test]
+    fn test_deterministic_proving_mode_rng() {
+        use super::ProvingMode;
+
+        let draw = |mode: ProvingMode| mode.rng().next_u64();
+        assert_eq!(
+            draw(ProvingMode::Deterministic(7)),
+            draw(ProvingMode::Deterministic(7))
+        );
+        assert_ne!(
+            draw(ProvingMode::Deterministic(7)),
+            draw(ProvingMode::Deterministic(8))
+        );
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_deterministic_proving_mode() {
+        use super::verify_proof_bytes;
+        use super::{gen_proof_with_mode, EvmVerifier, ProvingMode};
+
+        // the proof opens with the commitment to the first advice column, which is blinded
+        const ADVICE_COMMITMENT_BYTES: usize = 64;
+
+        let (param, pk, circuit) = EvmVerifier::setup(generate_tiny_proof_tuple()).unwrap();
+        let instances = circuit.instances();
+        let prove = |mode| {
+            let proof = gen_proof_with_mode(&param, &pk, circuit.clone(), mode).unwrap();
+            verify_proof_bytes(&param, pk.get_vk(), &proof, &instances).unwrap();
+            proof
+        };
+
+        let deterministic = prove(ProvingMode::Deterministic(7));
+        assert_eq!(deterministic, prove(ProvingMode::Deterministic(7)));
+        let other_seed = prove(ProvingMode::Deterministic(8));
+        assert_ne!(
+            deterministic[..ADVICE_COMMITMENT_BYTES],
+            other_seed[..ADVICE_COMMITMENT_BYTES]
+        );
+        let randomized = prove(ProvingMode::Randomized);
+        assert_ne!(
+            randomized[..ADVICE_COMMITMENT_BYTES],
+            prove(ProvingMode::Randomized)[..ADVICE_COMMITMENT_BYTES]
+        );
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_setup_retries_at_higher_k() {
+        use super::{min_k, EvmVerifier, MemoryGuard, MAX_K};
+        use halo2_proofs::poly::commitment::Params;
+
+        // the lookup table alone doesn't fit below `MIN_K`
+        let initial_k = MIN_K - 1;
+        let (param, _, circuit, stats) = EvmVerifier::setup_with_retry(
+            generate_tiny_proof_tuple(),
+            initial_k,
+            MAX_K,
+            None,
+            MemoryGuard::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.final_k, param.k());
+        assert_eq!(
+            stats.final_k,
+            min_k(&circuit, &circuit.instances()).unwrap()
+        );
+        assert_eq!(
+            stats.attempted_k,
+            (initial_k..=stats.final_k).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_setup_retry_max_k_exceeded() {
+        use super::{
+            gen_pk_with_guard, gen_srs, EvmVerifier, MaxKExceeded, MemoryGuard, SrsError,
+            VerifierApiError,
+        };
+
+        // the retry keys off keygen running out of rows
+        let circuit = EvmVerifier::circuit(generate_tiny_proof_tuple());
+        let param = gen_srs(MIN_K - 1, Some(0));
+        let guard = MemoryGuard {
+            force: true,
+            ..MemoryGuard::default()
+        };
+        assert!(matches!(
+            gen_pk_with_guard(&param, &circuit, guard),
+            Err(VerifierApiError::NotEnoughRows { k }) if k == MIN_K - 1
+        ));
+
+        let err = EvmVerifier::setup_with_retry(
+            generate_tiny_proof_tuple(),
+            MIN_K - 2,
+            MIN_K - 1,
+            None,
+            MemoryGuard::default(),
+        )
+        .err()
+        .unwrap();
+        let VerifierApiError::MaxKExceeded(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(
+            err,
+            MaxKExceeded {
+                attempted_k: vec![MIN_K - 2, MIN_K - 1],
+                max_k: MIN_K - 1,
+            }
+        );
+
+        // a supplied SRS has to reach the degree of the retry
+        let srs = gen_srs(MIN_K - 1, Some(0));
+        let err = EvmVerifier::setup_with_retry(
+            generate_tiny_proof_tuple(),
+            MIN_K - 1,
+            MIN_K,
+            Some(&srs),
+            MemoryGuard::default(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            VerifierApiError::Srs(SrsError::DegreeTooLow {
+                available,
+                requested,
+            }) if available == MIN_K - 1 && requested == MIN_K
+        ));
+    }
+
+    #[test]
+    fn test_memory_estimate_is_monotonic() {
+        use super::{constraint_system, estimate_memory, MAX_K};
+        use crate::plonky2_verifier::verifier_circuit::Verifier;
+
+        let cs = constraint_system::<Verifier>();
+        for k in MIN_K..MAX_K {
+            assert!(estimate_memory(k, &cs) < estimate_memory(k + 1, &cs));
+        }
+
+        let mut wider_cs = cs.clone();
+        wider_cs.advice_column();
+        wider_cs.fixed_column();
+        for k in MIN_K..=MAX_K {
+            assert!(estimate_memory(k, &cs) < estimate_memory(k, &wider_cs));
+        }
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_memory_guard_refuses_above_limit() {
+        use super::{
+            constraint_system, estimate_memory, gen_pk_with_guard, gen_srs, MemoryGuard,
+            ResourceLimit, VerifierApiError,
+        };
+        use crate::plonky2_verifier::verifier_circuit::Verifier;
+
+        let (circuit, _) = mock_verifier(generate_tiny_proof_tuple());
+        let estimated_bytes = estimate_memory(MIN_K, &constraint_system::<Verifier>());
+
+        let guard = MemoryGuard {
+            max_bytes: Some(estimated_bytes),
+            force: false,
+        };
+        assert_eq!(guard.check::<Verifier>(MIN_K), Ok(()));
+
+        let guard = MemoryGuard {
+            max_bytes: Some(estimated_bytes - 1),
+            force: false,
+        };
+        let expected = ResourceLimit {
+            k: MIN_K,
+            estimated_bytes,
+            limit_bytes: estimated_bytes - 1,
+        };
+        assert_eq!(guard.check::<Verifier>(MIN_K), Err(expected));
+        assert_eq!(
+            MemoryGuard {
+                force: true,
+                ..guard
+            }
+            .check::<Verifier>(MIN_K),
+            Ok(())
+        );
+
+        // refused before any key is generated
+        let param = gen_srs(MIN_K, Some(0));
+        match gen_pk_with_guard(&param, &circuit, guard) {
+            Err(VerifierApiError::ResourceLimit(err)) => assert_eq!(err, expected),
+            _ => panic!("expected the memory guard to refuse keygen"),
+        }
+    }
+
+    /// Proves `proof` and verifies it through the `gen_evm_verifier_wrapper` contract, checking
+    /// that the contract unpacks exactly the Goldilocks public inputs of the proof.
+    fn assert_evm_verifier_wrapper_accepts(
+        proof: ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>,
+    ) {
+        use super::{
+            encode_wrapper_calldata, function_selector, gen_evm_verifier, gen_evm_verifier_wrapper,
+            gen_srs, pack_goldilocks_instances,
+        };
+        use crate::plonky2_verifier::chip::native_chip::test_utils::create_proof_checked;
+        use halo2_proofs::{
+            halo2curves::ff::PrimeField,
+            plonk::{keygen_pk, keygen_vk},
+        };
+        use halo2_solidity_verifier::{compile_solidity, Evm};
+        use plonky2::field::types::PrimeField64;
+
+        let public_inputs = proof.0.public_inputs.clone();
+        let (circuit, instances) = mock_verifier(proof);
+        let param = gen_srs(19, None);
+        let vk = keygen_vk(&param, &circuit).unwrap();
+        let pk = keygen_pk(&param, vk.clone(), &circuit).unwrap();
+        let proof = create_proof_checked(&param, &pk, circuit, &instances, rand::thread_rng());
+
+        let mut evm = Evm::default();
+        let (verifier_creation_code, vk_creation_code) =
+            gen_evm_verifier(&param, &vk, instances.len());
+        let verifier_address: [u8; 20] = evm.create(verifier_creation_code).into();
+        let vk_address: [u8; 20] = evm.create(vk_creation_code).into();
+        let mut wrapper_creation_code =
+            compile_solidity(gen_evm_verifier_wrapper(public_inputs.len()));
+        for address in [verifier_address, vk_address] {
+            wrapper_creation_code.extend([0; 12]);
+            wrapper_creation_code.extend(address);
+        }
+        let wrapper_address = evm.create(wrapper_creation_code);
+
+        let word = |value: u64| {
+            let mut word = [0u8; 32];
+            word[24..].copy_from_slice(&value.to_be_bytes());
+            word.to_vec()
+        };
+        let (_, output) = evm.call(
+            wrapper_address,
+            function_selector("numPackedPublicInputs()").to_vec(),
+        );
+        assert_eq!(output, word((public_inputs.len() as u64 + 2) / 3));
+        let (_, output) = evm.call(
+            wrapper_address,
+            function_selector("circuitVersion()").to_vec(),
+        );
+        assert_eq!(output, word(CIRCUIT_VERSION));
+
+        // the contract unpacks the words the same way the circuit does
+        let public_inputs = public_inputs
+            .iter()
+            .map(|e| e.to_canonical_u64())
+            .collect::<Vec<_>>();
+        let packed = pack_goldilocks_instances(&public_inputs).unwrap();
+        for (i, packed) in packed.iter().enumerate() {
+            let mut calldata = function_selector("unpackGoldilocks(uint256)").to_vec();
+            calldata.extend(packed.to_repr().iter().rev());
+            let (_, output) = evm.call(wrapper_address, calldata);
+            let expected = (3 * i..3 * i + 3)
+                .flat_map(|i| word(public_inputs.get(i).copied().unwrap_or(0)))
+                .collect::<Vec<_>>();
+            assert_eq!(output, expected);
+        }
+
+        let (_, output) = evm.call(wrapper_address, encode_wrapper_calldata(&proof, &packed));
+        assert_eq!(output, word(1));
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_evm_verifier_wrapper() {
+        assert_evm_verifier_wrapper_accepts(generate_tiny_proof_tuple());
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_evm_verifier_wrapper_high_bit_public_inputs() {
+        assert_evm_verifier_wrapper_accepts(generate_high_bit_proof_tuple());
+    }
+
+    #[test]
+    fn test_high_bit_public_inputs() {
+        use super::pack_goldilocks_instances;
+        use crate::plonky2_verifier::{
+            bn245_poseidon::native::decode_fe, chip::native_chip::utils::fe_to_goldilocks,
+        };
+        use plonky2::field::types::PrimeField64;
+
+        let proof = generate_high_bit_proof_tuple();
+        let public_inputs = proof.0.public_inputs.clone();
+        assert!(public_inputs
+            .iter()
+            .all(|e| e.to_canonical_u64() >> 63 == 1));
+
+        // instances round trip through the scalar field
+        let (verifier, instances) = mock_verifier(proof.clone());
+        let round_trip = instances[1..]
+            .iter()
+            .map(|e| fe_to_goldilocks(*e))
+            .collect::<Vec<_>>();
+        assert_eq!(round_trip, public_inputs);
+
+        // the public inputs hash derived from the instances is plonky2's
+        let trace = verifier.witness_trace().unwrap();
+        assert_eq!(
+            trace.public_inputs_hash,
+            proof.0.get_public_inputs_hash().elements
+        );
+
+        // packing three per word unpacks to the same values
+        let canonical = public_inputs
+            .iter()
+            .map(|e| e.to_canonical_u64())
+            .collect::<Vec<_>>();
+        let packed = pack_goldilocks_instances(&canonical).unwrap();
+        assert_eq!(packed.len(), 1);
+        assert_eq!(decode_fe(packed[0]).to_vec(), public_inputs);
+
+        verify_mock(proof);
+    }
+
+    #[test]
+    #[ignore = "end-to-end, run with `make test-full`"]
+    fn test_goldilocks_packing_boundaries() {
+        use super::{
+            function_selector, gen_evm_verifier_wrapper, pack_goldilocks_instances,
+            NonCanonicalGoldilocks, GOLDILOCKS_MODULUS,
+        };
+        use halo2_proofs::halo2curves::ff::PrimeField;
+        use halo2_solidity_verifier::{compile_solidity, Evm};
+        use num_bigint::BigUint;
+        use std::panic::{self, AssertUnwindSafe};
+
+        let p = GOLDILOCKS_MODULUS;
+        let word = |value: &BigUint| {
+            let bytes = value.to_bytes_be();
+            let mut word = [0u8; 32];
+            word[32 - bytes.len()..].copy_from_slice(&bytes);
+            word.to_vec()
+        };
+        let u64_word = |value: u64| word(&BigUint::from(value));
+
+        // the verifier and vk addresses are never called by the pure helpers
+        let mut evm = Evm::default();
+        let mut creation_code = compile_solidity(gen_evm_verifier_wrapper(3));
+        creation_code.extend([0; 64]);
+        let wrapper_address = evm.create(creation_code);
+
+        let boundary = [0, 1, p - 1];
+        for x0 in boundary {
+            for x1 in boundary {
+                for x2 in boundary {
+                    let elements = [x0, x1, x2];
+                    let packed = pack_goldilocks_instances(&elements).unwrap();
+                    assert_eq!(packed.len(), 1);
+                    let packed_word = packed[0]
+                        .to_repr()
+                        .iter()
+                        .rev()
+                        .copied()
+                        .collect::<Vec<_>>();
+
+                    let mut calldata = function_selector("packGoldilocks(uint64[3])").to_vec();
+                    calldata.extend(elements.iter().flat_map(|e| u64_word(*e)));
+                    let (_, output) = evm.call(wrapper_address, calldata);
+                    assert_eq!(output, packed_word);
+
+                    let mut calldata = function_selector("unpackGoldilocks(uint256)").to_vec();
+                    calldata.extend(packed_word);
+                    let (_, output) = evm.call(wrapper_address, calldata);
+                    let expected = elements
+                        .iter()
+                        .flat_map(|e| u64_word(*e))
+                        .collect::<Vec<_>>();
+                    assert_eq!(output, expected);
+                }
+            }
+        }
+
+        assert_eq!(
+            pack_goldilocks_instances(&[1, p]),
+            Err(NonCanonicalGoldilocks { index: 1, value: p })
+        );
+        let mut reverts = |calldata: Vec<u8>| {
+            panic::catch_unwind(AssertUnwindSafe(|| evm.call(wrapper_address, calldata))).is_err()
+        };
+        let mut calldata = function_selector("packGoldilocks(uint64[3])").to_vec();
+        calldata.extend([1, p, 0].iter().flat_map(|e| u64_word(*e)));
+        assert!(reverts(calldata));
+        let mut calldata = function_selector("unpackGoldilocks(uint256)").to_vec();
+        calldata.extend(word(&BigUint::from(p).pow(3)));
+        assert!(reverts(calldata));
+    }
+
+    #[cfg(feature = "solidity")]
+    const GOLDEN_DEGREE: u32 = 19;
+
+    #[cfg(feature = "solidity")]
+    const GOLDEN_SRS_SEED: u64 = 0x5eed;
+
+    /// Keccak256 of the verifier deployment code followed by the vk deployment code, generated
+    /// for `generate_tiny_proof_tuple` at `GOLDEN_DEGREE` with the SRS seeded by
+    /// `GOLDEN_SRS_SEED`. `None` until it's pinned from a run of `make test-golden`.
+    #[cfg(feature = "solidity")]
+    const EXPECTED_EVM_VERIFIER_HASH: Option<&str> = None;
+
+    #[cfg(feature = "solidity")]
+    #[test]
+    fn test_evm_verifier_golden() {
+        use super::{gen_evm_verifier, gen_srs, keccak256_hex, EvmVerifier};
+        use halo2_proofs::plonk::keygen_vk;
+
+        let circuit = EvmVerifier::circuit(generate_tiny_proof_tuple());
+        let param = gen_srs(GOLDEN_DEGREE, Some(GOLDEN_SRS_SEED));
+        let vk = keygen_vk(&param, &circuit).unwrap();
+        let (verifier_creation_code, vk_creation_code) =
+            gen_evm_verifier(&param, &vk, circuit.instances().len());
+        let hash = keccak256_hex(&[verifier_creation_code, vk_creation_code].concat());
+        let expected = EXPECTED_EVM_VERIFIER_HASH.unwrap_or_else(|| {
+            panic!("no EVM verifier hash is pinned, set EXPECTED_EVM_VERIFIER_HASH to {hash}")
+        });
+        assert_eq!(
+            hash, expected,
+            "EVM verifier deployment code changed. Deployed verifiers are pinned downstream, so \
+             this must only happen when the verifier circuit is changed on purpose. In that case, \
+             bump CIRCUIT_VERSION, set EXPECTED_EVM_VERIFIER_HASH to {hash} and mention the \
+             change in the release notes."
+        );
     }
 }
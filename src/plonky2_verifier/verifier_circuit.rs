@@ -6,30 +6,69 @@ use halo2_proofs::{
 };
 use halo2wrong_maingate::{AssignedValue, MainGate, MainGateConfig, RangeChip, RangeConfig};
 use itertools::Itertools;
-use plonky2::plonk::{
-    circuit_data::{CommonCircuitData, VerifierOnlyCircuitData},
-    proof::ProofWithPublicInputs,
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::Field},
+    plonk::{
+        circuit_data::{CommonCircuitData, VerifierOnlyCircuitData},
+        proof::ProofWithPublicInputs,
+    },
 };
-use std::marker::PhantomData;
+use std::{fmt, io, iter, marker::PhantomData};
 
 use super::{
+    bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
     chip::{
+        fri_chip::QueryRoundProofs,
         goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
-        native_chip::all_chip::AllChipConfig,
+        native_chip::{all_chip::AllChipConfig, utils::fe_to_goldilocks},
         plonk::plonk_verifier_chip::PlonkVerifierChip,
     },
-    context::RegionCtx,
+    context::{RegionCtx, RowBudget},
     types::{
         assigned::{
-            AssignedProofValues, AssignedProofWithPisValues, AssignedVerificationKeyValues,
+            to_array, AssignedHashValues, AssignedProofChallenges, AssignedProofValues,
+            AssignedProofWithPisValues, AssignedVerificationKeyValues, ShapeError,
         },
         common_data::CommonData,
         proof::{FriProofValues, OpeningSetValues},
         verification_key::VerificationKeyValues,
-        HashValues, MerkleCapValues,
+        MerkleCapValues,
     },
+    witness_trace::WitnessTrace,
 };
 
+/// Extension degree of the inner proofs the verifier circuit supports. The public entry points
+/// take `ProofTuple<_, _, SUPPORTED_EXTENSION_DEGREE>`, and `Bn254PoseidonGoldilocksConfig` only
+/// implements `GenericConfig<2>`, so proofs of other degrees are rejected at compile time.
+pub const SUPPORTED_EXTENSION_DEGREE: usize = 2;
+
+/// Version of the verifier circuit, exposed as the first instance of `Verifier` and
+/// `BatchVerifier` so that on-chain consumers can tell proofs of different circuits apart. Bump
+/// it with every change of the constraint system, `test_evm_verifier_golden` fails until the
+/// pinned deployment code is updated along with it.
+pub const CIRCUIT_VERSION: u64 = 2;
+
+/// Assigns `CIRCUIT_VERSION` as a constant and exposes it as the first instance, so a proof
+/// only verifies against instances carrying this version.
+fn expose_circuit_version(
+    goldilocks_chip: &GoldilocksChip<Fr>,
+    layouter: &mut impl Layouter<Fr>,
+) -> Result<(), Error> {
+    let version = layouter.assign_region(
+        || "Circuit version",
+        |region| {
+            let ctx = &mut RegionCtx::new(region, 0);
+            let version = GoldilocksField::from_canonical_u64(CIRCUIT_VERSION);
+            goldilocks_chip.assign_constant(ctx, version)
+        },
+    )?;
+    goldilocks_chip.arithmetic_chip().expose_public(
+        layouter.namespace(|| "circuit version"),
+        version,
+        0,
+    )
+}
+
 pub type ProofTuple<F, C, const D: usize> = (
     ProofWithPublicInputs<F, C, D>,
     VerifierOnlyCircuitData<C, D>,
@@ -55,12 +94,98 @@ impl<F: PrimeField> MainGateWithRangeConfig<F> {
     }
 }
 
+/// How the instances of the verifier circuit relate to the public inputs of the inner proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PublicInputHandling {
+    /// The instances are the public inputs of the inner proof, hashed in-circuit.
+    #[default]
+    Raw,
+    /// The instances are the 4 elements of the public inputs hash of the inner proof, as
+    /// returned by `ProofWithPublicInputs::get_public_inputs_hash`. They are used as the hash
+    /// directly, which skips the in-circuit Poseidon permutations over the public inputs.
+    ///
+    /// Note that plonky2 always hashes the public inputs, even when an inner circuit exposes a
+    /// hash as its 4 public inputs, so the instances are the hash of those 4 values.
+    PreHashed,
+    /// The instances are the public inputs of the inner proof followed by the 4 elements of
+    /// their hash. The public inputs are hashed in-circuit as in `Raw` and the result is
+    /// constrained to equal the hash instances, so a verifier of the outer proof can check
+    /// either without recomputing the other.
+    RawWithHashInstance,
+}
+
+/// Checks that the instances of `PublicInputHandling::PreHashed` are the 4 elements of the
+/// public inputs hash.
+fn check_hash_instance<T>(instances: &[T]) -> Result<(), ShapeError> {
+    match instances.len() {
+        4 => Ok(()),
+        actual => Err(ShapeError {
+            expected: 4,
+            actual,
+        }),
+    }
+}
+
+/// Splits the instances of `PublicInputHandling::RawWithHashInstance` into the public inputs
+/// and the 4 elements of their hash.
+fn split_hash_instance<T>(instances: &[T]) -> Result<(&[T], &[T]), ShapeError> {
+    let num_public_inputs = instances.len().checked_sub(4).ok_or(ShapeError {
+        expected: 4,
+        actual: instances.len(),
+    })?;
+    Ok(instances.split_at(num_public_inputs))
+}
+
+/// How `Verifier` lays out the verification of its proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegionLayout {
+    /// The whole verification in one region.
+    #[default]
+    Single,
+    /// A region for the challenges, one for the vanishing polynomial, one for the part of FRI
+    /// shared by the query rounds and one per `query_rounds_per_region` query rounds. Assigned
+    /// values are carried between regions by copy constraints, so the floor planner places
+    /// smaller regions instead of one spanning the whole proof.
+    Chunked { query_rounds_per_region: usize },
+}
+
+/// Rows of a circuit `C` of `2^k` rows that regions can be assigned to. halo2 keeps the last
+/// `minimum_rows` rows of the constraint system for the blinding factors and `l_last`, except
+/// for the 2 rows `minimum_rows` also counts for `l_0` and at least one usable row.
+pub fn usable_rows<C: Circuit<Fr>>(k: u32) -> usize {
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+    (1usize << k).saturating_sub(cs.minimum_rows() - 2)
+}
+
+/// Query indices of `proof` as plonky2's verifier derives them, for
+/// `Verifier::with_plonky2_query_indices`. `None` if plonky2 can't derive its challenges.
+pub fn plonky2_query_indices(
+    proof: &ProofTuple<GoldilocksField, Bn254PoseidonGoldilocksConfig, SUPPORTED_EXTENSION_DEGREE>,
+) -> Option<Vec<usize>> {
+    let (proof_with_public_inputs, vd, cd) = proof;
+    proof_with_public_inputs
+        .fri_query_indices(&vd.circuit_digest, cd)
+        .ok()
+}
+
+/// `instances` preceded by `CIRCUIT_VERSION`, as a `Verifier` exposes them.
+pub(crate) fn circuit_instances(instances: &[Fr]) -> Vec<Fr> {
+    iter::once(Fr::from(CIRCUIT_VERSION))
+        .chain(instances.iter().copied())
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct Verifier {
     proof: ProofValues<Fr, 2>,
     instances: Vec<Fr>,
     vk: VerificationKeyValues<Fr>,
     common_data: CommonData<Fr>,
+    public_input_handling: PublicInputHandling,
+    region_layout: RegionLayout,
+    usable_rows: Option<usize>,
+    plonky2_query_indices: Option<Vec<usize>>,
 }
 
 impl Verifier {
@@ -75,6 +200,98 @@ impl Verifier {
             instances,
             vk,
             common_data,
+            public_input_handling: PublicInputHandling::default(),
+            region_layout: RegionLayout::default(),
+            usable_rows: None,
+            plonky2_query_indices: None,
+        }
+    }
+
+    /// Instances the circuit exposes: `CIRCUIT_VERSION`, followed by the instances `new` was
+    /// given, in the layout of its `PublicInputHandling`.
+    pub fn instances(&self) -> Vec<Fr> {
+        circuit_instances(&self.instances)
+    }
+
+    pub fn with_public_input_handling(
+        mut self,
+        public_input_handling: PublicInputHandling,
+    ) -> Self {
+        self.public_input_handling = public_input_handling;
+        self
+    }
+
+    pub fn with_region_layout(mut self, region_layout: RegionLayout) -> Self {
+        if let RegionLayout::Chunked {
+            query_rounds_per_region,
+        } = region_layout
+        {
+            assert!(
+                query_rounds_per_region > 0,
+                "regions must check at least one query round"
+            );
+        }
+        self.region_layout = region_layout;
+        self
+    }
+
+    /// Checks that the regions of the verification fit in a circuit of `2^k` rows while they are
+    /// laid out, failing with `RegionCapacityExceeded` naming the first region that doesn't,
+    /// instead of halo2 running out of rows once it is placed.
+    pub fn with_k(mut self, k: u32) -> Self {
+        self.usable_rows = Some(usable_rows::<Self>(k));
+        self
+    }
+
+    /// Checks the index the FRI chip takes from each assigned query challenge against
+    /// `indices`, the query indices plonky2 derives for the proof, see `plonky2_query_indices`.
+    pub fn with_plonky2_query_indices(mut self, indices: Vec<usize>) -> Self {
+        self.plonky2_query_indices = Some(indices);
+        self
+    }
+
+    pub(crate) fn proof(&self) -> &ProofValues<Fr, 2> {
+        &self.proof
+    }
+
+    pub(crate) fn common_data(&self) -> &CommonData<Fr> {
+        &self.common_data
+    }
+
+    /// Computes the witness trace, reading the public inputs hash from the instances in
+    /// `PublicInputHandling::PreHashed` mode. In `RawWithHashInstance` mode the hash instances
+    /// are left to the circuit to check.
+    pub(crate) fn witness_trace(&self) -> Result<WitnessTrace, Error> {
+        match self.public_input_handling {
+            PublicInputHandling::Raw => Ok(WitnessTrace::new(
+                &self.proof,
+                &self.instances,
+                &self.vk,
+                &self.common_data,
+            )?),
+            PublicInputHandling::PreHashed => {
+                let public_inputs_hash = to_array(
+                    self.instances
+                        .iter()
+                        .map(|e| fe_to_goldilocks(*e))
+                        .collect(),
+                )?;
+                Ok(WitnessTrace::with_public_inputs_hash(
+                    &self.proof,
+                    public_inputs_hash,
+                    &self.vk,
+                    &self.common_data,
+                )?)
+            }
+            PublicInputHandling::RawWithHashInstance => {
+                let (public_inputs, _) = split_hash_instance(&self.instances)?;
+                Ok(WitnessTrace::new(
+                    &self.proof,
+                    public_inputs,
+                    &self.vk,
+                    &self.common_data,
+                )?)
+            }
         }
     }
 
@@ -97,7 +314,9 @@ impl Verifier {
             MerkleCapValues::assign(config, ctx, &proof.plonk_zs_partial_products_cap)?;
         let quotient_polys_cap = MerkleCapValues::assign(config, ctx, &proof.quotient_polys_cap)?;
         let openings = OpeningSetValues::assign(config, ctx, &proof.openings)?;
-        let opening_proof = FriProofValues::assign(config, ctx, &proof.opening_proof)?;
+        // the query rounds are assigned by the FRI check, one round at a time
+        let opening_proof =
+            FriProofValues::assign_without_query_rounds(config, ctx, &proof.opening_proof)?;
         Ok(AssignedProofWithPisValues {
             proof: AssignedProofValues {
                 wires_cap,
@@ -116,15 +335,156 @@ impl Verifier {
         ctx: &mut RegionCtx<'_, Fr>,
         vk: &VerificationKeyValues<Fr>,
     ) -> Result<AssignedVerificationKeyValues<Fr>, Error> {
-        Ok(AssignedVerificationKeyValues {
-            constants_sigmas_cap: MerkleCapValues::assign_constant(
-                config,
+        VerificationKeyValues::assign_constant(config, ctx, vk)
+    }
+
+    /// Assigns the proof and verifies it against `trace`, returning the assigned proof and its
+    /// public inputs hash. The FRI query rounds are assigned while they're checked, so they're
+    /// left out of the returned proof.
+    fn assign_and_verify(
+        &self,
+        config: &GoldilocksChipConfig<Fr>,
+        ctx: &mut RegionCtx<'_, Fr>,
+        trace: &WitnessTrace,
+    ) -> Result<(AssignedProofWithPisValues<Fr, 2>, AssignedHashValues<Fr>), Error> {
+        let assigned = self.assign_and_get_challenges(config, ctx, trace)?;
+        let plonk_verifier_chip = PlonkVerifierChip::construct(config);
+        let proof = &assigned.proof_with_pis.proof;
+        plonk_verifier_chip.verify_vanishing_poly(
+            ctx,
+            proof,
+            &assigned.public_inputs_hash,
+            &assigned.challenges,
+            &self.common_data,
+        )?;
+        plonk_verifier_chip.verify_fri_with_rounds(
+            ctx,
+            proof,
+            self.query_round_proofs(),
+            &assigned.challenges,
+            &assigned.vk,
+            &self.common_data,
+        )?;
+        Ok((assigned.proof_with_pis, assigned.public_inputs_hash))
+    }
+
+    /// `assign_and_verify` across the regions of `RegionLayout::Chunked`, returning the
+    /// assigned public inputs.
+    fn assign_and_verify_chunked(
+        &self,
+        config: &GoldilocksChipConfig<Fr>,
+        layouter: &mut impl Layouter<Fr>,
+        budget: &mut RowBudget,
+        trace: &WitnessTrace,
+        query_rounds_per_region: usize,
+    ) -> Result<Vec<AssignedValue<Fr>>, Error> {
+        let plonk_verifier_chip = PlonkVerifierChip::construct(config);
+        let assigned = budget.assign_region(layouter, "challenges", |ctx| {
+            self.assign_and_get_challenges(config, ctx, trace)
+        })?;
+        let proof = &assigned.proof_with_pis.proof;
+        budget.assign_region(layouter, "vanishing polynomial", |ctx| {
+            plonk_verifier_chip.verify_vanishing_poly(
                 ctx,
-                &vk.constants_sigmas_cap,
-            )?,
-            circuit_digest: HashValues::assign_constant(config, ctx, &vk.circuit_digest)?,
+                proof,
+                &assigned.public_inputs_hash,
+                &assigned.challenges,
+                &self.common_data,
+            )
+        })?;
+        let fri = budget.assign_region(layouter, "fri", |ctx| {
+            plonk_verifier_chip.prepare_fri(
+                ctx,
+                proof,
+                &assigned.challenges,
+                &assigned.vk,
+                &self.common_data,
+            )
+        })?;
+        let query_round_proofs = self.query_round_proofs();
+        let num_query_rounds = query_round_proofs.len();
+        for start in (0..num_query_rounds).step_by(query_rounds_per_region) {
+            let rounds = start..num_query_rounds.min(start + query_rounds_per_region);
+            let name = format!("fri query rounds {rounds:?}");
+            budget.assign_region(layouter, &name, |ctx| {
+                plonk_verifier_chip.verify_fri_query_rounds(
+                    ctx,
+                    &fri,
+                    proof,
+                    query_round_proofs,
+                    &assigned.challenges,
+                    rounds.clone(),
+                )
+            })?;
+        }
+        Ok(assigned.proof_with_pis.public_inputs)
+    }
+
+    /// Assigns the proof and its verification key and derives the challenges, checking them
+    /// against `trace`.
+    fn assign_and_get_challenges(
+        &self,
+        config: &GoldilocksChipConfig<Fr>,
+        ctx: &mut RegionCtx<'_, Fr>,
+        trace: &WitnessTrace,
+    ) -> Result<AssignedVerification, Error> {
+        if self.public_input_handling == PublicInputHandling::PreHashed {
+            check_hash_instance(&self.instances)?;
+        }
+        let assigned_proof_with_pis =
+            self.assign_proof_with_pis(config, ctx, &self.proof, &self.instances)?;
+        let assigned_vk = self.assign_verification_key(config, ctx, &self.vk)?;
+        let plonk_verifier_chip = PlonkVerifierChip::construct(config);
+        let public_inputs_hash = match self.public_input_handling {
+            PublicInputHandling::Raw => plonk_verifier_chip
+                .get_public_inputs_hash(ctx, &assigned_proof_with_pis.public_inputs)?,
+            PublicInputHandling::PreHashed => {
+                AssignedHashValues::from_vec(assigned_proof_with_pis.public_inputs.clone())?
+            }
+            PublicInputHandling::RawWithHashInstance => {
+                let (public_inputs, hash_instance) =
+                    split_hash_instance(&assigned_proof_with_pis.public_inputs)?;
+                let public_inputs_hash =
+                    plonk_verifier_chip.get_public_inputs_hash(ctx, &public_inputs.to_vec())?;
+                let goldilocks_chip = GoldilocksChip::new(config);
+                for (computed, instance) in public_inputs_hash.iter().zip(hash_instance) {
+                    goldilocks_chip.assert_equal(ctx, computed, instance)?;
+                }
+                public_inputs_hash
+            }
+        };
+        let challenges = plonk_verifier_chip.get_challenges(
+            ctx,
+            &public_inputs_hash,
+            &assigned_vk.circuit_digest,
+            &self.common_data,
+            &assigned_proof_with_pis.proof,
+        )?;
+        trace.check_assigned(&public_inputs_hash, &challenges)?;
+        if let Some(indices) = &self.plonky2_query_indices {
+            trace.check_query_indices(&challenges.fri_challenges.fri_query_indices, indices)?;
+        }
+        Ok(AssignedVerification {
+            proof_with_pis: assigned_proof_with_pis,
+            vk: assigned_vk,
+            public_inputs_hash,
+            challenges,
         })
     }
+
+    /// FRI query rounds of the proof, assigned by the FRI check right before each round.
+    fn query_round_proofs(&self) -> QueryRoundProofs<'_, Fr, 2> {
+        QueryRoundProofs::Unassigned(&self.proof.opening_proof.query_round_proofs)
+    }
+}
+
+/// Assigned values of a proof shared by the verification components. The FRI query rounds
+/// aren't part of `proof_with_pis`, see `Verifier::query_round_proofs`.
+struct AssignedVerification {
+    proof_with_pis: AssignedProofWithPisValues<Fr, 2>,
+    vk: AssignedVerificationKeyValues<Fr>,
+    public_inputs_hash: AssignedHashValues<Fr>,
+    challenges: AssignedProofChallenges<Fr, 2>,
 }
 
 impl Circuit<Fr> for Verifier {
@@ -137,6 +497,10 @@ impl Circuit<Fr> for Verifier {
             instances: self.instances.clone(),
             vk: self.vk.clone(),
             common_data: self.common_data.clone(),
+            public_input_handling: self.public_input_handling,
+            region_layout: self.region_layout,
+            usable_rows: self.usable_rows,
+            plonky2_query_indices: self.plonky2_query_indices.clone(),
         }
     }
 
@@ -150,52 +514,766 @@ impl Circuit<Fr> for Verifier {
         config: Self::Config,
         mut layouter: impl Layouter<Fr>,
     ) -> Result<(), Error> {
+        self.common_data.validate()?;
+
+        // first pass: everything derived from the proof is computed natively up front, the
+        // assignment pass below is checked against it
+        let trace = self.witness_trace()?;
+
         let goldilocks_chip_config = config.clone();
         let goldilocks_chip = GoldilocksChip::new(&goldilocks_chip_config);
         goldilocks_chip.load_table(&mut layouter)?;
-        let assigned_proof_with_pis = layouter.assign_region(
-            || "Verify proof",
+        let mut budget = RowBudget::new(self.usable_rows);
+        let public_inputs = match self.region_layout {
+            RegionLayout::Single => {
+                let assigned_proof_with_pis =
+                    budget.assign_region(&mut layouter, "Verify proof", |ctx| {
+                        let (assigned_proof_with_pis, _) =
+                            self.assign_and_verify(&goldilocks_chip_config, ctx, &trace)?;
+                        Ok(assigned_proof_with_pis)
+                    })?;
+                assigned_proof_with_pis.public_inputs
+            }
+            RegionLayout::Chunked {
+                query_rounds_per_region,
+            } => self.assign_and_verify_chunked(
+                &goldilocks_chip_config,
+                &mut layouter,
+                &mut budget,
+                &trace,
+                query_rounds_per_region,
+            )?,
+        };
+        expose_circuit_version(&goldilocks_chip, &mut layouter)?;
+        for (row, public_input) in (1..=self.instances.len()).zip_eq(public_inputs) {
+            goldilocks_chip.arithmetic_chip().expose_public(
+                layouter.namespace(|| ""),
+                public_input,
+                row,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Part of the verification of a proof. `BatchVerifier` lays out each component of each proof
+/// in its own region, named by `region_name`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Component {
+    /// Assigning the proof and deriving the challenges.
+    Challenges,
+    /// Checking the vanishing polynomial against the quotient openings.
+    VanishingPoly,
+    /// Checking the openings against the commitments.
+    Fri,
+}
+
+impl Component {
+    pub const ALL: [Self; 3] = [Self::Challenges, Self::VanishingPoly, Self::Fri];
+
+    /// Name of the region verifying this component of the proof at `index` of a batch.
+    pub fn region_name(self, index: usize) -> String {
+        let component = match self {
+            Self::Challenges => "challenges",
+            Self::VanishingPoly => "vanishing polynomial",
+            Self::Fri => "fri",
+        };
+        format!("proof {index}: {component}")
+    }
+}
+
+/// Regions of a `BatchVerifier`, mapping region names back to the proof and the component they
+/// verify.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchLayout {
+    regions: Vec<(String, usize, Component)>,
+}
+
+impl BatchLayout {
+    /// Proof index and component verified in the region named `region_name`.
+    pub fn find(&self, region_name: &str) -> Option<(usize, Component)> {
+        self.regions
+            .iter()
+            .find(|(name, _, _)| name == region_name)
+            .map(|(_, index, component)| (*index, *component))
+    }
+}
+
+/// A proof of a `BatchVerifier` rejected before synthesis.
+#[derive(Debug)]
+pub struct BatchProofError {
+    /// Position of the proof in the batch.
+    pub index: usize,
+    pub error: Error,
+}
+
+impl fmt::Display for BatchProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "proof {} of the batch is rejected: {:?}",
+            self.index, self.error
+        )
+    }
+}
+
+impl std::error::Error for BatchProofError {}
+
+impl From<BatchProofError> for Error {
+    fn from(value: BatchProofError) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
+}
+
+/// Verifies several independent proofs in one circuit. Instead of failing on an invalid proof,
+/// the verification checks of each proof are recorded and combined, so the instances are
+/// `CIRCUIT_VERSION`, a single bit that is `1` iff every proof is valid, and the public inputs
+/// hash of each proof.
+#[derive(Clone)]
+pub struct BatchVerifier {
+    verifiers: Vec<Verifier>,
+    strict: bool,
+}
+
+impl BatchVerifier {
+    pub fn new(verifiers: Vec<Verifier>) -> Self {
+        Self {
+            verifiers,
+            strict: false,
+        }
+    }
+
+    /// Constrains the checks of every proof instead of recording them. An invalid proof then
+    /// fails the constraints of the component rejecting it, which `debug::attribute_failures`
+    /// maps back to the proof, instead of clearing the validity bit.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn layout(&self) -> BatchLayout {
+        let regions = (0..self.verifiers.len())
+            .flat_map(|index| {
+                Component::ALL
+                    .into_iter()
+                    .map(move |component| (component.region_name(index), index, component))
+            })
+            .collect();
+        BatchLayout { regions }
+    }
+
+    /// Validates the common data and computes the witness trace of every proof before anything
+    /// is assigned. The error points at the first proof rejected.
+    pub fn witness_traces(&self) -> Result<Vec<WitnessTrace>, BatchProofError> {
+        self.verifiers
+            .iter()
+            .enumerate()
+            .map(|(index, verifier)| {
+                verifier
+                    .common_data
+                    .validate()
+                    .map_err(Error::from)
+                    .and_then(|()| verifier.witness_trace())
+                    .map_err(|error| BatchProofError { index, error })
+            })
+            .collect()
+    }
+
+    /// Runs `verify` in the region of `component` of the proof at `index`, returning its output
+    /// and the checks it recorded.
+    fn verify_component<T>(
+        &self,
+        layouter: &mut impl Layouter<Fr>,
+        index: usize,
+        component: Component,
+        mut verify: impl FnMut(&mut RegionCtx<'_, Fr>) -> Result<T, Error>,
+    ) -> Result<(T, Vec<AssignedValue<Fr>>), Error> {
+        layouter.assign_region(
+            || component.region_name(index),
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                if !self.strict {
+                    ctx.record_checks();
+                }
+                let output = verify(ctx)?;
+                Ok((output, ctx.take_checks()))
+            },
+        )
+    }
+}
+
+impl Circuit<Fr> for BatchVerifier {
+    type Config = GoldilocksChipConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+        GoldilocksChip::configure(&all_chip_config)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let traces = self.witness_traces()?;
+
+        let goldilocks_chip = GoldilocksChip::new(&config);
+        goldilocks_chip.load_table(&mut layouter)?;
+        let plonk_verifier_chip = PlonkVerifierChip::construct(&config);
+        let mut checks = vec![];
+        let mut public_inputs_hashes = vec![];
+        for (index, (verifier, trace)) in self.verifiers.iter().zip_eq(traces.iter()).enumerate() {
+            let (assigned, challenges_checks) =
+                self.verify_component(&mut layouter, index, Component::Challenges, |ctx| {
+                    verifier.assign_and_get_challenges(&config, ctx, trace)
+                })?;
+            let proof = &assigned.proof_with_pis.proof;
+            let ((), vanishing_poly_checks) =
+                self.verify_component(&mut layouter, index, Component::VanishingPoly, |ctx| {
+                    plonk_verifier_chip.verify_vanishing_poly(
+                        ctx,
+                        proof,
+                        &assigned.public_inputs_hash,
+                        &assigned.challenges,
+                        &verifier.common_data,
+                    )
+                })?;
+            let ((), fri_checks) =
+                self.verify_component(&mut layouter, index, Component::Fri, |ctx| {
+                    plonk_verifier_chip.verify_fri_with_rounds(
+                        ctx,
+                        proof,
+                        verifier.query_round_proofs(),
+                        &assigned.challenges,
+                        &assigned.vk,
+                        &verifier.common_data,
+                    )
+                })?;
+            checks.push([challenges_checks, vanishing_poly_checks, fri_checks].concat());
+            public_inputs_hashes.push(assigned.public_inputs_hash);
+        }
+
+        let all_valid = layouter.assign_region(
+            || "Verify proofs",
             |region| {
                 let ctx = &mut RegionCtx::new(region, 0);
-                let assigned_proof_with_pis = self.assign_proof_with_pis(
-                    &goldilocks_chip_config,
-                    ctx,
-                    &self.proof,
-                    &self.instances,
-                )?;
-                let assigned_vk =
-                    self.assign_verification_key(&goldilocks_chip_config, ctx, &self.vk)?;
-                let plonk_verifier_chip = PlonkVerifierChip::construct(&goldilocks_chip_config);
-                let public_inputs_hash = plonk_verifier_chip
-                    .get_public_inputs_hash(ctx, &assigned_proof_with_pis.public_inputs)?;
-                let challenges = plonk_verifier_chip.get_challenges(
-                    ctx,
-                    &public_inputs_hash,
-                    &assigned_vk.circuit_digest,
-                    &self.common_data,
-                    &assigned_proof_with_pis.proof,
-                    self.common_data.config.num_challenges,
-                )?;
-                plonk_verifier_chip.verify_proof_with_challenges(
-                    ctx,
-                    &assigned_proof_with_pis.proof,
-                    &public_inputs_hash,
-                    &challenges,
-                    &assigned_vk,
-                    &self.common_data,
-                )?;
-                Ok(assigned_proof_with_pis)
+                let valid = checks
+                    .iter()
+                    .map(|checks| goldilocks_chip.and_many(ctx, checks))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                goldilocks_chip.and_many(ctx, &valid)
             },
         )?;
-        for (row, public_input) in
-            (0..self.instances.len()).zip_eq(assigned_proof_with_pis.public_inputs)
-        {
+        expose_circuit_version(&goldilocks_chip, &mut layouter)?;
+        let instances = iter::once(all_valid).chain(
+            public_inputs_hashes
+                .into_iter()
+                .flat_map(|hash| hash.elements),
+        );
+        for (row, instance) in (1..).zip(instances) {
             goldilocks_chip.arithmetic_chip().expose_public(
                 layouter.namespace(|| ""),
-                public_input,
+                instance,
                 row,
             )?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        halo2curves::{bn256::Fr, ff::Field as _},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use plonky2::{
+        field::{
+            extension::quadratic::QuadraticExtension, goldilocks_field::GoldilocksField,
+            types::Field,
+        },
+        plonk::proof::ProofWithPublicInputs,
+        util::reducing::ReducingFactor,
+    };
+
+    use super::{
+        BatchVerifier, Component, PublicInputHandling, RegionLayout, Verifier, CIRCUIT_VERSION,
+    };
+    use crate::plonky2_verifier::{
+        bn245_poseidon::plonky2_config::Bn254PoseidonGoldilocksConfig,
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            goldilocks_extension_chip::GoldilocksExtensionChip,
+            native_chip::{
+                all_chip::AllChipConfig,
+                test_utils::{mock_prove_small_table, mock_prover_at_min_k, SmallTable},
+                utils::goldilocks_to_fe,
+            },
+            plonk::plonk_verifier_chip::PlonkVerifierChip,
+            transcript_chip::TranscriptCountMismatch,
+        },
+        context::RegionCtx,
+        debug::attribute_failures,
+        fixtures::{
+            generate_proof_tuple, generate_random_access_proof_tuple, generate_tiny_proof_tuple,
+            mock_verifier,
+        },
+        types::{
+            assigned::ShapeError,
+            proof::{FriQueryRoundValues, MerkleProofValues},
+        },
+        witness_trace::WitnessTrace,
+    };
+
+    /// Evaluates the vanishing polynomial of `verifier`'s proof at zeta, asserting its
+    /// combination for every challenge equals `expected`.
+    #[derive(Clone)]
+    struct VanishingPolyCircuit {
+        verifier: Verifier,
+        expected: Vec<[GoldilocksField; 2]>,
+    }
+
+    impl Circuit<Fr> for VanishingPolyCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let verifier = &self.verifier;
+            let common_data = &verifier.common_data;
+            let trace = verifier.witness_trace()?;
+            GoldilocksChip::new(&config).load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "vanishing polynomial",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let assigned = verifier.assign_and_get_challenges(&config, ctx, &trace)?;
+                    let openings = &assigned.proof_with_pis.proof.openings;
+                    let challenges = &assigned.challenges;
+                    let extension_chip = GoldilocksExtensionChip::new(&config);
+                    let zeta_pow_deg = extension_chip.exp_power_of_2_extension(
+                        ctx,
+                        challenges.plonk_zeta.clone(),
+                        common_data.degree_bits(),
+                    )?;
+                    let vanishing_poly_zeta = PlonkVerifierChip::construct(&config)
+                        .eval_vanishing_poly(
+                            ctx,
+                            common_data,
+                            &challenges.plonk_zeta,
+                            &zeta_pow_deg,
+                            &openings.constants,
+                            &openings.wires,
+                            &assigned.public_inputs_hash,
+                            &openings.plonk_zs,
+                            &openings.plonk_zs_next,
+                            &openings.partial_products,
+                            &openings.plonk_sigmas,
+                            &challenges.plonk_betas,
+                            &challenges.plonk_gammas,
+                            &challenges.plonk_alphas,
+                        )?;
+                    assert_eq!(vanishing_poly_zeta.len(), self.expected.len());
+                    for (value, expected) in vanishing_poly_zeta.iter().zip(self.expected.iter()) {
+                        let expected = extension_chip.constant_extension(ctx, expected)?;
+                        extension_chip.assert_equal_extension(ctx, value, &expected)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// The vanishing polynomial at zeta of every challenge as plonky2 evaluated it: the prover
+    /// divided it by `Z_H`, so it is `Z_H(zeta)` times the quotient recombined from its chunks.
+    fn plonky2_vanishing_poly_zeta(
+        verifier: &Verifier,
+        proof_with_public_inputs: &ProofWithPublicInputs<
+            GoldilocksField,
+            Bn254PoseidonGoldilocksConfig,
+            2,
+        >,
+    ) -> Vec<[GoldilocksField; 2]> {
+        type FE = QuadraticExtension<GoldilocksField>;
+        let zeta = QuadraticExtension(verifier.witness_trace().unwrap().challenges.plonk_zeta);
+        let zeta_pow_deg = zeta.exp_power_of_2(verifier.common_data.degree_bits());
+        let z_h_zeta = zeta_pow_deg - FE::ONE;
+        proof_with_public_inputs
+            .proof
+            .openings
+            .quotient_polys
+            .chunks(verifier.common_data.quotient_degree_factor)
+            .map(|chunk| (z_h_zeta * ReducingFactor::new(zeta_pow_deg).reduce(chunk.iter())).0)
+            .collect()
+    }
+
+    #[test]
+    fn test_vanishing_poly_matches_plonky2() {
+        let proof_tuples = [
+            generate_tiny_proof_tuple(),
+            generate_proof_tuple(),
+            // `RandomAccessGate`s with many constraints, a number of gate constraints neither
+            // of the others has
+            generate_random_access_proof_tuple(),
+        ];
+        let num_gate_constraints = proof_tuples
+            .iter()
+            .map(|(_, _, cd)| cd.num_gate_constraints)
+            .collect::<Vec<_>>();
+        assert_ne!(num_gate_constraints[2], num_gate_constraints[0]);
+        assert_ne!(num_gate_constraints[2], num_gate_constraints[1]);
+        for proof_tuple in proof_tuples {
+            let (verifier, _) = mock_verifier(proof_tuple.clone());
+            let expected = plonky2_vanishing_poly_zeta(&verifier, &proof_tuple.0);
+            assert_eq!(expected.len(), verifier.common_data.config.num_challenges);
+            let circuit = VanishingPolyCircuit {
+                verifier,
+                expected: expected.clone(),
+            };
+            mock_prove_small_table(&circuit, vec![vec![]])
+                .unwrap()
+                .assert_satisfied();
+
+            let mut expected = expected;
+            expected[0][1] += GoldilocksField::ONE;
+            let circuit = VanishingPolyCircuit {
+                expected,
+                ..circuit
+            };
+            let prover = mock_prove_small_table(&circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_instances_commit_to_circuit_version() {
+        let proof_tuple = generate_tiny_proof_tuple();
+        let public_inputs = proof_tuple
+            .0
+            .public_inputs
+            .iter()
+            .map(|e| goldilocks_to_fe(*e))
+            .collect::<Vec<Fr>>();
+        let (verifier, instances) = mock_verifier(proof_tuple);
+        assert_eq!(
+            instances,
+            [vec![Fr::from(CIRCUIT_VERSION)], public_inputs].concat()
+        );
+        mock_prove_small_table(&verifier, vec![instances.clone()])
+            .unwrap()
+            .assert_satisfied();
+
+        // e.g. a consumer expecting the proofs of another release of the circuit
+        let mut other_version = instances;
+        other_version[0] = Fr::from(CIRCUIT_VERSION + 1);
+        let prover = mock_prove_small_table(&verifier, vec![other_version]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_chunked_regions_match_single_region() {
+        let proof = generate_tiny_proof_tuple();
+        let mut tampered = proof.clone();
+        // a leaf of the first initial tree, it doesn't affect any challenge
+        let initial_trees_proof =
+            &mut tampered.0.proof.opening_proof.query_round_proofs[0].initial_trees_proof;
+        initial_trees_proof.evals_proofs[0].0[0] += GoldilocksField::ONE;
+        let num_query_rounds = proof.2.config.fri_config.num_query_rounds;
+
+        for region_layout in [
+            RegionLayout::Single,
+            RegionLayout::Chunked {
+                query_rounds_per_region: 1,
+            },
+            // a last region with fewer rounds
+            RegionLayout::Chunked {
+                query_rounds_per_region: num_query_rounds / 2 + 1,
+            },
+        ] {
+            for (proof, valid) in [(&proof, true), (&tampered, false)] {
+                let (verifier, instances) = mock_verifier(proof.clone());
+                let verifier = verifier.with_region_layout(region_layout);
+                let prover = mock_prove_small_table(&verifier, vec![instances]).unwrap();
+                assert_eq!(prover.verify().is_ok(), valid, "{region_layout:?}");
+            }
+        }
+    }
+
+    /// Runs the FRI check of `verifier` with the query rounds assigned up front and checked by
+    /// `verify_fri`, or assigned by `verify_fri_with_rounds` while they're checked, recording the
+    /// rows it takes.
+    struct QueryRoundsCircuit {
+        verifier: Verifier,
+        lazy: bool,
+        rows: Cell<usize>,
+    }
+
+    impl Circuit<Fr> for QueryRoundsCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                verifier: self.verifier.clone(),
+                lazy: self.lazy,
+                rows: Cell::new(0),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let all_chip_config = AllChipConfig::<Fr>::configure(meta);
+            GoldilocksChip::configure(&all_chip_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let verifier = &self.verifier;
+            let trace = verifier.witness_trace()?;
+            GoldilocksChip::new(&config).load_table(&mut layouter)?;
+            layouter.assign_region(
+                || "Verify fri",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let mut assigned = verifier.assign_and_get_challenges(&config, ctx, &trace)?;
+                    let plonk_verifier_chip = PlonkVerifierChip::construct(&config);
+                    if self.lazy {
+                        plonk_verifier_chip.verify_fri_with_rounds(
+                            ctx,
+                            &assigned.proof_with_pis.proof,
+                            verifier.query_round_proofs(),
+                            &assigned.challenges,
+                            &assigned.vk,
+                            &verifier.common_data,
+                        )?;
+                    } else {
+                        let proof = &mut assigned.proof_with_pis.proof;
+                        proof.opening_proof.query_round_proofs = verifier
+                            .proof
+                            .opening_proof
+                            .query_round_proofs
+                            .iter()
+                            .map(|round| FriQueryRoundValues::assign(&config, ctx, round))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        plonk_verifier_chip.verify_fri(
+                            ctx,
+                            proof,
+                            &assigned.challenges,
+                            &assigned.vk,
+                            &verifier.common_data,
+                        )?;
+                    }
+                    self.rows.set(ctx.offset());
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// Cells assigned for a query round, each held until the round is checked.
+    fn query_round_cells(round: &FriQueryRoundValues<Fr, 2>) -> usize {
+        let merkle_proof_cells = |proof: &MerkleProofValues<Fr>| 4 * proof.siblings.len();
+        let initial_trees = round.initial_trees_proof.evals_proofs.iter();
+        let steps = round.steps.iter();
+        initial_trees
+            .map(|(evals, proof)| evals.len() + merkle_proof_cells(proof))
+            .chain(steps.map(|step| 2 * step.evals.len() + merkle_proof_cells(&step.merkle_proof)))
+            .sum()
+    }
+
+    #[test]
+    fn test_lazy_query_rounds_match_eager_assignment() {
+        let (verifier, _) = mock_verifier(generate_tiny_proof_tuple());
+
+        let rows = [false, true].map(|lazy| {
+            let circuit = SmallTable(QueryRoundsCircuit {
+                verifier: verifier.clone(),
+                lazy,
+                rows: Cell::new(0),
+            });
+            mock_prover_at_min_k(&circuit, vec![vec![]])
+                .unwrap()
+                .assert_satisfied();
+            circuit.0.rows.get()
+        });
+        // the same cells are assigned, only later, so the equality-enabled cells, and with them
+        // the permutation argument, are the same
+        assert_eq!(rows[0], rows[1]);
+
+        // the assigned query round cells held while the rounds are checked: all of them up
+        // front, one round at a time otherwise
+        let rounds = &verifier.proof.opening_proof.query_round_proofs;
+        let cells = rounds.iter().map(query_round_cells).collect::<Vec<_>>();
+        let eager_held = cells.iter().sum::<usize>();
+        let lazy_held = *cells.iter().max().unwrap();
+        assert!(rounds.len() > 1);
+        assert_eq!(eager_held, rounds.len() * lazy_held);
+    }
+
+    #[test]
+    fn test_missing_transcript_write_is_rejected() {
+        let mut proof = generate_tiny_proof_tuple();
+        // drops the write of one final polynomial coefficient
+        proof.0.proof.opening_proof.final_poly.coeffs.pop();
+        let (verifier, instances) = mock_verifier(proof);
+        let err = mock_prove_small_table(&verifier, vec![instances])
+            .err()
+            .unwrap();
+        let Error::Transcript(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        let mismatch = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<TranscriptCountMismatch>())
+            .unwrap();
+        assert_eq!(
+            mismatch.expected_observations,
+            verifier.common_data.num_transcript_observations()
+        );
+        assert_eq!(mismatch.observations + 2, mismatch.expected_observations);
+        assert_eq!(mismatch.challenges, mismatch.expected_challenges);
+    }
+
+    /// The public inputs hash `BatchVerifier` exposes for `proof`.
+    fn public_inputs_hash(
+        proof: &ProofWithPublicInputs<GoldilocksField, Bn254PoseidonGoldilocksConfig, 2>,
+    ) -> [Fr; 4] {
+        proof
+            .get_public_inputs_hash()
+            .elements
+            .map(goldilocks_to_fe::<Fr>)
+    }
+
+    #[test]
+    fn test_pre_hashed_public_inputs() {
+        // the inner circuit of `generate_proof_tuple` exposes a hash as its 4 public inputs, the
+        // tiny circuit exposes 2 plain values
+        for proof in [generate_proof_tuple(), generate_tiny_proof_tuple()] {
+            let public_inputs_hash = public_inputs_hash(&proof.0);
+            let (verifier, instances) = mock_verifier(proof);
+            let public_inputs = verifier.instances.clone();
+
+            let raw_trace = verifier.witness_trace().unwrap();
+            let pre_hashed_trace = WitnessTrace::with_public_inputs_hash(
+                &verifier.proof,
+                raw_trace.public_inputs_hash,
+                &verifier.vk,
+                &verifier.common_data,
+            )
+            .unwrap();
+            assert_eq!(pre_hashed_trace, raw_trace);
+
+            mock_prove_small_table(&verifier, vec![instances])
+                .unwrap()
+                .assert_satisfied();
+
+            let pre_hashed = |instances: &[Fr]| {
+                Verifier {
+                    instances: instances.to_vec(),
+                    ..verifier.clone()
+                }
+                .with_public_input_handling(PublicInputHandling::PreHashed)
+            };
+            // synthesis checks the assigned challenges against the trace
+            let circuit = pre_hashed(&public_inputs_hash);
+            let prover = mock_prove_small_table(&circuit, vec![circuit.instances()]).unwrap();
+            prover.assert_satisfied();
+
+            let circuit = pre_hashed(&public_inputs);
+            if public_inputs.len() == 4 {
+                // plonky2 hashes the public inputs even if they already are a hash
+                let result = mock_prove_small_table(&circuit, vec![circuit.instances()]);
+                assert!(matches!(result, Err(Error::Synthesis)));
+            } else {
+                let err = match mock_prove_small_table(&circuit, vec![circuit.instances()]) {
+                    Err(Error::Transcript(err)) => err,
+                    _ => panic!("expected a shape error"),
+                };
+                assert_eq!(
+                    err.get_ref().unwrap().downcast_ref::<ShapeError>(),
+                    Some(&ShapeError {
+                        expected: 4,
+                        actual: public_inputs.len()
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_verifier_ands_validity() {
+        let verifier = |tamper: bool| {
+            let mut proof = generate_tiny_proof_tuple();
+            if tamper {
+                // the proof keeps its shape, only the quotient opening is wrong
+                proof.0.proof.openings.quotient_polys[0] +=
+                    QuadraticExtension::<GoldilocksField>::ONE;
+            }
+            let public_inputs_hash = public_inputs_hash(&proof.0);
+            (mock_verifier(proof).0, public_inputs_hash)
+        };
+        let (valid, valid_hash) = verifier(false);
+        let (invalid, invalid_hash) = verifier(true);
+        let instances = |all_valid: bool, hashes: &[[Fr; 4]]| {
+            let version_and_validity = vec![Fr::from(CIRCUIT_VERSION), Fr::from(all_valid as u64)];
+            [version_and_validity, hashes.concat()].concat()
+        };
+
+        let circuit = BatchVerifier::new(vec![valid.clone()]);
+        let prover =
+            mock_prove_small_table(&circuit, vec![instances(true, &[valid_hash])]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = BatchVerifier::new(vec![valid, invalid]);
+        let hashes = [valid_hash, invalid_hash];
+        let prover = mock_prove_small_table(&circuit, vec![instances(false, &hashes)]).unwrap();
+        prover.assert_satisfied();
+        let prover = mock_prove_small_table(&circuit, vec![instances(true, &hashes)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_wire_openings_are_bound_to_cap() {
+        let mut proof = generate_tiny_proof_tuple();
+        // the wires cap is kept, only the opening of the first wire at zeta changes
+        proof.0.proof.openings.wires[0] += QuadraticExtension::<GoldilocksField>::ONE;
+        let public_inputs_hash = public_inputs_hash(&proof.0);
+
+        // the strict batch verifier constrains each component in its own region, so the FRI
+        // check has to reject the opening on its own, whatever the vanishing polynomial check
+        // makes of it
+        let (verifier, _) = mock_verifier(proof);
+        let circuit = BatchVerifier::new(vec![verifier]).strict();
+        let instances = [
+            vec![Fr::from(CIRCUIT_VERSION), Fr::ONE],
+            public_inputs_hash.to_vec(),
+        ]
+        .concat();
+        let prover = mock_prove_small_table(&circuit, vec![instances]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        let attributed = attribute_failures(&errors, &circuit.layout());
+        assert!(attributed.contains(&(0, Component::Fri)));
+    }
+}
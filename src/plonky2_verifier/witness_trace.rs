@@ -0,0 +1,701 @@
+use std::{collections::VecDeque, fmt, io};
+
+use halo2_proofs::{halo2curves::ff::PrimeField, plonk::Error};
+use halo2wrong_maingate::{fe_to_big, AssignedValue};
+use plonky2::{
+    field::{
+        extension::quadratic::QuadraticExtension,
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
+    hash::{hashing::hash_n_to_hash_no_pad, poseidon::PoseidonPermutation},
+    iop::challenger::Challenger,
+};
+
+use super::{
+    bn245_poseidon::plonky2_config::Bn254PoseidonHash,
+    chip::native_chip::utils::fe_to_goldilocks,
+    transcript_schedule::{take, transcript_schedule, Challenge, TranscriptEvent},
+    types::{
+        assigned::{known, AssignedHashValues, AssignedProofChallenges, ShapeError},
+        common_data::CommonData,
+        proof::ProofValues,
+        verification_key::VerificationKeyValues,
+        MerkleCapValues,
+    },
+};
+
+type NativeChallenger = Challenger<GoldilocksField, Bn254PoseidonHash>;
+
+/// Fiat-Shamir challenges of a proof, computed natively.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofChallengesValues {
+    pub plonk_betas: Vec<GoldilocksField>,
+    pub plonk_gammas: Vec<GoldilocksField>,
+    pub plonk_alphas: Vec<GoldilocksField>,
+    pub plonk_zeta: [GoldilocksField; 2],
+    pub fri_alpha: [GoldilocksField; 2],
+    pub fri_betas: Vec<[GoldilocksField; 2]>,
+    pub fri_pow_response: GoldilocksField,
+    /// Raw squeezed elements, before reduction to the LDE domain size.
+    pub fri_query_indices: Vec<GoldilocksField>,
+}
+
+/// The public inputs hash, challenges, reduced openings and query indices the verifier circuit
+/// derives from the proof, computed natively before any cell is assigned. Synthesis checks the
+/// assigned public inputs hash and challenges against the trace, so a divergence between the
+/// circuit and plonky2 is reported as a `TraceMismatch` naming the value that first differs
+/// instead of as an unsatisfied constraint somewhere downstream. The rest of the witness, e.g.
+/// the folded evaluations and the Poseidon states, is still computed while it's assigned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WitnessTrace {
+    pub public_inputs_hash: [GoldilocksField; 4],
+    pub challenges: ProofChallengesValues,
+    /// Openings of each FRI batch reduced with `fri_alpha`. They don't depend on the query
+    /// index, so every query round uses the same values.
+    pub reduced_openings: Vec<[GoldilocksField; 2]>,
+    /// Index of each FRI query round in the LDE, reduced from the squeezed challenge the way
+    /// plonky2's `fri_query_indices` does.
+    pub fri_query_x_indices: Vec<usize>,
+    /// Bits of the LDE size, the low bits of a query challenge the circuit keeps as its index.
+    pub lde_bits: usize,
+}
+
+impl WitnessTrace {
+    /// Computes the trace of `proof`. Its shape is checked before any challenge is derived, so a
+    /// proof with an extra commit phase cap is rejected with a `ShapeError` instead of squeezing
+    /// an extra beta and failing in FRI.
+    pub fn new<F: PrimeField>(
+        proof: &ProofValues<F, 2>,
+        instances: &[F],
+        vk: &VerificationKeyValues<F>,
+        common_data: &CommonData<F>,
+    ) -> Result<Self, ShapeError> {
+        let public_inputs = instances
+            .iter()
+            .map(|e| fe_to_goldilocks(*e))
+            .collect::<Vec<_>>();
+        let public_inputs_hash =
+            hash_n_to_hash_no_pad::<GoldilocksField, PoseidonPermutation>(&public_inputs).elements;
+        Self::with_public_inputs_hash(proof, public_inputs_hash, vk, common_data)
+    }
+
+    /// Like `new`, for a proof whose public inputs hash is already known.
+    pub fn with_public_inputs_hash<F: PrimeField>(
+        proof: &ProofValues<F, 2>,
+        public_inputs_hash: [GoldilocksField; 4],
+        vk: &VerificationKeyValues<F>,
+        common_data: &CommonData<F>,
+    ) -> Result<Self, ShapeError> {
+        proof
+            .opening_proof
+            .check_commit_phase_len(&common_data.fri_params)?;
+
+        let openings = &proof.openings;
+        let zeta_batch = [
+            openings.constants.as_slice(),
+            openings.plonk_sigmas.as_slice(),
+            openings.wires.as_slice(),
+            openings.plonk_zs.as_slice(),
+            openings.partial_products.as_slice(),
+            openings.quotient_polys.as_slice(),
+        ]
+        .concat();
+        let batches = [zeta_batch, openings.plonk_zs_next.clone()];
+
+        // replays the schedule like `PlonkVerifierChip::get_challenges`
+        let seeds = &common_data.challenger_seeds;
+        let mut hashes = seeds.hashes(vk.circuit_digest.elements, public_inputs_hash);
+        let mut constants = seeds.constants();
+        let mut caps = [
+            &proof.wires_cap,
+            &proof.plonk_zs_partial_products_cap,
+            &proof.quotient_polys_cap,
+        ]
+        .into_iter()
+        .chain(proof.opening_proof.commit_phase_merkle_cap_values.iter())
+        .collect::<VecDeque<_>>();
+        let mut extensions = batches
+            .iter()
+            .flatten()
+            .chain(proof.opening_proof.final_poly.0.iter())
+            .collect::<VecDeque<_>>();
+        let mut elements = VecDeque::from([proof.opening_proof.pow_witness]);
+
+        let mut challenger = NativeChallenger::new();
+        let mut challenges = ProofChallengesValues::default();
+        for event in transcript_schedule(common_data) {
+            match event {
+                TranscriptEvent::ObserveHash => {
+                    for hash in take(&mut hashes, 1) {
+                        challenger.observe_elements(&hash);
+                    }
+                }
+                TranscriptEvent::ObserveConstants(n) => {
+                    for constant in take(&mut constants, n) {
+                        challenger.observe_element(constant);
+                    }
+                }
+                TranscriptEvent::ObserveCap(_) => {
+                    for cap in take(&mut caps, 1) {
+                        observe_cap(&mut challenger, cap);
+                    }
+                }
+                TranscriptEvent::ObserveExtension(n) => {
+                    for ext in take(&mut extensions, n) {
+                        challenger.observe_elements(&ext.elements);
+                    }
+                }
+                TranscriptEvent::ObserveElement => {
+                    for element in take(&mut elements, 1) {
+                        challenger.observe_element(element);
+                    }
+                }
+                TranscriptEvent::Squeeze(challenge, n) => {
+                    let values = challenger.get_n_challenges(n);
+                    match challenge {
+                        Challenge::PlonkBetas => challenges.plonk_betas = values,
+                        Challenge::PlonkGammas => challenges.plonk_gammas = values,
+                        Challenge::PlonkAlphas => challenges.plonk_alphas = values,
+                        Challenge::PlonkZeta => challenges.plonk_zeta = to_extension(values)?,
+                        Challenge::FriAlpha => challenges.fri_alpha = to_extension(values)?,
+                        Challenge::FriBeta => challenges.fri_betas.push(to_extension(values)?),
+                        Challenge::FriPowResponse => challenges.fri_pow_response = values[0],
+                        Challenge::FriQueryIndices => challenges.fri_query_indices = values,
+                    }
+                }
+            }
+        }
+
+        let reduced_openings = batches
+            .iter()
+            .map(|batch| {
+                let alpha = QuadraticExtension(challenges.fri_alpha);
+                batch
+                    .iter()
+                    .rev()
+                    .fold(QuadraticExtension::ZERO, |acc, ext| {
+                        acc * alpha + QuadraticExtension(ext.elements)
+                    })
+                    .0
+            })
+            .collect();
+
+        let lde_bits = common_data.fri_params.lde_bits();
+        let fri_query_x_indices = challenges
+            .fri_query_indices
+            .iter()
+            .map(|challenge| challenge.to_canonical_u64() as usize % (1 << lde_bits))
+            .collect();
+
+        Ok(Self {
+            public_inputs_hash,
+            challenges,
+            reduced_openings,
+            fri_query_x_indices,
+            lde_bits,
+        })
+    }
+
+    /// Checks the assigned public inputs hash and challenges against the trace. Unknown values
+    /// (e.g. during keygen) are skipped.
+    pub fn check_assigned<F: PrimeField>(
+        &self,
+        public_inputs_hash: &AssignedHashValues<F>,
+        challenges: &AssignedProofChallenges<F, 2>,
+    ) -> Result<(), TraceMismatch> {
+        check_values(
+            "public_inputs_hash",
+            &public_inputs_hash.elements,
+            &self.public_inputs_hash,
+        )?;
+
+        let expected = &self.challenges;
+        check_values(
+            "plonk_betas",
+            &challenges.plonk_betas,
+            &expected.plonk_betas,
+        )?;
+        check_values(
+            "plonk_gammas",
+            &challenges.plonk_gammas,
+            &expected.plonk_gammas,
+        )?;
+        check_values(
+            "plonk_alphas",
+            &challenges.plonk_alphas,
+            &expected.plonk_alphas,
+        )?;
+        check_values(
+            "plonk_zeta",
+            challenges.plonk_zeta.limbs(),
+            &expected.plonk_zeta,
+        )?;
+
+        let fri_challenges = &challenges.fri_challenges;
+        check_values(
+            "fri_alpha",
+            fri_challenges.fri_alpha.limbs(),
+            &expected.fri_alpha,
+        )?;
+        let assigned_betas = fri_challenges
+            .fri_betas
+            .iter()
+            .flat_map(|beta| beta.limbs().iter().cloned())
+            .collect::<Vec<_>>();
+        check_values("fri_betas", &assigned_betas, &expected.fri_betas.concat())?;
+        check_values(
+            "fri_pow_response",
+            &[fri_challenges.fri_pow_response.clone()],
+            &[expected.fri_pow_response],
+        )?;
+        check_values(
+            "fri_query_indices",
+            &fri_challenges.fri_query_indices,
+            &expected.fri_query_indices,
+        )
+    }
+
+    /// Checks that the index the FRI chip derives from each assigned query challenge, the
+    /// `lde_bits` low bits of its decomposition, is the index plonky2 queries, as given by
+    /// plonky2's own `ProofWithPublicInputs::fri_query_indices`. A change to the challenger or
+    /// to the decomposition that breaks the parity fails here, before a Merkle proof is opened
+    /// at the wrong leaf.
+    pub fn check_query_indices<F: PrimeField>(
+        &self,
+        query_challenges: &[AssignedValue<F>],
+        plonky2_indices: &[usize],
+    ) -> Result<(), TraceMismatch> {
+        if query_challenges.len() != plonky2_indices.len() {
+            return Err(TraceMismatch::Len {
+                name: "fri_query_indices",
+                expected: plonky2_indices.len(),
+                assigned: query_challenges.len(),
+            });
+        }
+        for (round, (challenge, expected)) in
+            query_challenges.iter().zip(plonky2_indices).enumerate()
+        {
+            if let Some(derived) = known(
+                challenge
+                    .value()
+                    .map(|v| query_index_bits(*v, self.lde_bits)),
+            ) {
+                if derived != *expected {
+                    return Err(TraceMismatch::QueryIndex {
+                        round,
+                        expected: *expected,
+                        derived,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An assigned value that differs from the `WitnessTrace`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceMismatch {
+    /// The circuit assigned another number of `name` elements than the trace has.
+    Len {
+        name: &'static str,
+        expected: usize,
+        assigned: usize,
+    },
+    /// The element at `index` of `name`, counting extension limbs, differs from the trace.
+    Value {
+        name: &'static str,
+        index: usize,
+        expected: GoldilocksField,
+        assigned: GoldilocksField,
+    },
+    /// The FRI chip takes another index from the query challenge of `round` than plonky2.
+    QueryIndex {
+        round: usize,
+        expected: usize,
+        derived: usize,
+    },
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Len {
+                name,
+                expected,
+                assigned,
+            } => write!(
+                f,
+                "{assigned} elements of `{name}` assigned, the witness trace has {expected}"
+            ),
+            Self::Value {
+                name,
+                index,
+                expected,
+                assigned,
+            } => write!(
+                f,
+                "`{name}[{index}]` is assigned {assigned}, the witness trace has {expected}"
+            ),
+            Self::QueryIndex {
+                round,
+                expected,
+                derived,
+            } => write!(
+                f,
+                "query round {round} opens index {derived}, plonky2 queries {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TraceMismatch {}
+
+impl From<TraceMismatch> for Error {
+    fn from(value: TraceMismatch) -> Self {
+        Error::Transcript(io::Error::new(io::ErrorKind::InvalidData, value))
+    }
+}
+
+/// The `lde_bits` low bits of the binary decomposition of `challenge`, as the FRI chip takes
+/// them, read as an index.
+fn query_index_bits<F: PrimeField>(challenge: F, lde_bits: usize) -> usize {
+    let low_limb = fe_to_big(challenge)
+        .iter_u64_digits()
+        .next()
+        .unwrap_or_default();
+    (low_limb % (1 << lde_bits)) as usize
+}
+
+fn observe_cap<F: PrimeField>(challenger: &mut NativeChallenger, cap: &MerkleCapValues<F>) {
+    for hash in cap.0.iter() {
+        challenger.observe_elements(&hash.elements);
+    }
+}
+
+fn to_extension(values: Vec<GoldilocksField>) -> Result<[GoldilocksField; 2], ShapeError> {
+    let actual = values.len();
+    values.try_into().map_err(|_| ShapeError {
+        expected: 2,
+        actual,
+    })
+}
+
+fn check_values<F: PrimeField>(
+    name: &'static str,
+    assigned: &[AssignedValue<F>],
+    expected: &[GoldilocksField],
+) -> Result<(), TraceMismatch> {
+    if assigned.len() != expected.len() {
+        return Err(TraceMismatch::Len {
+            name,
+            expected: expected.len(),
+            assigned: assigned.len(),
+        });
+    }
+    for (index, (assigned, expected)) in assigned.iter().zip(expected).enumerate() {
+        if let Some(assigned) = known(assigned.value().map(|v| fe_to_goldilocks(*v))) {
+            if assigned != *expected {
+                return Err(TraceMismatch::Value {
+                    name,
+                    index,
+                    expected: *expected,
+                    assigned,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Layouter, Value},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use halo2wrong_maingate::AssignedValue;
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+
+    use super::{query_index_bits, TraceMismatch, WitnessTrace};
+    use crate::plonky2_verifier::{
+        chip::{
+            goldilocks_chip::{GoldilocksChip, GoldilocksChipConfig},
+            native_chip::{
+                all_chip::AllChipConfig,
+                arithmetic_chip::{TableModeConfig, GOLDILOCKS_MODULUS},
+                test_utils::mock_prove_small_table,
+                utils::goldilocks_to_fe,
+            },
+        },
+        context::RegionCtx,
+        fixtures::{
+            generate_duplicate_query_proof_tuple, generate_proof_tuple, generate_tiny_proof_tuple,
+            mock_verifier,
+        },
+        types::assigned::{
+            AssignedExtensionFieldValue, AssignedFriChallenges, AssignedHashValues,
+            AssignedProofChallenges, ShapeError,
+        },
+        verifier_circuit::plonky2_query_indices,
+    };
+
+    #[test]
+    fn test_witness_trace_matches_circuit() {
+        let proof = generate_tiny_proof_tuple();
+        let public_inputs_hash = proof.0.get_public_inputs_hash().elements;
+        let num_query_rounds = proof.2.config.fri_config.num_query_rounds;
+        let (verifier_circuit, instances) = mock_verifier(proof);
+
+        let trace = verifier_circuit.witness_trace().unwrap();
+        assert_eq!(trace.public_inputs_hash, public_inputs_hash);
+        assert_eq!(trace.challenges.fri_query_indices.len(), num_query_rounds);
+
+        // synthesis checks every assigned challenge against the trace
+        let prover = mock_prove_small_table(&verifier_circuit, vec![instances]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_extra_commit_phase_cap_is_shape_error() {
+        let mut proof = generate_tiny_proof_tuple();
+        // the extra cap would squeeze an extra beta and shift the pow response and the query
+        // indices, failing the proof in FRI
+        let extra_cap = proof.0.proof.wires_cap.clone();
+        proof
+            .0
+            .proof
+            .opening_proof
+            .commit_phase_merkle_caps
+            .push(extra_cap);
+        let num_reductions = proof.2.fri_params.reduction_arity_bits.len();
+        let expected = ShapeError {
+            expected: num_reductions,
+            actual: num_reductions + 1,
+        };
+
+        let (verifier_circuit, instances) = mock_verifier(proof);
+        let shape_error = |err: Error| match err {
+            Error::Transcript(err) => err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<ShapeError>())
+                .cloned(),
+            _ => None,
+        };
+        assert_eq!(
+            verifier_circuit.witness_trace().err().and_then(shape_error),
+            Some(expected.clone())
+        );
+        let err = mock_prove_small_table(&verifier_circuit, vec![instances]).err();
+        assert_eq!(err.and_then(shape_error), Some(expected));
+    }
+
+    #[test]
+    fn test_query_indices_match_plonky2() {
+        for proof in [
+            generate_proof_tuple(),
+            generate_tiny_proof_tuple(),
+            generate_duplicate_query_proof_tuple(),
+        ] {
+            let (proof_with_public_inputs, vd, cd) = &proof;
+            let expected = proof_with_public_inputs
+                .fri_query_indices(&vd.circuit_digest, cd)
+                .unwrap();
+            let (verifier, _) = mock_verifier(proof.clone());
+            let trace = verifier.witness_trace().unwrap();
+
+            assert_eq!(trace.fri_query_x_indices, expected);
+            // the circuit's derivation, the low bits of the squeezed challenge
+            let derived = trace
+                .challenges
+                .fri_query_indices
+                .iter()
+                .map(|challenge| goldilocks_to_fe::<Fr>(*challenge))
+                .map(|challenge| query_index_bits(challenge, trace.lde_bits))
+                .collect::<Vec<_>>();
+            assert_eq!(derived, expected);
+        }
+    }
+
+    #[test]
+    fn test_query_indices_checked_against_plonky2() {
+        let proof_tuple = generate_tiny_proof_tuple();
+        let indices = plonky2_query_indices(&proof_tuple).unwrap();
+        let (verifier_circuit, instances) = mock_verifier(proof_tuple);
+
+        let circuit = verifier_circuit
+            .clone()
+            .with_plonky2_query_indices(indices.clone());
+        mock_prove_small_table(&circuit, vec![instances.clone()])
+            .unwrap()
+            .assert_satisfied();
+
+        // an index plonky2 doesn't query, as a diverging challenger would derive it
+        let mut other = indices;
+        other[0] ^= 1;
+        let circuit = verifier_circuit.with_plonky2_query_indices(other.clone());
+        let expected = TraceMismatch::QueryIndex {
+            round: 0,
+            expected: other[0],
+            derived: other[0] ^ 1,
+        };
+        assert_eq!(trace_mismatch(&circuit, vec![instances]), expected);
+    }
+
+    /// The `TraceMismatch` synthesis of `circuit` fails with.
+    fn trace_mismatch<C>(circuit: &C, instances: Vec<Vec<Fr>>) -> TraceMismatch
+    where
+        C: Circuit<Fr> + Clone,
+        C::Config: TableModeConfig<Fr>,
+    {
+        match mock_prove_small_table(circuit, instances) {
+            Err(Error::Transcript(err)) => err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<TraceMismatch>())
+                .cloned()
+                .expect("expected a trace mismatch"),
+            _ => panic!("expected a trace mismatch"),
+        }
+    }
+
+    fn assign(
+        chip: &GoldilocksChip<Fr>,
+        ctx: &mut RegionCtx<'_, Fr>,
+        values: &[GoldilocksField],
+    ) -> Result<Vec<AssignedValue<Fr>>, Error> {
+        values
+            .iter()
+            .map(|value| chip.assign_value(ctx, Value::known(goldilocks_to_fe(*value))))
+            .collect()
+    }
+
+    /// Assigns the public inputs hash and challenges of `assigned` and checks them against
+    /// `expected`.
+    #[derive(Clone, Default)]
+    struct CheckAssignedCircuit {
+        assigned: WitnessTrace,
+        expected: WitnessTrace,
+    }
+
+    impl Circuit<Fr> for CheckAssignedCircuit {
+        type Config = GoldilocksChipConfig<Fr>;
+
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            GoldilocksChip::configure(&AllChipConfig::configure(meta))
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = GoldilocksChip::new(&config);
+            layouter.assign_region(
+                || "challenges",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let values = &self.assigned.challenges;
+                    let public_inputs_hash = AssignedHashValues::from_vec(assign(
+                        &chip,
+                        ctx,
+                        &self.assigned.public_inputs_hash,
+                    )?)?;
+                    let fri_betas = values
+                        .fri_betas
+                        .iter()
+                        .map(|beta| {
+                            AssignedExtensionFieldValue::from_vec(assign(&chip, ctx, beta)?)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let challenges = AssignedProofChallenges {
+                        plonk_betas: assign(&chip, ctx, &values.plonk_betas)?,
+                        plonk_gammas: assign(&chip, ctx, &values.plonk_gammas)?,
+                        plonk_alphas: assign(&chip, ctx, &values.plonk_alphas)?,
+                        plonk_zeta: AssignedExtensionFieldValue::from_vec(assign(
+                            &chip,
+                            ctx,
+                            &values.plonk_zeta,
+                        )?)?,
+                        fri_challenges: AssignedFriChallenges {
+                            fri_alpha: AssignedExtensionFieldValue::from_vec(assign(
+                                &chip,
+                                ctx,
+                                &values.fri_alpha,
+                            )?)?,
+                            fri_betas,
+                            fri_pow_response: assign(&chip, ctx, &[values.fri_pow_response])?
+                                .remove(0),
+                            fri_query_indices: assign(&chip, ctx, &values.fri_query_indices)?,
+                        },
+                    };
+                    self.expected
+                        .check_assigned(&public_inputs_hash, &challenges)?;
+                    Ok(())
+                },
+            )?;
+            chip.load_table(&mut layouter)
+        }
+    }
+
+    #[test]
+    fn test_check_assigned_names_mismatching_challenge() {
+        let (verifier, _) = mock_verifier(generate_tiny_proof_tuple());
+        let trace = verifier.witness_trace().unwrap();
+
+        let circuit = CheckAssignedCircuit {
+            assigned: trace.clone(),
+            expected: trace.clone(),
+        };
+        mock_prove_small_table(&circuit, vec![vec![]])
+            .unwrap()
+            .assert_satisfied();
+
+        let mut expected = trace.clone();
+        expected.challenges.plonk_gammas[1] += GoldilocksField::ONE;
+        let circuit = CheckAssignedCircuit {
+            assigned: trace.clone(),
+            expected: expected.clone(),
+        };
+        assert_eq!(
+            trace_mismatch(&circuit, vec![vec![]]),
+            TraceMismatch::Value {
+                name: "plonk_gammas",
+                index: 1,
+                expected: expected.challenges.plonk_gammas[1],
+                assigned: trace.challenges.plonk_gammas[1],
+            }
+        );
+
+        let mut expected = trace.clone();
+        expected.challenges.fri_betas.pop();
+        let circuit = CheckAssignedCircuit {
+            assigned: trace,
+            expected,
+        };
+        assert!(matches!(
+            trace_mismatch(&circuit, vec![vec![]]),
+            TraceMismatch::Len {
+                name: "fri_betas",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_non_canonical_query_challenge_breaks_index_parity() {
+        // `p + 1` reduces to the index 1, its low bits don't
+        let lde_bits = 10;
+        let non_canonical = Fr::from(GOLDILOCKS_MODULUS + 1);
+        let canonical = goldilocks_to_fe::<Fr>(GoldilocksField::ONE);
+        assert_eq!(query_index_bits(canonical, lde_bits), 1);
+        assert_eq!(query_index_bits(non_canonical, lde_bits), 2);
+    }
+}